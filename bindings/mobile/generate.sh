@@ -0,0 +1,16 @@
+#!/usr/bin/env bash
+# Generates Kotlin or Swift bindings of the node handle from the built library.
+# Usage: generate.sh <kotlin|swift> <out-dir>
+set -euo pipefail
+
+language="${1:?language (kotlin or swift) is required}"
+out_dir="${2:?output directory is required}"
+
+case "$(uname)" in
+Darwin) library="target/release/libavail_light.dylib" ;;
+*) library="target/release/libavail_light.so" ;;
+esac
+
+cargo rustc --release --lib --crate-type cdylib --features uniffi
+cargo run --release --features uniffi --bin uniffi-bindgen -- \
+	generate --library "$library" --language "$language" --out-dir "$out_dir"
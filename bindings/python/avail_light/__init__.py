@@ -0,0 +1,64 @@
+"""Python bindings for the Avail light client.
+
+Bindings are a PyO3 extension module of the light client crate (`python` feature), built with maturin.
+Native calls release the GIL while the light client is blocked.
+"""
+
+import asyncio
+import json
+from typing import AsyncIterator, Dict, List, Optional, Tuple
+
+from ._native import LightClientError, Node, VerificationEntry
+
+__all__ = ["LightClient", "LightClientError", "VerificationEntry"]
+
+# Timeout of the single wait for the verified block, so iteration ends soon after the light client is stopped
+_POLL_TIMEOUT = 1.0
+
+
+class LightClient:
+    """Light client node running in the background, until it is stopped"""
+
+    def __init__(self, config: Optional[Dict] = None, identity_path: str = "identity.toml"):
+        self._node = Node(json.dumps(config or {}), identity_path)
+
+    def __enter__(self) -> "LightClient":
+        return self
+
+    def __exit__(self, *_) -> None:
+        self.stop()
+
+    def stop(self) -> None:
+        """Stops the light client, waiting for the shutdown to complete"""
+        if not self._node.is_stopped():
+            self._node.stop()
+
+    def latest_block(self) -> Optional[int]:
+        """Returns the latest block with achieved confidence"""
+        return self._node.latest_block()
+
+    def confidence(self, block_number: int) -> Optional[float]:
+        """Returns confidence of the given block, if block is verified"""
+        return self._node.confidence(block_number)
+
+    def block_status(self, block_number: int) -> Optional[Dict]:
+        """Returns status and confidence of the given block, in the `/v2/blocks/{block_number}` format"""
+        block = self._node.block_json(block_number)
+        return None if block is None else json.loads(block)
+
+    def confidence_history(self, first: int, last: int) -> List[VerificationEntry]:
+        """Returns verification metadata of the sampled blocks in the given inclusive range, with sampling timestamps"""
+        return self._node.verification_history(first, last)
+
+    async def verified_blocks(self) -> AsyncIterator[Tuple[int, Optional[float]]]:
+        """Iterates over the verified blocks, yielding block number and confidence, until the light client is stopped"""
+        loop = asyncio.get_running_loop()
+        while not self._node.is_stopped():
+            try:
+                block = await loop.run_in_executor(None, self._node.next_verified_block, _POLL_TIMEOUT)
+            except LightClientError:
+                if self._node.is_stopped():
+                    return
+                raise
+            if block is not None:
+                yield block
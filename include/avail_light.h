@@ -0,0 +1,50 @@
+/* Avail light client C interface, exported from the shared library built with `cargo rustc --lib --crate-type cdylib --features ffi`. */
+
+#ifndef AVAIL_LIGHT_H
+#define AVAIL_LIGHT_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Handle of the running light client. */
+typedef struct NodeHandle NodeHandle;
+
+/* Verified block callback, called with the block number, confidence (negative if block is not sampled) and user data. */
+typedef void (*AvailLightBlockCallback)(uint32_t block_number, double confidence, void *user_data);
+
+/* Returns the last error which occurred on the calling thread, or NULL if there was none.
+ * Returned string is valid until the next call on the same thread. */
+const char *avail_light_last_error(void);
+
+/* Starts the light client with the JSON configuration and identity stored in the given keystore path.
+ * Returns NULL on failure. */
+NodeHandle *avail_light_init(const char *config_json, const char *identity_path);
+
+/* Registers the callback called for each verified block, from the light client thread.
+ * User data must be valid until the handle is stopped. */
+void avail_light_on_verified_block(const NodeHandle *handle, AvailLightBlockCallback callback, void *user_data);
+
+/* Returns confidence of the given block, or negative value if block is not verified or on failure. */
+double avail_light_confidence(const NodeHandle *handle, uint32_t block_number);
+
+/* Returns status and confidence of the given block as JSON string, or NULL if block is not received yet or on failure.
+ * Returned string must be released with avail_light_free_string. */
+char *avail_light_block(const NodeHandle *handle, uint32_t block_number);
+
+/* Releases the string returned by the light client. */
+void avail_light_free_string(char *value);
+
+/* Returns the latest block with achieved confidence, or negative value if there is none. */
+int64_t avail_light_latest_block(const NodeHandle *handle);
+
+/* Stops the light client and releases the handle, which must not be used after this call. */
+void avail_light_stop(NodeHandle *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AVAIL_LIGHT_H */
@@ -6,27 +6,45 @@
 //! * `/v1/status` - returns status of a latest processed block
 //! * `/v1/latest_block` - returns latest processed block
 //! * `/v1/confidence/{block_number}` - returns calculated confidence for a given block number
-//! * `/v1/appdata/{block_number}` - returns decoded extrinsic data for configured app_id and given block number
+//! * `/v1/appdata/{block_number}` - returns decoded extrinsic data for configured app_id and given block number (disabled if API tokens are configured)
+//!
+//! API is served on the configured host and port, or on the Unix domain socket, and WebSocket API optionally on the separate port.
 
 use crate::api::v2;
+use crate::attestation;
 use crate::data::Database;
+use crate::fleet;
+use crate::maintenance::MaintenanceStatus;
 use crate::network::p2p;
-use crate::shutdown::Controller;
-use crate::types::IdentityConfig;
+use crate::shutdown::{Controller, ShutdownReason};
+use crate::types::{ConfigReloadSender, IdentityConfig};
 use crate::{
 	api::v1,
 	network::rpc::{self},
 	types::{RuntimeConfig, State},
 };
-use color_eyre::eyre::WrapErr;
-use futures::{Future, FutureExt};
+#[cfg(not(unix))]
+use color_eyre::eyre::eyre;
+use color_eyre::{eyre::WrapErr, Result};
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use futures::{
+	future::{self, BoxFuture},
+	Future, FutureExt,
+};
+#[cfg(unix)]
+use futures::{stream, TryStream};
+#[cfg(unix)]
+use std::io;
 use std::{
 	net::SocketAddr,
 	str::FromStr,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
-use tracing::info;
-use warp::{Filter, Reply};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tracing::{info, warn};
+use warp::{filters::BoxedFilter, Filter, Reply};
 
 pub struct Server<T: Database> {
 	pub db: T,
@@ -37,8 +55,13 @@ pub struct Server<T: Database> {
 	pub network_version: String,
 	pub node_client: rpc::Client,
 	pub ws_clients: v2::types::WsClients,
-	pub shutdown: Controller<String>,
+	pub shutdown: Controller<ShutdownReason>,
 	pub p2p_client: p2p::Client,
+	pub config_reload_sender: ConfigReloadSender,
+	pub maintenance_status: MaintenanceStatus,
+	pub pp: Arc<PublicParameters>,
+	pub attestation_signer: attestation::Signer,
+	pub fleet_aggregates: fleet::Aggregates,
 }
 
 fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection> + Clone {
@@ -48,16 +71,49 @@ fn health_route() -> impl Filter<Extract = impl Reply, Error = warp::Rejection>
 		.map(|_| warp::reply::with_status("", warp::http::StatusCode::OK))
 }
 
+/// Matches the configured path prefix, or any path if prefix is not set
+fn prefix_filter(prefix: Option<String>) -> BoxedFilter<()> {
+	match prefix {
+		Some(prefix) => warp::path(prefix).boxed(),
+		None => warp::any().boxed(),
+	}
+}
+
+/// Listens on the Unix domain socket, replacing the stale socket file
+#[cfg(unix)]
+fn unix_listener(path: &str) -> Result<impl TryStream<Ok = UnixStream, Error = io::Error>> {
+	if std::fs::metadata(path).is_ok() {
+		std::fs::remove_file(path).wrap_err(format!("Unable to remove socket {path}"))?;
+	}
+	let listener = UnixListener::bind(path).wrap_err(format!("Unable to bind socket {path}"))?;
+	Ok(stream::unfold(listener, |listener| async move {
+		let stream = listener.accept().await.map(|(stream, _)| stream);
+		Some((stream, listener))
+	}))
+}
+
+fn socket_addr(host: &str, port: u16) -> Result<SocketAddr> {
+	SocketAddr::from_str(&format!("{host}:{port}"))
+		.wrap_err("Unable to parse host address from config")
+}
+
 impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
-	/// Creates a HTTP server that needs to be spawned into a runtime
-	pub fn bind(self) -> impl Future<Output = ()> {
+	/// Creates a HTTP server that needs to be spawned into a runtime.
+	/// WebSocket API is served on the separate port if configured.
+	pub fn bind(self) -> Result<impl Future<Output = ()>> {
 		let RuntimeConfig {
 			http_server_host: host,
 			http_server_port: port,
+			http_server_prefix: prefix,
+			http_server_socket: socket,
+			http_server_drain_timeout,
+			ws_server_port,
 			app_id,
 			..
 		} = self.cfg.clone();
 
+		let submitter = v2::submitter(&self.cfg, self.identity_cfg, self.node_client.clone());
+		let tokens = v2::tokens::Tokens::new(self.cfg.api_tokens.clone());
 		let v1_api = v1::routes(
 			self.db.clone(),
 			app_id,
@@ -68,12 +124,26 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 			self.version.clone(),
 			self.network_version.clone(),
 			self.state.clone(),
-			self.cfg,
-			self.identity_cfg,
+			self.cfg.clone(),
+			submitter.clone(),
 			self.node_client.clone(),
-			self.ws_clients.clone(),
 			self.db.clone(),
 			self.p2p_client.clone(),
+			self.config_reload_sender,
+			self.maintenance_status,
+			self.pp,
+			self.attestation_signer,
+			self.fleet_aggregates,
+			tokens.clone(),
+		);
+		let v2_ws_api = v2::ws_routes(
+			self.version.clone(),
+			self.network_version.clone(),
+			self.state.clone(),
+			self.cfg,
+			submitter,
+			self.ws_clients.clone(),
+			tokens,
 		);
 
 		let cors = warp::cors()
@@ -81,16 +151,73 @@ impl<T: Database + Clone + Send + Sync + 'static> Server<T> {
 			.allow_header("content-type")
 			.allow_methods(vec!["GET", "POST", "DELETE"]);
 
-		let routes = health_route().or(v1_api).or(v2_api).with(cors);
-
-		let addr = SocketAddr::from_str(format!("{host}:{port}").as_str())
-			.wrap_err("Unable to parse host address from config")
-			.unwrap();
-		info!("RPC running on http://{host}:{port}");
 		// warp graceful shutdown expects a signal that is [`Future<Output = ()>`]
-		let shutdown_signal = self.shutdown.triggered_shutdown().map(|_| ());
-		let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, shutdown_signal);
+		let shutdown_signal = || self.shutdown.triggered_shutdown().map(|_| ());
+		let mut servers: Vec<BoxFuture<'static, ()>> = vec![];
+
+		let api = health_route().or(v1_api).or(v2_api);
+		let routes = match ws_server_port {
+			Some(ws_port) => {
+				let addr = socket_addr(&host, ws_port)?;
+				let ws_routes = prefix_filter(prefix.clone())
+					.and(v2_ws_api)
+					.with(cors.clone());
+				let (_, server) = warp::serve(ws_routes)
+					.try_bind_with_graceful_shutdown(addr, shutdown_signal())
+					.wrap_err(format!("Unable to bind WebSocket API to {addr}"))?;
+				info!("WebSocket API running on ws://{host}:{ws_port}");
+				servers.push(server.boxed());
+
+				prefix_filter(prefix)
+					.and(api)
+					.with(cors)
+					.map(|reply| Box::new(reply) as Box<dyn Reply>)
+					.boxed()
+			},
+			None => prefix_filter(prefix)
+				.and(api.or(v2_ws_api))
+				.with(cors)
+				.map(|reply| Box::new(reply) as Box<dyn Reply>)
+				.boxed(),
+		};
+
+		match socket {
+			#[cfg(unix)]
+			Some(path) => {
+				let incoming = unix_listener(&path)?;
+				info!("RPC running on unix:{path}");
+				let server = warp::serve(routes)
+					.serve_incoming_with_graceful_shutdown(incoming, shutdown_signal());
+				servers.push(server.boxed());
+			},
+			#[cfg(not(unix))]
+			Some(_) => return Err(eyre!("Unix domain sockets are not supported")),
+			None => {
+				let addr = socket_addr(&host, port)?;
+				info!("RPC running on http://{host}:{port}");
+				let (_, server) = warp::serve(routes)
+					.try_bind_with_graceful_shutdown(addr, shutdown_signal())
+					.wrap_err(format!("Unable to bind HTTP API to {addr}"))?;
+				servers.push(server.boxed());
+			},
+		}
 
-		server
+		let drain_timeout = Duration::from_secs(http_server_drain_timeout);
+		let shutdown = self.shutdown.clone();
+		let ws_clients = self.ws_clients;
+		Ok(async move {
+			let mut servers = future::join_all(servers).map(|_| ());
+			tokio::select! {
+				_ = &mut servers => return,
+				_ = shutdown.triggered_shutdown() => {},
+			}
+			// servers stop accepting new connections on shutdown trigger,
+			// and WebSocket connections are not drained, so they are closed explicitly
+			let closed = ws_clients.close("Light client is shutting down").await;
+			info!(closed, "Draining API connections...");
+			if tokio::time::timeout(drain_timeout, servers).await.is_err() {
+				warn!("API connections are not drained within {drain_timeout:?}");
+			}
+		})
 	}
 }
@@ -37,6 +37,18 @@ fn with_cfg(
 	warp::any().map(move || cfg.clone())
 }
 
+/// Rejects requests if the route is disabled
+fn enabled(enabled: bool) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+	warp::any()
+		.and_then(move || async move {
+			if !enabled {
+				return Err(warp::reject::not_found());
+			}
+			Ok(())
+		})
+		.untuple_one()
+}
+
 pub fn routes(
 	db: impl Database + Clone + Send,
 	app_id: Option<u32>,
@@ -47,6 +59,9 @@ pub fn routes(
 		.and(with_app_id(app_id))
 		.map(handlers::mode);
 
+	// app data is served without authorization, so it is disabled if API tokens are configured
+	let appdata_enabled = cfg.api_tokens.is_empty();
+
 	let latest_block = warp::path!("v1" / "latest_block")
 		.and(with_state(state.clone()))
 		.map(handlers::latest_block);
@@ -58,6 +73,7 @@ pub fn routes(
 		.map(handlers::confidence);
 
 	let appdata = (warp::path!("v1" / "appdata" / u32))
+		.and(enabled(appdata_enabled))
 		.and(warp::query::<AppDataQuery>())
 		.and(with_db(db.clone()))
 		.and(with_app_id(app_id))
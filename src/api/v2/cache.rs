@@ -0,0 +1,182 @@
+//! Short-lived cache of the frequently polled API responses, with the ETag validation.
+//!
+//! Serialized responses are cached per path for the configured TTL, so the dashboards polling the same endpoint
+//! don't recompute the response on each request. Each response has the `ETag` header (hash of the response body),
+//! and requests with the matching `If-None-Match` header are answered with `304 Not Modified`, without the body.
+//!
+//! # Notes
+//!
+//! Status (with the latest block and the confidence ranges), block and confidence series responses are cached.
+//! Legacy `/v1` endpoints (e.g. `/v1/latest_block`) are not cached, since their clients are expected to move to the `/v2/status`.
+
+use serde::Serialize;
+use sp_core::blake2_128;
+use std::{
+	collections::HashMap,
+	future::Future,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use warp::{
+	http::{header, StatusCode},
+	Reply,
+};
+
+use super::types::Error;
+
+#[derive(Clone)]
+struct Entry {
+	body: Arc<Vec<u8>>,
+	etag: String,
+	cached_at: Instant,
+}
+
+/// Cache of the serialized responses, disabled if TTL is zero
+#[derive(Clone, Default)]
+pub struct ResponseCache {
+	ttl: Duration,
+	entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl ResponseCache {
+	pub fn new(ttl: Duration) -> Self {
+		ResponseCache {
+			ttl,
+			..Default::default()
+		}
+	}
+
+	fn get(&self, key: &str) -> Option<Entry> {
+		let entries = self.entries.lock().expect("Lock acquired");
+		entries
+			.get(key)
+			.filter(|entry| entry.cached_at.elapsed() < self.ttl)
+			.cloned()
+	}
+
+	fn insert(&self, key: String, entry: Entry) {
+		if self.ttl.is_zero() {
+			return;
+		}
+		let mut entries = self.entries.lock().expect("Lock acquired");
+		entries.retain(|_, entry| entry.cached_at.elapsed() < self.ttl);
+		entries.insert(key, entry);
+	}
+
+	/// Returns cached response of the key, or the response of the handler, which is cached if successful
+	pub async fn reply<T: Serialize>(
+		&self,
+		key: String,
+		if_none_match: Option<String>,
+		handler: impl Future<Output = Result<T, Error>>,
+	) -> Result<CachedReply, Error> {
+		let entry = match self.get(&key) {
+			Some(entry) => entry,
+			None => {
+				let body =
+					serde_json::to_vec(&handler.await?).map_err(Error::internal_server_error)?;
+				let entry = Entry {
+					etag: etag(&body),
+					body: Arc::new(body),
+					cached_at: Instant::now(),
+				};
+				self.insert(key, entry.clone());
+				entry
+			},
+		};
+		let not_modified = if_none_match.is_some_and(|tags| matches(&tags, &entry.etag));
+		Ok(CachedReply {
+			entry,
+			not_modified,
+		})
+	}
+}
+
+fn etag(body: &[u8]) -> String {
+	format!("\"{}\"", hex::encode(blake2_128(body)))
+}
+
+/// Returns `true` if one of the `If-None-Match` header tags matches the ETag (weak comparison)
+fn matches(if_none_match: &str, etag: &str) -> bool {
+	if_none_match
+		.split(',')
+		.map(|tag| tag.trim())
+		.any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+pub struct CachedReply {
+	entry: Entry,
+	not_modified: bool,
+}
+
+impl Reply for CachedReply {
+	fn into_response(self) -> warp::reply::Response {
+		let builder = warp::http::Response::builder()
+			.header(header::ETAG, &self.entry.etag)
+			.header(header::CACHE_CONTROL, "no-cache");
+		let response = if self.not_modified {
+			builder.status(StatusCode::NOT_MODIFIED).body(vec![])
+		} else {
+			builder
+				.header(header::CONTENT_TYPE, "application/json")
+				.body(self.entry.body.to_vec())
+		};
+		response
+			.expect("Can create cached response")
+			.into_response()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	async fn reply(
+		cache: &ResponseCache,
+		value: u32,
+		if_none_match: Option<&str>,
+	) -> warp::reply::Response {
+		cache
+			.reply(
+				"key".to_string(),
+				if_none_match.map(ToString::to_string),
+				async move { Ok(value) },
+			)
+			.await
+			.unwrap()
+			.into_response()
+	}
+
+	#[tokio::test]
+	async fn cached_reply() {
+		let cache = ResponseCache::new(Duration::from_secs(60));
+		let response = reply(&cache, 1, None).await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let etag = response.headers()[header::ETAG]
+			.to_str()
+			.unwrap()
+			.to_string();
+
+		// cached response is returned until TTL expires
+		let response = reply(&cache, 2, None).await;
+		let body = warp::hyper::body::to_bytes(response.into_body())
+			.await
+			.unwrap();
+		assert_eq!(&body[..], b"1");
+
+		let if_none_match = format!("\"other\", W/{etag}");
+		let response = reply(&cache, 2, Some(&if_none_match)).await;
+		assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+		let response = reply(&cache, 2, Some("\"other\"")).await;
+		assert_eq!(response.status(), StatusCode::OK);
+
+		// disabled cache returns the current response
+		let cache = ResponseCache::default();
+		let first = reply(&cache, 1, None).await;
+		let second = reply(&cache, 2, None).await;
+		assert_ne!(
+			first.headers()[header::ETAG],
+			second.headers()[header::ETAG]
+		);
+	}
+}
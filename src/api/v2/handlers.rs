@@ -1,34 +1,55 @@
 use super::{
+	tokens::Tokens,
 	transactions,
 	types::{
-		block_status, filter_fields, Block, BlockStatus, DataQuery, DataResponse, DataTransaction,
-		Error, FieldsQueryParameter, Header, Status, SubmitResponse, Subscription, SubscriptionId,
-		Transaction, Version, WsClients,
+		block_status, filter_fields, Block, BlockStatus, BlockVerification, CellPosition,
+		CellsQuery, CellsResponse, ConfidenceSeries, ConfidenceSeriesQuery, CrawlBlock,
+		DataProofQuery, DataProofResponse, DataQuery, DataResponse, DataTransaction, Error,
+		FieldsQueryParameter, Header, Maintenance, PositionsQueryParameter, Status, SubmitResponse,
+		Subscription, SubscriptionId, Topic, Transaction, VerifiedCell, Version, WsClients,
+		DEFAULT_CONFIDENCE_SERIES_RANGE, DEFAULT_CONFIDENCE_SERIES_RESOLUTION,
+		MAX_CELLS_PER_REQUEST, MAX_CONFIDENCE_SERIES_RANGE,
 	},
 	ws,
 };
 use crate::{
-	api::v2::types::{ErrorCode, InternalServerError},
-	data::{Database, Key},
-	types::{RuntimeConfig, State},
-	utils::calculate_confidence,
+	api::v2::types::{ErrorCode, InternalServerError, Unauthorized},
+	data::{BestBlock, Database, Key},
+	data_root,
+	maintenance::MaintenanceStatus,
+	network::{p2p, rpc},
+	proof,
+	types::{OptionBlockRange, RuntimeConfig, State},
+	utils::{calculate_confidence, extract_app_lookup, extract_kate},
+	verification::{parse_header, BlockCommitments},
 };
-use avail_subxt::primitives;
+use avail_core::AppId;
+use avail_subxt::{primitives, utils::H256};
+use codec::Encode;
 use color_eyre::{eyre::eyre, Result};
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use hyper::StatusCode;
-use std::{
-	convert::Infallible,
-	sync::{Arc, Mutex},
-};
-use tracing::error;
+use kate_recovery::matrix::Position;
+use sp_core::blake2_256;
+use std::sync::{Arc, Mutex};
+use tracing::{error, warn};
 use uuid::Uuid;
 use warp::{ws::Ws, Rejection, Reply};
+pub mod admin;
+pub mod fleet;
 pub mod p2p;
 
 pub async fn subscriptions(
 	subscription: Subscription,
+	authorization: Option<String>,
+	tokens: Tokens,
+	app_id: Option<u32>,
 	clients: WsClients,
-) -> Result<SubscriptionId, Infallible> {
+) -> Result<SubscriptionId, Error> {
+	// data of the configured app is published on the data-verified topic
+	if let Some(app_id) = app_id.filter(|_| subscription.topics.contains(&Topic::DataVerified)) {
+		tokens.authorize(authorization.as_deref(), app_id)?;
+	}
 	let subscription_id = Uuid::new_v4().to_string();
 	clients.subscribe(&subscription_id, subscription).await;
 	Ok(SubscriptionId { subscription_id })
@@ -71,11 +92,17 @@ pub async fn ws(
 	}))
 }
 
-pub fn status(config: RuntimeConfig, state: Arc<Mutex<State>>) -> impl Reply {
+pub fn status(config: RuntimeConfig, state: Arc<Mutex<State>>) -> Status {
 	let state = state.lock().expect("Lock should be acquired");
 	Status::new(&config, &state)
 }
 
+pub fn maintenance(maintenance_status: MaintenanceStatus) -> impl Reply {
+	Maintenance {
+		tasks: maintenance_status.tasks(),
+	}
+}
+
 pub fn log_internal_server_error(result: Result<impl Reply, Error>) -> Result<impl Reply, Error> {
 	if let Err(Error {
 		error_code: ErrorCode::InternalServerError,
@@ -94,23 +121,97 @@ pub async fn block(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database,
-) -> Result<impl Reply, Error> {
+) -> Result<Block, Error> {
 	let state = state.lock().expect("Lock should be acquired");
 	let sync_start_block = &config.sync_start_block;
 
+	let best_confidence = db
+		.get::<BestBlock>(Key::BestBlock(block_number))
+		.map_err(Error::internal_server_error)?
+		.map(|best_block| calculate_confidence(best_block.verified));
+
 	let block_status = db
 		.get(Key::BlockHeader(block_number))
 		.map_err(Error::internal_server_error)?
 		.map(|primitives::Header { extension, .. }| extension)
-		.and_then(|extension| block_status(sync_start_block, &state, block_number, extension))
-		.ok_or(Error::not_found())?;
+		.and_then(|extension| block_status(sync_start_block, &state, block_number, extension));
+
+	// best chain block which is sampled, but not yet finalized
+	let block_status = match (block_status, best_confidence) {
+		(Some(block_status), _) => block_status,
+		(None, Some(_)) => BlockStatus::Pending,
+		(None, None) => return Err(Error::not_found()),
+	};
 
 	let confidence = db
 		.get(Key::VerifiedCellCount(block_number))
 		.map_err(Error::internal_server_error)?
 		.map(calculate_confidence);
 
-	Ok(Block::new(block_status, confidence))
+	let data_root = db
+		.get(Key::DataRootVerification(block_number))
+		.map_err(Error::internal_server_error)?;
+
+	Ok(Block {
+		best_confidence,
+		data_root,
+		..Block::new(block_status, confidence)
+	})
+}
+
+pub async fn block_verification(
+	block_number: u32,
+	db: impl Database,
+) -> Result<BlockVerification, Error> {
+	db.get(Key::BlockVerification(block_number))
+		.map_err(Error::internal_server_error)?
+		.map(|metadata| BlockVerification::new(block_number, metadata))
+		.ok_or(Error::not_found())
+}
+
+pub async fn confidence_series(
+	query: ConfidenceSeriesQuery,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> Result<ConfidenceSeries, Error> {
+	let last_available = {
+		let state = state.lock().expect("Lock should be acquired");
+		state.confidence_achieved.last().unwrap_or(state.latest)
+	};
+	let to_block = query.to_block.unwrap_or(last_available);
+	let from_block = query
+		.from_block
+		.unwrap_or_else(|| to_block.saturating_sub(DEFAULT_CONFIDENCE_SERIES_RANGE - 1));
+	let resolution = query
+		.resolution
+		.unwrap_or(DEFAULT_CONFIDENCE_SERIES_RESOLUTION);
+
+	if from_block > to_block {
+		return Err(Error::bad_request_unknown(
+			"from_block must not be greater than to_block",
+		));
+	}
+	if to_block - from_block >= MAX_CONFIDENCE_SERIES_RANGE {
+		let message = format!("Block range must not exceed {MAX_CONFIDENCE_SERIES_RANGE} blocks");
+		return Err(Error::bad_request_unknown(&message));
+	}
+	if resolution == 0 {
+		return Err(Error::bad_request_unknown(
+			"resolution must be greater than 0",
+		));
+	}
+
+	ConfidenceSeries::new(from_block, to_block, resolution, |block_number| {
+		db.get(Key::BlockVerification(block_number))
+	})
+	.map_err(Error::internal_server_error)
+}
+
+pub async fn crawl_block(block_number: u32, db: impl Database) -> Result<CrawlBlock, Error> {
+	db.get(Key::CrawlResult(block_number))
+		.map_err(Error::internal_server_error)?
+		.map(|result| CrawlBlock::new(block_number, result))
+		.ok_or(Error::not_found())
 }
 
 pub async fn block_header(
@@ -142,16 +243,110 @@ pub async fn block_header(
 		.map_err(Error::internal_server_error)
 }
 
+/// Fetches requested cells of the block, DHT first, and verifies them against the block commitments
+#[allow(clippy::too_many_arguments)]
+pub async fn block_cells(
+	block_number: u32,
+	query: CellsQuery,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+	p2p_client: p2p::Client,
+	rpc_client: rpc::Client,
+	pp: Arc<PublicParameters>,
+) -> Result<CellsResponse, Error> {
+	let PositionsQueryParameter(positions) = query.positions;
+	if positions.len() > MAX_CELLS_PER_REQUEST {
+		return Err(Error::bad_request_unknown(&format!(
+			"Maximum number of requested cells is {MAX_CELLS_PER_REQUEST}"
+		)));
+	}
+
+	let header = db
+		.get::<primitives::Header>(Key::BlockHeader(block_number))
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::not_found())?;
+
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(
+			&config.sync_start_block,
+			&state,
+			block_number,
+			header.extension.clone(),
+		)
+		.ok_or(Error::not_found())?
+	};
+
+	if matches!(
+		block_status,
+		BlockStatus::Unavailable | BlockStatus::Pending | BlockStatus::VerifyingHeader
+	) {
+		return Err(Error::bad_request_unknown("Block header is not available"));
+	};
+
+	let BlockCommitments {
+		dimensions,
+		commitments,
+	} = parse_header(&header.extension)
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::bad_request_unknown("Block has no commitments"))?;
+
+	let positions = positions.iter().map(Position::from).collect::<Vec<_>>();
+	if positions.iter().any(|position| {
+		position.row >= dimensions.extended_rows() || position.col >= dimensions.cols().get()
+	}) {
+		return Err(Error::bad_request_unknown("Cell position is out of range"));
+	}
+
+	let (mut fetched, unfetched) = p2p_client
+		.fetch_cells_from_dht(block_number, &positions)
+		.await;
+
+	if !unfetched.is_empty() && !config.disable_rpc {
+		let block_hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+		match rpc_client.request_kate_proof(block_hash, &unfetched).await {
+			Ok(cells) => fetched.extend(cells),
+			Err(error) => warn!(block_number, "Failed to fetch cells from RPC: {error:#}"),
+		}
+	}
+
+	let (verified, failed) = proof::verify(block_number, dimensions, &fetched, &commitments, pp)
+		.await
+		.map_err(Error::internal_server_error)?;
+
+	let cells = fetched
+		.iter()
+		.filter(|cell| verified.contains(&cell.position))
+		.map(VerifiedCell::from)
+		.collect();
+	let unfetched = positions
+		.iter()
+		.filter(|&position| fetched.iter().all(|cell| cell.position != *position))
+		.map(CellPosition::from)
+		.collect();
+
+	Ok(CellsResponse {
+		block_number,
+		cells,
+		unfetched,
+		failed: failed.iter().map(CellPosition::from).collect(),
+	})
+}
+
 pub async fn block_data(
 	block_number: u32,
 	query: DataQuery,
+	authorization: Option<String>,
+	tokens: Tokens,
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database,
 ) -> Result<DataResponse, Error> {
-	let state = state.lock().expect("Lock should be acquired");
-
 	let app_id = config.app_id.ok_or(Error::not_found())?;
+	tokens.authorize(authorization.as_deref(), app_id)?;
+
+	let state = state.lock().expect("Lock should be acquired");
 	let sync_start_block = &config.sync_start_block;
 
 	let block_status = db
@@ -192,9 +387,77 @@ pub async fn block_data(
 	})
 }
 
+pub async fn app_data_proof(
+	app_id: u32,
+	block_number: u32,
+	query: DataProofQuery,
+	authorization: Option<String>,
+	tokens: Tokens,
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database,
+) -> Result<DataProofResponse, Error> {
+	tokens.authorize(authorization.as_deref(), app_id)?;
+	if config.app_id != Some(app_id) {
+		return Err(Error::not_found());
+	}
+
+	let header = db
+		.get::<primitives::Header>(Key::BlockHeader(block_number))
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::not_found())?;
+
+	let block_status = {
+		let state = state.lock().expect("Lock should be acquired");
+		block_status(
+			&config.sync_start_block,
+			&state,
+			block_number,
+			header.extension.clone(),
+		)
+		.ok_or(Error::not_found())?
+	};
+
+	if block_status != BlockStatus::Finished {
+		return Err(Error::bad_request_unknown("Block data is not available"));
+	};
+
+	let lookup = extract_app_lookup(&header.extension)
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::not_found())?;
+
+	// data root commits to the blobs of all apps, so proof can be created only if block contains data of this app only
+	if !data_root::is_complete(&lookup, AppId(app_id)) {
+		return Err(Error::bad_request_unknown(
+			"Block contains data of other apps",
+		));
+	}
+
+	let data = db
+		.get::<Vec<Vec<u8>>>(Key::AppData(app_id, block_number))
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::not_found())?;
+
+	let blobs = data_root::blobs(&data).map_err(Error::internal_server_error)?;
+	let proof = data_root::proof(&blobs, query.index)
+		.ok_or(Error::bad_request_unknown("Data index is out of range"))?;
+
+	let expected = extract_kate(&header.extension).map(|(_, _, data_root, _)| data_root);
+	if expected != Some(proof.root) {
+		return Err(Error::bad_request_unknown(
+			"Data root of the app data doesn't match the header",
+		));
+	}
+
+	Ok(DataProofResponse::new(block_number, proof))
+}
+
 pub async fn handle_rejection(error: Rejection) -> Result<impl Reply, Rejection> {
 	if error.find::<InternalServerError>().is_some() {
 		return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
 	}
+	if error.find::<Unauthorized>().is_some() {
+		return Ok(Error::unauthorized().into_response());
+	}
 	Err(error)
 }
@@ -0,0 +1,141 @@
+use crate::{
+	api::v2::{
+		tokens::{TokenUsage, Tokens},
+		types::Error,
+	},
+	data::{CellSource, Database, ProofFailure},
+	quarantine,
+	types::{ConfigReloadSender, ReloadableConfig, SamplingBoost},
+};
+use color_eyre::eyre::eyre;
+use kate_recovery::config;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use warp::reply::Reply;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConfigReloadResponse {
+	pub log_level: String,
+	pub confidence: f64,
+	pub sampling_boosts: Vec<SamplingBoost>,
+	pub ot_flush_block_interval: u32,
+	pub full_node_ws: Vec<String>,
+}
+
+impl From<ReloadableConfig> for ConfigReloadResponse {
+	fn from(config: ReloadableConfig) -> Self {
+		ConfigReloadResponse {
+			log_level: config.log_level,
+			confidence: config.confidence,
+			sampling_boosts: config.sampling_boosts,
+			ot_flush_block_interval: config.ot_flush_block_interval,
+			full_node_ws: config.full_node_ws,
+		}
+	}
+}
+
+impl Reply for ConfigReloadResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+pub async fn reload_config(
+	config_reload_sender: ConfigReloadSender,
+) -> Result<ConfigReloadResponse, Error> {
+	let (response_sender, response_receiver) = oneshot::channel();
+	config_reload_sender
+		.send(response_sender)
+		.map_err(|_| Error::internal_server_error(eyre!("Configuration reload is not running")))?;
+
+	response_receiver
+		.await
+		.map_err(|_| Error::internal_server_error(eyre!("Configuration reload was cancelled")))?
+		.map(ConfigReloadResponse::from)
+		.map_err(Error::internal_server_error)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ProofFailureResponse {
+	pub block_number: u32,
+	pub row: u32,
+	pub col: u16,
+	pub proof: String,
+	pub data: String,
+	pub commitment: String,
+	pub source: CellSource,
+	pub quarantined_at: u64,
+}
+
+impl From<ProofFailure> for ProofFailureResponse {
+	fn from(failure: ProofFailure) -> Self {
+		let (proof, data) = failure
+			.content
+			.split_at(config::COMMITMENT_SIZE.min(failure.content.len()));
+		ProofFailureResponse {
+			block_number: failure.block_number,
+			row: failure.row,
+			col: failure.col,
+			proof: format!("0x{}", hex::encode(proof)),
+			data: format!("0x{}", hex::encode(data)),
+			commitment: format!("0x{}", hex::encode(&failure.commitment)),
+			source: failure.source,
+			quarantined_at: failure.quarantined_at,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProofFailuresResponse {
+	pub failures: Vec<ProofFailureResponse>,
+}
+
+impl Reply for ProofFailuresResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+/// Returns quarantined proof failures, the most recent first
+pub async fn proof_failures(db: impl Database) -> Result<ProofFailuresResponse, Error> {
+	let failures = quarantine::failures(&db).map_err(Error::internal_server_error)?;
+	Ok(ProofFailuresResponse {
+		failures: failures
+			.into_iter()
+			.rev()
+			.map(ProofFailureResponse::from)
+			.collect(),
+	})
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TokenUsageResponse {
+	pub name: String,
+	pub app_ids: Vec<u32>,
+	#[serde(flatten)]
+	pub usage: TokenUsage,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TokensResponse {
+	pub tokens: Vec<TokenUsageResponse>,
+}
+
+impl Reply for TokensResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+pub fn token_usage(tokens: Tokens) -> TokensResponse {
+	let tokens = tokens
+		.usage()
+		.into_iter()
+		.map(|(name, app_ids, usage)| TokenUsageResponse {
+			name,
+			app_ids,
+			usage,
+		})
+		.collect();
+	TokensResponse { tokens }
+}
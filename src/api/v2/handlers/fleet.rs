@@ -0,0 +1,55 @@
+use crate::{
+	api::v2::types::Error,
+	attestation::{Attestation, Signer},
+	data::{Database, Key, VerificationMetadata},
+	fleet::{Aggregates, FleetConfidence},
+	utils::{calculate_confidence, unix_timestamp_millis},
+};
+use avail_subxt::{primitives::Header, utils::H256};
+use codec::Encode;
+use sp_core::blake2_256;
+use warp::reply::Reply;
+
+impl Reply for Attestation {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+impl Reply for FleetConfidence {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+pub async fn block_attestation(
+	block_number: u32,
+	signer: Signer,
+	db: impl Database,
+) -> Result<Attestation, Error> {
+	let confidence = db
+		.get(Key::VerifiedCellCount(block_number))
+		.map_err(Error::internal_server_error)?
+		.map(calculate_confidence)
+		.ok_or(Error::not_found())?;
+
+	let header: Header = db
+		.get(Key::BlockHeader(block_number))
+		.map_err(Error::internal_server_error)?
+		.ok_or(Error::not_found())?;
+	let block_hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+
+	// verification time is not stored for the blocks verified before the upgrade
+	let timestamp = db
+		.get::<VerificationMetadata>(Key::BlockVerification(block_number))
+		.map_err(Error::internal_server_error)?
+		.map_or_else(unix_timestamp_millis, |metadata| metadata.finished_at);
+
+	signer
+		.sign(block_number, block_hash, confidence, timestamp)
+		.map_err(Error::internal_server_error)
+}
+
+pub fn fleet_block(block_number: u32, aggregates: Aggregates) -> Result<FleetConfidence, Error> {
+	aggregates.get(block_number).ok_or(Error::not_found())
+}
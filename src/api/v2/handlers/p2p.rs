@@ -4,6 +4,7 @@ use crate::{
 };
 use libp2p::{swarm::DialError, Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use warp::reply::Reply;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -80,6 +81,90 @@ pub async fn get_peer_info(p2p_client: p2p::Client) -> Result<PeerInfoResponse,
 	})
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PeerStatsEntry {
+	pub peer_id: String,
+	pub cells_served: u64,
+	pub invalid_cells: u64,
+	pub average_latency_ms: u64,
+	pub score: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerStatsResponse {
+	pub peers: Vec<PeerStatsEntry>,
+}
+
+impl Reply for PeerStatsResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+pub fn get_peer_stats(p2p_client: p2p::Client) -> PeerStatsResponse {
+	let peers = p2p_client
+		.peer_stats()
+		.get()
+		.into_iter()
+		.map(|(peer_id, stat)| PeerStatsEntry {
+			peer_id: peer_id.to_string(),
+			cells_served: stat.cells_served,
+			invalid_cells: stat.invalid_cells,
+			average_latency_ms: stat.average_latency().as_millis() as u64,
+			score: stat.score(),
+		})
+		.collect();
+	PeerStatsResponse { peers }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PeerAgentEntry {
+	pub peer_id: String,
+	pub agent_version: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PeerAgentsResponse {
+	pub peers: Vec<PeerAgentEntry>,
+	/// Number of the connected peers per agent version
+	pub agent_versions: BTreeMap<String, usize>,
+}
+
+impl From<Vec<(PeerId, String)>> for PeerAgentsResponse {
+	fn from(agents: Vec<(PeerId, String)>) -> Self {
+		let mut agent_versions = BTreeMap::new();
+		let mut peers = agents
+			.into_iter()
+			.map(|(peer_id, agent_version)| {
+				*agent_versions.entry(agent_version.clone()).or_default() += 1;
+				PeerAgentEntry {
+					peer_id: peer_id.to_string(),
+					agent_version,
+				}
+			})
+			.collect::<Vec<_>>();
+		peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+		PeerAgentsResponse {
+			peers,
+			agent_versions,
+		}
+	}
+}
+
+impl Reply for PeerAgentsResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+pub async fn get_peer_agents(p2p_client: p2p::Client) -> Result<PeerAgentsResponse, Error> {
+	p2p_client
+		.list_connected_agents()
+		.await
+		.map(PeerAgentsResponse::from)
+		.map_err(Error::internal_server_error)
+}
+
 pub async fn dial_external_peer(
 	p2p_client: p2p::Client,
 	peer_address: ExternalPeerMultiaddress,
@@ -1,26 +1,39 @@
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use std::{
 	convert::Infallible,
 	fmt::Display,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
-use tokio::sync::broadcast;
 use tracing::{debug, error, info};
 use warp::{Filter, Rejection, Reply};
 
 use self::{
+	cache::ResponseCache,
 	handlers::{handle_rejection, log_internal_server_error},
-	types::{DataQuery, PublishMessage, Version, WsClients},
+	tokens::Tokens,
+	transactions::Submitter,
+	types::{
+		CellsQuery, ConfidenceSeriesQuery, DataProofQuery, DataQuery, PublishMessage, Unauthorized,
+		Version, WsClients,
+	},
 };
 
 use crate::{
 	api::v2::types::Topic,
+	attestation,
 	data::Database,
+	event_bus::Receiver,
+	fleet::Aggregates,
+	maintenance::MaintenanceStatus,
 	network::{p2p, rpc::Client},
-	types::{IdentityConfig, RuntimeConfig, State},
+	types::{AdminToken, ConfigReloadSender, IdentityConfig, RuntimeConfig, State},
 };
 
+mod cache;
 mod handlers;
-mod transactions;
+pub(crate) mod tokens;
+pub(crate) mod transactions;
 pub mod types;
 mod ws;
 
@@ -37,6 +50,39 @@ fn with_db<T: Database + Clone + Send>(
 	warp::any().map(move || db.clone())
 }
 
+fn with_tokens(tokens: Tokens) -> impl Filter<Extract = (Tokens,), Error = Infallible> + Clone {
+	warp::any().map(move || tokens.clone())
+}
+
+fn with_cache(
+	cache: ResponseCache,
+) -> impl Filter<Extract = (ResponseCache, Option<String>), Error = Rejection> + Clone {
+	warp::any()
+		.map(move || cache.clone())
+		.and(warp::header::optional::<String>("if-none-match"))
+}
+
+fn with_authorization() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+	warp::header::optional::<String>("authorization")
+}
+
+/// Rejects requests without the admin token in the `Authorization` header
+fn with_admin_token(
+	admin_token: Option<String>,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+	with_authorization()
+		.and_then(move |authorization: Option<String>| {
+			let is_admin = tokens::is_admin(admin_token.as_deref(), authorization.as_deref());
+			async move {
+				if !is_admin {
+					return Err(warp::reject::custom(Unauthorized {}));
+				}
+				Ok(())
+			}
+		})
+		.untuple_one()
+}
+
 fn with_ws_clients(
 	clients: WsClients,
 ) -> impl Filter<Extract = (WsClients,), Error = Infallible> + Clone {
@@ -54,25 +100,52 @@ fn version_route(
 fn status_route(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
+	cache: ResponseCache,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "status")
 		.and(warp::get())
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
-		.map(handlers::status)
+		.and(with_cache(cache))
+		.then(
+			|config, state, cache: ResponseCache, if_none_match| async move {
+				let status = async { Ok(handlers::status(config, state)) };
+				cache
+					.reply("status".to_string(), if_none_match, status)
+					.await
+			},
+		)
+		.map(log_internal_server_error)
+}
+
+fn maintenance_route(
+	maintenance_status: MaintenanceStatus,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "maintenance")
+		.and(warp::get())
+		.and(warp::any().map(move || maintenance_status.clone()))
+		.map(handlers::maintenance)
 }
 
 fn block_route(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database + Clone + Send,
+	cache: ResponseCache,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "blocks" / u32)
 		.and(warp::get())
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
 		.and(with_db(db))
-		.then(handlers::block)
+		.and(with_cache(cache))
+		.then(
+			|block_number, config, state, db, cache: ResponseCache, if_none_match| async move {
+				let block = handlers::block(block_number, config, state, db);
+				let key = format!("blocks/{block_number}");
+				cache.reply(key, if_none_match, block).await
+			},
+		)
 		.map(log_internal_server_error)
 }
 
@@ -90,14 +163,103 @@ fn block_header_route(
 		.map(log_internal_server_error)
 }
 
+fn block_verification_route(
+	db: impl Database + Clone + Send,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "verification")
+		.and(warp::get())
+		.and(with_db(db))
+		.then(handlers::block_verification)
+		.map(log_internal_server_error)
+}
+
+fn confidence_series_route(
+	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send,
+	cache: ResponseCache,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "metrics" / "confidence")
+		.and(warp::get())
+		.and(warp::query::<ConfidenceSeriesQuery>())
+		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
+		.and(with_cache(cache))
+		.then(
+			|query: ConfidenceSeriesQuery, state, db, cache: ResponseCache, if_none_match| async move {
+				let key = format!(
+					"metrics/confidence?from_block={:?}&to_block={:?}&resolution={:?}",
+					query.from_block, query.to_block, query.resolution
+				);
+				let series = handlers::confidence_series(query, state, db);
+				cache.reply(key, if_none_match, series).await
+			},
+		)
+		.map(log_internal_server_error)
+}
+
+fn block_attestation_route(
+	signer: attestation::Signer,
+	db: impl Database + Clone + Send,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "attestation")
+		.and(warp::get())
+		.and(warp::any().map(move || signer.clone()))
+		.and(with_db(db))
+		.then(handlers::fleet::block_attestation)
+		.map(log_internal_server_error)
+}
+
+fn fleet_block_route(
+	aggregates: Aggregates,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "fleet" / "blocks" / u32)
+		.and(warp::get())
+		.and(warp::any().map(move || aggregates.clone()))
+		.map(handlers::fleet::fleet_block)
+}
+
+fn crawl_block_route(
+	db: impl Database + Clone + Send,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "crawl" / "blocks" / u32)
+		.and(warp::get())
+		.and(with_db(db))
+		.then(handlers::crawl_block)
+		.map(log_internal_server_error)
+}
+
+fn block_cells_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send,
+	p2p_client: p2p::Client,
+	rpc_client: Client,
+	pp: Arc<PublicParameters>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "blocks" / u32 / "cells")
+		.and(warp::get())
+		.and(warp::query::<CellsQuery>())
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
+		.and(warp::any().map(move || p2p_client.clone()))
+		.and(warp::any().map(move || rpc_client.clone()))
+		.and(warp::any().map(move || pp.clone()))
+		.then(handlers::block_cells)
+		.map(log_internal_server_error)
+}
+
 fn block_data_route(
 	config: RuntimeConfig,
 	state: Arc<Mutex<State>>,
 	db: impl Database + Clone + Send,
+	tokens: Tokens,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "blocks" / u32 / "data")
 		.and(warp::get())
 		.and(warp::query::<DataQuery>())
+		.and(with_authorization())
+		.and(with_tokens(tokens))
 		.and(warp::any().map(move || config.clone()))
 		.and(warp::any().map(move || state.clone()))
 		.and(with_db(db))
@@ -105,6 +267,24 @@ fn block_data_route(
 		.map(log_internal_server_error)
 }
 
+fn app_data_proof_route(
+	config: RuntimeConfig,
+	state: Arc<Mutex<State>>,
+	db: impl Database + Clone + Send,
+	tokens: Tokens,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "apps" / u32 / "data" / u32 / "proof")
+		.and(warp::get())
+		.and(warp::query::<DataProofQuery>())
+		.and(with_authorization())
+		.and(with_tokens(tokens))
+		.and(warp::any().map(move || config.clone()))
+		.and(warp::any().map(move || state.clone()))
+		.and(with_db(db))
+		.then(handlers::app_data_proof)
+		.map(log_internal_server_error)
+}
+
 fn submit_route(
 	submitter: Option<Arc<impl transactions::Submit + Clone + Send + Sync>>,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
@@ -126,6 +306,25 @@ fn p2p_local_info_route(
 		.map(log_internal_server_error)
 }
 
+fn p2p_peers_stats_route(
+	p2p_client: p2p::Client,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "p2p" / "peers" / "stats")
+		.and(warp::get())
+		.and(warp::any().map(move || p2p_client.clone()))
+		.map(handlers::p2p::get_peer_stats)
+}
+
+fn p2p_peers_agents_route(
+	p2p_client: p2p::Client,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "p2p" / "peers" / "agents")
+		.and(warp::get())
+		.and(warp::any().map(move || p2p_client.clone()))
+		.then(handlers::p2p::get_peer_agents)
+		.map(log_internal_server_error)
+}
+
 fn p2p_peers_dial_route(
 	p2p_client: p2p::Client,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
@@ -137,14 +336,54 @@ fn p2p_peers_dial_route(
 		.map(log_internal_server_error)
 }
 
+fn admin_reload_route(
+	config_reload_sender: ConfigReloadSender,
+	admin_token: Option<String>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "admin" / "reload")
+		.and(warp::post())
+		.and(with_admin_token(admin_token))
+		.and(warp::any().map(move || config_reload_sender.clone()))
+		.then(handlers::admin::reload_config)
+		.map(log_internal_server_error)
+}
+
+fn admin_failures_route(
+	db: impl Database + Clone + Send,
+	admin_token: Option<String>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "admin" / "failures")
+		.and(warp::get())
+		.and(with_admin_token(admin_token))
+		.and(with_db(db))
+		.then(handlers::admin::proof_failures)
+		.map(log_internal_server_error)
+}
+
+fn admin_tokens_route(
+	tokens: Tokens,
+	admin_token: Option<String>,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	warp::path!("v2" / "admin" / "tokens")
+		.and(warp::get())
+		.and(with_admin_token(admin_token))
+		.and(with_tokens(tokens))
+		.map(handlers::admin::token_usage)
+}
+
 fn subscriptions_route(
 	clients: WsClients,
+	app_id: Option<u32>,
+	tokens: Tokens,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	warp::path!("v2" / "subscriptions")
 		.and(warp::post())
 		.and(warp::body::json())
+		.and(with_authorization())
+		.and(with_tokens(tokens))
+		.and(warp::any().map(move || app_id))
 		.and(with_ws_clients(clients))
-		.and_then(handlers::subscriptions)
+		.then(handlers::subscriptions)
 }
 
 fn ws_route(
@@ -166,18 +405,15 @@ fn ws_route(
 
 pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 	topic: Topic,
-	mut receiver: broadcast::Receiver<T>,
+	mut receiver: impl Receiver<T>,
 	clients: WsClients,
 ) where
 	<T as TryInto<PublishMessage>>::Error: Display,
 {
 	loop {
-		let message = match receiver.recv().await {
-			Ok(value) => value,
-			Err(error) => {
-				error!(?topic, "Cannot receive message: {error}");
-				return;
-			},
+		let Some(message) = receiver.receive().await else {
+			error!(?topic, "Cannot receive message: channel is closed");
+			return;
 		};
 
 		let message: PublishMessage = match message.try_into() {
@@ -202,61 +438,145 @@ pub async fn publish<T: Clone + TryInto<PublishMessage>>(
 	}
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn routes(
+/// Returns WebSocket API routes, served with the HTTP API routes or on the separate port
+pub fn ws_routes(
 	version: String,
 	network_version: String,
 	state: Arc<Mutex<State>>,
 	config: RuntimeConfig,
-	identity_config: IdentityConfig,
-	rpc_client: Client,
+	submitter: Option<Arc<Submitter>>,
 	ws_clients: WsClients,
-	db: impl Database + Clone + Send,
-	p2p_client: p2p::Client,
+	tokens: Tokens,
 ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
 	let version = Version {
 		version,
 		network_version,
 	};
 
-	let app_id = config.app_id.as_ref();
+	subscriptions_route(ws_clients.clone(), config.app_id, tokens)
+		.or(ws_route(ws_clients, version, config, submitter, state))
+		.recover(handle_rejection)
+}
 
-	let submitter = app_id.map(|&app_id| {
-		Arc::new(transactions::Submitter {
+/// Returns transaction submitter, if app ID is configured
+pub fn submitter(
+	config: &RuntimeConfig,
+	identity_config: IdentityConfig,
+	rpc_client: Client,
+) -> Option<Arc<Submitter>> {
+	config.app_id.map(|app_id| {
+		Arc::new(Submitter {
 			rpc_client,
 			app_id,
 			signer: identity_config.avail_key_pair,
 		})
-	});
+	})
+}
+
+/// Returns HTTP API routes, without the WebSocket API routes
+#[allow(clippy::too_many_arguments)]
+pub fn routes(
+	version: String,
+	network_version: String,
+	state: Arc<Mutex<State>>,
+	config: RuntimeConfig,
+	submitter: Option<Arc<Submitter>>,
+	rpc_client: Client,
+	db: impl Database + Clone + Send,
+	p2p_client: p2p::Client,
+	config_reload_sender: ConfigReloadSender,
+	maintenance_status: MaintenanceStatus,
+	pp: Arc<PublicParameters>,
+	attestation_signer: attestation::Signer,
+	fleet_aggregates: Aggregates,
+	tokens: Tokens,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+	let version = Version {
+		version,
+		network_version,
+	};
 
-	version_route(version.clone())
-		.or(status_route(config.clone(), state.clone()))
-		.or(block_route(config.clone(), state.clone(), db.clone()))
+	let admin_token = config
+		.api_admin_token
+		.clone()
+		.map(|AdminToken(token)| token);
+	let cache = ResponseCache::new(Duration::from_millis(config.http_server_cache_ttl));
+
+	let cells_route = block_cells_route(
+		config.clone(),
+		state.clone(),
+		db.clone(),
+		p2p_client.clone(),
+		rpc_client,
+		pp,
+	);
+
+	version_route(version)
+		.or(status_route(config.clone(), state.clone(), cache.clone()))
+		.or(block_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			cache.clone(),
+		))
 		.or(block_header_route(
 			config.clone(),
 			state.clone(),
 			db.clone(),
 		))
-		.or(block_data_route(config.clone(), state.clone(), db.clone()))
-		.or(subscriptions_route(ws_clients.clone()))
-		.or(submit_route(submitter.clone()))
-		.or(ws_route(ws_clients, version, config, submitter, state))
+		.or(block_data_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			tokens.clone(),
+		))
+		.or(app_data_proof_route(
+			config.clone(),
+			state.clone(),
+			db.clone(),
+			tokens.clone(),
+		))
+		.or(cells_route)
+		.or(block_verification_route(db.clone()))
+		.or(confidence_series_route(state.clone(), db.clone(), cache))
+		.or(block_attestation_route(attestation_signer, db.clone()))
+		.or(fleet_block_route(fleet_aggregates))
+		.or(crawl_block_route(db.clone()))
+		.or(submit_route(submitter))
 		.or(p2p_local_info_route(p2p_client.clone()))
 		.or(p2p_peers_dial_route(p2p_client.clone()))
+		.or(p2p_peers_stats_route(p2p_client.clone()))
+		.or(p2p_peers_agents_route(p2p_client.clone()))
+		.or(admin_reload_route(
+			config_reload_sender,
+			admin_token.clone(),
+		))
+		.or(admin_failures_route(db, admin_token.clone()))
+		.or(admin_tokens_route(tokens, admin_token))
+		.or(maintenance_route(maintenance_status))
 		.recover(handle_rejection)
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{transactions, types::Transaction};
+	use super::{
+		cache::ResponseCache, handlers::handle_rejection, tokens::Tokens, transactions,
+		types::Transaction,
+	};
 	use crate::{
 		api::v2::types::{
 			DataField, ErrorCode, SubmitResponse, Subscription, SubscriptionId, Topic, Version,
 			WsClients, WsError, WsResponse,
 		},
 		data::Key,
-		data::{mem_db, Database},
-		types::{BlockRange, OptionBlockRange, RuntimeConfig, State},
+		data::{
+			mem_db, CellSource, CrawlCoverage, CrawlResult, Database, ProofFailure,
+			VerificationMetadata,
+		},
+		maintenance::{MaintenanceStatus, Task},
+		network::p2p,
+		randomness::RandomnessSource,
+		types::{ApiToken, BlockRange, OptionBlockRange, ReloadableConfig, RuntimeConfig, State},
 	};
 	use async_trait::async_trait;
 	use avail_subxt::{api::runtime_types::avail_core::AppId, utils::H256};
@@ -268,16 +588,21 @@ mod tests {
 		},
 		primitives::Header as DaHeader,
 	};
+	use color_eyre::eyre::eyre;
 	use hyper::StatusCode;
 	use kate_recovery::matrix::Partition;
+	use libp2p::PeerId;
 	use std::{
 		collections::HashSet,
 		str::FromStr,
 		sync::{Arc, Mutex},
+		time::Duration,
 	};
 	use subxt::config::substrate::Digest;
 	use test_case::test_case;
+	use tokio::sync::mpsc;
 	use uuid::Uuid;
+	use warp::Filter;
 
 	fn v1() -> Version {
 		Version {
@@ -303,10 +628,43 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn maintenance_route() {
+		let status = MaintenanceStatus::default();
+		let route = super::maintenance_route(status.clone());
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/maintenance")
+			.reply(&route)
+			.await;
+		assert_eq!(response.body(), r#"{"tasks":{}}"#);
+
+		let _ = status.track(Task::Pruning, 10, async { Ok(5) }).await;
+		let _ = status
+			.track(Task::TelemetryFlush, 10, async {
+				Err::<(), _>(eyre!("Export failed"))
+			})
+			.await;
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/maintenance")
+			.reply(&route)
+			.await;
+		let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		let tasks = body["tasks"].as_object().unwrap();
+		assert_eq!(tasks.len(), 2);
+		assert_eq!(tasks["pruning"]["block_number"], 10);
+		assert_eq!(tasks["pruning"]["result"], "success");
+		assert!(tasks["pruning"].get("error").is_none());
+		assert_eq!(tasks["telemetry_flush"]["result"], "failure");
+		assert_eq!(tasks["telemetry_flush"]["error"], "Export failed");
+	}
+
 	#[tokio::test]
 	async fn status_route_defaults() {
 		let state = Arc::new(Mutex::new(State::default()));
-		let route = super::status_route(RuntimeConfig::default(), state);
+		let route = super::status_route(RuntimeConfig::default(), state, ResponseCache::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/status")
@@ -315,7 +673,7 @@ mod tests {
 
 		let gen_hash = H256::default();
 		let expected = format!(
-			r#"{{"modes":["light"],"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":0}}}}"#,
+			r#"{{"modes":["light"],"genesis_hash":"{:x?}","network":"{NETWORK}","state":"starting","blocks":{{"latest":0}}}}"#,
 			gen_hash
 		);
 		assert_eq!(response.body(), &expected);
@@ -347,7 +705,7 @@ mod tests {
 			state.sync_data_verified.set(18);
 		}
 
-		let route = super::status_route(runtime_config, state);
+		let route = super::status_route(runtime_config, state, ResponseCache::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/status")
@@ -356,7 +714,7 @@ mod tests {
 
 		let gen_hash = H256::default();
 		let expected = format!(
-			r#"{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:#x}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}"#,
+			r#"{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:#x}","network":"{NETWORK}","state":"starting","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}"#,
 			gen_hash
 		);
 		assert_eq!(response.body(), &expected);
@@ -374,7 +732,7 @@ mod tests {
 			state.latest = latest;
 		}
 		let db = mem_db::MemoryDB::default();
-		let route = super::block_route(config, state, db);
+		let route = super::block_route(config, state, db, ResponseCache::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path(&format!("/v2/blocks/{block_number}"))
@@ -396,7 +754,7 @@ mod tests {
 		}
 		let db = mem_db::MemoryDB::default();
 		_ = db.put(Key::BlockHeader(10), incomplete_header());
-		let route = super::block_route(config, state, db);
+		let route = super::block_route(config, state, db, ResponseCache::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/10")
@@ -423,7 +781,7 @@ mod tests {
 		let db = mem_db::MemoryDB::default();
 		_ = db.put(Key::VerifiedCellCount(10), 4);
 		_ = db.put(Key::BlockHeader(10), header());
-		let route = super::block_route(config, state, db);
+		let route = super::block_route(config, state, db, ResponseCache::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/10")
@@ -482,6 +840,337 @@ mod tests {
 		assert_eq!(response.status(), StatusCode::NOT_FOUND);
 	}
 
+	#[tokio::test]
+	async fn block_verification_route_not_found() {
+		let db = mem_db::MemoryDB::default();
+		let route = super::block_verification_route(db);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/10/verification")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn block_verification_route_ok() {
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(
+			Key::BlockVerification(10),
+			VerificationMetadata {
+				started_at: 1000,
+				finished_at: 1500,
+				processing_delay: 20000,
+				cells_requested: 4,
+				dht_fetched: 3,
+				rpc_fetched: 1,
+				verified: 4,
+				randomness: RandomnessSource::Beacon { round: 100 },
+			},
+		);
+		let route = super::block_verification_route(db);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/blocks/10/verification")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"block_number":10,"started_at":1000,"finished_at":1500,"duration_ms":500,"processing_delay_ms":20000,"cells":{"requested":4,"dht_fetched":3,"rpc_fetched":1,"verified":4},"confidence":93.75,"randomness":{"type":"beacon","round":100}}"#
+		);
+	}
+
+	#[tokio::test]
+	async fn confidence_series_route_ok() {
+		let db = mem_db::MemoryDB::default();
+		for (block_number, verified, processing_delay) in
+			[(10, 4, 1000), (11, 2, 3000), (13, 4, 500)]
+		{
+			_ = db.put(
+				Key::BlockVerification(block_number),
+				VerificationMetadata {
+					started_at: 1000,
+					finished_at: 1500,
+					processing_delay,
+					cells_requested: 4,
+					dht_fetched: 4,
+					rpc_fetched: 0,
+					verified,
+					randomness: RandomnessSource::Os,
+				},
+			);
+		}
+		let mut state = State::default();
+		state.confidence_achieved.set(10);
+		state.confidence_achieved.set(13);
+		let route = super::confidence_series_route(
+			Arc::new(Mutex::new(state)),
+			db,
+			ResponseCache::default(),
+		);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/metrics/confidence?from_block=10&to_block=14&resolution=2")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"from_block":10,"to_block":14,"resolution":2,"points":[{"from_block":10,"to_block":11,"blocks":2,"confidence_avg":84.375,"confidence_min":75.0,"latency_ms_avg":2500,"latency_ms_max":3500},{"from_block":12,"to_block":13,"blocks":1,"confidence_avg":93.75,"confidence_min":93.75,"latency_ms_avg":1000,"latency_ms_max":1000}]}"#
+		);
+
+		// range ends with the last available block by default
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/metrics/confidence?resolution=1")
+			.reply(&route)
+			.await;
+		let series: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		assert_eq!(series["from_block"], 0);
+		assert_eq!(series["to_block"], 13);
+		assert_eq!(series["points"].as_array().unwrap().len(), 3);
+
+		for query in [
+			"from_block=14&to_block=10",
+			"from_block=0&to_block=10000",
+			"resolution=0",
+		] {
+			let response = warp::test::request()
+				.method("GET")
+				.path(&format!("/v2/metrics/confidence?{query}"))
+				.reply(&route)
+				.await;
+			assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+		}
+	}
+
+	#[tokio::test]
+	async fn confidence_series_route_cached() {
+		let mut state = State::default();
+		state.confidence_achieved.set(10);
+		let route = super::confidence_series_route(
+			Arc::new(Mutex::new(state)),
+			mem_db::MemoryDB::default(),
+			ResponseCache::new(Duration::from_secs(60)),
+		);
+		let request = |query: &str| {
+			warp::test::request()
+				.method("GET")
+				.path(&format!("/v2/metrics/confidence?{query}"))
+		};
+
+		// responses are cached per query
+		let first = request("from_block=1&to_block=5").reply(&route).await;
+		let second = request("from_block=1&to_block=6").reply(&route).await;
+		assert_ne!(first.body(), second.body());
+
+		let etag = first.headers()["etag"].to_str().unwrap();
+		let response = request("from_block=1&to_block=5")
+			.header("if-none-match", etag)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+	}
+
+	#[tokio::test]
+	async fn crawl_block_route_not_found() {
+		let db = mem_db::MemoryDB::default();
+		let route = super::crawl_block_route(db);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/crawl/blocks/10")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::NOT_FOUND);
+	}
+
+	#[tokio::test]
+	async fn crawl_block_route_ok() {
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(
+			Key::CrawlResult(10),
+			CrawlResult {
+				crawled_at: 1000,
+				delay: 20,
+				cells: Some(CrawlCoverage {
+					total: 8,
+					fetched: 6,
+				}),
+				rows: None,
+			},
+		);
+		let route = super::crawl_block_route(db);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/crawl/blocks/10")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"block_number":10,"crawled_at":1000,"delay":20,"cells":{"total":8,"fetched":6,"success_rate":0.75}}"#
+		);
+	}
+
+	#[tokio::test]
+	async fn admin_reload_route_ok() {
+		let (sender, mut receiver) = mpsc::unbounded_channel();
+		tokio::spawn(async move {
+			while let Some(response_sender) = receiver.recv().await {
+				let config = ReloadableConfig::from(&RuntimeConfig::default());
+				let _ = response_sender.send(Ok(config));
+			}
+		});
+		let route = super::admin_reload_route(sender, Some("admin".to_string()));
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/admin/reload")
+			.header("authorization", "Bearer admin")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			r#"{"log_level":"INFO","confidence":99.9,"sampling_boosts":[],"ot_flush_block_interval":15,"full_node_ws":["ws://127.0.0.1:9944"]}"#
+		);
+	}
+
+	#[test_case(Some("admin"), None ; "No authorization")]
+	#[test_case(Some("admin"), Some("Bearer other") ; "Invalid token")]
+	#[test_case(None, Some("Bearer admin") ; "Admin token not configured")]
+	#[tokio::test]
+	async fn admin_reload_route_unauthorized(
+		admin_token: Option<&str>,
+		authorization: Option<&str>,
+	) {
+		let (sender, _receiver) = mpsc::unbounded_channel();
+		let route = super::admin_reload_route(sender, admin_token.map(String::from))
+			.recover(handle_rejection);
+		let mut request = warp::test::request()
+			.method("POST")
+			.path("/v2/admin/reload");
+		if let Some(authorization) = authorization {
+			request = request.header("authorization", authorization);
+		}
+		let response = request.reply(&route).await;
+		assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+	}
+
+	#[tokio::test]
+	async fn admin_reload_route_not_running() {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		drop(receiver);
+		let route = super::admin_reload_route(sender, Some("admin".to_string()));
+		let response = warp::test::request()
+			.method("POST")
+			.path("/v2/admin/reload")
+			.header("authorization", "Bearer admin")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+	}
+
+	#[tokio::test]
+	async fn p2p_peers_stats_route_ok() {
+		let (sender, _receiver) = p2p::command_channel();
+		let p2p_client = p2p::Client::new(sender, 1, 3600);
+		let peer_id = PeerId::random();
+		p2p_client
+			.peer_stats()
+			.record_served(peer_id, Duration::from_millis(40));
+		p2p_client.peer_stats().record_invalid(peer_id);
+
+		let route = super::p2p_peers_stats_route(p2p_client);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/p2p/peers/stats")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response.body(),
+			&format!(
+				r#"{{"peers":[{{"peer_id":"{peer_id}","cells_served":1,"invalid_cells":1,"average_latency_ms":40,"score":0.0}}]}}"#
+			)
+		);
+	}
+
+	#[test]
+	fn p2p_peer_agents() {
+		let (first, second, third) = (PeerId::random(), PeerId::random(), PeerId::random());
+		let client = "avail-light-client/1.9.2/rust-client+fleet-1/client";
+		let server = "avail-light-client/1.9.2/rust-client/server";
+		let response = handlers::p2p::PeerAgentsResponse::from(vec![
+			(first, client.to_string()),
+			(second, server.to_string()),
+			(third, client.to_string()),
+		]);
+		assert_eq!(response.peers.len(), 3);
+		assert!(response
+			.peers
+			.windows(2)
+			.all(|peers| peers[0].peer_id <= peers[1].peer_id));
+		assert_eq!(
+			serde_json::to_value(&response.agent_versions).unwrap(),
+			serde_json::json!({ client: 2, server: 1 })
+		);
+	}
+
+	#[tokio::test]
+	async fn admin_failures_route_ok() {
+		let db = mem_db::MemoryDB::default();
+		let route = super::admin_failures_route(db.clone(), Some("admin".to_string()))
+			.recover(handle_rejection);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/admin/failures")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/admin/failures")
+			.header("authorization", "Bearer admin")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(response.body(), r#"{"failures":[]}"#);
+
+		let mut content = vec![1; 48];
+		content.extend([2; 32]);
+		let failure = |block_number| ProofFailure {
+			block_number,
+			row: 1,
+			col: 2,
+			content: content.clone(),
+			commitment: vec![3; 48],
+			source: CellSource::Dht { peer_id: None },
+			quarantined_at: 1000,
+		};
+		_ = db.put(Key::ProofFailures, vec![failure(10), failure(11)]);
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/admin/failures")
+			.header("authorization", "Bearer admin")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		let failures = body["failures"].as_array().unwrap();
+		assert_eq!(failures.len(), 2);
+		assert_eq!(failures[0]["block_number"], 11);
+		assert_eq!(failures[0]["proof"], format!("0x{}", "01".repeat(48)));
+		assert_eq!(failures[0]["data"], format!("0x{}", "02".repeat(32)));
+		assert_eq!(failures[0]["commitment"], format!("0x{}", "03".repeat(48)));
+		assert_eq!(
+			failures[0]["source"],
+			serde_json::json!({"type": "dht", "peer_id": null})
+		);
+	}
+
 	fn header() -> DaHeader {
 		DaHeader {
 			parent_hash: H256::default(),
@@ -563,7 +1252,7 @@ mod tests {
 		}));
 		let db = mem_db::MemoryDB::default();
 		_ = db.put(Key::BlockHeader(block_number), header());
-		let route = super::block_data_route(config, state, db);
+		let route = super::block_data_route(config, state, db, Tokens::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path(&format!("/v2/blocks/{block_number}/data"))
@@ -581,7 +1270,7 @@ mod tests {
 			..Default::default()
 		}));
 		let db = mem_db::MemoryDB::default();
-		let route = super::block_data_route(config, state, db);
+		let route = super::block_data_route(config, state, db, Tokens::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/11/data")
@@ -605,7 +1294,7 @@ mod tests {
 		}));
 		let db = mem_db::MemoryDB::default();
 		_ = db.put(Key::BlockHeader(5), header());
-		let route = super::block_data_route(config, state, db);
+		let route = super::block_data_route(config, state, db, Tokens::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/5/data")
@@ -618,6 +1307,72 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn block_data_route_tokens() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let state = Arc::new(Mutex::new(State {
+			latest: 10,
+			header_verified: Some(BlockRange::init(5)),
+			confidence_achieved: Some(BlockRange::init(5)),
+			data_verified: Some(BlockRange::init(5)),
+			..Default::default()
+		}));
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(Key::BlockHeader(5), header());
+		let token = |name: &str, app_id| ApiToken {
+			name: name.to_string(),
+			token: format!("{name}-secret"),
+			app_ids: vec![app_id],
+		};
+		let tokens = Tokens::new(vec![token("rollup-a", 1), token("rollup-b", 2)]);
+		let route = super::block_data_route(config, state, db, tokens.clone());
+
+		for (authorization, status) in [
+			(None, StatusCode::UNAUTHORIZED),
+			(Some("Bearer unknown"), StatusCode::UNAUTHORIZED),
+			(Some("Bearer rollup-b-secret"), StatusCode::FORBIDDEN),
+			(Some("Bearer rollup-a-secret"), StatusCode::OK),
+		] {
+			let mut request = warp::test::request()
+				.method("GET")
+				.path("/v2/blocks/5/data");
+			if let Some(authorization) = authorization {
+				request = request.header("authorization", authorization);
+			}
+			assert_eq!(request.reply(&route).await.status(), status);
+		}
+
+		let route = super::admin_tokens_route(tokens, Some("admin".to_string()));
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/admin/tokens")
+			.header("authorization", "Bearer admin")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+		let tokens = body["tokens"].as_array().unwrap();
+		assert_eq!(tokens.len(), 2);
+		assert_eq!(tokens[0]["name"], "rollup-a");
+		assert_eq!(tokens[0]["requests"], 1);
+		assert_eq!(tokens[1]["denied"], 1);
+		assert!(tokens[0].get("token").is_none());
+	}
+
+	fn test_extrinsic() -> Vec<u8> {
+		vec![
+			189, 1, 132, 0, 212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159, 214,
+			130, 44, 133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125, 1, 50, 12,
+			43, 176, 19, 42, 23, 73, 70, 223, 198, 180, 103, 34, 60, 246, 184, 49, 140, 113, 174,
+			234, 229, 95, 71, 18, 92, 158, 185, 168, 140, 126, 12, 191, 156, 50, 234, 8, 4, 68,
+			137, 5, 156, 94, 209, 7, 169, 105, 62, 63, 1, 122, 253, 195, 112, 173, 239, 21, 73,
+			163, 240, 106, 109, 131, 0, 4, 0, 4, 29, 1, 20, 116, 101, 115, 116, 10,
+		]
+	}
+
 	#[tokio::test]
 	async fn block_data_route_ok() {
 		let config = RuntimeConfig {
@@ -632,20 +1387,9 @@ mod tests {
 			..Default::default()
 		}));
 		let db = mem_db::MemoryDB::default();
-		_ = db.put(
-			Key::AppData(1, 5),
-			vec![vec![
-				189, 1, 132, 0, 212, 53, 147, 199, 21, 253, 211, 28, 97, 20, 26, 189, 4, 169, 159,
-				214, 130, 44, 133, 88, 133, 76, 205, 227, 154, 86, 132, 231, 165, 109, 162, 125, 1,
-				50, 12, 43, 176, 19, 42, 23, 73, 70, 223, 198, 180, 103, 34, 60, 246, 184, 49, 140,
-				113, 174, 234, 229, 95, 71, 18, 92, 158, 185, 168, 140, 126, 12, 191, 156, 50, 234,
-				8, 4, 68, 137, 5, 156, 94, 209, 7, 169, 105, 62, 63, 1, 122, 253, 195, 112, 173,
-				239, 21, 73, 163, 240, 106, 109, 131, 0, 4, 0, 4, 29, 1, 20, 116, 101, 115, 116,
-				10,
-			]],
-		);
+		_ = db.put(Key::AppData(1, 5), vec![test_extrinsic()]);
 		_ = db.put(Key::BlockHeader(5), header());
-		let route = super::block_data_route(config, state, db);
+		let route = super::block_data_route(config, state, db, Tokens::default());
 		let response = warp::test::request()
 			.method("GET")
 			.path("/v2/blocks/5/data")
@@ -658,6 +1402,92 @@ mod tests {
 		);
 	}
 
+	fn app_header(data_root: H256) -> DaHeader {
+		DaHeader {
+			extension: HeaderExtension::V3(v3::HeaderExtension {
+				commitment: KateCommitment {
+					data_root,
+					..Default::default()
+				},
+				app_lookup: CompactDataLookup {
+					size: 1,
+					index: vec![DataLookupItem {
+						app_id: AppId(1),
+						start: 0,
+					}],
+				},
+			}),
+			..header()
+		}
+	}
+
+	#[test_case(true, "/v2/apps/2/data/5/proof?index=0", StatusCode::NOT_FOUND ; "Other app")]
+	#[test_case(true, "/v2/apps/1/data/6/proof?index=0", StatusCode::BAD_REQUEST ; "Block data is not available")]
+	#[test_case(true, "/v2/apps/1/data/5/proof?index=1", StatusCode::BAD_REQUEST ; "Index out of range")]
+	#[test_case(false, "/v2/apps/1/data/5/proof?index=0", StatusCode::BAD_REQUEST ; "Data root mismatch")]
+	#[tokio::test]
+	async fn app_data_proof_route_errors(valid_root: bool, path: &str, expected: StatusCode) {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let state = Arc::new(Mutex::new(State {
+			latest: 10,
+			header_verified: Some(BlockRange { first: 5, last: 6 }),
+			confidence_achieved: Some(BlockRange { first: 5, last: 6 }),
+			data_verified: Some(BlockRange::init(5)),
+			..Default::default()
+		}));
+		let data_root = match valid_root {
+			true => H256::from(sp_core::keccak_256(b"test\n")),
+			false => H256::repeat_byte(1),
+		};
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(Key::AppData(1, 5), vec![test_extrinsic()]);
+		_ = db.put(Key::BlockHeader(5), app_header(data_root));
+		_ = db.put(Key::BlockHeader(6), app_header(data_root));
+		let route = super::app_data_proof_route(config, state, db, Tokens::default());
+		let response = warp::test::request()
+			.method("GET")
+			.path(path)
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), expected);
+	}
+
+	#[tokio::test]
+	async fn app_data_proof_route_ok() {
+		let config = RuntimeConfig {
+			app_id: Some(1),
+			..Default::default()
+		};
+		let state = Arc::new(Mutex::new(State {
+			latest: 10,
+			header_verified: Some(BlockRange::init(5)),
+			confidence_achieved: Some(BlockRange::init(5)),
+			data_verified: Some(BlockRange::init(5)),
+			..Default::default()
+		}));
+		let leaf = H256::from(sp_core::keccak_256(b"test\n"));
+		let db = mem_db::MemoryDB::default();
+		_ = db.put(Key::AppData(1, 5), vec![test_extrinsic()]);
+		_ = db.put(Key::BlockHeader(5), app_header(leaf));
+		let route = super::app_data_proof_route(config, state, db, Tokens::default());
+		let response = warp::test::request()
+			.method("GET")
+			.path("/v2/apps/1/data/5/proof?index=0")
+			.reply(&route)
+			.await;
+		assert_eq!(response.status(), StatusCode::OK);
+		let leaf = serde_json::to_string(&leaf).unwrap();
+		assert_eq!(
+			response.body(),
+			&format!(
+				r#"{{"block_number":5,"data_root":{leaf},"proof":[],"number_of_leaves":1,"leaf_index":0,"leaf":{leaf}}}"#
+			)
+		);
+	}
+
 	fn all_topics() -> HashSet<Topic> {
 		vec![
 			Topic::HeaderVerified,
@@ -723,7 +1553,7 @@ mod tests {
 	#[tokio::test]
 	async fn subscriptions_route() {
 		let clients = WsClients::default();
-		let route = super::subscriptions_route(clients.clone());
+		let route = super::subscriptions_route(clients.clone(), Some(1), Tokens::default());
 
 		let body = r#"{"topics":["confidence-achieved","data-verified","header-verified"],"data_fields":["data","extrinsic"]}"#;
 		let response = warp::test::request()
@@ -746,6 +1576,40 @@ mod tests {
 		assert!(client.subscription == expected);
 	}
 
+	#[tokio::test]
+	async fn subscriptions_route_tokens() {
+		let tokens = Tokens::new(vec![ApiToken {
+			name: "rollup-a".to_string(),
+			token: "secret".to_string(),
+			app_ids: vec![1],
+		}]);
+		let route = super::subscriptions_route(WsClients::default(), Some(1), tokens);
+
+		for (topics, authorization, status) in [
+			(r#"["header-verified"]"#, None, StatusCode::OK),
+			(r#"["data-verified"]"#, None, StatusCode::UNAUTHORIZED),
+			(
+				r#"["data-verified"]"#,
+				Some("Bearer other"),
+				StatusCode::UNAUTHORIZED,
+			),
+			(
+				r#"["data-verified"]"#,
+				Some("Bearer secret"),
+				StatusCode::OK,
+			),
+		] {
+			let mut request = warp::test::request()
+				.method("POST")
+				.body(format!(r#"{{"topics":{topics},"data_fields":[]}}"#))
+				.path("/v2/subscriptions");
+			if let Some(authorization) = authorization {
+				request = request.header("authorization", authorization);
+			}
+			assert_eq!(request.reply(&route).await.status(), status);
+		}
+	}
+
 	struct MockSetup {
 		ws_client: warp::test::WsClient,
 		state: Arc<Mutex<State>>,
@@ -824,7 +1688,7 @@ mod tests {
 
 		let gen_hash = H256::default();
 		let expected = format!(
-			r#"{{"topic":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2","message":{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:x?}","network":"{NETWORK}","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}}}"#,
+			r#"{{"topic":"status","request_id":"363c71fc-90f7-4276-a5b6-bec688bf01e2","message":{{"modes":["light","app","partition"],"app_id":1,"genesis_hash":"{:x?}","network":"{NETWORK}","state":"starting","blocks":{{"latest":30,"available":{{"first":20,"last":29}},"app_data":{{"first":20,"last":29}},"historical_sync":{{"synced":false,"available":{{"first":10,"last":19}},"app_data":{{"first":10,"last":18}}}}}},"partition":"1/10"}}}}"#,
 			gen_hash
 		);
 
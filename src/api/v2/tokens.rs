@@ -0,0 +1,152 @@
+//! Per-app API tokens, for the nodes serving data of several applications.
+//!
+//! If tokens are configured, data endpoints and the `data-verified` subscriptions require the `Authorization: Bearer {token}` header,
+//! and the requested app has to be one of the token apps.
+//! Accepted and denied requests are accounted per token name, and exposed with `GET /v2/admin/tokens`.
+//! Admin endpoints require the separately configured admin token, and they are disabled if it is not configured.
+//!
+//! # Notes
+//!
+//! Usage is kept in memory, and it is reset on restart.
+
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+use subtle::ConstantTimeEq;
+
+use super::types::Error;
+use crate::{types::ApiToken, utils::unix_timestamp_millis};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TokenUsage {
+	/// Number of the accepted requests
+	pub requests: u64,
+	/// Number of the requests denied for the apps not allowed by the token
+	pub denied: u64,
+	/// Last time the token was used (milliseconds since UNIX epoch)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_used: Option<u64>,
+}
+
+/// Configured API tokens with their usage
+#[derive(Clone, Default)]
+pub struct Tokens {
+	tokens: Arc<Vec<ApiToken>>,
+	usage: Arc<Mutex<HashMap<String, TokenUsage>>>,
+}
+
+impl Tokens {
+	pub fn new(tokens: Vec<ApiToken>) -> Self {
+		Tokens {
+			tokens: Arc::new(tokens),
+			usage: Default::default(),
+		}
+	}
+
+	/// Checks if the data of the app can be read with the `Authorization` header value, recording the token usage.
+	/// Any request is authorized if no tokens are configured.
+	pub fn authorize(&self, authorization: Option<&str>, app_id: u32) -> Result<(), Error> {
+		if self.tokens.is_empty() {
+			return Ok(());
+		}
+		let token = authorization
+			.and_then(|value| value.strip_prefix("Bearer "))
+			.and_then(|value| {
+				let value = value.trim();
+				self.tokens
+					.iter()
+					.find(|token| token_eq(&token.token, value))
+			})
+			.ok_or(Error::unauthorized())?;
+
+		let allowed = token.app_ids.contains(&app_id);
+		let mut usage = self.usage.lock().expect("Lock acquired");
+		let usage = usage.entry(token.name.clone()).or_default();
+		usage.last_used = Some(unix_timestamp_millis());
+		if !allowed {
+			usage.denied += 1;
+			return Err(Error::forbidden());
+		}
+		usage.requests += 1;
+		Ok(())
+	}
+
+	/// Returns names and apps of the configured tokens with their usage
+	pub fn usage(&self) -> Vec<(String, Vec<u32>, TokenUsage)> {
+		let usage = self.usage.lock().expect("Lock acquired");
+		self.tokens
+			.iter()
+			.map(|token| {
+				let token_usage = usage.get(&token.name).cloned().unwrap_or_default();
+				(token.name.clone(), token.app_ids.clone(), token_usage)
+			})
+			.collect()
+	}
+}
+
+/// Compares tokens in constant time, so the token cannot be guessed from the response times
+fn token_eq(token: &str, value: &str) -> bool {
+	token.as_bytes().ct_eq(value.as_bytes()).into()
+}
+
+/// Checks if the `Authorization` header value has the admin token. Requests are not authorized if admin token is not configured.
+pub fn is_admin(admin_token: Option<&str>, authorization: Option<&str>) -> bool {
+	let Some(admin_token) = admin_token else {
+		return false;
+	};
+	authorization
+		.and_then(|value| value.strip_prefix("Bearer "))
+		.is_some_and(|value| token_eq(admin_token, value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::api::v2::types::ErrorCode;
+
+	fn tokens() -> Tokens {
+		Tokens::new(vec![ApiToken {
+			name: "rollup-a".to_string(),
+			token: "secret".to_string(),
+			app_ids: vec![1, 2],
+		}])
+	}
+
+	#[test]
+	fn authorize() {
+		let tokens = tokens();
+		assert!(tokens.authorize(Some("Bearer secret"), 1).is_ok());
+		assert!(tokens.authorize(Some("Bearer secret"), 2).is_ok());
+		let error = tokens.authorize(Some("Bearer secret"), 3).unwrap_err();
+		assert_eq!(error.error_code, ErrorCode::Forbidden);
+		for authorization in [None, Some("secret"), Some("Bearer other")] {
+			let error = tokens.authorize(authorization, 1).unwrap_err();
+			assert_eq!(error.error_code, ErrorCode::Unauthorized);
+		}
+
+		let usage = tokens.usage();
+		assert_eq!(usage.len(), 1);
+		let (name, app_ids, usage) = &usage[0];
+		assert_eq!((name.as_str(), app_ids), ("rollup-a", &vec![1, 2]));
+		assert_eq!((usage.requests, usage.denied), (2, 1));
+		assert!(usage.last_used.is_some());
+
+		assert!(Tokens::default().authorize(None, 1).is_ok());
+	}
+
+	#[test]
+	fn admin_token() {
+		assert!(is_admin(Some("admin"), Some("Bearer admin")));
+		for authorization in [
+			None,
+			Some("admin"),
+			Some("Bearer other"),
+			Some("Bearer admi"),
+		] {
+			assert!(!is_admin(Some("admin"), authorization));
+		}
+		assert!(!is_admin(None, Some("Bearer admin")));
+	}
+}
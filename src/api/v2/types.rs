@@ -10,11 +10,16 @@ use color_eyre::{
 };
 use derive_more::From;
 use hyper::{http, StatusCode};
-use kate_recovery::{com::AppData, commitments, config, matrix::Partition};
+use kate_recovery::{
+	com::AppData,
+	commitments, config,
+	data::Cell,
+	matrix::{Partition, Position},
+};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use sp_core::{blake2_256, H256};
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
 	sync::Arc,
 };
 use tokio::sync::{mpsc::UnboundedSender, RwLock};
@@ -25,11 +30,19 @@ use warp::{
 };
 
 use crate::{
-	network::rpc::Event as RpcEvent,
+	best_client::{BlockReorged, ChainReorg},
+	data::{recovery::DatabaseRecovery, CrawlCoverage, CrawlResult, VerificationMetadata},
+	data_root::{DataProof, DataRootVerification},
+	lifecycle::{Degradation, LifecycleState},
+	maintenance::{Task, TaskStatus},
+	network::rpc::{Event as RpcEvent, RuntimeUpgraded},
+	proof::Parallelism,
+	randomness::RandomnessSource,
+	rpc_health::SubscriptionHealth,
 	types::{
 		self, block_matrix_partition_format, BlockVerified, OptionBlockRange, RuntimeConfig, State,
 	},
-	utils::{decode_app_data, OptionalExtension},
+	utils::{calculate_confidence, decode_app_data, unix_timestamp_millis, OptionalExtension},
 };
 
 #[derive(Debug)]
@@ -37,6 +50,11 @@ pub struct InternalServerError {}
 
 impl warp::reject::Reject for InternalServerError {}
 
+#[derive(Debug)]
+pub struct Unauthorized {}
+
+impl warp::reject::Reject for Unauthorized {}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Version {
 	pub version: String,
@@ -91,12 +109,33 @@ pub struct Status {
 	pub app_id: Option<u32>,
 	pub genesis_hash: String,
 	pub network: String,
+	pub state: LifecycleState,
 	pub blocks: Blocks,
 	#[serde(
 		skip_serializing_if = "Option::is_none",
 		with = "block_matrix_partition_format"
 	)]
 	pub partition: Option<Partition>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub update_available: Option<String>,
+	#[serde(skip_serializing_if = "std::ops::Not::not")]
+	pub clock_skewed: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub database_recovery: Option<DatabaseRecovery>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub verification: Option<Parallelism>,
+	#[serde(skip_serializing_if = "BTreeSet::is_empty")]
+	pub degradations: BTreeSet<Degradation>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rpc_subscription: Option<RpcSubscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RpcSubscription {
+	#[serde(flatten)]
+	pub health: SubscriptionHealth,
+	/// Seconds since the last received header, or since the subscription start
+	pub seconds_since_last_header: u64,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
@@ -187,8 +226,23 @@ impl Status {
 			app_id: config.app_id,
 			genesis_hash: format!("{:?}", node.genesis_hash),
 			network: node.network(),
+			state: state.lifecycle,
 			blocks,
 			partition: config.block_matrix_partition,
+			update_available: state.update_available.clone(),
+			clock_skewed: state.clock_skewed,
+			database_recovery: state.database_recovery.clone(),
+			verification: state.verification.clone(),
+			degradations: state.degradations.clone(),
+			rpc_subscription: state
+				.rpc_subscription
+				.clone()
+				.map(|health| RpcSubscription {
+					seconds_since_last_header: health
+						.since_last_header(unix_timestamp_millis())
+						.as_secs(),
+					health,
+				}),
 		}
 	}
 }
@@ -221,12 +275,27 @@ impl Reply for Status {
 	}
 }
 
+#[derive(Serialize)]
+pub struct Maintenance {
+	pub tasks: BTreeMap<Task, TaskStatus>,
+}
+
+impl Reply for Maintenance {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum Topic {
 	HeaderVerified,
 	ConfidenceAchieved,
 	DataVerified,
+	LifecycleChanged,
+	BlockReorged,
+	Reorg,
+	RuntimeUpgraded,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -317,11 +386,20 @@ pub fn block_status(
 pub struct Block {
 	pub status: BlockStatus,
 	pub confidence: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub best_confidence: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none", default)]
+	pub data_root: Option<DataRootVerification>,
 }
 
 impl Block {
 	pub fn new(status: BlockStatus, confidence: Option<f64>) -> Self {
-		Self { status, confidence }
+		Self {
+			status,
+			confidence,
+			best_confidence: None,
+			data_root: None,
+		}
 	}
 }
 
@@ -331,6 +409,200 @@ impl Reply for Block {
 	}
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Cells {
+	pub requested: u32,
+	pub dht_fetched: u32,
+	pub rpc_fetched: u32,
+	pub verified: u32,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct BlockVerification {
+	pub block_number: u32,
+	pub started_at: u64,
+	pub finished_at: u64,
+	pub duration_ms: u64,
+	pub processing_delay_ms: u64,
+	pub cells: Cells,
+	pub confidence: f64,
+	pub randomness: RandomnessSource,
+}
+
+impl BlockVerification {
+	pub fn new(block_number: u32, metadata: VerificationMetadata) -> Self {
+		Self {
+			block_number,
+			started_at: metadata.started_at,
+			finished_at: metadata.finished_at,
+			duration_ms: metadata.finished_at.saturating_sub(metadata.started_at),
+			processing_delay_ms: metadata.processing_delay,
+			cells: Cells {
+				requested: metadata.cells_requested,
+				dht_fetched: metadata.dht_fetched,
+				rpc_fetched: metadata.rpc_fetched,
+				verified: metadata.verified,
+			},
+			confidence: calculate_confidence(metadata.verified),
+			randomness: metadata.randomness,
+		}
+	}
+}
+
+impl Reply for BlockVerification {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+/// Default number of the blocks in the confidence series, ending with the last available block
+pub const DEFAULT_CONFIDENCE_SERIES_RANGE: u32 = 1000;
+/// Default number of the blocks aggregated in each point of the confidence series
+pub const DEFAULT_CONFIDENCE_SERIES_RESOLUTION: u32 = 10;
+/// Maximum number of the blocks in the confidence series
+pub const MAX_CONFIDENCE_SERIES_RANGE: u32 = 10000;
+
+#[derive(Deserialize)]
+pub struct ConfidenceSeriesQuery {
+	pub from_block: Option<u32>,
+	pub to_block: Option<u32>,
+	pub resolution: Option<u32>,
+}
+
+/// Confidence and latency of the sampled blocks in the range
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ConfidencePoint {
+	pub from_block: u32,
+	pub to_block: u32,
+	/// Number of the sampled blocks in the range
+	pub blocks: u32,
+	pub confidence_avg: f64,
+	pub confidence_min: f64,
+	/// Latency between the header receipt and the sampling end, in milliseconds
+	pub latency_ms_avg: u64,
+	pub latency_ms_max: u64,
+}
+
+impl ConfidencePoint {
+	fn new(from_block: u32, to_block: u32, samples: &[(f64, u64)]) -> Option<Self> {
+		if samples.is_empty() {
+			return None;
+		}
+		let blocks = samples.len();
+		let confidences = samples.iter().map(|(confidence, _)| *confidence);
+		let latencies = samples.iter().map(|(_, latency)| *latency);
+		Some(ConfidencePoint {
+			from_block,
+			to_block,
+			blocks: blocks as u32,
+			confidence_avg: confidences.clone().sum::<f64>() / blocks as f64,
+			confidence_min: confidences.fold(f64::MAX, f64::min),
+			latency_ms_avg: latencies.clone().sum::<u64>() / blocks as u64,
+			latency_ms_max: latencies.max().unwrap_or_default(),
+		})
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ConfidenceSeries {
+	pub from_block: u32,
+	pub to_block: u32,
+	pub resolution: u32,
+	/// Points of the ranges with at least one sampled block
+	pub points: Vec<ConfidencePoint>,
+}
+
+impl ConfidenceSeries {
+	/// Downsamples verification metadata of the blocks, aggregating `resolution` blocks in each point
+	pub fn new(
+		from_block: u32,
+		to_block: u32,
+		resolution: u32,
+		mut metadata: impl FnMut(u32) -> Result<Option<VerificationMetadata>>,
+	) -> Result<Self> {
+		let mut points = vec![];
+		let mut start = from_block;
+		while start <= to_block {
+			let end = start.saturating_add(resolution - 1).min(to_block);
+			let mut samples = vec![];
+			for block_number in start..=end {
+				if let Some(metadata) = metadata(block_number)? {
+					let latency = metadata.processing_delay
+						+ metadata.finished_at.saturating_sub(metadata.started_at);
+					samples.push((calculate_confidence(metadata.verified), latency));
+				}
+			}
+			points.extend(ConfidencePoint::new(start, end, &samples));
+			let Some(next) = end.checked_add(1) else {
+				break;
+			};
+			start = next;
+		}
+		Ok(ConfidenceSeries {
+			from_block,
+			to_block,
+			resolution,
+			points,
+		})
+	}
+}
+
+impl Reply for ConfidenceSeries {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct Coverage {
+	pub total: u32,
+	pub fetched: u32,
+	pub success_rate: f64,
+}
+
+impl From<CrawlCoverage> for Coverage {
+	fn from(coverage: CrawlCoverage) -> Self {
+		let success_rate = match coverage.total {
+			0 => 0.0,
+			total => coverage.fetched as f64 / total as f64,
+		};
+		Coverage {
+			total: coverage.total,
+			fetched: coverage.fetched,
+			success_rate,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct CrawlBlock {
+	pub block_number: u32,
+	pub crawled_at: u64,
+	pub delay: u64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cells: Option<Coverage>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rows: Option<Coverage>,
+}
+
+impl CrawlBlock {
+	pub fn new(block_number: u32, result: CrawlResult) -> Self {
+		Self {
+			block_number,
+			crawled_at: result.crawled_at,
+			delay: result.delay,
+			cells: result.cells.map(Into::into),
+			rows: result.rows.map(Into::into),
+		}
+	}
+}
+
+impl Reply for CrawlBlock {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
 impl TryFrom<avail_subxt::primitives::Header> for HeaderMessage {
 	type Error = Report;
 
@@ -465,6 +737,84 @@ pub struct ConfidenceMessage {
 	confidence: Option<f64>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LifecycleMessage {
+	state: LifecycleState,
+}
+
+impl TryFrom<LifecycleState> for PublishMessage {
+	type Error = Report;
+
+	fn try_from(state: LifecycleState) -> Result<Self, Self::Error> {
+		Ok(PublishMessage::LifecycleChanged(LifecycleMessage { state }))
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReorgMessage {
+	block_number: u32,
+	best_hash: H256,
+	finalized_hash: H256,
+	best_confidence: f64,
+}
+
+impl TryFrom<BlockReorged> for PublishMessage {
+	type Error = Report;
+
+	fn try_from(reorg: BlockReorged) -> Result<Self, Self::Error> {
+		Ok(PublishMessage::BlockReorged(ReorgMessage {
+			block_number: reorg.block_number,
+			best_hash: reorg.best_hash,
+			finalized_hash: reorg.finalized_hash,
+			best_confidence: reorg.best_confidence,
+		}))
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChainReorgMessage {
+	block_number: u32,
+	block_hash: H256,
+	previous_block_number: u32,
+	previous_block_hash: H256,
+	depth: u32,
+	rolled_back: Vec<u32>,
+}
+
+impl TryFrom<ChainReorg> for PublishMessage {
+	type Error = Report;
+
+	fn try_from(reorg: ChainReorg) -> Result<Self, Self::Error> {
+		Ok(PublishMessage::Reorg(ChainReorgMessage {
+			block_number: reorg.block_number,
+			block_hash: reorg.block_hash,
+			previous_block_number: reorg.previous_block_number,
+			previous_block_hash: reorg.previous_block_hash,
+			depth: reorg.depth,
+			rolled_back: reorg.rolled_back,
+		}))
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RuntimeUpgradeMessage {
+	block_number: u32,
+	previous_spec_version: u32,
+	spec_version: u32,
+}
+
+impl TryFrom<RuntimeUpgraded> for PublishMessage {
+	type Error = Report;
+
+	fn try_from(upgrade: RuntimeUpgraded) -> Result<Self, Self::Error> {
+		Ok(PublishMessage::RuntimeUpgraded(RuntimeUpgradeMessage {
+			block_number: upgrade.block_number,
+			previous_spec_version: upgrade.previous_spec_version,
+			spec_version: upgrade.spec_version,
+		}))
+	}
+}
+
 impl TryFrom<BlockVerified> for PublishMessage {
 	type Error = Report;
 
@@ -510,6 +860,134 @@ impl Reply for DataResponse {
 	}
 }
 
+/// Maximum number of cells which can be requested at once
+pub const MAX_CELLS_PER_REQUEST: usize = 64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CellPosition {
+	pub row: u32,
+	pub col: u16,
+}
+
+impl From<&CellPosition> for Position {
+	fn from(position: &CellPosition) -> Self {
+		Position {
+			row: position.row,
+			col: position.col,
+		}
+	}
+}
+
+impl From<&Position> for CellPosition {
+	fn from(position: &Position) -> Self {
+		CellPosition {
+			row: position.row,
+			col: position.col,
+		}
+	}
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(try_from = "String")]
+pub struct PositionsQueryParameter(pub Vec<CellPosition>);
+
+impl TryFrom<String> for PositionsQueryParameter {
+	type Error = Report;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value
+			.split(',')
+			.map(|part| {
+				let (row, col) = part
+					.split_once(':')
+					.ok_or_else(|| eyre!("Invalid cell position {part}"))?;
+				Ok(CellPosition {
+					row: row.parse().wrap_err("Invalid cell row")?,
+					col: col.parse().wrap_err("Invalid cell column")?,
+				})
+			})
+			.collect::<Result<Vec<_>>>()
+			.map(PositionsQueryParameter)
+	}
+}
+
+#[derive(Deserialize)]
+pub struct CellsQuery {
+	pub positions: PositionsQueryParameter,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct VerifiedCell {
+	pub row: u32,
+	pub col: u16,
+	/// Hex encoded cell data
+	pub data: String,
+	/// Hex encoded KZG proof of the cell
+	pub proof: String,
+}
+
+impl From<&Cell> for VerifiedCell {
+	fn from(cell: &Cell) -> Self {
+		let (proof, data) = cell.content.split_at(config::COMMITMENT_SIZE);
+		VerifiedCell {
+			row: cell.position.row,
+			col: cell.position.col,
+			data: format!("0x{}", hex::encode(data)),
+			proof: format!("0x{}", hex::encode(proof)),
+		}
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CellsResponse {
+	pub block_number: u32,
+	pub cells: Vec<VerifiedCell>,
+	/// Cells which are fetched neither from DHT nor from RPC
+	pub unfetched: Vec<CellPosition>,
+	/// Fetched cells which failed the proof verification
+	pub failed: Vec<CellPosition>,
+}
+
+impl Reply for CellsResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DataProofQuery {
+	pub index: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DataProofResponse {
+	pub block_number: u32,
+	pub data_root: H256,
+	pub proof: Vec<H256>,
+	pub number_of_leaves: usize,
+	pub leaf_index: usize,
+	pub leaf: H256,
+}
+
+impl DataProofResponse {
+	pub fn new(block_number: u32, proof: DataProof) -> Self {
+		DataProofResponse {
+			block_number,
+			data_root: proof.root,
+			proof: proof.proof,
+			number_of_leaves: proof.number_of_leaves,
+			leaf_index: proof.leaf_index,
+			leaf: proof.leaf,
+		}
+	}
+}
+
+impl Reply for DataProofResponse {
+	fn into_response(self) -> warp::reply::Response {
+		warp::reply::json(&self).into_response()
+	}
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DataMessage {
 	block_number: u32,
@@ -569,6 +1047,10 @@ pub enum PublishMessage {
 	HeaderVerified(Box<HeaderMessage>),
 	ConfidenceAchieved(ConfidenceMessage),
 	DataVerified(DataMessage),
+	LifecycleChanged(LifecycleMessage),
+	BlockReorged(ReorgMessage),
+	Reorg(ChainReorgMessage),
+	RuntimeUpgraded(RuntimeUpgradeMessage),
 }
 
 impl PublishMessage {
@@ -576,6 +1058,10 @@ impl PublishMessage {
 		match self {
 			PublishMessage::HeaderVerified(_) => (),
 			PublishMessage::ConfidenceAchieved(_) => (),
+			PublishMessage::LifecycleChanged(_) => (),
+			PublishMessage::BlockReorged(_) => (),
+			PublishMessage::Reorg(_) => (),
+			PublishMessage::RuntimeUpgraded(_) => (),
 			PublishMessage::DataVerified(data) => {
 				filter_fields(&mut data.data_transactions, fields)
 			},
@@ -640,6 +1126,21 @@ impl WsClients {
 		clients.insert(subscription_id.to_string(), WsClient::new(subscription));
 	}
 
+	/// Sends close frame with the reason to the connected clients, returning number of the closed clients
+	pub async fn close(&self, reason: &'static str) -> usize {
+		let clients = self.0.read().await;
+		clients
+			.values()
+			.flat_map(|client| client.sender.as_ref())
+			// 1001 (going away) indicates that the server is shutting down
+			.filter(|sender| {
+				sender
+					.send(Ok(ws::Message::close_with(1001u16, reason)))
+					.is_ok()
+			})
+			.count()
+	}
+
 	pub async fn publish(&self, topic: &Topic, message: PublishMessage) -> Result<Vec<Result<()>>> {
 		let clients = self.0.read().await;
 		Ok(clients
@@ -720,6 +1221,8 @@ impl TryFrom<ws::Message> for Request {
 pub enum ErrorCode {
 	NotFound,
 	BadRequest,
+	Unauthorized,
+	Forbidden,
 	InternalServerError,
 }
 
@@ -769,10 +1272,20 @@ impl Error {
 		Self::new(Some(request_id), None, ErrorCode::BadRequest, message)
 	}
 
+	pub fn unauthorized() -> Self {
+		Self::new(None, None, ErrorCode::Unauthorized, "Unauthorized")
+	}
+
+	pub fn forbidden() -> Self {
+		Self::new(None, None, ErrorCode::Forbidden, "Forbidden")
+	}
+
 	fn status(&self) -> StatusCode {
 		match self.error_code {
 			ErrorCode::NotFound => StatusCode::NOT_FOUND,
 			ErrorCode::BadRequest => StatusCode::BAD_REQUEST,
+			ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+			ErrorCode::Forbidden => StatusCode::FORBIDDEN,
 			ErrorCode::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
 		}
 	}
@@ -823,8 +1336,8 @@ mod tests {
 	};
 
 	use super::{
-		block_status, Base64, ConfidenceMessage, DataField, DataMessage, DataTransaction,
-		Subscription, Topic, WsClients,
+		block_status, Base64, CellPosition, ConfidenceMessage, DataField, DataMessage,
+		DataTransaction, PositionsQueryParameter, Subscription, Topic, WsClients,
 	};
 
 	fn subscription(topics: Vec<Topic>, fields: Vec<DataField>) -> Subscription {
@@ -944,6 +1457,19 @@ mod tests {
 		};
 	}
 
+	#[tokio::test]
+	async fn clients_close() {
+		let clients = WsClients::default();
+		let (sender, mut receiver) = mpsc::unbounded_channel();
+		clients.subscribe("1", subscription(vec![], vec![])).await;
+		clients.subscribe("2", subscription(vec![], vec![])).await;
+		clients.set_sender("1", sender).await.unwrap();
+
+		assert_eq!(clients.close("Shutting down").await, 1);
+		let message = receiver.recv().await.unwrap().unwrap();
+		assert_eq!(message.close_frame(), Some((1001, "Shutting down")));
+	}
+
 	struct ExtensionNone;
 
 	impl OptionalExtension for ExtensionNone {
@@ -960,6 +1486,21 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn positions_query_parameter() {
+		let positions = PositionsQueryParameter::try_from("0:1,2:3".to_string()).unwrap();
+		assert_eq!(
+			positions.0,
+			vec![
+				CellPosition { row: 0, col: 1 },
+				CellPosition { row: 2, col: 3 },
+			]
+		);
+		assert!(PositionsQueryParameter::try_from("0:1,2".to_string()).is_err());
+		assert!(PositionsQueryParameter::try_from("0:70000".to_string()).is_err());
+		assert!(PositionsQueryParameter::try_from("".to_string()).is_err());
+	}
+
 	#[test]
 	fn block_status_none() {
 		let mut state = State::default();
@@ -7,10 +7,11 @@
 //! Get app data rows from node
 //! Verify commitment equality for each row
 //! Decode app data and store it into local database under the `app_id:block_number` key
+//! Verify data root of the app data against the header, if the block contains data of the configured app only
 //!
 //! # Notes
 //!
-//! If application client fails to run or stops its execution, error is logged, and other tasks continue with execution.
+//! If application client fails, error is returned and application client is restarted by the [`supervisor`](crate::supervisor), until restarts are exhausted and the shutdown is triggered.
 use async_trait::async_trait;
 use avail_core::AppId;
 use avail_subxt::utils::H256;
@@ -37,14 +38,15 @@ use std::{
 	ops::Range,
 	sync::{Arc, Mutex},
 };
-use tokio::sync::broadcast;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, info, instrument, warn};
 
 use crate::{
-	data::{Database, Key},
+	data::{Database, Key, StoragePause},
+	data_root::{self, DataRootVerification},
+	event_bus::{EventBus, Subscriber},
 	network::{p2p::Client as P2pClient, rpc::Client as RpcClient},
 	proof,
-	shutdown::Controller,
+	shutdown::{Controller, ShutdownReason},
 	types::{AppClientConfig, BlockVerified, OptionBlockRange, State},
 };
 
@@ -281,6 +283,7 @@ async fn process_block(
 	app_id: AppId,
 	block: &BlockVerified,
 	pp: Arc<PublicParameters>,
+	storage_pause: &StoragePause,
 ) -> Result<AppData> {
 	let Some(extension) = &block.extension else {
 		return Err(eyre!("Missing header extension"));
@@ -392,8 +395,33 @@ async fn process_block(
 	let data = decode_app_extrinsics(lookup, dimensions, data_cells, app_id)
 		.wrap_err("Failed to decode app extrinsics")?;
 
+	// failure to decode the blobs doesn't fail the block processing, since app data is already verified against commitments
+	match data_root::verify(lookup, app_id, &data, extension.data_root) {
+		Ok(verification) => {
+			if verification == DataRootVerification::Mismatch {
+				warn!(
+					block_number,
+					"Data root of the app data doesn't match the header"
+				);
+			}
+			debug!(
+				block_number,
+				?verification,
+				"Data root verification finished"
+			);
+			db.put(Key::DataRootVerification(block_number), verification)
+				.wrap_err("App Client failed to store data root verification")?;
+		},
+		Err(error) => warn!(block_number, "Cannot verify data root: {error:#}"),
+	}
+
 	debug!(block_number, "Storing data into database");
 
+	if storage_pause.is_paused() {
+		warn!(block_number, "Storing of app data is paused");
+		return Ok(data);
+	}
+
 	// store encoded App Data into the database
 	db.put(Key::AppData(app_id.0, block_number), data.clone())
 		.wrap_err("App Client failed to store App Data into database")?;
@@ -415,6 +443,7 @@ async fn process_block(
 /// * `app_id` - Application ID
 /// * `block_receive` - Channel used to receive header of verified block
 /// * `pp` - Public parameters (i.e. SRS) needed for proof verification
+/// * `storage_pause` - Flag used to pause storing of app data
 #[allow(clippy::too_many_arguments)]
 pub async fn run(
 	cfg: AppClientConfig,
@@ -422,13 +451,14 @@ pub async fn run(
 	network_client: P2pClient,
 	rpc_client: RpcClient,
 	app_id: AppId,
-	mut block_receive: broadcast::Receiver<BlockVerified>,
+	mut block_receive: Subscriber<BlockVerified>,
 	pp: Arc<PublicParameters>,
 	state: Arc<Mutex<State>>,
 	sync_range: Range<u32>,
-	data_verified_sender: broadcast::Sender<(u32, AppData)>,
-	shutdown: Controller<String>,
-) {
+	data_verified_sender: EventBus<(u32, AppData)>,
+	storage_pause: StoragePause,
+	shutdown: Controller<ShutdownReason>,
+) -> Result<()> {
 	info!("Starting for app {app_id}...");
 
 	fn set_data_verified_state(
@@ -447,15 +477,22 @@ pub async fn run(
 	}
 
 	loop {
-		let block = match block_receive.recv().await {
-			Ok(block) => block,
-			Err(error) => {
-				error!("Cannot receive message: {error}");
-				let _ = shutdown.trigger_shutdown(format!("Cannot receive message: {error:#}"));
-				return;
+		let received = tokio::select! {
+			received = block_receive.recv() => received,
+			// stop receiving new blocks once the shutdown is triggered,
+			// block being processed is drained in the verification shutdown phase
+			_ = shutdown.triggered_shutdown() => {
+				info!("Shutdown triggered, stopping block verification");
+				return Ok(());
 			},
 		};
 
+		let Some(block) = received else {
+			return Err(eyre!(
+				"Cannot receive message: verified blocks channel is closed"
+			));
+		};
+
 		let block_number = block.block_num;
 		let Some(extension) = &block.extension else {
 			info!(block_number, "Skipping block without header extension");
@@ -479,22 +516,22 @@ pub async fn run(
 			p2p_client: network_client.clone(),
 			rpc_client: rpc_client.clone(),
 		};
-		let data =
-			match process_block(app_client, db.clone(), &cfg, app_id, &block, pp.clone()).await {
-				Ok(data) => data,
-				Err(error) => {
-					error!(block_number, "Cannot process block: {error}");
-					let _ = shutdown.trigger_shutdown(format!("Cannot process block: {error:#}"));
-					return;
-				},
-			};
+		let data = match process_block(
+			app_client,
+			db.clone(),
+			&cfg,
+			app_id,
+			&block,
+			pp.clone(),
+			&storage_pause,
+		)
+		.await
+		{
+			Ok(data) => data,
+			Err(error) => return Err(error.wrap_err("Cannot process block")),
+		};
 		set_data_verified_state(state.clone(), &sync_range, block_number);
-		if let Err(error) = data_verified_sender.send((block_number, data)) {
-			error!("Cannot send data verified message: {error}");
-			let _ =
-				shutdown.trigger_shutdown(format!("Cannot send data verified message: {error:#}"));
-			return;
-		}
+		data_verified_sender.send((block_number, data));
 		debug!(block_number, "Block processed");
 	}
 }
@@ -546,6 +583,7 @@ mod tests {
 					],
 				]
 				.to_vec(),
+				data_root: H256::zero(),
 			}),
 			confidence: None,
 		};
@@ -562,9 +600,17 @@ mod tests {
 			.expect_reconstruct_rows_from_dht()
 			.returning(|_, _, _, _, _| Box::pin(async move { Ok(vec![]) }));
 
-		process_block(mock_client, db, &cfg, AppId(1), &block, pp)
-			.await
-			.unwrap();
+		process_block(
+			mock_client,
+			db,
+			&cfg,
+			AppId(1),
+			&block,
+			pp,
+			&StoragePause::default(),
+		)
+		.await
+		.unwrap();
 	}
 
 	#[tokio::test]
@@ -603,6 +649,7 @@ mod tests {
 					],
 				]
 				.to_vec(),
+				data_root: H256::zero(),
 			}),
 			confidence: None,
 		};
@@ -626,8 +673,16 @@ mod tests {
 			.expect_reconstruct_rows_from_dht()
 			.returning(|_, _, _, _, _| Box::pin(async move { Ok(vec![]) }));
 
-		process_block(mock_client, db, &cfg, AppId(1), &block, pp)
-			.await
-			.unwrap();
+		process_block(
+			mock_client,
+			db,
+			&cfg,
+			AppId(1),
+			&block,
+			pp,
+			&StoragePause::default(),
+		)
+		.await
+		.unwrap();
 	}
 }
@@ -0,0 +1,208 @@
+//! Signed availability attestations.
+//!
+//! Attestation states that the block is available with the confidence, at the time of the verification.
+//! It is signed with the libp2p identity key, which identifies the light client (peer ID),
+//! and with the keystore Avail key, which identifies the operator (Avail address),
+//! so consumers can verify that the claim comes from the specific operator.
+//!
+//! Attestations are served on `GET /v2/blocks/{block_number}/attestation`, and if `attestation_gossip` is enabled,
+//! attestation of each verified block is published to the `{protocol_version}/attestations/1` gossipsub topic.
+//!
+//! # Signed message
+//!
+//! Both signatures sign the SCALE encoded `("avail-light/attestation/1", block_number, block_hash, confidence_bits, timestamp)` tuple,
+//! where `confidence_bits` is the IEEE 754 representation of the confidence.
+
+use avail_subxt::utils::H256;
+use codec::Encode;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use libp2p::{
+	identity::{Keypair, PublicKey},
+	PeerId,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+use subxt_signer::sr25519::Keypair as OperatorKeypair;
+use tracing::{debug, info, warn};
+
+use crate::{
+	event_bus::Subscriber, network::p2p, types::BlockVerified, utils::unix_timestamp_millis,
+};
+
+/// Domain of the signed attestation message
+const DOMAIN: &[u8] = b"avail-light/attestation/1";
+
+/// Operator signature of the attestation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Operator {
+	/// SS58 encoded Avail address of the keystore key
+	pub address: String,
+	/// Hex encoded sr25519 signature
+	pub signature: String,
+}
+
+/// Confidence of the block, signed by the light client and its operator
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attestation {
+	pub block_number: u32,
+	pub block_hash: H256,
+	pub confidence: f64,
+	/// Unix timestamp of the verification, in milliseconds
+	pub timestamp: u64,
+	/// Hex encoded protobuf of the libp2p public key
+	pub public_key: String,
+	/// Hex encoded signature of the libp2p identity key
+	pub signature: String,
+	pub operator: Operator,
+}
+
+fn message(block_number: u32, block_hash: H256, confidence: f64, timestamp: u64) -> Vec<u8> {
+	(
+		DOMAIN,
+		block_number,
+		block_hash,
+		confidence.to_bits(),
+		timestamp,
+	)
+		.encode()
+}
+
+impl Attestation {
+	/// Verifies both signatures, returning the peer ID of the signer
+	pub fn verify(&self) -> Result<PeerId> {
+		let message = message(
+			self.block_number,
+			self.block_hash,
+			self.confidence,
+			self.timestamp,
+		);
+
+		let public_key = hex::decode(&self.public_key).wrap_err("Invalid public key encoding")?;
+		let public_key =
+			PublicKey::try_decode_protobuf(&public_key).wrap_err("Invalid public key")?;
+		let signature = hex::decode(&self.signature).wrap_err("Invalid signature encoding")?;
+		if !public_key.verify(&message, &signature) {
+			return Err(eyre!("Invalid signature"));
+		}
+
+		let operator = sr25519::Public::from_ss58check(&self.operator.address)
+			.map_err(|error| eyre!("Invalid operator address: {error:?}"))?;
+		let signature: [u8; 64] = hex::decode(&self.operator.signature)
+			.wrap_err("Invalid operator signature encoding")?
+			.try_into()
+			.map_err(|_| eyre!("Invalid operator signature length"))?;
+		let signature = sr25519::Signature::from_raw(signature);
+		if !sr25519::Pair::verify(&signature, &message, &operator) {
+			return Err(eyre!("Invalid operator signature"));
+		}
+
+		Ok(public_key.to_peer_id())
+	}
+}
+
+/// Signs attestations with the identity and the keystore keys
+#[derive(Clone)]
+pub struct Signer {
+	keypair: Keypair,
+	operator: OperatorKeypair,
+}
+
+impl Signer {
+	pub fn new(keypair: Keypair, operator: OperatorKeypair) -> Self {
+		Signer { keypair, operator }
+	}
+
+	pub fn sign(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		confidence: f64,
+		timestamp: u64,
+	) -> Result<Attestation> {
+		let message = message(block_number, block_hash, confidence, timestamp);
+		let signature = self
+			.keypair
+			.sign(&message)
+			.wrap_err("Failed to sign attestation")?;
+		Ok(Attestation {
+			block_number,
+			block_hash,
+			confidence,
+			timestamp,
+			public_key: hex::encode(self.keypair.public().encode_protobuf()),
+			signature: hex::encode(signature),
+			operator: Operator {
+				address: self.operator.public_key().to_account_id().to_string(),
+				signature: hex::encode(self.operator.sign(&message).0),
+			},
+		})
+	}
+}
+
+/// Publishes attestation of each verified block to the gossipsub topic
+pub async fn gossip(
+	signer: Signer,
+	mut blocks: Subscriber<BlockVerified>,
+	p2p_client: p2p::Client,
+) {
+	info!("Starting attestation gossip...");
+	while let Some(block) = blocks.recv().await {
+		// confidence is not set for the skipped blocks
+		let Some(confidence) = block.confidence else {
+			continue;
+		};
+		let block_number = block.block_num;
+		let attestation = signer
+			.sign(
+				block_number,
+				block.header_hash,
+				confidence,
+				unix_timestamp_millis(),
+			)
+			.and_then(|attestation| Ok(serde_json::to_vec(&attestation)?));
+		let result = match attestation {
+			Ok(attestation) => p2p_client.publish_attestation(attestation).await,
+			Err(error) => Err(error),
+		};
+		match result {
+			Ok(()) => debug!(block_number, "Attestation published"),
+			Err(error) => warn!(block_number, "Failed to publish attestation: {error:#}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use subxt_signer::sr25519::dev;
+
+	#[test]
+	fn signed_attestation() {
+		let keypair = Keypair::generate_ed25519();
+		let peer_id = keypair.public().to_peer_id();
+		let signer = Signer::new(keypair, dev::alice());
+		let attestation = signer.sign(10, H256::repeat_byte(1), 99.0, 1000).unwrap();
+		assert_eq!(attestation.verify().unwrap(), peer_id);
+		assert_eq!(
+			attestation.operator.address,
+			"5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+		);
+
+		let mut forged = attestation.clone();
+		forged.confidence = 99.9;
+		assert!(forged.verify().is_err());
+
+		// signature of the other operator doesn't match the address
+		let other = Signer::new(Keypair::generate_ed25519(), dev::bob());
+		let mut forged = attestation.clone();
+		forged.operator.signature = other
+			.sign(10, H256::repeat_byte(1), 99.0, 1000)
+			.unwrap()
+			.operator
+			.signature;
+		assert!(forged.verify().is_err());
+	}
+}
@@ -0,0 +1,494 @@
+//! Sampling of the best chain blocks, and detection of the reorged blocks.
+//!
+//! # Flow
+//!
+//! * Subscribe to the best block headers of the connected RPC node
+//! * Sample each best block the same way as the finalized one, and store its hash and confidence separately from the finalized chain confidence
+//! * On each finalized block, compare its hash with the sampled best block hash
+//! * If sampled best block is not finalized (it was reorged out), remove its confidence and publish [`BlockReorged`] event
+//! * On each best block which doesn't descend from the previously processed one, walk back the new best chain to the fork point,
+//!   remove confidence of the abandoned branch blocks and publish [`ChainReorg`] event with the reorg depth
+//!
+//! # Notes
+//!
+//! Best chain confidence is an early indication of the data availability, and it is not stored for the reorged blocks.
+//! Only the last [`MAX_REORG_DEPTH`] processed best blocks are tracked, so the depth of the deeper reorgs is truncated.
+//! Finalized chain confidence is not affected by the best chain sampling.
+
+use avail_subxt::{primitives::Header, utils::H256};
+use codec::Encode;
+use color_eyre::{eyre::WrapErr, Result};
+use futures::StreamExt;
+use kate_recovery::{commitments, matrix::Dimensions};
+use serde::Serialize;
+use sp_core::blake2_256;
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+	data::{BestBlock, Database, Key},
+	event_bus::{EventBus, Subscriber},
+	network::{self, rpc},
+	shutdown::{Controller, ShutdownReason},
+	telemetry::{MetricCounter, MetricValue, Metrics},
+	types::{BlockVerified, ReloadableConfig},
+	utils::{calculate_confidence, extract_kate},
+};
+
+/// Delay before the best headers subscription is recreated
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+/// Maximum number of tracked processed best blocks, and of the blocks walked back on the new best chain
+pub const MAX_REORG_DEPTH: u32 = 64;
+
+/// Previously sampled best chain block, which is not finalized
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BlockReorged {
+	pub block_number: u32,
+	/// Hash of the sampled best chain block
+	pub best_hash: H256,
+	/// Hash of the finalized block with the same number
+	pub finalized_hash: H256,
+	/// Confidence achieved for the reorged block
+	pub best_confidence: f64,
+}
+
+/// Best chain reorganization, where the new best block doesn't descend from the previously processed one
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ChainReorg {
+	pub block_number: u32,
+	/// Hash of the new best block
+	pub block_hash: H256,
+	pub previous_block_number: u32,
+	/// Hash of the previously processed best block, which is abandoned
+	pub previous_block_hash: H256,
+	/// Number of the processed best blocks which are not in the new best chain
+	pub depth: u32,
+	/// Abandoned best blocks with removed confidence
+	pub rolled_back: Vec<u32>,
+}
+
+/// Hashes of the recently processed best blocks, by block number
+#[derive(Debug, Default)]
+pub struct ProcessedBlocks(BTreeMap<u32, H256>);
+
+impl ProcessedBlocks {
+	pub fn insert(&mut self, block_number: u32, block_hash: H256) {
+		self.0.insert(block_number, block_hash);
+		while self.0.len() > MAX_REORG_DEPTH as usize {
+			self.0.pop_first();
+		}
+	}
+
+	fn first_number(&self) -> Option<u32> {
+		self.0.first_key_value().map(|(&number, _)| number)
+	}
+
+	fn last(&self) -> Option<(u32, H256)> {
+		self.0
+			.last_key_value()
+			.map(|(&number, &hash)| (number, hash))
+	}
+}
+
+/// Checks if the new best block descends from the previously processed one.
+/// If it doesn't, walks back the new best chain (using `parent_of` to get the parent hash) to the fork point,
+/// removes confidence of the abandoned blocks, and returns the reorg.
+pub async fn check_chain_reorg<F, Fut>(
+	db: &impl Database,
+	processed: &mut ProcessedBlocks,
+	block_number: u32,
+	block_hash: H256,
+	parent_hash: H256,
+	parent_of: F,
+) -> Result<Option<ChainReorg>>
+where
+	F: Fn(H256) -> Fut,
+	Fut: Future<Output = Result<H256>>,
+{
+	let Some((previous_number, previous_hash)) = processed.last() else {
+		return Ok(None);
+	};
+	if processed.0.get(&block_number) == Some(&block_hash) {
+		return Ok(None);
+	}
+
+	// best heads stream can skip heights, so missing blocks are walked through until the tracked one matches
+	let lowest_number = processed.first_number().unwrap_or(previous_number);
+	let (mut number, mut hash) = (block_number.saturating_sub(1), parent_hash);
+	let fork = loop {
+		match processed.0.get(&number) {
+			Some(&known) if known == hash => break number,
+			// fork point is below the tracked blocks
+			None if number < lowest_number => break number,
+			_ if number == 0 || block_number - number >= MAX_REORG_DEPTH => {
+				if number > previous_number {
+					debug!(block_number, "Cannot reach previously processed best block");
+					return Ok(None);
+				}
+				break number;
+			},
+			_ => {
+				hash = parent_of(hash).await?;
+				number -= 1;
+			},
+		}
+	};
+
+	let depth = previous_number - fork;
+	if depth == 0 {
+		return Ok(None);
+	}
+
+	let mut rolled_back = vec![];
+	for number in fork + 1..=previous_number {
+		let best_block: Option<BestBlock> = db.get(Key::BestBlock(number))?;
+		if best_block.is_some() {
+			db.delete(Key::BestBlock(number))?;
+			rolled_back.push(number);
+		}
+	}
+	processed.0.retain(|&number, _| number <= fork);
+
+	Ok(Some(ChainReorg {
+		block_number,
+		block_hash,
+		previous_block_number: previous_number,
+		previous_block_hash: previous_hash,
+		depth,
+		rolled_back,
+	}))
+}
+
+/// Samples best chain block, storing its hash and number of verified cells.
+/// Returns achieved confidence, or `None` if block is skipped or not all sampled cells are verified.
+pub async fn process_block(
+	db: &impl Database,
+	network_client: &impl network::Client,
+	metrics: &Arc<impl Metrics>,
+	confidence: f64,
+	header: &Header,
+) -> Result<Option<f64>> {
+	let block_number = header.number;
+	let block_hash: H256 = Encode::using_encoded(header, blake2_256).into();
+
+	let Some((rows, cols, _, commitment)) = extract_kate(&header.extension) else {
+		debug!(block_number, "Skipping best block without header extension");
+		return Ok(None);
+	};
+	let Some(dimensions) = Dimensions::new(rows, cols).filter(|d| d.cols().get() > 2) else {
+		debug!(
+			block_number,
+			"Skipping best block with dimensions {rows}x{cols}"
+		);
+		return Ok(None);
+	};
+
+	let commitments = commitments::from_slice(&commitment)?;
+	let positions =
+		rpc::generate_random_cells(dimensions, rpc::cell_count_for_confidence(confidence));
+	let (fetched, unfetched, _) = network_client
+		.fetch_verified(
+			block_number,
+			block_hash,
+			dimensions,
+			&commitments,
+			&positions,
+			None,
+		)
+		.await?;
+
+	if !unfetched.is_empty() {
+		warn!(
+			block_number,
+			"Failed to fetch {} cells of the best block",
+			unfetched.len()
+		);
+		return Ok(None);
+	}
+
+	let verified = fetched.len() as u32;
+	db.put(
+		Key::BestBlock(block_number),
+		BestBlock {
+			hash: block_hash,
+			verified,
+		},
+	)
+	.wrap_err("Failed to store best block")?;
+
+	let confidence = calculate_confidence(verified);
+	info!(block_number, confidence, "Best block confidence achieved");
+	metrics
+		.record(MetricValue::BestBlockHeight(block_number))
+		.await;
+	metrics
+		.record(MetricValue::BestBlockConfidence(confidence))
+		.await;
+	Ok(Some(confidence))
+}
+
+/// Compares finalized block with the sampled best block of the same number.
+/// If hashes differ, best block confidence is removed and the reorg is returned.
+pub fn check_reorg(db: &impl Database, block: &BlockVerified) -> Result<Option<BlockReorged>> {
+	let best_block: Option<BestBlock> = db.get(Key::BestBlock(block.block_num))?;
+	let Some(best_block) = best_block.filter(|best| best.hash != block.header_hash) else {
+		return Ok(None);
+	};
+	db.delete(Key::BestBlock(block.block_num))?;
+	Ok(Some(BlockReorged {
+		block_number: block.block_num,
+		best_hash: best_block.hash,
+		finalized_hash: block.header_hash,
+		best_confidence: calculate_confidence(best_block.verified),
+	}))
+}
+
+/// Runs best chain sampling and reorg detection, until the shutdown is triggered
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+	db: impl Database,
+	rpc_client: rpc::Client,
+	network_client: impl network::Client,
+	metrics: Arc<impl Metrics>,
+	reloadable_config: watch::Receiver<ReloadableConfig>,
+	mut finalized_blocks: Subscriber<BlockVerified>,
+	reorgs: EventBus<BlockReorged>,
+	chain_reorgs: EventBus<ChainReorg>,
+	shutdown: Controller<ShutdownReason>,
+) {
+	info!("Starting best chain sampling...");
+
+	let mut processed = ProcessedBlocks::default();
+	let rpc = &rpc_client;
+	let parent_of = move |hash| async move {
+		let header = rpc.get_header_by_hash(hash).await?;
+		Ok(header.parent_hash)
+	};
+
+	loop {
+		let headers = match rpc_client.best_headers_stream().await {
+			Ok(headers) => headers,
+			Err(error) => {
+				warn!("Cannot subscribe to best headers: {error:#}");
+				if !resubscribe_delay(&shutdown).await {
+					return;
+				}
+				continue;
+			},
+		};
+		futures::pin_mut!(headers);
+
+		loop {
+			tokio::select! {
+				header = headers.next() => match header {
+					Some(Ok(header)) => {
+						let block_hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+						match check_chain_reorg(&db, &mut processed, header.number, block_hash, header.parent_hash, parent_of).await {
+							Ok(Some(reorg)) => {
+								warn!(
+									block_number = reorg.block_number,
+									depth = reorg.depth,
+									previous_block_hash = ?reorg.previous_block_hash,
+									"Best chain reorganized"
+								);
+								metrics.count(MetricCounter::ChainReorgs).await;
+								chain_reorgs.send(reorg);
+							},
+							Ok(None) => (),
+							Err(error) => error!(block_number = header.number, "Cannot check best chain reorg: {error:#}"),
+						}
+						processed.insert(header.number, block_hash);
+
+						let confidence = reloadable_config.borrow().confidence;
+						if let Err(error) = process_block(&db, &network_client, &metrics, confidence, &header).await {
+							error!(block_number = header.number, "Cannot process best block: {error:#}");
+						}
+					},
+					Some(Err(error)) => {
+						warn!("Best headers subscription failed: {error:#}");
+						break;
+					},
+					None => break,
+				},
+				block = finalized_blocks.recv() => {
+					let Some(block) = block else {
+						return;
+					};
+					match check_reorg(&db, &block) {
+						Ok(Some(reorg)) => {
+							warn!(
+								block_number = reorg.block_number,
+								best_hash = ?reorg.best_hash,
+								finalized_hash = ?reorg.finalized_hash,
+								"Sampled best block is reorged out"
+							);
+							metrics.count(MetricCounter::BlockReorgs).await;
+							reorgs.send(reorg);
+						},
+						Ok(None) => (),
+						Err(error) => error!(block_number = block.block_num, "Cannot check reorg: {error:#}"),
+					}
+				},
+				_ = shutdown.triggered_shutdown() => {
+					info!("Shutdown triggered, stopping best chain sampling");
+					return;
+				},
+			}
+		}
+
+		if !resubscribe_delay(&shutdown).await {
+			return;
+		}
+	}
+}
+
+/// Waits before resubscribing to the best headers, returns `false` if shutdown is triggered meanwhile
+async fn resubscribe_delay(shutdown: &Controller<ShutdownReason>) -> bool {
+	tokio::select! {
+		_ = tokio::time::sleep(RESUBSCRIBE_DELAY) => true,
+		_ = shutdown.triggered_shutdown() => {
+			info!("Shutdown triggered, stopping best chain sampling");
+			false
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::mem_db::MemoryDB;
+	use color_eyre::eyre::eyre;
+	use std::collections::HashMap;
+
+	fn finalized(block_num: u32, header_hash: H256) -> BlockVerified {
+		BlockVerified {
+			header_hash,
+			block_num,
+			extension: None,
+			confidence: None,
+		}
+	}
+
+	#[test]
+	fn reorg_detection() {
+		let db = MemoryDB::default();
+		let best_hash = H256::repeat_byte(1);
+		db.put(
+			Key::BestBlock(10),
+			BestBlock {
+				hash: best_hash,
+				verified: 10,
+			},
+		)
+		.unwrap();
+
+		assert_eq!(check_reorg(&db, &finalized(10, best_hash)).unwrap(), None);
+		assert_eq!(check_reorg(&db, &finalized(11, best_hash)).unwrap(), None);
+
+		let finalized_hash = H256::repeat_byte(2);
+		let reorg = check_reorg(&db, &finalized(10, finalized_hash)).unwrap();
+		assert_eq!(
+			reorg,
+			Some(BlockReorged {
+				block_number: 10,
+				best_hash,
+				finalized_hash,
+				best_confidence: calculate_confidence(10),
+			})
+		);
+		let best_block: Option<BestBlock> = db.get(Key::BestBlock(10)).unwrap();
+		assert_eq!(best_block, None);
+	}
+
+	#[tokio::test]
+	async fn chain_reorg_detection() {
+		let db = MemoryDB::default();
+		let (a, b) = (|n| H256::repeat_byte(n), |n| H256::repeat_byte(0x10 + n));
+		let mut processed = ProcessedBlocks::default();
+		for number in 1..=5 {
+			processed.insert(number as u32, a(number));
+		}
+		for number in [4, 5] {
+			let best_block = BestBlock {
+				hash: a(number as u8),
+				verified: 10,
+			};
+			db.put(Key::BestBlock(number), best_block).unwrap();
+		}
+		let parents = HashMap::from([(b(4), a(3)), (b(7), b(6)), (b(6), b(5))]);
+		let parent_of = |hash| {
+			let parent = parents.get(&hash).copied().ok_or(eyre!("Unknown block"));
+			async move { parent }
+		};
+
+		// block 5 on the branch which forks from block 3
+		let reorg = check_chain_reorg(&db, &mut processed, 5, b(5), b(4), parent_of)
+			.await
+			.unwrap();
+		assert_eq!(
+			reorg,
+			Some(ChainReorg {
+				block_number: 5,
+				block_hash: b(5),
+				previous_block_number: 5,
+				previous_block_hash: a(5),
+				depth: 2,
+				rolled_back: vec![4, 5],
+			})
+		);
+		let best_block: Option<BestBlock> = db.get(Key::BestBlock(4)).unwrap();
+		assert_eq!(best_block, None);
+		processed.insert(5, b(5));
+
+		// descendants of the new best block, with the gap
+		let reorg = check_chain_reorg(&db, &mut processed, 6, b(6), b(5), parent_of).await;
+		assert_eq!(reorg.unwrap(), None);
+		processed.insert(6, b(6));
+		let reorg = check_chain_reorg(&db, &mut processed, 8, b(8), b(7), parent_of).await;
+		assert_eq!(reorg.unwrap(), None);
+	}
+
+	#[tokio::test]
+	async fn chain_reorg_detection_processed_gap() {
+		let db = MemoryDB::default();
+		let (a, b) = (|n| H256::repeat_byte(n), |n| H256::repeat_byte(0x10 + n));
+		let mut processed = ProcessedBlocks::default();
+		// block 5 is skipped by the best heads stream
+		for number in [1, 2, 3, 4, 6] {
+			processed.insert(number as u32, a(number));
+		}
+		for number in [3, 4, 6] {
+			let best_block = BestBlock {
+				hash: a(number as u8),
+				verified: 10,
+			};
+			db.put(Key::BestBlock(number), best_block).unwrap();
+		}
+		let parents = HashMap::from([(b(5), b(4)), (b(4), b(3)), (b(3), a(2))]);
+		let parent_of = |hash| {
+			let parent = parents.get(&hash).copied().ok_or(eyre!("Unknown block"));
+			async move { parent }
+		};
+
+		// block 6 on the branch which forks from block 2, walked through the gap
+		let reorg = check_chain_reorg(&db, &mut processed, 6, b(6), b(5), parent_of)
+			.await
+			.unwrap();
+		assert_eq!(
+			reorg,
+			Some(ChainReorg {
+				block_number: 6,
+				block_hash: b(6),
+				previous_block_number: 6,
+				previous_block_hash: a(6),
+				depth: 4,
+				rolled_back: vec![3, 4, 6],
+			})
+		);
+		for number in [3, 4, 6] {
+			let best_block: Option<BestBlock> = db.get(Key::BestBlock(number)).unwrap();
+			assert_eq!(best_block, None);
+		}
+	}
+}
@@ -33,9 +33,18 @@ async fn main() -> Result<()> {
 	});
 
 	let shutdown = Controller::new();
-	let (rpc_client, _, subscriptions) =
-		rpc::init(db, state, &[command_args.url], "DEV", retry_cfg, shutdown).await?;
-	tokio::spawn(subscriptions.run());
+	let (rpc_client, _, mut subscriptions) = rpc::init(
+		db,
+		state,
+		&[command_args.url],
+		"DEV",
+		retry_cfg,
+		None,
+		None,
+		shutdown,
+	)
+	.await?;
+	tokio::spawn(async move { subscriptions.run().await });
 
 	let mut correct: bool = true;
 
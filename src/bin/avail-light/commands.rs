@@ -0,0 +1,215 @@
+//! Implementation of the operational subcommands, which run to completion without starting the light client.
+
+use avail_light::{
+	data::{
+		backup,
+		export::{self, ExportFormat},
+		inspect,
+		rocks_db::RocksDB,
+	},
+	doctor,
+	keystore::{self, Keystore, Secrets},
+	network::p2p,
+	replay, status,
+	types::{template, CliOpts, IdentityConfig, RuntimeConfig, SecretKey},
+};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use std::{
+	fs::{self, File},
+	io::{BufReader, BufWriter},
+	path::Path,
+	str::FromStr,
+};
+use subxt_signer::SecretUri;
+
+fn load_config(opts: &CliOpts) -> Result<RuntimeConfig> {
+	let mut cfg: RuntimeConfig = RuntimeConfig::default();
+	cfg.load_runtime_config(opts)?;
+	Ok(cfg)
+}
+
+pub fn generate_config(opts: &CliOpts, output: &str) -> Result<()> {
+	if Path::new(output).exists() {
+		return Err(eyre!("Configuration file {output} already exists"));
+	}
+	let mut cfg = RuntimeConfig::default();
+	if let Some(network) = &opts.network {
+		cfg.apply_network_preset(network)?;
+	}
+	fs::write(output, template::commented_config(&cfg)?)
+		.wrap_err(format!("Failed to write configuration file {output}"))?;
+	println!("Configuration written to {output}");
+	Ok(())
+}
+
+pub fn check_config(opts: &CliOpts) -> Result<()> {
+	load_config(opts)?.validate()?;
+	println!("Configuration is valid");
+	Ok(())
+}
+
+pub fn inspect_db(opts: &CliOpts, path: Option<&str>, json: bool) -> Result<()> {
+	let cfg = load_config(opts)?;
+	let path = path.unwrap_or(&cfg.avail_path);
+
+	let db = inspect::open_read_only(path)?;
+	let inspection = inspect::inspect(&db, path)?;
+	if json {
+		println!("{}", serde_json::to_string_pretty(&inspection)?);
+	} else {
+		println!("{inspection}");
+	}
+	Ok(())
+}
+
+pub fn export_db(opts: &CliOpts, path: Option<&str>, output: &str) -> Result<()> {
+	let cfg = load_config(opts)?;
+	let path = path.unwrap_or(&cfg.avail_path);
+
+	let db = inspect::open_read_only(path)?;
+	let file = File::create(output).wrap_err(format!("Failed to create export file {output}"))?;
+	let count = backup::export(&db, BufWriter::new(file))?;
+	println!("Exported {count} entries from {path} to {output}");
+	Ok(())
+}
+
+pub fn import_db(opts: &CliOpts, path: Option<&str>, input: &str) -> Result<()> {
+	let cfg = load_config(opts)?;
+	let path = path.unwrap_or(&cfg.avail_path);
+
+	let file = File::open(input).wrap_err(format!("Failed to open import file {input}"))?;
+	let (_, db) = RocksDB::open(path)?;
+	let count = backup::import(&db, BufReader::new(file))?;
+	println!("Imported {count} entries from {input} to {path}");
+	Ok(())
+}
+
+pub fn export_app_data(
+	opts: &CliOpts,
+	path: Option<&str>,
+	app_id: u32,
+	(from, to): (u32, u32),
+	format: ExportFormat,
+	output: &str,
+) -> Result<()> {
+	let cfg = load_config(opts)?;
+	let path = path.unwrap_or(&cfg.avail_path);
+
+	let db = inspect::open_read_only(path)?;
+	let file = File::create(output).wrap_err(format!("Failed to create export file {output}"))?;
+	let count = export::export_app_data(&db, app_id, from, to, format, BufWriter::new(file))?;
+	println!("Exported app data of {count} blocks from {path} to {output}");
+	Ok(())
+}
+
+pub fn replay(input: &str, block: Option<u32>) -> Result<()> {
+	let file = File::open(input).wrap_err(format!("Failed to open recording file {input}"))?;
+	let pp = kate_recovery::couscous::public_params();
+
+	let (mut replayed, mut failed) = (0, 0);
+	for recording in replay::read(BufReader::new(file)) {
+		let recording = recording?;
+		if block.is_some_and(|block| block != recording.block_number) {
+			continue;
+		}
+		let replay = replay::replay(&pp, &recording)?;
+		replayed += 1;
+		if !replay.failed.is_empty() {
+			failed += 1;
+		}
+		println!("{}", serde_json::to_string(&replay)?);
+	}
+	println!("Replayed {replayed} blocks, {failed} with failed cell verification");
+	Ok(())
+}
+
+pub async fn doctor(opts: &CliOpts) -> Result<()> {
+	let cfg = load_config(opts)?;
+	let report = doctor::run(&cfg).await;
+	println!("{report}");
+	if !report.is_passed() {
+		return Err(eyre!("Self-test failed"));
+	}
+	Ok(())
+}
+
+pub async fn status(opts: &CliOpts) -> Result<()> {
+	for (index, cfg) in load_config(opts)?.network_configs()?.iter().enumerate() {
+		if index > 0 {
+			println!();
+		}
+		println!("{}", status::run(cfg).await?);
+	}
+	Ok(())
+}
+
+fn print_identity(opts: &CliOpts) -> Result<()> {
+	let identity_cfg = IdentityConfig::load_or_init(
+		&opts.identity,
+		opts.avail_secret(),
+		opts.keystore_password().as_deref(),
+	)?;
+	println!("Avail address: {}", identity_cfg.avail_address);
+	println!("Avail public key: {}", identity_cfg.avail_public_key);
+
+	let mut cfg = load_config(opts)?;
+	if let (None, Some(key)) = (&cfg.secret_key, identity_cfg.libp2p_secret_key) {
+		cfg.secret_key = Some(SecretKey::Key { key });
+	}
+	if cfg.secret_key.is_some() {
+		let (_, peer_id) = p2p::keypair(&(&cfg).into())?;
+		println!("Peer ID: {peer_id}");
+	} else {
+		println!("Peer ID: random (libp2p secret key is not configured)");
+	}
+	Ok(())
+}
+
+pub fn generate_key(opts: &CliOpts, force: bool) -> Result<()> {
+	if Path::new(&opts.identity).exists() && !force {
+		return Err(eyre!(
+			"Identity file {} already exists, use --force to overwrite it",
+			opts.identity
+		));
+	}
+	let password = opts.keystore_password();
+	let mut keystore = Keystore::new(&opts.identity);
+	keystore.secrets = Secrets {
+		avail_secret_uri: Some(keystore::generate_avail_secret_uri()?),
+		avail_secret_seed_phrase: None,
+		libp2p_secret_key: Some(keystore::generate_libp2p_secret_key()),
+	};
+	keystore.store(password.as_deref())?;
+	println!("Keystore written to {}", opts.identity);
+	print_identity(opts)
+}
+
+pub fn import_key(opts: &CliOpts, suri: Option<&str>, libp2p_key: Option<&str>) -> Result<()> {
+	if suri.is_none() && libp2p_key.is_none() {
+		return Err(eyre!("Either --suri or --libp2p-key must be set"));
+	}
+	let password = opts.keystore_password();
+	let mut keystore = Keystore::load(&opts.identity, password.as_deref())?;
+	if let Some(suri) = suri {
+		SecretUri::from_str(suri).wrap_err("Invalid Avail secret URI")?;
+		keystore.secrets.avail_secret_uri = Some(suri.to_string());
+		keystore.secrets.avail_secret_seed_phrase = None;
+	}
+	if let Some(key) = libp2p_key {
+		keystore::libp2p_keypair(key)?;
+		keystore.secrets.libp2p_secret_key = Some(key.to_string());
+	}
+	keystore.store(password.as_deref())?;
+	println!("Keystore written to {}", opts.identity);
+	print_identity(opts)
+}
+
+pub fn show_key(opts: &CliOpts) -> Result<()> {
+	if !Path::new(&opts.identity).exists() {
+		return Err(eyre!("Identity file {} doesn't exist", opts.identity));
+	}
+	print_identity(opts)
+}
@@ -0,0 +1,429 @@
+#![doc = include_str!("../../../README.md")]
+
+#[cfg(windows)]
+use avail_light::types::ServiceCommand;
+use avail_light::{
+	node::{LogLevelReload, Node, CLIENT_ROLE},
+	shutdown::{Controller, ShutdownReason},
+	types::{
+		CliOpts, Command, ConfigCommand, DbCommand, IdentityConfig, KeyCommand, RuntimeConfig,
+		SecretKey,
+	},
+};
+use clap::Parser;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use std::{fs, path::Path, sync::Arc, time::Duration};
+use tokio::sync::oneshot;
+use tracing::{error, info, metadata::ParseLevelError, warn, Level, Subscriber};
+use tracing_subscriber::{fmt::format, EnvFilter, FmtSubscriber};
+
+mod commands;
+#[cfg(windows)]
+mod service;
+
+#[cfg(not(target_env = "msvc"))]
+use tikv_jemallocator::Jemalloc;
+
+#[cfg(not(target_env = "msvc"))]
+#[global_allocator]
+static GLOBAL: Jemalloc = Jemalloc;
+
+/// Light Client for Avail Blockchain
+
+fn json_subscriber(log_level: Level) -> (impl Subscriber + Send + Sync, LogLevelReload) {
+	let builder = FmtSubscriber::builder()
+		.json()
+		.with_env_filter(EnvFilter::new(format!("avail_light={log_level}")))
+		.with_span_events(format::FmtSpan::CLOSE)
+		.with_filter_reloading();
+	let handle = builder.reload_handle();
+	let reload: LogLevelReload = Box::new(move |log_level| {
+		handle
+			.reload(EnvFilter::new(format!("avail_light={log_level}")))
+			.wrap_err("Unable to reload log level")
+	});
+	(builder.finish(), reload)
+}
+
+fn default_subscriber(log_level: Level) -> (impl Subscriber + Send + Sync, LogLevelReload) {
+	let builder = FmtSubscriber::builder()
+		.with_env_filter(EnvFilter::new(format!("avail_light={log_level}")))
+		.with_span_events(format::FmtSpan::CLOSE)
+		.with_filter_reloading();
+	let handle = builder.reload_handle();
+	let reload: LogLevelReload = Box::new(move |log_level| {
+		handle
+			.reload(EnvFilter::new(format!("avail_light={log_level}")))
+			.wrap_err("Unable to reload log level")
+	});
+	(builder.finish(), reload)
+}
+
+fn parse_log_level(log_level: &str, default: Level) -> (Level, Option<ParseLevelError>) {
+	log_level
+		.to_uppercase()
+		.parse::<Level>()
+		.map(|log_level| (log_level, None))
+		.unwrap_or_else(|parse_err| (default, Some(parse_err)))
+}
+
+/// Loads the configuration from the configuration file and CLI parameters, on configuration reload
+fn load_config() -> Result<RuntimeConfig> {
+	let opts = CliOpts::try_parse()?;
+	let mut cfg: RuntimeConfig = RuntimeConfig::default();
+	cfg.load_runtime_config(&opts)?;
+	Ok(cfg)
+}
+
+async fn run(opts: CliOpts, shutdown: Controller<ShutdownReason>) -> Result<Vec<Node>> {
+	let mut cfg: RuntimeConfig = RuntimeConfig::default();
+	cfg.load_runtime_config(&opts)
+		.and_then(|_| cfg.validate())
+		.map_err(|error| shutdown.fail(ShutdownReason::ConfigError, error))?;
+
+	let (log_level, parse_error) = parse_log_level(&cfg.log_level, Level::INFO);
+
+	let log_level_reload = if cfg.log_format_json {
+		let (subscriber, log_level_reload) = json_subscriber(log_level);
+		tracing::subscriber::set_global_default(subscriber).expect("global json subscriber is set");
+		log_level_reload
+	} else {
+		let (subscriber, log_level_reload) = default_subscriber(log_level);
+		tracing::subscriber::set_global_default(subscriber)
+			.expect("global default subscriber is set");
+		log_level_reload
+	};
+	// log level reload is shared by the networks in the multi-network mode
+	let log_level_reload = Arc::new(log_level_reload);
+
+	if opts.avail_passphrase.is_some() {
+		warn!("Using deprecated CLI parameter `--avail-passphrase`, use `--avail-suri` instead.");
+	}
+
+	let load_identity = || {
+		IdentityConfig::load_or_init(
+			&opts.identity,
+			opts.avail_secret(),
+			opts.keystore_password().as_deref(),
+		)
+		.map_err(|error| shutdown.fail(ShutdownReason::ConfigError, error))
+	};
+	let identity_cfg = load_identity()?;
+	info!("Identity loaded from {}", &opts.identity);
+
+	let version = clap::crate_version!();
+	info!("Running Avail light client version: {version}.");
+	info!(
+		"Avail ss58 address: {}, public key: {}",
+		&identity_cfg.avail_address, &identity_cfg.avail_public_key
+	);
+
+	if let Some(error) = parse_error {
+		warn!("Using default log level: {}", error);
+	}
+
+	if opts.clean && Path::new(&cfg.avail_path).exists() {
+		info!("Cleaning up local state directory");
+		fs::remove_dir_all(&cfg.avail_path).wrap_err("Failed to remove local state directory")?;
+	}
+
+	tokio::spawn(shutdown_watchdog(
+		shutdown.clone(),
+		Duration::from_secs(cfg.shutdown_grace_period),
+	));
+
+	let network_configs = cfg
+		.network_configs()
+		.map_err(|error| shutdown.fail(ShutdownReason::ConfigError, error))?;
+	let mut nodes = Vec::with_capacity(network_configs.len());
+	for (index, mut cfg) in network_configs.into_iter().enumerate() {
+		let identity_cfg = load_identity()?;
+
+		// Configured libp2p secret key takes precedence over the one stored in the keystore
+		if cfg.secret_key.is_none() {
+			if let Some(key) = identity_cfg.libp2p_secret_key.clone() {
+				info!("Using libp2p secret key from the keystore");
+				cfg.secret_key = Some(SecretKey::Key { key });
+			}
+		}
+
+		let client_role = if cfg.is_fat_client() {
+			info!("Fat client mode");
+			"fatnode"
+		} else {
+			CLIENT_ROLE
+		};
+
+		let network = cfg.http_server_prefix.as_deref().unwrap_or("default");
+		info!("Starting {network} network. Role: {client_role}.");
+		info!("Using config: {cfg:?}");
+
+		// reloaded configuration of the network is derived from the reloaded base configuration
+		let config_loader = Box::new(move || {
+			load_config()?
+				.network_configs()?
+				.into_iter()
+				.nth(index)
+				.ok_or_else(|| eyre!("Network is removed from the configuration"))
+		});
+		let log_level_reload = log_level_reload.clone();
+
+		let node = Node::builder(cfg, identity_cfg)
+			.shutdown(shutdown.clone())
+			.client_role(client_role)
+			.config_loader(config_loader)
+			.log_level_reload(Box::new(move |log_level| log_level_reload(log_level)))
+			.start()
+			.await?;
+
+		#[cfg(unix)]
+		tokio::task::spawn(shutdown.with_cancel(hangup_signal(node.config_reload_sender.clone())));
+
+		nodes.push(node);
+	}
+
+	Ok(nodes)
+}
+
+/// Requests configuration reload on each SIGHUP signal
+#[cfg(unix)]
+async fn hangup_signal(
+	config_reload_sender: avail_light::types::ConfigReloadSender,
+) -> std::io::Result<()> {
+	let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+	while hangup.recv().await.is_some() {
+		info!("Received SIGHUP, reloading configuration...");
+		let (response_sender, _) = oneshot::channel();
+		if config_reload_sender.send(response_sender).is_err() {
+			break;
+		}
+	}
+	Ok(())
+}
+
+fn install_panic_hooks(shutdown: Controller<ShutdownReason>) -> Result<()> {
+	// initialize color-eyre hooks
+	let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
+		.display_location_section(true)
+		.display_env_section(true)
+		.into_hooks();
+
+	// install hook as global handler
+	eyre_hook.install()?;
+
+	std::panic::set_hook(Box::new(move |panic_info| {
+		// trigger shutdown to stop other tasks if panic occurs
+		let _ = shutdown.trigger_shutdown(ShutdownReason::InternalPanic(
+			"Panic occurred, shuting down".to_string(),
+		));
+
+		let msg = format!("{}", panic_hook.panic_report(panic_info));
+		error!("Error: {}", strip_ansi_escapes::strip_str(msg));
+
+		#[cfg(debug_assertions)]
+		{
+			// better-panic stacktrace that is only enabled when debugging
+			better_panic::Settings::auto()
+				.most_recent_first(false)
+				.lineno_suffix(true)
+				.verbosity(better_panic::Verbosity::Medium)
+				.create_panic_handler()(panic_info);
+		}
+	}));
+	Ok(())
+}
+
+/// Waits for any of the default termination signals, returning the name of the received signal.
+///
+/// On Unix-based systems, these signals are Ctrl-C (SIGINT) or SIGTERM,
+/// and on Windows, they are Ctrl-C, Ctrl-Close, Ctrl-Shutdown.
+async fn user_signal() -> &'static str {
+	let ctrl_c = tokio::signal::ctrl_c();
+	#[cfg(all(unix, not(windows)))]
+	{
+		let sig = async {
+			let mut os_sig =
+				tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+			os_sig.recv().await;
+			std::io::Result::Ok(())
+		};
+
+		tokio::select! {
+			_ = ctrl_c => "SIGINT",
+			_ = sig => "SIGTERM",
+		}
+	}
+
+	#[cfg(all(not(unix), windows))]
+	{
+		let ctrl_close = async {
+			let mut sig = tokio::signal::windows::ctrl_close()?;
+			sig.recv().await;
+			std::io::Result::Ok(())
+		};
+		let ctrl_shutdown = async {
+			let mut sig = tokio::signal::windows::ctrl_shutdown()?;
+			sig.recv().await;
+			std::io::Result::Ok(())
+		};
+		tokio::select! {
+			_ = ctrl_c => "CTRL_C",
+			_ = ctrl_close => "CTRL_CLOSE",
+			_ = ctrl_shutdown => "CTRL_SHUTDOWN",
+		}
+	}
+}
+
+/// Forces the process exit if the shutdown is not completed within the grace period,
+/// or if another shutdown signal is received in the meantime
+async fn shutdown_watchdog(shutdown: Controller<ShutdownReason>, grace_period: Duration) {
+	let reason = shutdown.triggered_shutdown().await;
+	info!(?grace_period, "Shutting down gracefully: {reason}");
+	tokio::select! {
+		_ = tokio::time::sleep(grace_period) => {
+			error!(?grace_period, "Shutdown grace period exceeded, forcing exit");
+		},
+		signal = user_signal() => {
+			error!("Received {signal} during the graceful shutdown, forcing exit");
+		},
+	}
+	std::process::exit(1);
+}
+
+#[tokio::main]
+pub async fn main() -> Result<()> {
+	let opts = CliOpts::parse();
+
+	if opts.dry_run {
+		return commands::check_config(&opts);
+	}
+
+	match &opts.command {
+		None | Some(Command::Run) => (),
+		#[cfg(feature = "crawl")]
+		Some(Command::Crawl) => (),
+		Some(Command::Config(ConfigCommand::Generate { output })) => {
+			return commands::generate_config(&opts, output)
+		},
+		Some(Command::Config(ConfigCommand::Check)) => return commands::check_config(&opts),
+		Some(Command::Db(DbCommand::Inspect { path, json })) => {
+			return commands::inspect_db(&opts, path.as_deref(), *json)
+		},
+		Some(Command::Db(DbCommand::Export { path, output })) => {
+			return commands::export_db(&opts, path.as_deref(), output)
+		},
+		Some(Command::Db(DbCommand::Import { path, input })) => {
+			return commands::import_db(&opts, path.as_deref(), input)
+		},
+		Some(Command::Key(KeyCommand::Generate { force })) => {
+			return commands::generate_key(&opts, *force)
+		},
+		Some(Command::Key(KeyCommand::Import { suri, libp2p_key })) => {
+			return commands::import_key(&opts, suri.as_deref(), libp2p_key.as_deref())
+		},
+		Some(Command::Key(KeyCommand::Show)) => return commands::show_key(&opts),
+		Some(Command::Replay { input, block }) => return commands::replay(input, *block),
+		Some(Command::Doctor) => return commands::doctor(&opts).await,
+		Some(Command::Status) => return commands::status(&opts).await,
+		Some(Command::Export {
+			app_id,
+			from,
+			to,
+			format,
+			output,
+			path,
+		}) => {
+			return commands::export_app_data(
+				&opts,
+				path.as_deref(),
+				*app_id,
+				(*from, *to),
+				*format,
+				output,
+			)
+		},
+		#[cfg(windows)]
+		Some(Command::Service(ServiceCommand::Install)) => return service::install(),
+		#[cfg(windows)]
+		Some(Command::Service(ServiceCommand::Uninstall)) => return service::uninstall(),
+		#[cfg(windows)]
+		Some(Command::Service(ServiceCommand::Run)) => {
+			let runtime = tokio::runtime::Handle::current();
+			return tokio::task::block_in_place(|| {
+				service::dispatch(Box::new(move |shutdown| {
+					runtime
+						.block_on(run_until_stopped(opts, shutdown))
+						.unwrap_or_else(|error| {
+							service::report_event(&format!("{error:#}"), true);
+							1
+						})
+				}))
+			});
+		},
+	}
+
+	let exit_code = run_until_stopped(opts, Controller::new()).await?;
+	if exit_code != 0 {
+		std::process::exit(exit_code);
+	}
+	Ok(())
+}
+
+/// Runs the light client until the shutdown is completed, returning the process exit code
+async fn run_until_stopped(opts: CliOpts, shutdown: Controller<ShutdownReason>) -> Result<i32> {
+	// install custom panic hooks
+	install_panic_hooks(shutdown.clone())?;
+
+	// spawn a task to watch for ctrl-c signals from user to trigger the shutdown
+	tokio::spawn({
+		let shutdown = shutdown.clone();
+		async move {
+			let signal = user_signal().await;
+			info!("Received {signal}, shutting down...");
+			let _ = shutdown.trigger_shutdown(ShutdownReason::Signal(format!("{signal} received")));
+		}
+	});
+
+	let nodes = match run(opts, shutdown.clone()).await {
+		Ok(nodes) => nodes,
+		Err(error) => {
+			error!("{error:#}");
+			let error = shutdown.fail(ShutdownReason::Error, error);
+			let exit_code = shutdown
+				.shutdown_reason()
+				.map_or(1, |reason| reason.exit_code());
+			eprintln!(
+				"Error: {:?}",
+				error.wrap_err("Starting Light Client failed")
+			);
+			return Ok(exit_code);
+		},
+	};
+
+	let reason = shutdown.completed_shutdown().await;
+	let timed_out = nodes
+		.iter()
+		.flat_map(Node::timed_out_phases)
+		.collect::<Vec<_>>();
+	if !timed_out.is_empty() {
+		error!(
+			?timed_out,
+			"Shutdown phases exceeded deadline, forcing exit"
+		);
+		return Ok(1);
+	}
+	let exit_code = reason.exit_code();
+	if exit_code == 0 {
+		info!("Light Client stopped: {reason}");
+		return Ok(0);
+	}
+
+	// we are not logging error here since expectation is
+	// to log terminating condition before sending message to this channel
+	let error = eyre!(reason).wrap_err("Running Light Client encountered an error");
+	eprintln!("Error: {error:?}");
+	Ok(exit_code)
+}
@@ -0,0 +1,271 @@
+//! Windows service support.
+//!
+//! Service is installed with the `service install` command, and started by the service control manager with the `service run` command.
+//! Global options used on install (e.g. `--config` and `--identity`) are passed to the service.
+//! Stop and system shutdown requests trigger the light client shutdown.
+//! Service start, stop and failures are reported to the Windows event log, under the service name source.
+
+use avail_light::shutdown::{Controller, ShutdownReason};
+use color_eyre::{eyre::eyre, Result};
+use std::{
+	ffi::c_void,
+	io, ptr,
+	sync::{Mutex, OnceLock},
+};
+use windows_sys::Win32::{
+	Foundation::{ERROR_SERVICE_SPECIFIC_ERROR, NO_ERROR},
+	System::{
+		EventLog::{
+			DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+			EVENTLOG_INFORMATION_TYPE,
+		},
+		Services::{
+			CloseServiceHandle, CreateServiceW, DeleteService, OpenSCManagerW, OpenServiceW,
+			RegisterServiceCtrlHandlerExW, SetServiceStatus, StartServiceCtrlDispatcherW,
+			SC_MANAGER_ALL_ACCESS, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP,
+			SERVICE_ALL_ACCESS, SERVICE_AUTO_START, SERVICE_CONTROL_INTERROGATE,
+			SERVICE_CONTROL_SHUTDOWN, SERVICE_CONTROL_STOP, SERVICE_ERROR_NORMAL, SERVICE_RUNNING,
+			SERVICE_START_PENDING, SERVICE_STATUS, SERVICE_STATUS_CURRENT_STATE,
+			SERVICE_STATUS_HANDLE, SERVICE_STOPPED, SERVICE_STOP_PENDING, SERVICE_TABLE_ENTRYW,
+			SERVICE_WIN32_OWN_PROCESS,
+		},
+	},
+};
+
+pub const SERVICE_NAME: &str = "avail-light";
+const SERVICE_DISPLAY_NAME: &str = "Avail Light Client";
+
+/// Light client run by the service, returning the exit code
+type ServiceRun = Box<dyn FnOnce(Controller<ShutdownReason>) -> i32 + Send>;
+
+static SERVICE_RUN: Mutex<Option<ServiceRun>> = Mutex::new(None);
+static STATUS_HANDLE: OnceLock<SERVICE_STATUS_HANDLE> = OnceLock::new();
+
+fn wide(value: &str) -> Vec<u16> {
+	value.encode_utf16().chain(Some(0)).collect()
+}
+
+/// Reports message to the Windows event log
+pub fn report_event(message: &str, is_error: bool) {
+	let source = wide(SERVICE_NAME);
+	let message = wide(message);
+	let strings = [message.as_ptr()];
+	let event_type = if is_error {
+		EVENTLOG_ERROR_TYPE
+	} else {
+		EVENTLOG_INFORMATION_TYPE
+	};
+	// SAFETY: Pointers are valid null terminated strings for the duration of the calls
+	unsafe {
+		let event_log = RegisterEventSourceW(ptr::null(), source.as_ptr());
+		if event_log == 0 {
+			return;
+		}
+		ReportEventW(
+			event_log,
+			event_type,
+			0,
+			0,
+			ptr::null_mut(),
+			1,
+			0,
+			strings.as_ptr(),
+			ptr::null(),
+		);
+		DeregisterEventSource(event_log);
+	}
+}
+
+fn set_status(state: SERVICE_STATUS_CURRENT_STATE, exit_code: i32) {
+	let Some(handle) = STATUS_HANDLE.get() else {
+		return;
+	};
+	let controls_accepted = if state == SERVICE_RUNNING {
+		SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN
+	} else {
+		0
+	};
+	let status = SERVICE_STATUS {
+		dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+		dwCurrentState: state,
+		dwControlsAccepted: controls_accepted,
+		dwWin32ExitCode: if exit_code == 0 {
+			NO_ERROR
+		} else {
+			ERROR_SERVICE_SPECIFIC_ERROR
+		},
+		dwServiceSpecificExitCode: exit_code as u32,
+		dwCheckPoint: 0,
+		dwWaitHint: 0,
+	};
+	// SAFETY: Handle is returned by the service control manager
+	unsafe { SetServiceStatus(*handle, &status) };
+}
+
+unsafe extern "system" fn control_handler(
+	control: u32,
+	_event_type: u32,
+	_event_data: *mut c_void,
+	context: *mut c_void,
+) -> u32 {
+	// SAFETY: Context is the shutdown controller leaked in the service main
+	let shutdown = &*(context as *const Controller<ShutdownReason>);
+	match control {
+		SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+			set_status(SERVICE_STOP_PENDING, 0);
+			let _ = shutdown
+				.trigger_shutdown(ShutdownReason::Signal("Service stop requested".to_string()));
+			NO_ERROR
+		},
+		SERVICE_CONTROL_INTERROGATE => NO_ERROR,
+		_ => windows_sys::Win32::Foundation::ERROR_CALL_NOT_IMPLEMENTED,
+	}
+}
+
+unsafe extern "system" fn service_main(_argc: u32, _argv: *mut *mut u16) {
+	let Some(run) = SERVICE_RUN.lock().expect("Lock acquired").take() else {
+		return;
+	};
+	let shutdown = Controller::new();
+	// controller is used by the control handler until the process exits
+	let context = Box::into_raw(Box::new(shutdown.clone()));
+	let name = wide(SERVICE_NAME);
+	let handle =
+		RegisterServiceCtrlHandlerExW(name.as_ptr(), Some(control_handler), context as *const _);
+	if handle == 0 {
+		report_event(
+			&format!(
+				"Failed to register service control handler: {}",
+				io::Error::last_os_error()
+			),
+			true,
+		);
+		return;
+	}
+	let _ = STATUS_HANDLE.set(handle);
+
+	set_status(SERVICE_START_PENDING, 0);
+	report_event("Avail light client service started", false);
+	set_status(SERVICE_RUNNING, 0);
+
+	let exit_code = run(shutdown);
+	if exit_code == 0 {
+		report_event("Avail light client service stopped", false);
+	} else {
+		report_event(
+			&format!("Avail light client service failed with exit code {exit_code}"),
+			true,
+		);
+	}
+	set_status(SERVICE_STOPPED, exit_code);
+}
+
+/// Connects to the service control manager, running the light client in the service main thread.
+/// Blocks until the service is stopped.
+pub fn dispatch(run: ServiceRun) -> Result<()> {
+	*SERVICE_RUN.lock().expect("Lock acquired") = Some(run);
+	let mut name = wide(SERVICE_NAME);
+	let table = [
+		SERVICE_TABLE_ENTRYW {
+			lpServiceName: name.as_mut_ptr(),
+			lpServiceProc: Some(service_main),
+		},
+		SERVICE_TABLE_ENTRYW {
+			lpServiceName: ptr::null_mut(),
+			lpServiceProc: None,
+		},
+	];
+	// SAFETY: Service table is null terminated and valid until the dispatcher returns
+	if unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) } == 0 {
+		return Err(eyre!(
+			"Failed to start service dispatcher, service can be started only by the service control manager: {}",
+			io::Error::last_os_error()
+		));
+	}
+	Ok(())
+}
+
+/// Returns service command line, with the global options used on install
+fn service_command_line() -> Result<String> {
+	let executable = std::env::current_exe()?;
+	let args = std::env::args()
+		.skip(1)
+		.filter(|arg| arg != "service" && arg != "install")
+		.map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+		.collect::<Vec<_>>();
+	Ok(format!(
+		"\"{}\" {} service run",
+		executable.display(),
+		args.join(" ")
+	))
+}
+
+/// Installs the light client as an automatically started Windows service
+pub fn install() -> Result<()> {
+	let command_line = wide(&service_command_line()?);
+	let name = wide(SERVICE_NAME);
+	let display_name = wide(SERVICE_DISPLAY_NAME);
+	// SAFETY: Pointers are valid null terminated strings, and handles are closed after use
+	unsafe {
+		let manager = OpenSCManagerW(ptr::null(), ptr::null(), SC_MANAGER_ALL_ACCESS);
+		if manager == 0 {
+			return Err(eyre!(
+				"Failed to open service control manager: {}",
+				io::Error::last_os_error()
+			));
+		}
+		let service = CreateServiceW(
+			manager,
+			name.as_ptr(),
+			display_name.as_ptr(),
+			SERVICE_ALL_ACCESS,
+			SERVICE_WIN32_OWN_PROCESS,
+			SERVICE_AUTO_START,
+			SERVICE_ERROR_NORMAL,
+			command_line.as_ptr(),
+			ptr::null(),
+			ptr::null_mut(),
+			ptr::null(),
+			ptr::null(),
+			ptr::null(),
+		);
+		let error = io::Error::last_os_error();
+		CloseServiceHandle(manager);
+		if service == 0 {
+			return Err(eyre!("Failed to install service: {error}"));
+		}
+		CloseServiceHandle(service);
+	}
+	println!("Service {SERVICE_NAME} installed");
+	Ok(())
+}
+
+/// Removes the installed Windows service
+pub fn uninstall() -> Result<()> {
+	let name = wide(SERVICE_NAME);
+	// SAFETY: Pointers are valid null terminated strings, and handles are closed after use
+	unsafe {
+		let manager = OpenSCManagerW(ptr::null(), ptr::null(), SC_MANAGER_ALL_ACCESS);
+		if manager == 0 {
+			return Err(eyre!(
+				"Failed to open service control manager: {}",
+				io::Error::last_os_error()
+			));
+		}
+		let service = OpenServiceW(manager, name.as_ptr(), SERVICE_ALL_ACCESS);
+		if service == 0 {
+			let error = io::Error::last_os_error();
+			CloseServiceHandle(manager);
+			return Err(eyre!("Failed to open service: {error}"));
+		}
+		let deleted = DeleteService(service);
+		let error = io::Error::last_os_error();
+		CloseServiceHandle(service);
+		CloseServiceHandle(manager);
+		if deleted == 0 {
+			return Err(eyre!("Failed to uninstall service: {error}"));
+		}
+	}
+	println!("Service {SERVICE_NAME} uninstalled");
+	Ok(())
+}
@@ -0,0 +1,110 @@
+//! Queue depth and dropped events metrics of the internal channels.
+//!
+//! Stats of the event bus subscribers and the network command queue are recorded every [`INTERVAL`], regardless of the event flow,
+//! so stalled or lagging consumers are visible on dashboards even if no events are received.
+//! `avail.light.channel.{channel}.queued` is the longest queue of the channel subscribers (or the number of queued network commands),
+//! and `avail.light.channel.{channel}.dropped` is the number of events dropped on overflow since the start.
+//!
+//! API events channel includes the `api_*` subscribers of all event buses, and the blocks channel includes the other verified blocks subscribers.
+//! Network command queue is unbounded, so commands are never dropped.
+
+use std::{sync::Arc, time::Duration};
+use tracing::info;
+
+use crate::{
+	event_bus::{Monitor, SubscriberStats},
+	network::p2p,
+	telemetry::{Channel, MetricValue, Metrics},
+};
+
+/// Interval of the channel metrics recording
+pub const INTERVAL: Duration = Duration::from_secs(15);
+
+fn is_api(stats: &SubscriberStats) -> bool {
+	stats.name.starts_with("api_")
+}
+
+/// Returns the longest queue and the number of dropped events of the subscribers
+fn aggregate<'a>(stats: impl Iterator<Item = &'a SubscriberStats>) -> (u64, u64) {
+	stats.fold((0, 0), |(queued, dropped), stats| {
+		(queued.max(stats.queued as u64), dropped + stats.dropped)
+	})
+}
+
+/// Returns queue depth and dropped events of the blocks and API events channels
+fn channel_stats(
+	blocks: &[SubscriberStats],
+	events: &[SubscriberStats],
+) -> [(Channel, u64, u64); 2] {
+	let (queued, dropped) = aggregate(blocks.iter().filter(|stats| !is_api(stats)));
+	let (api_queued, api_dropped) =
+		aggregate(blocks.iter().chain(events).filter(|stats| is_api(stats)));
+	[
+		(Channel::Blocks, queued, dropped),
+		(Channel::ApiEvents, api_queued, api_dropped),
+	]
+}
+
+/// Records channel metrics periodically
+pub async fn run(
+	blocks: Monitor,
+	events: Vec<Monitor>,
+	p2p_client: p2p::Client,
+	metrics: Arc<impl Metrics>,
+) {
+	info!("Starting channel metrics...");
+	let mut interval = tokio::time::interval(INTERVAL);
+	loop {
+		interval.tick().await;
+		let events = events.iter().flat_map(Monitor::stats).collect::<Vec<_>>();
+		for (channel, queued, dropped) in channel_stats(&blocks.stats(), &events) {
+			metrics
+				.record(MetricValue::ChannelQueued(channel, queued))
+				.await;
+			metrics
+				.record(MetricValue::ChannelDropped(channel, dropped))
+				.await;
+		}
+		let queued = p2p_client.queued_commands() as u64;
+		metrics
+			.record(MetricValue::ChannelQueued(Channel::DhtCommands, queued))
+			.await;
+		metrics
+			.record(MetricValue::ChannelDropped(Channel::DhtCommands, 0))
+			.await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stats(name: &'static str, queued: usize, dropped: u64) -> SubscriberStats {
+		SubscriberStats {
+			name,
+			queued,
+			dropped,
+		}
+	}
+
+	#[test]
+	fn aggregated_channel_stats() {
+		let blocks = [
+			stats("maintenance", 3, 10),
+			stats("fleet", 1, 5),
+			stats("api_confidence_achieved", 2, 1),
+		];
+		let events = [
+			stats("api_data_verified", 4, 2),
+			stats("runtime_upgrade_metrics", 9, 9),
+		];
+		assert_eq!(
+			channel_stats(&blocks, &events),
+			[(Channel::Blocks, 3, 15), (Channel::ApiEvents, 4, 3)]
+		);
+		assert_eq!(
+			channel_stats(&[], &[]),
+			[(Channel::Blocks, 0, 0), (Channel::ApiEvents, 0, 0)]
+		);
+	}
+}
@@ -0,0 +1,111 @@
+//! Detection of the local clock skew against the block timestamps.
+//!
+//! Timestamp of each finalized header is compared with the local time at which the header is received.
+//! Skew is recorded with the `avail.light.clock_skew` metric (in seconds, positive if the local clock is ahead),
+//! and if it exceeds the threshold, it is logged and flagged with the `clock_skewed` field of `GET /v2/status`.
+//!
+//! # Notes
+//!
+//! Finalized headers are received after the finality delay, so the threshold has to be greater than the usual finality lag.
+//! Large skew breaks TTL based DHT pruning and latency metrics.
+
+use avail_subxt::utils::H256;
+use codec::Encode;
+use sp_core::blake2_256;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::{
+	network::rpc::{Client as RpcClient, Event},
+	telemetry::{MetricValue, Metrics},
+	types::State,
+	utils::unix_timestamp_millis,
+};
+
+/// Returns the difference between the local clock and the block timestamp, in milliseconds (positive if the local clock is ahead)
+fn clock_skew(local_timestamp: u64, block_timestamp: u64) -> i64 {
+	local_timestamp as i64 - block_timestamp as i64
+}
+
+fn is_skewed(skew: i64, threshold: Duration) -> bool {
+	skew.unsigned_abs() > threshold.as_millis() as u64
+}
+
+/// Checks clock skew against the timestamps of the received headers
+pub async fn run(
+	rpc_client: RpcClient,
+	mut rpc_events: broadcast::Receiver<Event>,
+	state: Arc<Mutex<State>>,
+	metrics: Arc<impl Metrics>,
+	threshold: Duration,
+) {
+	info!("Starting clock skew detection...");
+	loop {
+		let (header, received_at) = match rpc_events.recv().await {
+			Ok(Event::HeaderUpdate {
+				header,
+				received_at,
+			}) => (header, received_at),
+			Err(broadcast::error::RecvError::Lagged(skipped)) => {
+				debug!(skipped, "Headers skipped by clock skew detection");
+				continue;
+			},
+			Err(broadcast::error::RecvError::Closed) => return,
+		};
+		let local_timestamp =
+			unix_timestamp_millis().saturating_sub(received_at.elapsed().as_millis() as u64);
+
+		let block_hash: H256 = Encode::using_encoded(&header, blake2_256).into();
+		let block_timestamp = match rpc_client.get_timestamp_by_hash(block_hash).await {
+			Ok(timestamp) => timestamp,
+			Err(error) => {
+				warn!(
+					block_number = header.number,
+					"Cannot get block timestamp: {error:#}"
+				);
+				continue;
+			},
+		};
+
+		let skew = clock_skew(local_timestamp, block_timestamp);
+		metrics
+			.record(MetricValue::ClockSkew(skew as f64 / 1000.0))
+			.await;
+
+		let skewed = is_skewed(skew, threshold);
+		let was_skewed = std::mem::replace(
+			&mut state.lock().expect("Lock acquired").clock_skewed,
+			skewed,
+		);
+		let skew = Duration::from_millis(skew.unsigned_abs());
+		// skew is logged as warning once, until it is resolved
+		match (was_skewed, skewed) {
+			(false, true) => warn!(
+				block_number = header.number,
+				"Local clock differs from the block timestamp by {skew:?} (maximum {threshold:?})"
+			),
+			(true, false) => info!(block_number = header.number, "Local clock skew is resolved"),
+			_ => debug!(block_number = header.number, "Local clock skew is {skew:?}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn clock_skew_threshold() {
+		assert_eq!(clock_skew(61_000, 1_000), 60_000);
+		assert_eq!(clock_skew(1_000, 61_000), -60_000);
+		let threshold = Duration::from_secs(60);
+		assert!(!is_skewed(60_000, threshold));
+		assert!(!is_skewed(-60_000, threshold));
+		assert!(is_skewed(60_001, threshold));
+		assert!(is_skewed(-60_001, threshold));
+	}
+}
@@ -1,14 +1,22 @@
 use crate::{
+	data::{CrawlCoverage, CrawlResult, Database, Key},
 	network::{
 		p2p::Client,
 		rpc::{self, Event},
 	},
 	telemetry::{MetricValue, Metrics},
 	types::{self, block_matrix_partition_format, Delay},
+	utils::unix_timestamp_millis,
 };
-use kate_recovery::matrix::Partition;
+use avail_subxt::api::runtime_types::avail_core::header::extension::{v3, HeaderExtension};
+use color_eyre::{eyre::WrapErr, Result};
+use kate_recovery::matrix::{Dimensions, Partition, Position};
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use std::{
+	collections::BTreeMap,
+	fmt::Write,
+	ops::Range,
 	sync::Arc,
 	time::{Duration, Instant},
 };
@@ -28,6 +36,13 @@ pub enum CrawlMode {
 	Both,
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrawlReportFormat {
+	Json,
+	Csv,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct CrawlConfig {
@@ -40,6 +55,14 @@ pub struct CrawlConfig {
 	/// Fraction and number of the block matrix part to crawl (e.g. 2/20 means second 1/20 part of a matrix) (default: None)
 	#[serde(with = "block_matrix_partition_format")]
 	pub crawl_block_matrix_partition: Option<Partition>,
+	/// Fraction of the partition cells and rows which are randomly sampled and crawled per block, in range (0, 1] (default: 1.0)
+	pub crawl_block_sampling_fraction: f64,
+	/// Path of the coverage report file, which is written periodically if set (default: None)
+	pub crawl_report_path: Option<String>,
+	/// Coverage report format. Available formats are "json" and "csv" (default: "json")
+	pub crawl_report_format: CrawlReportFormat,
+	/// Number of crawled blocks after which the coverage report is written (default: 10)
+	pub crawl_report_interval: u64,
 }
 
 impl Default for CrawlConfig {
@@ -49,27 +72,198 @@ impl Default for CrawlConfig {
 			crawl_block_delay: 20,
 			crawl_block_mode: CrawlMode::Cells,
 			crawl_block_matrix_partition: None,
+			crawl_block_sampling_fraction: 1.0,
+			crawl_report_path: None,
+			crawl_report_format: CrawlReportFormat::Json,
+			crawl_report_interval: 10,
+		}
+	}
+}
+
+impl CrawlConfig {
+	pub fn validation_errors(&self) -> Vec<String> {
+		let mut errors = vec![];
+		let fraction = self.crawl_block_sampling_fraction;
+		if !(fraction > 0.0 && fraction <= 1.0) {
+			errors.push(format!(
+				"crawl_block_sampling_fraction: {fraction} is not in range (0, 1]"
+			));
+		}
+		if self.crawl_report_interval == 0 {
+			errors.push("crawl_report_interval: must be greater than 0".to_string());
+		}
+		errors
+	}
+}
+
+/// Number of crawled and successfully fetched cells or rows
+#[derive(Serialize, Default, Clone, Copy, Debug, PartialEq)]
+pub struct Coverage {
+	pub total: u64,
+	pub fetched: u64,
+	pub success_rate: f64,
+}
+
+impl Coverage {
+	fn add(&mut self, fetched: bool) {
+		self.total += 1;
+		self.fetched += fetched as u64;
+		self.success_rate = self.fetched as f64 / self.total as f64;
+	}
+}
+
+#[derive(Serialize, Default, Clone, Debug, PartialEq)]
+pub struct AppCoverage {
+	pub cells: Coverage,
+	pub rows: Coverage,
+}
+
+/// Network-wide availability summary of the crawled blocks, with per application breakdown
+#[derive(Serialize, Default, Clone, Debug, PartialEq)]
+pub struct CrawlReport {
+	pub first_block: Option<u32>,
+	pub last_block: Option<u32>,
+	pub blocks: u64,
+	pub cells: Coverage,
+	pub rows: Coverage,
+	pub apps: BTreeMap<u32, AppCoverage>,
+}
+
+impl CrawlReport {
+	fn add_block(&mut self, block_number: u32) {
+		self.first_block.get_or_insert(block_number);
+		self.last_block = Some(block_number);
+		self.blocks += 1;
+	}
+
+	fn add_cell(&mut self, app_id: Option<u32>, fetched: bool) {
+		self.cells.add(fetched);
+		if let Some(app_id) = app_id {
+			self.apps.entry(app_id).or_default().cells.add(fetched);
 		}
 	}
+
+	fn add_row(&mut self, app_ids: &[u32], fetched: bool) {
+		self.rows.add(fetched);
+		for app_id in app_ids {
+			self.apps.entry(*app_id).or_default().rows.add(fetched);
+		}
+	}
+
+	/// Renders CSV report, with one line per network-wide and per application coverage
+	pub fn to_csv(&self) -> String {
+		let mut output = "scope,app_id,kind,total,fetched,success_rate\n".to_string();
+		let mut line = |scope: &str, app_id: Option<u32>, kind: &str, coverage: &Coverage| {
+			let app_id = app_id.map(|id| id.to_string()).unwrap_or_default();
+			let Coverage {
+				total,
+				fetched,
+				success_rate,
+			} = coverage;
+			_ = writeln!(
+				output,
+				"{scope},{app_id},{kind},{total},{fetched},{success_rate}"
+			);
+		};
+		line("network", None, "cells", &self.cells);
+		line("network", None, "rows", &self.rows);
+		for (app_id, coverage) in &self.apps {
+			line("app", Some(*app_id), "cells", &coverage.cells);
+			line("app", Some(*app_id), "rows", &coverage.rows);
+		}
+		output
+	}
+
+	pub fn render(&self, format: CrawlReportFormat) -> Result<String> {
+		match format {
+			CrawlReportFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+			CrawlReportFormat::Csv => Ok(self.to_csv()),
+		}
+	}
+}
+
+/// Data ranges of each application in the original (non-extended) matrix
+fn app_ranges(extension: &HeaderExtension) -> Vec<(u32, Range<u32>)> {
+	let HeaderExtension::V3(v3::HeaderExtension { app_lookup, .. }) = extension;
+	let index = &app_lookup.index;
+	index
+		.iter()
+		.enumerate()
+		.map(|(i, item)| {
+			let end = index.get(i + 1).map_or(app_lookup.size, |next| next.start);
+			(item.app_id.0, item.start..end)
+		})
+		.collect()
+}
+
+/// Returns application of the extended matrix cell, parity cells are not attributed to any application
+fn cell_app_id(
+	ranges: &[(u32, Range<u32>)],
+	dimensions: Dimensions,
+	position: Position,
+) -> Option<u32> {
+	if position.row % 2 != 0 {
+		return None;
+	}
+	let index = (position.row / 2) * dimensions.cols().get() as u32 + position.col as u32;
+	ranges
+		.iter()
+		.find(|(_, range)| range.contains(&index))
+		.map(|(app_id, _)| *app_id)
+}
+
+/// Returns applications with data in the extended matrix row, parity rows are not attributed to any application
+fn row_app_ids(ranges: &[(u32, Range<u32>)], dimensions: Dimensions, row: u32) -> Vec<u32> {
+	if row % 2 != 0 {
+		return vec![];
+	}
+	let cols = dimensions.cols().get() as u32;
+	let row = (row / 2) * cols..(row / 2 + 1) * cols;
+	ranges
+		.iter()
+		.filter(|(_, range)| range.start < row.end && row.start < range.end)
+		.map(|(app_id, _)| *app_id)
+		.collect()
+}
+
+fn sample<T>(mut items: Vec<T>, fraction: f64) -> Vec<T> {
+	if fraction >= 1.0 {
+		return items;
+	}
+	let amount = ((items.len() as f64 * fraction).ceil() as usize).min(items.len());
+	items.shuffle(&mut rand::thread_rng());
+	items.truncate(amount);
+	items
+}
+
+async fn write_report(report: &CrawlReport, path: &str, format: CrawlReportFormat) -> Result<()> {
+	let output = report.render(format)?;
+	tokio::fs::write(path, output)
+		.await
+		.wrap_err(format!("Failed to write crawl report to {path}"))
 }
 
 pub async fn run(
 	mut message_rx: broadcast::Receiver<Event>,
 	network_client: Client,
-	delay: u64,
+	db: impl Database,
 	metrics: Arc<impl Metrics>,
-	mode: CrawlMode,
-	partition: Partition,
+	config: CrawlConfig,
 ) {
 	info!("Starting crawl client...");
 
-	let delay = Delay(Some(Duration::from_secs(delay)));
+	let delay = Delay(Some(Duration::from_secs(config.crawl_block_delay)));
+	let mode = config.crawl_block_mode;
+	let partition = config.crawl_block_matrix_partition.unwrap_or(ENTIRE_BLOCK);
+	let fraction = config.crawl_block_sampling_fraction;
+	let mut report = CrawlReport::default();
 
 	while let Ok(rpc::Event::HeaderUpdate {
 		header,
 		received_at,
 	}) = message_rx.recv().await
 	{
+		let ranges = app_ranges(&header.extension);
 		let block = match types::BlockVerified::try_from((header, None)) {
 			Ok(block) => block,
 			Err(error) => {
@@ -94,19 +288,38 @@ pub async fn run(
 		info!(block_number, "Crawling block...");
 
 		let start = Instant::now();
+		let dimensions = extension.dimensions;
+		report.add_block(block_number);
+		let mut result = CrawlResult {
+			crawled_at: unix_timestamp_millis(),
+			delay: received_at.elapsed().as_secs(),
+			cells: None,
+			rows: None,
+		};
 
 		if matches!(mode, CrawlMode::Cells | CrawlMode::Both) {
-			let positions = extension
-				.dimensions
+			let positions = dimensions
 				.iter_extended_partition_positions(&partition)
 				.collect::<Vec<_>>();
+			let positions = sample(positions, fraction);
 
 			let total = positions.len();
-			let fetched = network_client
+			let (fetched_cells, unfetched) = network_client
 				.fetch_cells_from_dht(block_number, &positions)
-				.await
-				.0
-				.len();
+				.await;
+			let fetched = fetched_cells.len();
+
+			for cell in &fetched_cells {
+				report.add_cell(cell_app_id(&ranges, dimensions, cell.position), true);
+			}
+			for position in unfetched {
+				report.add_cell(cell_app_id(&ranges, dimensions, position), false);
+			}
+
+			result.cells = Some(CrawlCoverage {
+				total: total as u32,
+				fetched: fetched as u32,
+			});
 
 			let success_rate = fetched as f64 / total as f64;
 			let partition = format!("{}/{}", partition.number, partition.fraction);
@@ -119,17 +332,25 @@ pub async fn run(
 				.await;
 		}
 
-		if matches!(mode, CrawlMode::Cells | CrawlMode::Both) {
-			let dimensions = extension.dimensions;
+		if matches!(mode, CrawlMode::Rows | CrawlMode::Both) {
 			let rows: Vec<u32> = (0..dimensions.extended_rows()).step_by(2).collect();
+			let rows = sample(rows, fraction);
 			let total = rows.len();
-			let fetched = network_client
+			let fetched_rows = network_client
 				.fetch_rows_from_dht(block_number, dimensions, &rows)
-				.await
-				.iter()
-				.step_by(2)
-				.flatten()
-				.count();
+				.await;
+
+			let mut fetched = 0;
+			for row in &rows {
+				let is_fetched = fetched_rows[*row as usize].is_some();
+				fetched += is_fetched as usize;
+				report.add_row(&row_app_ids(&ranges, dimensions, *row), is_fetched);
+			}
+
+			result.rows = Some(CrawlCoverage {
+				total: total as u32,
+				fetched: fetched as u32,
+			});
 
 			let success_rate = fetched as f64 / total as f64;
 			info!(
@@ -142,6 +363,99 @@ pub async fn run(
 		}
 
 		let elapsed = start.elapsed();
-		info!(block_number, "Crawling block finished in {elapsed:?}")
+		info!(block_number, "Crawling block finished in {elapsed:?}");
+
+		if let Err(error) = db.put(Key::CrawlResult(block_number), result) {
+			error!(block_number, "Failed to store crawl result: {error:#}");
+		}
+
+		if let Some(path) = &config.crawl_report_path {
+			if report.blocks % config.crawl_report_interval == 0 {
+				if let Err(error) = write_report(&report, path, config.crawl_report_format).await {
+					error!("{error:#}");
+				}
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use avail_subxt::api::runtime_types::avail_core::{
+		data_lookup::compact::{CompactDataLookup, DataLookupItem},
+		kate_commitment::v3::KateCommitment,
+		AppId,
+	};
+	use avail_subxt::utils::H256;
+
+	fn extension() -> HeaderExtension {
+		HeaderExtension::V3(v3::HeaderExtension {
+			app_lookup: CompactDataLookup {
+				size: 6,
+				index: vec![
+					DataLookupItem {
+						app_id: AppId(1),
+						start: 0,
+					},
+					DataLookupItem {
+						app_id: AppId(2),
+						start: 3,
+					},
+				],
+			},
+			commitment: KateCommitment {
+				rows: 2,
+				cols: 4,
+				commitment: vec![],
+				data_root: H256::zero(),
+			},
+		})
+	}
+
+	#[test]
+	fn app_coverage() {
+		let ranges = app_ranges(&extension());
+		assert_eq!(ranges, vec![(1, 0..3), (2, 3..6)]);
+
+		let dimensions = Dimensions::new(2, 4).unwrap();
+		let app_id = |row, col| cell_app_id(&ranges, dimensions, Position { row, col });
+		assert_eq!(app_id(0, 2), Some(1));
+		assert_eq!(app_id(0, 3), Some(2));
+		assert_eq!(app_id(1, 0), None);
+		assert_eq!(app_id(2, 1), Some(2));
+		assert_eq!(app_id(2, 2), None);
+
+		assert_eq!(row_app_ids(&ranges, dimensions, 0), vec![1, 2]);
+		assert_eq!(row_app_ids(&ranges, dimensions, 2), vec![2]);
+		assert!(row_app_ids(&ranges, dimensions, 3).is_empty());
+	}
+
+	#[test]
+	fn report_csv() {
+		let mut report = CrawlReport::default();
+		report.add_block(1);
+		report.add_cell(Some(1), true);
+		report.add_cell(Some(1), false);
+		report.add_cell(None, false);
+		report.add_row(&[1, 2], true);
+
+		assert_eq!(
+			report.to_csv(),
+			"scope,app_id,kind,total,fetched,success_rate\n\
+			network,,cells,3,1,0.3333333333333333\n\
+			network,,rows,1,1,1\n\
+			app,1,cells,2,1,0.5\n\
+			app,1,rows,1,1,1\n\
+			app,2,cells,0,0,0\n\
+			app,2,rows,1,1,1\n"
+		);
+	}
+
+	#[test]
+	fn sample_fraction() {
+		assert_eq!(sample((0..10).collect(), 1.0).len(), 10);
+		assert_eq!(sample((0..10).collect(), 0.25).len(), 3);
+		assert_eq!(sample((0..10).collect::<Vec<u32>>(), 0.01).len(), 1);
 	}
 }
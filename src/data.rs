@@ -1,11 +1,21 @@
 use codec::{Decode, Encode};
 use color_eyre::eyre::Result;
 use serde::{Deserialize, Serialize};
-use sp_core::ed25519;
+use sp_core::{ed25519, H256};
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
 
+use crate::randomness::RandomnessSource;
+
+pub mod backup;
+pub mod export;
+pub mod inspect;
+pub mod recovery;
 pub mod rocks_db;
 
-#[cfg(test)]
+#[cfg(any(test, feature = "simulation"))]
 pub mod mem_db;
 
 pub trait Database {
@@ -26,6 +36,28 @@ pub trait Database {
 
 	/// Deletes value from the database for the given key.
 	fn delete(&self, key: Key) -> Result<()>;
+
+	/// Gets all values stored in the given column family.
+	/// Values are deserialized into the given type.
+	fn values<T>(&self, column_family: &'static str) -> Result<Vec<T>>
+	where
+		for<'a> T: Deserialize<'a> + Decode;
+}
+
+/// Shared flag used to pause storing of app data, e.g. when available disk space is low
+#[derive(Clone, Default, Debug)]
+pub struct StoragePause(Arc<AtomicBool>);
+
+impl StoragePause {
+	/// Returns `true` if storing of app data is paused
+	pub fn is_paused(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	/// Sets pause flag, returning previous value
+	pub fn set(&self, paused: bool) -> bool {
+		self.0.swap(paused, Ordering::Relaxed)
+	}
 }
 
 /// Column family for confidence factor
@@ -40,18 +72,51 @@ pub const APP_DATA_CF: &str = "avail_light_app_data_cf";
 /// Column family for state
 pub const STATE_CF: &str = "avail_light_state_cf";
 
+/// Column family for block verification metadata
+pub const VERIFICATION_CF: &str = "avail_light_verification_cf";
+
+/// Column family for block availability results recorded by the crawler
+pub const CRAWL_CF: &str = "avail_light_crawl_cf";
+
+/// Column family for confidence of the best chain blocks
+pub const BEST_BLOCK_CF: &str = "avail_light_best_block_cf";
+
+/// Column family for data root verification results
+pub const DATA_ROOT_CF: &str = "avail_light_data_root_cf";
+
+/// Column family for finality justifications of the block headers
+pub const JUSTIFICATION_CF: &str = "avail_light_justification_cf";
+
 /// Column family for Kademlia store
 pub const KADEMLIA_STORE_CF: &str = "avail_light_kademlia_store_cf";
 
+/// Column family for failed DHT inserts queued for the retry, keyed by block number and record key
+pub const DHT_RETRY_CF: &str = "avail_light_dht_retry_cf";
+
 /// Sync finality checkpoint key name
 const FINALITY_SYNC_CHECKPOINT_KEY: &str = "finality_sync_checkpoint";
 
+/// Known peers key name
+const KNOWN_PEERS_KEY: &str = "known_peers";
+
+/// Quarantined proof failures key name
+const PROOF_FAILURES_KEY: &str = "proof_failures";
+
 #[derive(Clone)]
 pub enum Key {
 	AppData(u32, u32),
 	BlockHeader(u32),
+	Justification(u32),
 	VerifiedCellCount(u32),
+	BlockVerification(u32),
+	CrawlResult(u32),
+	BestBlock(u32),
+	DataRootVerification(u32),
 	FinalitySyncCheckpoint,
+	KnownPeers,
+	ProofFailures,
+	/// Failed DHT insert of the record with the given key, in the given block
+	FailedPut(u32, Vec<u8>),
 }
 
 #[derive(Serialize, Deserialize, Debug, Decode, Encode)]
@@ -60,3 +125,102 @@ pub struct FinalitySyncCheckpoint {
 	pub set_id: u64,
 	pub validator_set: Vec<ed25519::Public>,
 }
+
+/// Connected routing table peer, persisted to speed up DHT re-entry after restart
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct KnownPeer {
+	pub peer_id: String,
+	pub addresses: Vec<String>,
+	/// Last time the peer was seen in the routing table (milliseconds since UNIX epoch)
+	pub last_seen: u64,
+}
+
+/// Source from which the cell is fetched
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum CellSource {
+	/// DHT peer which returned the record, `None` if record is found in the local store
+	Dht { peer_id: Option<String> },
+	/// Connected RPC node
+	Rpc { host: String },
+	/// One of the configured HTTP gateways
+	Gateway,
+}
+
+/// Cell which failed proof verification, quarantined for the forensics
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct ProofFailure {
+	pub block_number: u32,
+	pub row: u32,
+	pub col: u16,
+	/// Cell content (proof followed by the data)
+	pub content: Vec<u8>,
+	/// Row commitment against which the proof is verified
+	pub commitment: Vec<u8>,
+	pub source: CellSource,
+	/// Quarantine time (milliseconds since UNIX epoch)
+	pub quarantined_at: u64,
+}
+
+/// DHT record which failed to be inserted, queued for the retry
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct FailedPut {
+	pub key: Vec<u8>,
+	pub value: Vec<u8>,
+	pub block_number: u32,
+	/// Number of the retried inserts
+	pub attempts: u32,
+	/// Next retry time (milliseconds since UNIX epoch)
+	pub next_retry: u64,
+	/// Record expiration time (milliseconds since UNIX epoch), `None` if record doesn't expire
+	pub expires_at: Option<u64>,
+}
+
+/// Block verification metadata, recorded by the light client during sampling
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct VerificationMetadata {
+	/// Sampling start time (milliseconds since UNIX epoch)
+	pub started_at: u64,
+	/// Sampling end time (milliseconds since UNIX epoch)
+	pub finished_at: u64,
+	/// Delay between the header receipt and the sampling start, in milliseconds
+	pub processing_delay: u64,
+	/// Number of cells requested for sampling
+	pub cells_requested: u32,
+	/// Number of cells fetched from DHT
+	pub dht_fetched: u32,
+	/// Number of cells fetched from RPC
+	pub rpc_fetched: u32,
+	/// Number of verified cells
+	pub verified: u32,
+	/// Source of the sampled cell positions randomness
+	pub randomness: RandomnessSource,
+}
+
+/// Block of the best (not yet finalized) chain, sampled by the light client
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct BestBlock {
+	pub hash: H256,
+	/// Number of verified cells
+	pub verified: u32,
+}
+
+/// Number of crawled and fetched cells or rows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Decode, Encode, PartialEq)]
+pub struct CrawlCoverage {
+	pub total: u32,
+	pub fetched: u32,
+}
+
+/// Block availability results, recorded by the crawler for each crawled source
+#[derive(Serialize, Deserialize, Debug, Clone, Decode, Encode, PartialEq)]
+pub struct CrawlResult {
+	/// Crawling start time (milliseconds since UNIX epoch)
+	pub crawled_at: u64,
+	/// Delay between the block finalization and crawling in seconds
+	pub delay: u64,
+	/// Coverage of the crawled DHT cells, if cells are crawled
+	pub cells: Option<CrawlCoverage>,
+	/// Coverage of the crawled DHT rows, if rows are crawled
+	pub rows: Option<CrawlCoverage>,
+}
@@ -0,0 +1,122 @@
+//! Export and import of the light client database.
+//!
+//! Database is exported as JSON lines, one entry per line, with hex encoded keys and values.
+//! Kademlia store is not exported, since its records are expiring and re-fetched from the network.
+
+use color_eyre::eyre::{eyre, Context, Result};
+use rocksdb::IteratorMode;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+use crate::data::{
+	APP_DATA_CF, BEST_BLOCK_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, CRAWL_CF, DATA_ROOT_CF,
+	JUSTIFICATION_CF, STATE_CF, VERIFICATION_CF,
+};
+
+/// Column families included in the export
+pub const EXPORTED_CFS: [&str; 9] = [
+	CONFIDENCE_FACTOR_CF,
+	BLOCK_HEADER_CF,
+	APP_DATA_CF,
+	STATE_CF,
+	VERIFICATION_CF,
+	CRAWL_CF,
+	BEST_BLOCK_CF,
+	DATA_ROOT_CF,
+	JUSTIFICATION_CF,
+];
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Entry {
+	pub column_family: String,
+	pub key: String,
+	pub value: String,
+}
+
+/// Writes database entries to the writer, returning number of exported entries
+pub fn export(db: &rocksdb::DB, mut writer: impl Write) -> Result<usize> {
+	let mut count = 0;
+	for column_family in EXPORTED_CFS {
+		let Some(cf) = db.cf_handle(column_family) else {
+			continue;
+		};
+		for item in db.iterator_cf(&cf, IteratorMode::Start) {
+			let (key, value) = item?;
+			let entry = Entry {
+				column_family: column_family.to_string(),
+				key: hex::encode(key),
+				value: hex::encode(value),
+			};
+			serde_json::to_writer(&mut writer, &entry)?;
+			writeln!(writer)?;
+			count += 1;
+		}
+	}
+	writer.flush()?;
+	Ok(count)
+}
+
+/// Reads database entries from the reader and stores them, returning number of imported entries
+pub fn import(db: &rocksdb::DB, reader: impl BufRead) -> Result<usize> {
+	let mut count = 0;
+	for (index, line) in reader.lines().enumerate() {
+		let line = line?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let entry: Entry = serde_json::from_str(&line)
+			.wrap_err(format!("Failed to parse entry on line {}", index + 1))?;
+		if !EXPORTED_CFS.contains(&entry.column_family.as_str()) {
+			return Err(eyre!(
+				"Unknown column family {} on line {}",
+				entry.column_family,
+				index + 1
+			));
+		}
+		let cf = db
+			.cf_handle(&entry.column_family)
+			.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+		let key = hex::decode(&entry.key).wrap_err("Failed to decode entry key")?;
+		let value = hex::decode(&entry.value).wrap_err("Failed to decode entry value")?;
+		db.put_cf(&cf, key, value)
+			.wrap_err("Put operation with Column Family failed on RocksDB")?;
+		count += 1;
+	}
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::{rocks_db::RocksDB, Database, Key};
+
+	#[test]
+	fn export_import() {
+		let dir = std::env::temp_dir().join(format!("avail_light_backup_{}", std::process::id()));
+		let source_path = dir.join("source");
+		let target_path = dir.join("target");
+
+		let (source, source_db) = RocksDB::open(source_path.to_str().unwrap()).unwrap();
+		source.put(Key::VerifiedCellCount(1), 10u32).unwrap();
+		source
+			.put(Key::AppData(1, 1), vec![vec![1u8, 2, 3]])
+			.unwrap();
+
+		let mut exported = vec![];
+		assert_eq!(export(&source_db, &mut exported).unwrap(), 2);
+
+		let (target, target_db) = RocksDB::open(target_path.to_str().unwrap()).unwrap();
+		assert_eq!(import(&target_db, &exported[..]).unwrap(), 2);
+		assert_eq!(
+			target.get::<u32>(Key::VerifiedCellCount(1)).unwrap(),
+			Some(10)
+		);
+		assert_eq!(
+			target.get::<Vec<Vec<u8>>>(Key::AppData(1, 1)).unwrap(),
+			Some(vec![vec![1u8, 2, 3]])
+		);
+
+		drop((source, source_db, target, target_db));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+}
@@ -0,0 +1,254 @@
+//! Export of the verified application data for a range of blocks.
+//!
+//! Used by the `export` command, so the application data can be reprocessed offline, without querying the HTTP API.
+//! With the JSON format, each exported block is written as one JSON line, with data transactions in the format of `GET /v2/blocks/{block_number}/data`.
+//! With the Parquet format (`export-parquet` feature), each data transaction is written as one row, with `block_number`, `app_id`,
+//! `index` (of the transaction in the block), `data` (decoded application data, if any) and `extrinsic` columns.
+//! Blocks without the stored application data (not verified, or without the application transactions) are skipped.
+
+#[cfg(feature = "export-parquet")]
+use arrow_array::{ArrayRef, BinaryArray, RecordBatch, UInt32Array};
+#[cfg(feature = "export-parquet")]
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use clap::{builder::PossibleValue, ValueEnum};
+use codec::Decode;
+use color_eyre::eyre::{eyre, Context, Result};
+#[cfg(feature = "export-parquet")]
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use serde::Serialize;
+use std::io::Write;
+#[cfg(feature = "export-parquet")]
+use std::sync::Arc;
+
+#[cfg(feature = "export-parquet")]
+use crate::utils::decode_app_data;
+use crate::{api::v2::types::DataTransaction, data::APP_DATA_CF};
+
+/// Format of the exported application data
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportFormat {
+	/// JSON lines, one block per line
+	#[default]
+	Json,
+	/// Parquet file, one data transaction per row
+	#[cfg(feature = "export-parquet")]
+	Parquet,
+}
+
+// Implemented manually, so formats of the disabled features are not listed in the CLI help
+impl ValueEnum for ExportFormat {
+	fn value_variants<'a>() -> &'a [Self] {
+		&[
+			ExportFormat::Json,
+			#[cfg(feature = "export-parquet")]
+			ExportFormat::Parquet,
+		]
+	}
+
+	fn to_possible_value(&self) -> Option<PossibleValue> {
+		Some(match self {
+			ExportFormat::Json => PossibleValue::new("json").help("JSON lines, one block per line"),
+			#[cfg(feature = "export-parquet")]
+			ExportFormat::Parquet => {
+				PossibleValue::new("parquet").help("Parquet file, one data transaction per row")
+			},
+		})
+	}
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExportedBlock {
+	pub block_number: u32,
+	pub app_id: u32,
+	pub data_transactions: Vec<DataTransaction>,
+}
+
+enum Writer<W: Write + Send> {
+	Json(W),
+	#[cfg(feature = "export-parquet")]
+	Parquet(Box<ArrowWriter<W>>),
+}
+
+#[cfg(feature = "export-parquet")]
+fn parquet_schema() -> SchemaRef {
+	Arc::new(Schema::new(vec![
+		Field::new("block_number", DataType::UInt32, false),
+		Field::new("app_id", DataType::UInt32, false),
+		Field::new("index", DataType::UInt32, false),
+		Field::new("data", DataType::Binary, true),
+		Field::new("extrinsic", DataType::Binary, false),
+	]))
+}
+
+#[cfg(feature = "export-parquet")]
+fn parquet_batch(block_number: u32, app_id: u32, extrinsics: &[Vec<u8>]) -> Result<RecordBatch> {
+	let data = extrinsics
+		.iter()
+		.map(|extrinsic| decode_app_data(extrinsic))
+		.collect::<Result<Vec<_>>>()?;
+	let rows = extrinsics.len();
+	let columns: Vec<ArrayRef> = vec![
+		Arc::new(UInt32Array::from(vec![block_number; rows])),
+		Arc::new(UInt32Array::from(vec![app_id; rows])),
+		Arc::new(UInt32Array::from_iter_values(0..rows as u32)),
+		Arc::new(BinaryArray::from_iter(data.iter().map(Option::as_deref))),
+		Arc::new(BinaryArray::from_iter_values(extrinsics.iter())),
+	];
+	Ok(RecordBatch::try_new(parquet_schema(), columns)?)
+}
+
+/// Writes application data of the blocks in the range (inclusive) to the writer, returning number of exported blocks
+pub fn export_app_data(
+	db: &rocksdb::DB,
+	app_id: u32,
+	from: u32,
+	to: u32,
+	format: ExportFormat,
+	writer: impl Write + Send,
+) -> Result<usize> {
+	if from > to {
+		return Err(eyre!("Invalid block range {from}..{to}"));
+	}
+	let cf = db
+		.cf_handle(APP_DATA_CF)
+		.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+
+	let mut writer = match format {
+		ExportFormat::Json => Writer::Json(writer),
+		#[cfg(feature = "export-parquet")]
+		ExportFormat::Parquet => {
+			let properties = WriterProperties::builder()
+				.set_compression(Compression::SNAPPY)
+				.build();
+			let writer = ArrowWriter::try_new(writer, parquet_schema(), Some(properties))?;
+			Writer::Parquet(Box::new(writer))
+		},
+	};
+
+	let mut count = 0;
+	for block_number in from..=to {
+		let key = format!("{app_id}:{block_number}");
+		let Some(value) = db.get_cf(&cf, key.as_bytes())? else {
+			continue;
+		};
+		let data = Vec::<Vec<u8>>::decode(&mut &value[..])
+			.wrap_err(format!("Failed decoding app data of block {block_number}"))?;
+		match writer {
+			Writer::Json(ref mut writer) => {
+				let block = ExportedBlock {
+					block_number,
+					app_id,
+					data_transactions: data
+						.into_iter()
+						.map(DataTransaction::try_from)
+						.collect::<Result<_>>()?,
+				};
+				serde_json::to_writer(&mut *writer, &block)?;
+				writeln!(writer)?;
+			},
+			#[cfg(feature = "export-parquet")]
+			Writer::Parquet(ref mut writer) => {
+				writer.write(&parquet_batch(block_number, app_id, &data)?)?;
+			},
+		}
+		count += 1;
+	}
+	match writer {
+		Writer::Json(mut writer) => writer.flush()?,
+		#[cfg(feature = "export-parquet")]
+		Writer::Parquet(writer) => writer.into_inner()?.flush()?,
+	}
+	Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::{rocks_db::RocksDB, Database, Key};
+
+	#[test]
+	fn exported_app_data() {
+		let dir = std::env::temp_dir().join(format!("avail_light_export_{}", std::process::id()));
+		let (db, rocks_db) = RocksDB::open(dir.to_str().unwrap()).unwrap();
+		db.put(Key::AppData(1, 1), vec![vec![1u8, 2, 3]]).unwrap();
+		db.put(Key::AppData(1, 3), vec![vec![4u8], vec![5u8]])
+			.unwrap();
+		db.put(Key::AppData(2, 2), vec![vec![6u8]]).unwrap();
+		db.put(Key::AppData(1, 5), vec![vec![7u8]]).unwrap();
+
+		let mut exported = vec![];
+		let count = export_app_data(&rocks_db, 1, 1, 4, ExportFormat::Json, &mut exported).unwrap();
+		assert_eq!(count, 2);
+		let blocks = String::from_utf8(exported)
+			.unwrap()
+			.lines()
+			.map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(blocks.len(), 2);
+		assert_eq!(blocks[0]["block_number"], 1);
+		assert_eq!(blocks[1]["block_number"], 3);
+		assert_eq!(blocks[1]["app_id"], 1);
+		assert_eq!(blocks[1]["data_transactions"].as_array().unwrap().len(), 2);
+
+		assert!(export_app_data(&rocks_db, 1, 4, 1, ExportFormat::Json, vec![]).is_err());
+
+		drop((db, rocks_db));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+
+	#[cfg(feature = "export-parquet")]
+	#[test]
+	fn exported_app_data_parquet() {
+		use arrow_array::Array;
+		use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+		let dir =
+			std::env::temp_dir().join(format!("avail_light_export_parquet_{}", std::process::id()));
+		let (db, rocks_db) = RocksDB::open(dir.to_str().unwrap()).unwrap();
+		db.put(Key::AppData(1, 1), vec![vec![1u8, 2, 3]]).unwrap();
+		db.put(Key::AppData(1, 3), vec![vec![4u8], vec![5u8]])
+			.unwrap();
+		db.put(Key::AppData(2, 2), vec![vec![6u8]]).unwrap();
+
+		let output = dir.join("export.parquet");
+		let file = std::fs::File::create(&output).unwrap();
+		let count = export_app_data(&rocks_db, 1, 1, 4, ExportFormat::Parquet, file).unwrap();
+		assert_eq!(count, 2);
+
+		let file = std::fs::File::open(&output).unwrap();
+		let batches = ParquetRecordBatchReaderBuilder::try_new(file)
+			.unwrap()
+			.build()
+			.unwrap()
+			.collect::<Result<Vec<_>, _>>()
+			.unwrap();
+		let column = |name: &str| {
+			batches
+				.iter()
+				.flat_map(|batch| {
+					let column = batch.column_by_name(name).unwrap();
+					let column = column.as_any().downcast_ref::<UInt32Array>().unwrap();
+					column.values().to_vec()
+				})
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(column("block_number"), vec![1, 3, 3]);
+		assert_eq!(column("app_id"), vec![1, 1, 1]);
+		assert_eq!(column("index"), vec![0, 0, 1]);
+		let extrinsics = batches
+			.iter()
+			.flat_map(|batch| {
+				let column = batch.column_by_name("extrinsic").unwrap();
+				let column = column.as_any().downcast_ref::<BinaryArray>().unwrap();
+				column
+					.iter()
+					.map(|value| value.unwrap().to_vec())
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(extrinsics, vec![vec![1u8, 2, 3], vec![4u8], vec![5u8]]);
+
+		drop((db, rocks_db));
+		let _ = std::fs::remove_dir_all(dir);
+	}
+}
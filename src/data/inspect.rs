@@ -0,0 +1,207 @@
+//! Read-only inspection of the light client database.
+//!
+//! Used by the `db inspect` command to summarize stored data while the light client is stopped.
+
+use codec::Decode;
+use color_eyre::eyre::{eyre, Context, Result};
+use rocksdb::IteratorMode;
+use serde::Serialize;
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter},
+};
+
+use crate::{
+	data::{
+		FinalitySyncCheckpoint, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF,
+		FINALITY_SYNC_CHECKPOINT_KEY, STATE_CF,
+	},
+	utils::calculate_confidence,
+};
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct HeadersSummary {
+	pub count: usize,
+	pub first: Option<u32>,
+	pub last: Option<u32>,
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct ConfidenceSummary {
+	pub count: usize,
+	pub min: Option<f64>,
+	pub max: Option<f64>,
+	pub average: Option<f64>,
+}
+
+impl ConfidenceSummary {
+	/// Aggregates confidence statistics from verified cell counts
+	pub fn from_cell_counts(counts: &[u32]) -> Self {
+		if counts.is_empty() {
+			return Self::default();
+		}
+		let confidences = counts
+			.iter()
+			.map(|&count| calculate_confidence(count))
+			.collect::<Vec<_>>();
+		let sum: f64 = confidences.iter().sum();
+		Self {
+			count: confidences.len(),
+			min: confidences.iter().cloned().reduce(f64::min),
+			max: confidences.iter().cloned().reduce(f64::max),
+			average: Some(sum / confidences.len() as f64),
+		}
+	}
+}
+
+#[derive(Serialize, Default, Debug, PartialEq)]
+pub struct AppDataSummary {
+	pub blocks: usize,
+	pub bytes: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Inspection {
+	pub path: String,
+	pub headers: HeadersSummary,
+	pub confidence: ConfidenceSummary,
+	pub app_data: BTreeMap<u32, AppDataSummary>,
+	pub finality_sync_checkpoint: Option<u32>,
+}
+
+fn format_option<T: Display>(value: &Option<T>) -> String {
+	value
+		.as_ref()
+		.map(ToString::to_string)
+		.unwrap_or_else(|| "n/a".to_string())
+}
+
+impl Display for Inspection {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Database: {}", self.path)?;
+		writeln!(
+			f,
+			"Block headers: {} (first: {}, last: {})",
+			self.headers.count,
+			format_option(&self.headers.first),
+			format_option(&self.headers.last)
+		)?;
+		writeln!(
+			f,
+			"Confidence: {} blocks (min: {}, max: {}, average: {})",
+			self.confidence.count,
+			format_option(&self.confidence.min),
+			format_option(&self.confidence.max),
+			format_option(&self.confidence.average)
+		)?;
+		if self.app_data.is_empty() {
+			writeln!(f, "App data: none")?;
+		}
+		for (app_id, summary) in &self.app_data {
+			writeln!(
+				f,
+				"App data (app_id {app_id}): {} blocks, {} bytes",
+				summary.blocks, summary.bytes
+			)?;
+		}
+		write!(
+			f,
+			"Finality sync checkpoint: {}",
+			format_option(&self.finality_sync_checkpoint)
+		)
+	}
+}
+
+fn block_number(key: &[u8]) -> Result<u32> {
+	let bytes: [u8; 4] = key
+		.try_into()
+		.map_err(|_| eyre!("Invalid block number key length: {}", key.len()))?;
+	Ok(u32::from_be_bytes(bytes))
+}
+
+/// Opens database in read-only mode, with all existing column families
+pub fn open_read_only(path: &str) -> Result<rocksdb::DB> {
+	let opts = rocksdb::Options::default();
+	let column_families = rocksdb::DB::list_cf(&opts, path).wrap_err(format!(
+		"Failed to list column families of database at {path}"
+	))?;
+	rocksdb::DB::open_cf_for_read_only(&opts, path, column_families, false).wrap_err(format!(
+		"Failed to open database at {path} in read-only mode"
+	))
+}
+
+/// Collects summary of the data stored in the database
+pub fn inspect(db: &rocksdb::DB, path: &str) -> Result<Inspection> {
+	let mut headers = HeadersSummary::default();
+	if let Some(cf) = db.cf_handle(BLOCK_HEADER_CF) {
+		for item in db.iterator_cf(&cf, IteratorMode::Start) {
+			let (key, _) = item?;
+			let number = block_number(&key)?;
+			headers.count += 1;
+			headers.first = Some(headers.first.map_or(number, |first| first.min(number)));
+			headers.last = Some(headers.last.map_or(number, |last| last.max(number)));
+		}
+	}
+
+	let mut counts = vec![];
+	if let Some(cf) = db.cf_handle(CONFIDENCE_FACTOR_CF) {
+		for item in db.iterator_cf(&cf, IteratorMode::Start) {
+			let (_, value) = item?;
+			counts.push(u32::decode(&mut &value[..]).wrap_err("Failed decoding cell count")?);
+		}
+	}
+
+	let mut app_data = BTreeMap::<u32, AppDataSummary>::new();
+	if let Some(cf) = db.cf_handle(APP_DATA_CF) {
+		for item in db.iterator_cf(&cf, IteratorMode::Start) {
+			let (key, value) = item?;
+			let key = String::from_utf8_lossy(&key);
+			let Some(app_id) = key.split(':').next().and_then(|id| id.parse().ok()) else {
+				return Err(eyre!("Invalid app data key: {key}"));
+			};
+			let summary = app_data.entry(app_id).or_default();
+			summary.blocks += 1;
+			summary.bytes += value.len();
+		}
+	}
+
+	let finality_sync_checkpoint = match db.cf_handle(STATE_CF) {
+		Some(cf) => db
+			.get_cf(&cf, FINALITY_SYNC_CHECKPOINT_KEY.as_bytes())?
+			.map(|value| FinalitySyncCheckpoint::decode(&mut &value[..]))
+			.transpose()
+			.wrap_err("Failed decoding finality sync checkpoint")?
+			.map(|checkpoint| checkpoint.number),
+		None => None,
+	};
+
+	Ok(Inspection {
+		path: path.to_string(),
+		headers,
+		confidence: ConfidenceSummary::from_cell_counts(&counts),
+		app_data,
+		finality_sync_checkpoint,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn confidence_summary_empty() {
+		assert_eq!(
+			ConfidenceSummary::from_cell_counts(&[]),
+			ConfidenceSummary::default()
+		);
+	}
+
+	#[test]
+	fn confidence_summary_stats() {
+		let summary = ConfidenceSummary::from_cell_counts(&[1, 2, 3]);
+		assert_eq!(summary.count, 3);
+		assert_eq!(summary.min, Some(50.0));
+		assert_eq!(summary.max, Some(87.5));
+		assert_eq!(summary.average, Some((50.0 + 75.0 + 87.5) / 3.0));
+	}
+}
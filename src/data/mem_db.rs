@@ -1,5 +1,7 @@
 use crate::data::{
-	Database, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, FINALITY_SYNC_CHECKPOINT_KEY,
+	Database, Key, APP_DATA_CF, BEST_BLOCK_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, CRAWL_CF,
+	DATA_ROOT_CF, DHT_RETRY_CF, FINALITY_SYNC_CHECKPOINT_KEY, JUSTIFICATION_CF, KNOWN_PEERS_KEY,
+	PROOF_FAILURES_KEY, VERIFICATION_CF,
 };
 use color_eyre::eyre::{eyre, Result};
 use serde::{Deserialize, Serialize};
@@ -51,6 +53,18 @@ impl Database for MemoryDB {
 		map.remove(&key.into());
 		Ok(())
 	}
+
+	fn values<T>(&self, column_family: &'static str) -> Result<Vec<T>>
+	where
+		T: for<'a> Deserialize<'a>,
+	{
+		let prefix = format!("{column_family}:");
+		let map = self.map.read().expect("Lock acquired");
+		map.iter()
+			.filter(|(HashMapKey(key), _)| key.starts_with(&prefix))
+			.map(|(_, value)| serde_json::from_str(value).map_err(|error| eyre!("{error}")))
+			.collect()
+	}
 }
 
 impl From<Key> for HashMapKey {
@@ -62,10 +76,27 @@ impl From<Key> for HashMapKey {
 			Key::BlockHeader(block_number) => {
 				HashMapKey(format!("{BLOCK_HEADER_CF}:{block_number}"))
 			},
+			Key::Justification(block_number) => {
+				HashMapKey(format!("{JUSTIFICATION_CF}:{block_number}"))
+			},
 			Key::VerifiedCellCount(block_number) => {
 				HashMapKey(format!("{CONFIDENCE_FACTOR_CF}:{block_number}"))
 			},
+			Key::BlockVerification(block_number) => {
+				HashMapKey(format!("{VERIFICATION_CF}:{block_number}"))
+			},
+			Key::CrawlResult(block_number) => HashMapKey(format!("{CRAWL_CF}:{block_number}")),
+			Key::BestBlock(block_number) => HashMapKey(format!("{BEST_BLOCK_CF}:{block_number}")),
+			Key::DataRootVerification(block_number) => {
+				HashMapKey(format!("{DATA_ROOT_CF}:{block_number}"))
+			},
 			Key::FinalitySyncCheckpoint => HashMapKey(FINALITY_SYNC_CHECKPOINT_KEY.to_string()),
+			Key::KnownPeers => HashMapKey(KNOWN_PEERS_KEY.to_string()),
+			Key::ProofFailures => HashMapKey(PROOF_FAILURES_KEY.to_string()),
+			Key::FailedPut(block_number, key) => HashMapKey(format!(
+				"{DHT_RETRY_CF}:{block_number}:{}",
+				hex::encode(key)
+			)),
 		}
 	}
 }
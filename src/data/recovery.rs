@@ -0,0 +1,173 @@
+//! Recovery of the corrupted database on startup.
+//!
+//! If the database cannot be opened due to the corruption, it is repaired with the RocksDB repairer.
+//! If the repaired database still cannot be opened, the corrupted directory is moved aside (to `{avail_path}.corrupt-{timestamp}`),
+//! and the new database is created. Finality sync checkpoint and known peers are restored from the corrupted database, if it is readable,
+//! so the finality sync continues from the checkpoint, and the rest of the state is rebuilt by the sync.
+//!
+//! Recovery is logged, and the last recovery is exposed on `GET /v2/status`.
+
+use color_eyre::eyre::{eyre, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, sync::Arc};
+use tracing::{info, warn};
+
+use crate::{
+	data::{
+		inspect::open_read_only, rocks_db::RocksDB, FINALITY_SYNC_CHECKPOINT_KEY, KNOWN_PEERS_KEY,
+		STATE_CF,
+	},
+	utils::unix_timestamp_millis,
+};
+
+/// State keys restored from the corrupted database
+const RESTORED_KEYS: [&str; 2] = [FINALITY_SYNC_CHECKPOINT_KEY, KNOWN_PEERS_KEY];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+	/// Database is repaired in place
+	Repaired,
+	/// Corrupted database is moved aside and the new one is created
+	Rebuilt,
+}
+
+/// Recovery of the corrupted database
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DatabaseRecovery {
+	pub action: RecoveryAction,
+	/// Error on which the recovery is started
+	pub error: String,
+	/// Path to which the corrupted database is moved
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub corrupted_path: Option<String>,
+	/// Number of the state entries restored from the corrupted database
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub restored_entries: Option<usize>,
+	/// Recovery time (milliseconds since UNIX epoch)
+	pub recovered_at: u64,
+}
+
+fn is_corruption(error: &color_eyre::Report) -> bool {
+	error
+		.downcast_ref::<rocksdb::Error>()
+		.is_some_and(|error| error.kind() == rocksdb::ErrorKind::Corruption)
+}
+
+/// Copies restored state entries from the corrupted database, returning number of copied entries
+fn restore(corrupted_path: &str, db: &rocksdb::DB) -> Result<usize> {
+	let corrupted = open_read_only(corrupted_path)?;
+	let source_cf = corrupted
+		.cf_handle(STATE_CF)
+		.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+	let target_cf = db
+		.cf_handle(STATE_CF)
+		.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+	let mut count = 0;
+	for key in RESTORED_KEYS {
+		if let Some(value) = corrupted.get_cf(&source_cf, key.as_bytes())? {
+			db.put_cf(&target_cf, key.as_bytes(), value)?;
+			count += 1;
+		}
+	}
+	Ok(count)
+}
+
+/// Moves the corrupted database aside and creates the new one, restoring the state entries
+fn rebuild(path: &str, error: String) -> Result<(RocksDB, Arc<rocksdb::DB>, DatabaseRecovery)> {
+	let recovered_at = unix_timestamp_millis();
+	let corrupted_path = format!("{}.corrupt-{recovered_at}", path.trim_end_matches('/'));
+	fs::rename(path, &corrupted_path).wrap_err(format!(
+		"Failed to move corrupted database to {corrupted_path}"
+	))?;
+	warn!("Corrupted database is moved to {corrupted_path}, creating the new database");
+
+	let (db, rocks_db) = RocksDB::open(path)?;
+	let restored_entries = match restore(&corrupted_path, &rocks_db) {
+		Ok(count) => Some(count),
+		Err(error) => {
+			warn!("Cannot restore state from the corrupted database: {error:#}");
+			None
+		},
+	};
+	let recovery = DatabaseRecovery {
+		action: RecoveryAction::Rebuilt,
+		error,
+		corrupted_path: Some(corrupted_path),
+		restored_entries,
+		recovered_at,
+	};
+	Ok((db, rocks_db, recovery))
+}
+
+/// Opens the database, recovering it if it is corrupted.
+/// Returns the recovery, if the database is recovered.
+pub fn open(path: &str) -> Result<(RocksDB, Arc<rocksdb::DB>, Option<DatabaseRecovery>)> {
+	let error = match RocksDB::open(path) {
+		Ok((db, rocks_db)) => return Ok((db, rocks_db, None)),
+		Err(error) if !is_corruption(&error) => return Err(error),
+		Err(error) => format!("{error:#}"),
+	};
+	warn!("Database at {path} is corrupted, repairing: {error}");
+
+	let repaired = rocksdb::DB::repair(&rocksdb::Options::default(), path)
+		.wrap_err("Failed to repair database")
+		.and_then(|_| RocksDB::open(path));
+	match repaired {
+		Ok((db, rocks_db)) => {
+			info!("Database at {path} is repaired");
+			let recovery = DatabaseRecovery {
+				action: RecoveryAction::Repaired,
+				error,
+				corrupted_path: None,
+				restored_entries: None,
+				recovered_at: unix_timestamp_millis(),
+			};
+			Ok((db, rocks_db, Some(recovery)))
+		},
+		Err(repair_error) => {
+			warn!("Cannot repair database at {path}: {repair_error:#}");
+			let (db, rocks_db, recovery) = rebuild(path, error)?;
+			Ok((db, rocks_db, Some(recovery)))
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::{Database, FinalitySyncCheckpoint, Key};
+
+	#[test]
+	fn rebuilt_database() {
+		let dir = std::env::temp_dir().join(format!("avail_light_recovery_{}", std::process::id()));
+		let path = dir.join("db");
+		let path = path.to_str().unwrap();
+
+		let (db, rocks_db) = RocksDB::open(path).unwrap();
+		let checkpoint = FinalitySyncCheckpoint {
+			number: 10,
+			set_id: 2,
+			validator_set: vec![],
+		};
+		db.put(Key::FinalitySyncCheckpoint, checkpoint).unwrap();
+		db.put(Key::VerifiedCellCount(1), 10u32).unwrap();
+		drop((db, rocks_db));
+
+		let (db, rocks_db, recovery) = rebuild(path, "corrupted".to_string()).unwrap();
+		assert_eq!(recovery.action, RecoveryAction::Rebuilt);
+		assert_eq!(recovery.restored_entries, Some(1));
+		let corrupted_path = recovery.corrupted_path.unwrap();
+		assert!(fs::metadata(&corrupted_path).is_ok());
+
+		let checkpoint = db
+			.get::<FinalitySyncCheckpoint>(Key::FinalitySyncCheckpoint)
+			.unwrap()
+			.unwrap();
+		assert_eq!((checkpoint.number, checkpoint.set_id), (10, 2));
+		assert_eq!(db.get::<u32>(Key::VerifiedCellCount(1)).unwrap(), None);
+
+		drop((db, rocks_db));
+		let _ = fs::remove_dir_all(dir);
+	}
+}
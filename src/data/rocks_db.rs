@@ -1,13 +1,14 @@
 use crate::{
 	data::{
-		self, Key, APP_DATA_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF,
-		FINALITY_SYNC_CHECKPOINT_KEY, KADEMLIA_STORE_CF, STATE_CF,
+		self, Key, APP_DATA_CF, BEST_BLOCK_CF, BLOCK_HEADER_CF, CONFIDENCE_FACTOR_CF, CRAWL_CF,
+		DATA_ROOT_CF, DHT_RETRY_CF, FINALITY_SYNC_CHECKPOINT_KEY, JUSTIFICATION_CF,
+		KADEMLIA_STORE_CF, KNOWN_PEERS_KEY, PROOF_FAILURES_KEY, STATE_CF, VERIFICATION_CF,
 	},
 	network::p2p::ExpirationCompactionFilterFactory,
 };
 use codec::{Decode, Encode};
 use color_eyre::eyre::{eyre, Context, Result};
-use rocksdb::{ColumnFamilyDescriptor, Options};
+use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -26,7 +27,13 @@ impl RocksDB {
 			ColumnFamilyDescriptor::new(BLOCK_HEADER_CF, Options::default()),
 			ColumnFamilyDescriptor::new(APP_DATA_CF, Options::default()),
 			ColumnFamilyDescriptor::new(STATE_CF, Options::default()),
+			ColumnFamilyDescriptor::new(VERIFICATION_CF, Options::default()),
+			ColumnFamilyDescriptor::new(CRAWL_CF, Options::default()),
+			ColumnFamilyDescriptor::new(BEST_BLOCK_CF, Options::default()),
+			ColumnFamilyDescriptor::new(DATA_ROOT_CF, Options::default()),
+			ColumnFamilyDescriptor::new(JUSTIFICATION_CF, Options::default()),
 			ColumnFamilyDescriptor::new(KADEMLIA_STORE_CF, kademlia_store_cf_opts),
+			ColumnFamilyDescriptor::new(DHT_RETRY_CF, Options::default()),
 		];
 
 		let mut db_opts = Options::default();
@@ -36,6 +43,35 @@ impl RocksDB {
 		let db = Arc::new(rocksdb::DB::open_cf_descriptors(&db_opts, path, cf_opts)?);
 		Ok((RocksDB { db: db.clone() }, db))
 	}
+
+	/// Syncs write-ahead log and flushes memtables of all column families to the disk
+	pub fn flush(&self) -> Result<()> {
+		self.db
+			.flush_wal(true)
+			.wrap_err("Failed to flush write-ahead log")?;
+		for name in [
+			CONFIDENCE_FACTOR_CF,
+			BLOCK_HEADER_CF,
+			APP_DATA_CF,
+			STATE_CF,
+			VERIFICATION_CF,
+			CRAWL_CF,
+			BEST_BLOCK_CF,
+			DATA_ROOT_CF,
+			JUSTIFICATION_CF,
+			KADEMLIA_STORE_CF,
+			DHT_RETRY_CF,
+		] {
+			let cf = self
+				.db
+				.cf_handle(name)
+				.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+			self.db
+				.flush_cf(&cf)
+				.wrap_err(format!("Failed to flush column family {name}"))?;
+		}
+		Ok(())
+	}
 }
 
 type RocksKey = (Option<&'static str>, Vec<u8>);
@@ -50,14 +86,33 @@ impl From<Key> for (Option<&'static str>, Vec<u8>) {
 			Key::BlockHeader(block_number) => {
 				(Some(BLOCK_HEADER_CF), block_number.to_be_bytes().to_vec())
 			},
+			Key::Justification(block_number) => {
+				(Some(JUSTIFICATION_CF), block_number.to_be_bytes().to_vec())
+			},
 			Key::VerifiedCellCount(block_number) => (
 				Some(CONFIDENCE_FACTOR_CF),
 				block_number.to_be_bytes().to_vec(),
 			),
+			Key::BlockVerification(block_number) => {
+				(Some(VERIFICATION_CF), block_number.to_be_bytes().to_vec())
+			},
+			Key::CrawlResult(block_number) => (Some(CRAWL_CF), block_number.to_be_bytes().to_vec()),
+			Key::BestBlock(block_number) => {
+				(Some(BEST_BLOCK_CF), block_number.to_be_bytes().to_vec())
+			},
+			Key::DataRootVerification(block_number) => {
+				(Some(DATA_ROOT_CF), block_number.to_be_bytes().to_vec())
+			},
 			Key::FinalitySyncCheckpoint => (
 				Some(STATE_CF),
 				FINALITY_SYNC_CHECKPOINT_KEY.as_bytes().to_vec(),
 			),
+			Key::KnownPeers => (Some(STATE_CF), KNOWN_PEERS_KEY.as_bytes().to_vec()),
+			Key::ProofFailures => (Some(STATE_CF), PROOF_FAILURES_KEY.as_bytes().to_vec()),
+			Key::FailedPut(block_number, key) => (
+				Some(DHT_RETRY_CF),
+				[&block_number.to_be_bytes()[..], &key].concat(),
+			),
 		}
 	}
 }
@@ -134,4 +189,21 @@ impl data::Database for RocksDB {
 			.delete_cf(&cf_handle, key)
 			.wrap_err("Delete operation with Column Family failed on RocksDB")
 	}
+
+	fn values<T>(&self, column_family: &'static str) -> Result<Vec<T>>
+	where
+		T: for<'a> Deserialize<'a> + Decode,
+	{
+		let cf_handle = self
+			.db
+			.cf_handle(column_family)
+			.ok_or_else(|| eyre!("Couldn't get Column Family handle from RocksDB"))?;
+		self.db
+			.iterator_cf(&cf_handle, IteratorMode::Start)
+			.map(|item| {
+				let (_, value) = item.wrap_err("Iteration with Column Family failed on RocksDB")?;
+				<T>::decode(&mut &value[..]).wrap_err("Failed decoding the value.")
+			})
+			.collect()
+	}
 }
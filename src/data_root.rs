@@ -0,0 +1,139 @@
+//! Verification of the app data against the header data root.
+//!
+//! Data root is the Merkle root (Keccak-256 binary Merkle tree) of the blobs submitted in the block, in the extrinsics order.
+//! Once the app data is reconstructed, data root is recomputed from the decoded blobs and compared with the data root in the header extension,
+//! so the reconstructed data is verified end-to-end, not only against the commitments.
+//!
+//! # Notes
+//!
+//! Data root commits to the blobs of all apps, so it can be recomputed only if the block contains data of the configured app only.
+//! Otherwise, verification result is `incomplete`.
+
+use avail_core::{AppId, DataLookup};
+use codec::{Decode, Encode};
+use color_eyre::Result;
+use kate_recovery::com::AppData;
+use serde::{Deserialize, Serialize};
+use sp_core::{keccak_256, KeccakHasher, H256};
+
+use crate::utils::decode_app_data;
+
+/// Result of the data root verification
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Encode, Decode, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataRootVerification {
+	/// Recomputed data root matches the header data root
+	Verified,
+	/// Recomputed data root doesn't match the header data root
+	Mismatch,
+	/// Block contains data of other apps, so the data root cannot be recomputed
+	Incomplete,
+}
+
+/// Returns blobs submitted in the decoded app extrinsics, in the extrinsics order
+pub fn blobs(app_data: &AppData) -> Result<Vec<Vec<u8>>> {
+	let blobs = app_data
+		.iter()
+		.map(|extrinsic| decode_app_data(extrinsic))
+		.collect::<Result<Vec<_>>>()?;
+	Ok(blobs.into_iter().flatten().collect())
+}
+
+/// Computes data root of the given blobs
+pub fn data_root(blobs: &[Vec<u8>]) -> H256 {
+	binary_merkle_tree::merkle_root::<KeccakHasher, _>(blobs)
+}
+
+/// Merkle proof of the blob against the data root
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataProof {
+	pub root: H256,
+	/// Sibling hashes, from the leaf to the root
+	pub proof: Vec<H256>,
+	pub number_of_leaves: usize,
+	pub leaf_index: usize,
+	/// Hash of the blob
+	pub leaf: H256,
+}
+
+/// Creates Merkle proof of the blob with the given index, or `None` if index is out of range
+pub fn proof(blobs: &[Vec<u8>], index: usize) -> Option<DataProof> {
+	let blob = blobs.get(index)?;
+	let proof = binary_merkle_tree::merkle_proof::<KeccakHasher, _, _>(blobs, index);
+	Some(DataProof {
+		root: proof.root,
+		proof: proof.proof,
+		number_of_leaves: proof.number_of_leaves,
+		leaf_index: proof.leaf_index,
+		leaf: keccak_256(blob).into(),
+	})
+}
+
+/// Checks if the block contains data of the given app only, so the data root can be recomputed from its data
+pub fn is_complete(lookup: &DataLookup, app_id: AppId) -> bool {
+	lookup
+		.range_of(app_id)
+		.is_some_and(|range| range.start == 0 && range.end == lookup.len())
+}
+
+/// Verifies data root of the block, using the reconstructed app data
+pub fn verify(
+	lookup: &DataLookup,
+	app_id: AppId,
+	app_data: &AppData,
+	expected: H256,
+) -> Result<DataRootVerification> {
+	if !is_complete(lookup, app_id) {
+		return Ok(DataRootVerification::Incomplete);
+	}
+	if data_root(&blobs(app_data)?) != expected {
+		return Ok(DataRootVerification::Mismatch);
+	}
+	Ok(DataRootVerification::Verified)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn data_root_of_blobs() {
+		assert_eq!(data_root(&[]), H256::zero());
+
+		let blob = b"blob".to_vec();
+		let leaf = keccak_256(&blob);
+		assert_eq!(data_root(&[blob.clone()]), H256::from(leaf));
+
+		let other = b"other".to_vec();
+		let other_leaf = keccak_256(&other);
+		let root = keccak_256(&[leaf, other_leaf].concat());
+		assert_eq!(data_root(&[blob, other]), H256::from(root));
+	}
+
+	#[test]
+	fn blob_proof() {
+		let blobs = vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()];
+		assert_eq!(proof(&blobs, 3), None);
+
+		let proof = proof(&blobs, 1).unwrap();
+		assert_eq!(proof.root, data_root(&blobs));
+		assert_eq!(proof.leaf, H256::from(keccak_256(b"second")));
+		assert!(binary_merkle_tree::verify_proof::<KeccakHasher, _, _>(
+			&proof.root,
+			proof.proof,
+			proof.number_of_leaves,
+			proof.leaf_index,
+			&blobs[1],
+		));
+	}
+
+	#[test]
+	fn incomplete_verification() {
+		let lookup =
+			DataLookup::from_id_and_len_iter([(1u32, 1usize), (2, 1)].into_iter()).unwrap();
+		assert_eq!(
+			verify(&lookup, AppId(1), &vec![], H256::zero()).unwrap(),
+			DataRootVerification::Incomplete
+		);
+	}
+}
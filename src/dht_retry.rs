@@ -0,0 +1,375 @@
+//! Persistent queue of the failed DHT inserts.
+//!
+//! Cell and row records which failed to be inserted into the DHT (quorum failure or timeout) are queued in the database,
+//! instead of being dropped, and re-inserted by the maintenance with exponential backoff.
+//! Records are removed from the queue once inserted, after [`MAX_ATTEMPTS`] retries, or once they expire.
+//!
+//! # Notes
+//!
+//! Queue is updated by the single task, so concurrent insert results and retries don't overwrite each other.
+//! Each queued record is stored separately, keyed by the block number and the record key, so only the changed records are written.
+//! If the queue limit is reached, records of the oldest blocks are evicted.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use libp2p::kad::{Record, RecordKey};
+use std::{
+	collections::{BTreeSet, HashMap},
+	time::{Duration, Instant},
+};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+	data::{Database, FailedPut, Key, DHT_RETRY_CF},
+	utils::unix_timestamp_millis,
+};
+
+/// Maximum number of the retries of the failed insert
+pub const MAX_ATTEMPTS: u32 = 8;
+/// Delay before the first retry, doubled on each retry
+const BASE_DELAY: Duration = Duration::from_secs(30);
+/// Maximum delay between the retries
+const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+pub enum Message {
+	Failed { record: Record, block_number: u32 },
+	Inserted(RecordKey),
+	Due(oneshot::Sender<Vec<(u32, Record)>>),
+}
+
+/// Sender of the DHT insert results to the retry queue, noop if queue is not running
+#[derive(Clone, Default)]
+pub struct RetryQueue(Option<mpsc::UnboundedSender<Message>>);
+
+impl RetryQueue {
+	pub fn new() -> (Self, mpsc::UnboundedReceiver<Message>) {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		(RetryQueue(Some(sender)), receiver)
+	}
+
+	fn send(&self, message: Message) {
+		if let Some(sender) = &self.0 {
+			if sender.send(message).is_err() {
+				debug!("DHT retry queue is closed");
+			}
+		}
+	}
+
+	/// Queues the record which failed to be inserted
+	pub fn failed(&self, record: Record, block_number: u32) {
+		self.send(Message::Failed {
+			record,
+			block_number,
+		});
+	}
+
+	/// Removes the inserted record from the queue
+	pub fn inserted(&self, key: RecordKey) {
+		self.send(Message::Inserted(key));
+	}
+
+	/// Returns queued records due for the retry, with their block numbers
+	pub async fn due(&self) -> Result<Vec<(u32, Record)>> {
+		let Some(sender) = &self.0 else {
+			return Ok(vec![]);
+		};
+		let (response_sender, response_receiver) = oneshot::channel();
+		sender
+			.send(Message::Due(response_sender))
+			.map_err(|_| eyre!("DHT retry queue is closed"))?;
+		response_receiver
+			.await
+			.wrap_err("DHT retry queue is closed")
+	}
+}
+
+fn backoff(attempts: u32) -> Duration {
+	BASE_DELAY
+		.saturating_mul(2u32.saturating_pow(attempts))
+		.min(MAX_DELAY)
+}
+
+fn to_record(failed: &FailedPut, now: u64) -> Record {
+	Record {
+		key: failed.key.clone().into(),
+		value: failed.value.clone(),
+		publisher: None,
+		expires: failed.expires_at.map(|expires_at| {
+			Instant::now() + Duration::from_millis(expires_at.saturating_sub(now))
+		}),
+	}
+}
+
+/// Change of the queued record, which has to be persisted
+enum Update {
+	Put(FailedPut),
+	Delete(u32, Vec<u8>),
+}
+
+struct Queue {
+	records: HashMap<Vec<u8>, FailedPut>,
+	/// Block numbers and keys of the queued records, so the records of the oldest blocks are evicted first
+	by_block: BTreeSet<(u32, Vec<u8>)>,
+	limit: usize,
+	/// Changes of the records since the last [`Queue::take_updates`] call
+	updates: Vec<Update>,
+}
+
+impl Queue {
+	fn new(records: Vec<FailedPut>, limit: usize) -> Self {
+		Queue {
+			by_block: records
+				.iter()
+				.map(|failed| (failed.block_number, failed.key.clone()))
+				.collect(),
+			records: records
+				.into_iter()
+				.map(|failed| (failed.key.clone(), failed))
+				.collect(),
+			limit,
+			updates: vec![],
+		}
+	}
+
+	fn insert(&mut self, failed: FailedPut) {
+		self.by_block
+			.insert((failed.block_number, failed.key.clone()));
+		self.updates.push(Update::Put(failed.clone()));
+		self.records.insert(failed.key.clone(), failed);
+	}
+
+	fn remove(&mut self, key: &[u8]) -> bool {
+		let Some(failed) = self.records.remove(key) else {
+			return false;
+		};
+		self.by_block
+			.remove(&(failed.block_number, failed.key.clone()));
+		self.updates
+			.push(Update::Delete(failed.block_number, failed.key));
+		true
+	}
+
+	/// Returns changes of the records since the last call
+	fn take_updates(&mut self) -> Vec<Update> {
+		std::mem::take(&mut self.updates)
+	}
+
+	/// Applies the message, returning `true` if the queue is changed
+	fn apply(&mut self, message: Message, now: u64) -> bool {
+		match message {
+			Message::Failed {
+				record,
+				block_number,
+			} => {
+				let key = record.key.to_vec();
+				// record which failed again is already rescheduled
+				if self.records.contains_key(&key) {
+					return false;
+				}
+				if self.records.len() >= self.limit {
+					if let Some((_, evicted)) = self.by_block.first().cloned() {
+						self.remove(&evicted);
+					}
+				}
+				let expires_at = record.expires.map(|expires| {
+					now + expires
+						.saturating_duration_since(Instant::now())
+						.as_millis() as u64
+				});
+				let failed = FailedPut {
+					key,
+					value: record.value,
+					block_number,
+					attempts: 0,
+					next_retry: now + BASE_DELAY.as_millis() as u64,
+					expires_at,
+				};
+				self.insert(failed);
+				true
+			},
+			Message::Inserted(key) => self.remove(key.as_ref()),
+			Message::Due(response_sender) => {
+				let dropped_keys = self
+					.records
+					.values()
+					.filter(|failed| {
+						let is_expired = failed
+							.expires_at
+							.is_some_and(|expires_at| expires_at <= now);
+						let is_due = failed.next_retry <= now;
+						is_expired || (is_due && failed.attempts >= MAX_ATTEMPTS)
+					})
+					.map(|failed| failed.key.clone())
+					.collect::<Vec<_>>();
+				for key in &dropped_keys {
+					self.remove(key);
+				}
+				let dropped = dropped_keys.len();
+				if dropped > 0 {
+					warn!(dropped, "Failed DHT inserts dropped from the retry queue");
+				}
+
+				let mut due = vec![];
+				for failed in self.records.values_mut() {
+					if failed.next_retry > now {
+						continue;
+					}
+					failed.attempts += 1;
+					failed.next_retry = now + backoff(failed.attempts).as_millis() as u64;
+					due.push((failed.block_number, to_record(failed, now)));
+					self.updates.push(Update::Put(failed.clone()));
+				}
+				let changed = dropped > 0 || !due.is_empty();
+				_ = response_sender.send(due);
+				changed
+			},
+		}
+	}
+}
+
+/// Returns queued failed inserts
+pub fn queued(db: &impl Database) -> Result<Vec<FailedPut>> {
+	db.values(DHT_RETRY_CF)
+		.wrap_err("Failed to get DHT retry queue from the database")
+}
+
+fn persist(db: &impl Database, update: Update) -> Result<()> {
+	match update {
+		Update::Put(failed) => db.put(
+			Key::FailedPut(failed.block_number, failed.key.clone()),
+			failed,
+		),
+		Update::Delete(block_number, key) => db.delete(Key::FailedPut(block_number, key)),
+	}
+}
+
+/// Applies insert results and retries received from the queue senders, persisting the changed records
+pub async fn run(db: impl Database, limit: usize, mut receiver: mpsc::UnboundedReceiver<Message>) {
+	info!("Starting DHT retry queue...");
+	let records = match queued(&db) {
+		Ok(records) => records,
+		Err(error) => {
+			error!("Cannot load DHT retry queue: {error:#}");
+			vec![]
+		},
+	};
+	if !records.is_empty() {
+		info!(queued = records.len(), "Failed DHT inserts loaded");
+	}
+	let mut queue = Queue::new(records, limit);
+
+	while let Some(message) = receiver.recv().await {
+		queue.apply(message, unix_timestamp_millis());
+		for update in queue.take_updates() {
+			if let Err(error) = persist(&db, update) {
+				error!("Failed to store DHT retry queue record: {error:#}");
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::mem_db::MemoryDB;
+
+	fn record(key: &str) -> Record {
+		Record {
+			key: key.as_bytes().to_vec().into(),
+			value: vec![1],
+			publisher: None,
+			expires: Some(Instant::now() + Duration::from_secs(3600)),
+		}
+	}
+
+	fn due(queue: &mut Queue, now: u64) -> Vec<(u32, Record)> {
+		let (sender, mut receiver) = oneshot::channel();
+		queue.apply(Message::Due(sender), now);
+		receiver.try_recv().unwrap()
+	}
+
+	#[test]
+	fn retry_with_backoff() {
+		let mut queue = Queue::new(vec![], 2);
+		let now = 1_000_000;
+		assert!(queue.apply(
+			Message::Failed {
+				record: record("1:0:0"),
+				block_number: 1,
+			},
+			now,
+		));
+		assert!(due(&mut queue, now).is_empty());
+
+		let retried = due(&mut queue, now + 30_000);
+		assert_eq!(retried.len(), 1);
+		assert_eq!(retried[0].0, 1);
+		assert_eq!(retried[0].1.value, vec![1]);
+		// next retry is delayed twice as long
+		assert!(due(&mut queue, now + 89_999).is_empty());
+		assert_eq!(due(&mut queue, now + 90_000).len(), 1);
+
+		// failed retry doesn't reset the attempts
+		assert!(!queue.apply(
+			Message::Failed {
+				record: record("1:0:0"),
+				block_number: 1,
+			},
+			now,
+		));
+		assert_eq!(queue.records[b"1:0:0".as_slice()].attempts, 2);
+
+		assert!(queue.apply(Message::Inserted(record("1:0:0").key), now));
+		assert!(queue.records.is_empty());
+	}
+
+	#[test]
+	fn limit_and_expiration() {
+		let mut queue = Queue::new(vec![], 2);
+		let now = 1_000_000;
+		for (key, block_number) in [("2:0:0", 2), ("1:0:0", 1), ("3:0:0", 3)] {
+			queue.apply(
+				Message::Failed {
+					record: record(key),
+					block_number,
+				},
+				now,
+			);
+		}
+		// record of the oldest block is evicted
+		assert_eq!(queue.records.len(), 2);
+		assert!(!queue.records.contains_key(b"1:0:0".as_slice()));
+		assert_eq!(queue.by_block.first().map(|(block, _)| *block), Some(2));
+
+		// expired records are dropped
+		assert!(due(&mut queue, now + 3_600_000).is_empty());
+		assert!(queue.records.is_empty());
+	}
+
+	#[tokio::test]
+	async fn persisted_queue() {
+		let db = MemoryDB::default();
+		let (retry_queue, receiver) = RetryQueue::new();
+		retry_queue.failed(record("1:0:0"), 1);
+		drop(retry_queue);
+		run(db.clone(), 10, receiver).await;
+		let queued = queued(&db).unwrap();
+		assert_eq!(queued.len(), 1);
+		assert_eq!((queued[0].block_number, queued[0].attempts), (1, 0));
+
+		// inserted records are deleted, other records are kept
+		let (retry_queue, receiver) = RetryQueue::new();
+		retry_queue.failed(record("2:0:0"), 2);
+		retry_queue.inserted(record("1:0:0").key);
+		drop(retry_queue);
+		run(db.clone(), 10, receiver).await;
+		let queued = queued(&db).unwrap();
+		assert_eq!(queued.len(), 1);
+		assert_eq!(queued[0].block_number, 2);
+
+		assert!(RetryQueue::default().due().await.unwrap().is_empty());
+	}
+}
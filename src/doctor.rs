@@ -0,0 +1,266 @@
+//! Startup self-test of the light client environment.
+//!
+//! # Checks
+//!
+//! * Data directory is writable, and the existing database can be opened and read without errors
+//! * Each configured RPC node is reachable, with the expected genesis hash and system version
+//! * Local clock doesn't drift from the best block timestamp of the first reachable RPC node
+//! * Each bootstrap peer can be dialed
+//!
+//! Checks are independent, so failed check doesn't skip the others.
+//! Self-test is meant to be run before the light client is started, since database and P2P port cannot be shared with the running client.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use libp2p::{Multiaddr, PeerId};
+use std::{
+	fmt::{self, Display},
+	fs,
+	future::Future,
+	path::Path,
+	sync::Arc,
+	time::Duration,
+};
+use tokio::sync::mpsc;
+
+use crate::{
+	data::inspect,
+	network::{p2p, rpc},
+	shutdown::{Controller, Phase, ShutdownReason},
+	telemetry::NoopMetrics,
+	types::{LibP2PConfig, RuntimeConfig},
+	utils::unix_timestamp_millis,
+};
+
+/// Timeout of the single network check
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum allowed difference between the local clock and the best block timestamp,
+/// which includes the block time, since best block can be produced up to one block time ago
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// Result of the single check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+	pub name: String,
+	pub passed: bool,
+	/// Check details on success, or the failure reason
+	pub details: String,
+}
+
+impl Check {
+	fn new(name: impl Into<String>, result: Result<String>) -> Self {
+		let name = name.into();
+		match result {
+			Ok(details) => Check {
+				name,
+				passed: true,
+				details,
+			},
+			Err(error) => Check {
+				name,
+				passed: false,
+				details: format!("{error:#}"),
+			},
+		}
+	}
+}
+
+/// Results of all checks
+#[derive(Debug, Default)]
+pub struct Report {
+	pub checks: Vec<Check>,
+}
+
+impl Report {
+	pub fn is_passed(&self) -> bool {
+		self.checks.iter().all(|check| check.passed)
+	}
+}
+
+impl Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for check in &self.checks {
+			let status = if check.passed { "PASS" } else { "FAIL" };
+			writeln!(f, "[{status}] {}: {}", check.name, check.details)?;
+		}
+		let failed = self.checks.iter().filter(|check| !check.passed).count();
+		write!(f, "{} checks, {failed} failed", self.checks.len())
+	}
+}
+
+async fn with_timeout<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+	tokio::time::timeout(CHECK_TIMEOUT, future)
+		.await
+		.map_err(|_| eyre!("Timed out after {CHECK_TIMEOUT:?}"))?
+}
+
+fn check_data_dir(path: &str) -> Result<String> {
+	fs::create_dir_all(path).wrap_err(format!("Failed to create directory {path}"))?;
+	let probe = Path::new(path).join(".doctor");
+	fs::write(&probe, b"doctor").wrap_err(format!("Directory {path} is not writable"))?;
+	fs::remove_file(&probe).wrap_err(format!("Failed to remove {}", probe.display()))?;
+	Ok(format!("{path} is writable"))
+}
+
+fn check_database(path: &str) -> Result<String> {
+	if !Path::new(path).join("CURRENT").exists() {
+		return Ok(format!(
+			"No database at {path}, it will be created on start"
+		));
+	}
+	// reading all headers and stored confidence surfaces the corrupted entries
+	let db = inspect::open_read_only(path)?;
+	let inspection = inspect::inspect(&db, path)?;
+	Ok(format!(
+		"Database opened, {} headers stored",
+		inspection.headers.count
+	))
+}
+
+/// Returns the difference between the local clock and the block timestamp, in milliseconds
+fn clock_skew(now: u64, block_timestamp: u64) -> u64 {
+	now.abs_diff(block_timestamp)
+}
+
+fn check_clock_skew(skew: u64) -> Result<String> {
+	let skew = Duration::from_millis(skew);
+	if skew > MAX_CLOCK_SKEW {
+		return Err(eyre!(
+			"Local clock differs from the best block timestamp by {skew:?} (maximum {MAX_CLOCK_SKEW:?})"
+		));
+	}
+	Ok(format!(
+		"Local clock differs from the best block timestamp by {skew:?}"
+	))
+}
+
+async fn dial(client: &p2p::Client, peer_id: PeerId, address: Multiaddr) -> Result<String> {
+	let info = with_timeout(client.dial_peer(peer_id, vec![address])).await?;
+	Ok(format!("Connected in {:?}", info.established_in))
+}
+
+async fn check_bootstraps(cfg: &RuntimeConfig, report: &mut Report) -> Result<()> {
+	let shutdown = Controller::<ShutdownReason>::new();
+	let cfg_libp2p: LibP2PConfig = cfg.into();
+	let (id_keys, _) = p2p::keypair(&cfg_libp2p)?;
+
+	// temporary database is used, since configured one can be used by the light client
+	#[cfg(feature = "kademlia-rocksdb")]
+	let temp_path = std::env::temp_dir().join(format!("avail-light-doctor-{}", std::process::id()));
+	#[cfg(feature = "kademlia-rocksdb")]
+	let (_, rocks_db) = crate::data::rocks_db::RocksDB::open(&temp_path.to_string_lossy())?;
+
+	let (sender, receiver) = mpsc::unbounded_channel();
+	let event_loop = p2p::EventLoop::new(
+		cfg_libp2p,
+		&id_keys,
+		false,
+		cfg.ws_transport_enable,
+		shutdown.phase(Phase::Network),
+		#[cfg(feature = "kademlia-rocksdb")]
+		rocks_db,
+	)
+	.await;
+	tokio::spawn(event_loop.run(Arc::new(NoopMetrics), receiver));
+	let client = p2p::Client::new(sender, cfg.dht_parallelization_limit, cfg.kad_record_ttl);
+
+	let bootstraps: Vec<(PeerId, Multiaddr)> = cfg.bootstraps.iter().map(Into::into).collect();
+	for (peer_id, address) in bootstraps {
+		let name = format!("Bootstrap {peer_id}");
+		report
+			.checks
+			.push(Check::new(name, dial(&client, peer_id, address).await));
+	}
+
+	// event loop exits once the command channel is closed
+	drop(client);
+	#[cfg(feature = "kademlia-rocksdb")]
+	_ = fs::remove_dir_all(temp_path);
+	Ok(())
+}
+
+/// Runs all checks, returning the report
+pub async fn run(cfg: &RuntimeConfig) -> Report {
+	let mut report = Report::default();
+
+	report.checks.push(Check::new(
+		"Data directory",
+		check_data_dir(&cfg.avail_path),
+	));
+	report
+		.checks
+		.push(Check::new("Database", check_database(&cfg.avail_path)));
+
+	let mut skew = None;
+	let proxy = cfg.outbound_proxy();
+	let user_agent = cfg.rpc_user_agent();
+	for host in &cfg.full_node_ws {
+		let probe = rpc::Client::probe(
+			host,
+			&cfg.genesis_hash,
+			proxy.as_ref(),
+			user_agent.as_deref(),
+		);
+		let result = match with_timeout(probe).await {
+			Ok((node, block_timestamp)) => {
+				skew = skew.or(Some(clock_skew(unix_timestamp_millis(), block_timestamp)));
+				Ok(format!(
+					"Connected to {}, spec version {}",
+					node.system_version, node.spec_version
+				))
+			},
+			Err(error) => Err(error),
+		};
+		report
+			.checks
+			.push(Check::new(format!("RPC {host}"), result));
+	}
+
+	let skew = skew.ok_or_else(|| eyre!("No RPC node is reachable"));
+	report
+		.checks
+		.push(Check::new("Clock skew", skew.and_then(check_clock_skew)));
+
+	if let Err(error) = check_bootstraps(cfg, &mut report).await {
+		report.checks.push(Check::new(
+			"Bootstraps",
+			Err(error.wrap_err("Cannot start P2P")),
+		));
+	}
+
+	report
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn clock_skew_check() {
+		assert_eq!(clock_skew(1_000, 61_000), 60_000);
+		assert_eq!(clock_skew(61_000, 1_000), 60_000);
+		assert!(check_clock_skew(60_000).is_ok());
+		assert!(check_clock_skew(60_001).is_err());
+	}
+
+	#[test]
+	fn report() {
+		let mut report = Report::default();
+		report
+			.checks
+			.push(Check::new("Database", Ok("Database opened".to_string())));
+		assert!(report.is_passed());
+
+		report.checks.push(Check::new(
+			"Clock skew",
+			Err(eyre!("No RPC node is reachable")),
+		));
+		assert!(!report.is_passed());
+		assert_eq!(
+			report.to_string(),
+			"[PASS] Database: Database opened\n[FAIL] Clock skew: No RPC node is reachable\n2 checks, 1 failed"
+		);
+	}
+}
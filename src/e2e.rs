@@ -0,0 +1,310 @@
+//! End-to-end test harness against a local Avail dev node.
+//!
+//! Harness launches the dev node binary (or attaches to an already running node), starts the light client in the app client mode,
+//! submits data with the dev account, and waits until the light client reaches the target confidence and reconstructs the submitted data.
+//! Tests are run with the `e2e` feature enabled:
+//!
+//! ```sh
+//! AVAIL_E2E_NODE_BIN=./avail-node cargo test --features e2e e2e:: -- --test-threads 1
+//! ```
+//!
+//! Harness is configured with environment variables:
+//!
+//! * `AVAIL_E2E_NODE_URL` - WebSocket URL of the running node to attach to (default: `ws://127.0.0.1:9944`)
+//! * `AVAIL_E2E_NODE_BIN` - path of the node binary, launched with `--dev --tmp` if node URL is not set
+//! * `AVAIL_E2E_APP_ID` - application ID of the submitted data (default: 1)
+//! * `AVAIL_E2E_TIMEOUT` - timeout in seconds of each step (default: 120)
+//!
+//! # Notes
+//!
+//! Light client runs without bootstrap peers, so cells are fetched from RPC, and the P2P part of the pipeline is not covered.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use std::{
+	env,
+	path::PathBuf,
+	process::Stdio,
+	time::{Duration, Instant},
+};
+use subxt_signer::sr25519::dev;
+use tokio::{
+	net::TcpStream,
+	process::{Child, Command},
+};
+use tracing::info;
+
+use crate::{
+	api::v2::{
+		transactions::{Submit, Submitter},
+		types::{Base64, SubmitResponse, Transaction},
+	},
+	data::{Database, Key},
+	node::Node,
+	shutdown::ShutdownReason,
+	types::{IdentityConfig, OptionBlockRange, RuntimeConfig},
+	utils::{calculate_confidence, decode_app_data},
+};
+
+const DEFAULT_NODE_URL: &str = "ws://127.0.0.1:9944";
+const DEFAULT_APP_ID: u32 = 1;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+/// Interval in which the node readiness and the light client progress are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Configuration of the harness, loaded from the environment
+#[derive(Clone, Debug)]
+pub struct HarnessConfig {
+	pub node_url: Option<String>,
+	pub node_bin: Option<PathBuf>,
+	pub app_id: u32,
+	pub timeout: Duration,
+}
+
+impl HarnessConfig {
+	pub fn from_env() -> Result<Self> {
+		let app_id = env::var("AVAIL_E2E_APP_ID")
+			.map(|app_id| app_id.parse().wrap_err("Invalid AVAIL_E2E_APP_ID"))
+			.unwrap_or(Ok(DEFAULT_APP_ID))?;
+		let timeout = env::var("AVAIL_E2E_TIMEOUT")
+			.map(|timeout| {
+				timeout
+					.parse()
+					.map(Duration::from_secs)
+					.wrap_err("Invalid AVAIL_E2E_TIMEOUT")
+			})
+			.unwrap_or(Ok(DEFAULT_TIMEOUT))?;
+		Ok(HarnessConfig {
+			node_url: env::var("AVAIL_E2E_NODE_URL").ok(),
+			node_bin: env::var("AVAIL_E2E_NODE_BIN").ok().map(PathBuf::from),
+			app_id,
+			timeout,
+		})
+	}
+}
+
+/// Local dev node, killed on drop if launched by the harness
+pub struct DevNode {
+	pub url: String,
+	/// Launched node process, `None` if attached to the running node
+	_process: Option<Child>,
+}
+
+impl DevNode {
+	/// Attaches to the running node, or launches the configured node binary
+	pub async fn start(cfg: &HarnessConfig) -> Result<Self> {
+		let node = match (&cfg.node_url, &cfg.node_bin) {
+			(Some(url), _) => DevNode::attach(url),
+			(None, Some(node_bin)) => DevNode::launch(node_bin)?,
+			(None, None) => DevNode::attach(DEFAULT_NODE_URL),
+		};
+		node.wait_ready(cfg.timeout).await?;
+		Ok(node)
+	}
+
+	fn attach(url: &str) -> Self {
+		info!("Attaching to the dev node at {url}");
+		DevNode {
+			url: url.to_string(),
+			_process: None,
+		}
+	}
+
+	fn launch(node_bin: &PathBuf) -> Result<Self> {
+		info!("Launching the dev node {}", node_bin.display());
+		let process = Command::new(node_bin)
+			.args(["--dev", "--tmp", "--rpc-port", "9944"])
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.kill_on_drop(true)
+			.spawn()
+			.wrap_err(format!("Failed to launch {}", node_bin.display()))?;
+		Ok(DevNode {
+			url: DEFAULT_NODE_URL.to_string(),
+			_process: Some(process),
+		})
+	}
+
+	/// Waits until the node RPC port accepts connections
+	async fn wait_ready(&self, timeout: Duration) -> Result<()> {
+		let address = self
+			.url
+			.trim_start_matches("ws://")
+			.trim_start_matches("wss://")
+			.trim_end_matches('/')
+			.to_string();
+		let started = Instant::now();
+		while TcpStream::connect(&address).await.is_err() {
+			if started.elapsed() > timeout {
+				return Err(eyre!(
+					"Dev node at {} is not ready after {timeout:?}",
+					self.url
+				));
+			}
+			tokio::time::sleep(POLL_INTERVAL).await;
+		}
+		Ok(())
+	}
+}
+
+/// Light client running against the dev node, with the submitter of the dev account
+pub struct Harness {
+	pub cfg: HarnessConfig,
+	pub dev_node: DevNode,
+	pub node: Node,
+	/// Target confidence of the light client
+	pub confidence: f64,
+	submitter: Submitter,
+	avail_path: PathBuf,
+}
+
+impl Harness {
+	/// Starts the dev node and the light client in the app client mode
+	pub async fn start(cfg: HarnessConfig) -> Result<Self> {
+		let dev_node = DevNode::start(&cfg).await?;
+
+		let avail_path = env::temp_dir().join(format!("avail_light_e2e_{}", std::process::id()));
+		let identity_path = avail_path.join("identity.toml");
+		std::fs::create_dir_all(&avail_path)?;
+
+		let mut runtime_cfg = RuntimeConfig {
+			full_node_ws: vec![dev_node.url.clone()],
+			app_id: Some(cfg.app_id),
+			avail_path: avail_path.join("db").display().to_string(),
+			http_server_port: 0,
+			port: 0,
+			bootstraps: vec![],
+			..Default::default()
+		};
+		runtime_cfg.validate()?;
+		let confidence = runtime_cfg.confidence;
+		let identity_cfg =
+			IdentityConfig::load_or_init(&identity_path.display().to_string(), None, None)?;
+
+		let node = tokio::time::timeout(
+			cfg.timeout,
+			Node::builder(runtime_cfg, identity_cfg).start(),
+		)
+		.await
+		.map_err(|_| eyre!("Light client is not started after {:?}", cfg.timeout))??;
+
+		let submitter = Submitter {
+			rpc_client: node.rpc_client.clone(),
+			app_id: cfg.app_id,
+			signer: dev::alice(),
+		};
+
+		Ok(Harness {
+			cfg,
+			dev_node,
+			node,
+			confidence,
+			submitter,
+			avail_path,
+		})
+	}
+
+	/// Submits data with the dev account, returning once it is finalized
+	pub async fn submit(&self, data: &[u8]) -> Result<SubmitResponse> {
+		let transaction = Transaction::Data(Base64(data.to_vec()));
+		tokio::time::timeout(self.cfg.timeout, self.submitter.submit(transaction))
+			.await
+			.map_err(|_| eyre!("Data is not finalized after {:?}", self.cfg.timeout))?
+	}
+
+	/// Waits until the block reaches the target confidence, returning the achieved confidence
+	pub async fn wait_for_confidence(&self, block_number: u32, target: f64) -> Result<f64> {
+		let started = Instant::now();
+		loop {
+			let count: Option<u32> = self.node.db.get(Key::VerifiedCellCount(block_number))?;
+			let confidence = count.map(calculate_confidence);
+			if let Some(confidence) = confidence.filter(|confidence| *confidence >= target) {
+				return Ok(confidence);
+			}
+			if started.elapsed() > self.cfg.timeout {
+				let latest = self.latest_block();
+				return Err(eyre!(
+					"Block {block_number} confidence is {confidence:?} after {:?}, target is {target} (latest block: {latest:?})",
+					self.cfg.timeout
+				));
+			}
+			tokio::time::sleep(POLL_INTERVAL).await;
+		}
+	}
+
+	/// Waits until the app data of the block is reconstructed, returning the decoded data of its transactions
+	pub async fn wait_for_app_data(&self, block_number: u32) -> Result<Vec<Vec<u8>>> {
+		let started = Instant::now();
+		loop {
+			let app_data: Option<Vec<Vec<u8>>> = self
+				.node
+				.db
+				.get(Key::AppData(self.cfg.app_id, block_number))?;
+			if let Some(app_data) = app_data {
+				return app_data
+					.iter()
+					.filter_map(|extrinsic| decode_app_data(extrinsic).transpose())
+					.collect();
+			}
+			if started.elapsed() > self.cfg.timeout {
+				return Err(eyre!(
+					"App data of block {block_number} is not reconstructed after {:?}",
+					self.cfg.timeout
+				));
+			}
+			tokio::time::sleep(POLL_INTERVAL).await;
+		}
+	}
+
+	/// Returns the latest block with achieved confidence
+	pub fn latest_block(&self) -> Option<u32> {
+		self.node
+			.state
+			.lock()
+			.expect("Lock acquired")
+			.confidence_achieved
+			.last()
+	}
+
+	/// Stops the light client, removing its data directory. Launched dev node is killed on drop.
+	pub async fn stop(self) -> Result<()> {
+		let _ = self
+			.node
+			.shutdown
+			.trigger_shutdown(ShutdownReason::Signal("Stopped by the harness".to_string()));
+		self.node.stopped().await;
+		drop(self.node);
+		std::fs::remove_dir_all(&self.avail_path).wrap_err("Failed to remove data directory")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn submitted_data_is_verified_and_reconstructed() {
+		let harness = Harness::start(HarnessConfig::from_env().unwrap())
+			.await
+			.unwrap();
+		let target = harness.confidence;
+
+		let data = b"avail light client e2e".repeat(16);
+		let submitted = harness.submit(&data).await.unwrap();
+		let confidence = harness
+			.wait_for_confidence(submitted.block_number, target)
+			.await
+			.unwrap();
+		assert!(confidence >= target);
+
+		let app_data = harness
+			.wait_for_app_data(submitted.block_number)
+			.await
+			.unwrap();
+		assert!(app_data.contains(&data));
+
+		harness.stop().await.unwrap();
+	}
+}
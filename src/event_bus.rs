@@ -0,0 +1,358 @@
+//! Internal event bus with bounded per-subscriber queues.
+//!
+//! Unlike the broadcast channel, each subscriber has its own queue, with the capacity and the overflow policy set on subscribe,
+//! so slow subscriber doesn't affect the others, and lagging is not surfaced as a receive error.
+//! Events dropped on overflow are counted per subscriber, logged, and exposed with [`EventBus::stats`] for metrics.
+//!
+//! Receiving returns `None` once all the bus handles are dropped, and the queue is drained.
+//! Broadcast channels which are not replaced by the bus can be consumed through the same [`Receiver`] interface,
+//! with lagging logged instead of returned as an error.
+
+use async_trait::async_trait;
+use std::{
+	collections::VecDeque,
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+};
+use tokio::sync::{
+	broadcast::{self, error::RecvError},
+	Notify,
+};
+use tracing::warn;
+
+/// Policy applied when event is published to the full subscriber queue
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+	/// Oldest queued event is dropped, so subscriber receives the latest events
+	DropOldest,
+	/// Published event is dropped, so subscriber receives queued events in order
+	DropNewest,
+}
+
+/// Queue length and number of dropped events of the subscriber
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubscriberStats {
+	pub name: &'static str,
+	pub queued: usize,
+	pub dropped: u64,
+}
+
+struct Slot<T> {
+	name: &'static str,
+	capacity: usize,
+	policy: OverflowPolicy,
+	queue: Mutex<VecDeque<T>>,
+	dropped: AtomicU64,
+	// dropped events which are not yet logged by the subscriber
+	lagged: AtomicU64,
+	notify: Notify,
+}
+
+impl<T> Slot<T> {
+	fn push(&self, event: T) {
+		let mut queue = self.queue.lock().expect("Lock acquired");
+		if queue.len() >= self.capacity {
+			self.dropped.fetch_add(1, Ordering::Relaxed);
+			self.lagged.fetch_add(1, Ordering::Relaxed);
+			match self.policy {
+				OverflowPolicy::DropOldest => {
+					queue.pop_front();
+				},
+				OverflowPolicy::DropNewest => return,
+			}
+		}
+		queue.push_back(event);
+		drop(queue);
+		self.notify.notify_one();
+	}
+
+	fn stats(&self) -> SubscriberStats {
+		SubscriberStats {
+			name: self.name,
+			queued: self.queue.lock().expect("Lock acquired").len(),
+			dropped: self.dropped.load(Ordering::Relaxed),
+		}
+	}
+}
+
+struct Shared<T> {
+	slots: Mutex<Vec<Arc<Slot<T>>>>,
+	senders: AtomicUsize,
+}
+
+/// Publishing handle of the event bus
+pub struct EventBus<T> {
+	shared: Arc<Shared<T>>,
+}
+
+impl<T: Clone> EventBus<T> {
+	pub fn new() -> Self {
+		EventBus {
+			shared: Arc::new(Shared {
+				slots: Mutex::new(vec![]),
+				senders: AtomicUsize::new(1),
+			}),
+		}
+	}
+
+	/// Publishes event to all subscribers, returning the number of subscribers
+	pub fn send(&self, event: T) -> usize {
+		let slots = self.shared.slots.lock().expect("Lock acquired");
+		for slot in slots.iter() {
+			slot.push(event.clone());
+		}
+		slots.len()
+	}
+
+	/// Subscribes to the events published after this call
+	pub fn subscribe(
+		&self,
+		name: &'static str,
+		capacity: usize,
+		policy: OverflowPolicy,
+	) -> Subscriber<T> {
+		subscribe(&self.shared, name, capacity, policy)
+	}
+
+	/// Returns stats of the active subscribers
+	pub fn stats(&self) -> Vec<SubscriberStats> {
+		stats(&self.shared)
+	}
+}
+
+impl<T: Send + 'static> EventBus<T> {
+	/// Returns handle which reads the subscriber stats, without keeping the bus open
+	pub fn monitor(&self) -> Monitor {
+		let shared = self.shared.clone();
+		Monitor(Arc::new(move || stats(&shared)))
+	}
+}
+
+/// Subscriber stats of the event bus, regardless of the event type
+#[derive(Clone)]
+pub struct Monitor(Arc<dyn Fn() -> Vec<SubscriberStats> + Send + Sync>);
+
+impl Monitor {
+	pub fn stats(&self) -> Vec<SubscriberStats> {
+		(self.0)()
+	}
+}
+
+impl<T: Clone> Default for EventBus<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> Clone for EventBus<T> {
+	fn clone(&self) -> Self {
+		self.shared.senders.fetch_add(1, Ordering::SeqCst);
+		EventBus {
+			shared: self.shared.clone(),
+		}
+	}
+}
+
+impl<T> Drop for EventBus<T> {
+	fn drop(&mut self) {
+		if self.shared.senders.fetch_sub(1, Ordering::SeqCst) == 1 {
+			for slot in self.shared.slots.lock().expect("Lock acquired").iter() {
+				slot.notify.notify_one();
+			}
+		}
+	}
+}
+
+fn subscribe<T>(
+	shared: &Arc<Shared<T>>,
+	name: &'static str,
+	capacity: usize,
+	policy: OverflowPolicy,
+) -> Subscriber<T> {
+	let slot = Arc::new(Slot {
+		name,
+		capacity: capacity.max(1),
+		policy,
+		queue: Mutex::new(VecDeque::new()),
+		dropped: AtomicU64::new(0),
+		lagged: AtomicU64::new(0),
+		notify: Notify::new(),
+	});
+	shared
+		.slots
+		.lock()
+		.expect("Lock acquired")
+		.push(slot.clone());
+	Subscriber {
+		shared: shared.clone(),
+		slot,
+	}
+}
+
+fn stats<T>(shared: &Shared<T>) -> Vec<SubscriberStats> {
+	let slots = shared.slots.lock().expect("Lock acquired");
+	slots.iter().map(|slot| slot.stats()).collect()
+}
+
+/// Receiving handle of the event bus, with its own bounded queue
+pub struct Subscriber<T> {
+	shared: Arc<Shared<T>>,
+	slot: Arc<Slot<T>>,
+}
+
+impl<T> Subscriber<T> {
+	fn pop(&self) -> Option<T> {
+		let event = self.slot.queue.lock().expect("Lock acquired").pop_front();
+		let lagged = self.slot.lagged.swap(0, Ordering::Relaxed);
+		if lagged > 0 {
+			warn!(
+				subscriber = self.slot.name,
+				lagged, "Subscriber lagged, events dropped"
+			);
+		}
+		event
+	}
+
+	/// Receives the next event, or `None` if the bus is closed and the queue is drained
+	pub async fn recv(&mut self) -> Option<T> {
+		loop {
+			let notified = self.slot.notify.notified();
+			if let Some(event) = self.pop() {
+				return Some(event);
+			}
+			if self.shared.senders.load(Ordering::SeqCst) == 0 {
+				return None;
+			}
+			notified.await;
+		}
+	}
+
+	/// Receives the queued event without waiting
+	pub fn try_recv(&mut self) -> Option<T> {
+		self.pop()
+	}
+
+	/// Creates new subscriber with the same name, capacity and policy, receiving events published after this call
+	pub fn resubscribe(&self) -> Self {
+		subscribe(
+			&self.shared,
+			self.slot.name,
+			self.slot.capacity,
+			self.slot.policy,
+		)
+	}
+
+	/// Returns stats of all active subscribers of the bus
+	pub fn stats(&self) -> Vec<SubscriberStats> {
+		stats(&self.shared)
+	}
+}
+
+/// Common interface of the event bus subscribers and broadcast receivers
+#[async_trait]
+pub trait Receiver<T>: Send {
+	/// Receives the next event, or `None` if the source is closed
+	async fn receive(&mut self) -> Option<T>;
+}
+
+#[async_trait]
+impl<T: Send> Receiver<T> for Subscriber<T> {
+	async fn receive(&mut self) -> Option<T> {
+		self.recv().await
+	}
+}
+
+#[async_trait]
+impl<T: Clone + Send> Receiver<T> for broadcast::Receiver<T> {
+	async fn receive(&mut self) -> Option<T> {
+		loop {
+			match self.recv().await {
+				Ok(event) => return Some(event),
+				Err(RecvError::Lagged(lagged)) => {
+					warn!(lagged, "Receiver lagged, events dropped")
+				},
+				Err(RecvError::Closed) => return None,
+			}
+		}
+	}
+}
+
+impl<T> Drop for Subscriber<T> {
+	fn drop(&mut self) {
+		let mut slots = self.shared.slots.lock().expect("Lock acquired");
+		slots.retain(|slot| !Arc::ptr_eq(slot, &self.slot));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn independent_queues() {
+		let bus = EventBus::new();
+		let mut fast = bus.subscribe("fast", 10, OverflowPolicy::DropOldest);
+		let mut slow = bus.subscribe("slow", 2, OverflowPolicy::DropOldest);
+		for event in 0..5 {
+			assert_eq!(bus.send(event), 2);
+			assert_eq!(fast.recv().await, Some(event));
+		}
+		assert_eq!(slow.recv().await, Some(3));
+		assert_eq!(slow.recv().await, Some(4));
+		assert_eq!(
+			bus.stats(),
+			vec![
+				SubscriberStats {
+					name: "fast",
+					queued: 0,
+					dropped: 0
+				},
+				SubscriberStats {
+					name: "slow",
+					queued: 0,
+					dropped: 3
+				}
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn drop_newest() {
+		let bus = EventBus::new();
+		let mut subscriber = bus.subscribe("subscriber", 2, OverflowPolicy::DropNewest);
+		for event in 0..5 {
+			bus.send(event);
+		}
+		assert_eq!(subscriber.try_recv(), Some(0));
+		assert_eq!(subscriber.try_recv(), Some(1));
+		assert_eq!(subscriber.try_recv(), None);
+	}
+
+	#[tokio::test]
+	async fn closed_bus() {
+		let bus = EventBus::new();
+		let mut subscriber = bus.subscribe("subscriber", 2, OverflowPolicy::DropOldest);
+		let resubscribed = subscriber.resubscribe();
+		bus.send(1);
+		assert_eq!(bus.stats().len(), 2);
+		drop(resubscribed);
+		assert_eq!(bus.stats().len(), 1);
+
+		let handle = tokio::spawn(async move {
+			let mut received = vec![];
+			while let Some(event) = subscriber.recv().await {
+				received.push(event);
+			}
+			received
+		});
+		let sender = bus.clone();
+		let monitor = bus.monitor();
+		drop(bus);
+		sender.send(2);
+		drop(sender);
+		assert_eq!(handle.await.unwrap(), vec![1, 2]);
+		assert!(monitor.stats().is_empty());
+	}
+}
@@ -0,0 +1,158 @@
+//! Append-only log of the block verification outcomes.
+//!
+//! One JSON line is appended for each sampled block, with the confidence, timing and the cell sources,
+//! so the node history can be processed without access to RocksDB or the metrics backend.
+//! Once the log exceeds the maximum size, it is rotated to `<path>.1`, replacing the previously rotated log.
+//!
+//! # Record
+//!
+//! `{"block_number": 100, "block_hash": "0x..", "confidence": 99.951171875, "confidence_achieved": true, "timed_out": false, "started_at": 1700000000000, "finished_at": 1700000001200, "processing_delay_ms": 15, "cells": {"requested": 11, "dht_fetched": 9, "rpc_fetched": 2, "verified": 11}, "randomness": {"type": "os"}}`
+
+use avail_subxt::utils::H256;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Serialize;
+use std::{
+	fs::{self, OpenOptions},
+	io::Write,
+	sync::Mutex,
+};
+
+use crate::{data::VerificationMetadata, randomness::RandomnessSource};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Cells {
+	pub requested: u32,
+	pub dht_fetched: u32,
+	pub rpc_fetched: u32,
+	pub verified: u32,
+}
+
+/// Verification outcome of a single block
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct VerificationOutcome {
+	pub block_number: u32,
+	pub block_hash: H256,
+	/// Confidence of the verified cells, partial if confidence is not achieved
+	pub confidence: f64,
+	pub confidence_achieved: bool,
+	/// Sampling is abandoned at the verification deadline
+	pub timed_out: bool,
+	pub started_at: u64,
+	pub finished_at: u64,
+	pub processing_delay_ms: u64,
+	pub cells: Cells,
+	pub randomness: RandomnessSource,
+}
+
+impl VerificationOutcome {
+	pub fn new(
+		block_number: u32,
+		block_hash: H256,
+		confidence: f64,
+		timed_out: bool,
+		metadata: &VerificationMetadata,
+	) -> Self {
+		VerificationOutcome {
+			block_number,
+			block_hash,
+			confidence,
+			confidence_achieved: metadata.verified >= metadata.cells_requested,
+			timed_out,
+			started_at: metadata.started_at,
+			finished_at: metadata.finished_at,
+			processing_delay_ms: metadata.processing_delay,
+			cells: Cells {
+				requested: metadata.cells_requested,
+				dht_fetched: metadata.dht_fetched,
+				rpc_fetched: metadata.rpc_fetched,
+				verified: metadata.verified,
+			},
+			randomness: metadata.randomness,
+		}
+	}
+}
+
+/// Appends verification outcomes to the file, rotated by size
+pub struct EventLog {
+	path: String,
+	/// Maximum size of the log in bytes
+	max_size: u64,
+	lock: Mutex<()>,
+}
+
+impl EventLog {
+	pub fn new(path: String, max_size: u64) -> Self {
+		EventLog {
+			path,
+			max_size,
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn rotated_path(&self) -> String {
+		format!("{}.1", self.path)
+	}
+
+	pub fn append(&self, outcome: &VerificationOutcome) -> Result<()> {
+		let _lock = self.lock.lock().expect("Lock acquired");
+		let mut line = serde_json::to_vec(outcome)?;
+		line.push(b'\n');
+
+		let size = fs::metadata(&self.path).map_or(0, |metadata| metadata.len());
+		if size > 0 && size + line.len() as u64 > self.max_size {
+			fs::rename(&self.path, self.rotated_path())
+				.wrap_err(format!("Failed to rotate event log {}", self.path))?;
+		}
+
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.wrap_err(format!("Failed to open event log {}", self.path))?;
+		file.write_all(&line)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn append_and_rotate() {
+		let path =
+			std::env::temp_dir().join(format!("avail_light_event_log_{}", std::process::id()));
+		let path = path.to_string_lossy().to_string();
+		let metadata = VerificationMetadata {
+			started_at: 1000,
+			finished_at: 2000,
+			processing_delay: 10,
+			cells_requested: 4,
+			dht_fetched: 3,
+			rpc_fetched: 1,
+			verified: 4,
+			randomness: RandomnessSource::Os,
+		};
+		let outcome = |block_number| {
+			VerificationOutcome::new(block_number, H256::zero(), 93.75, false, &metadata)
+		};
+		let line_size = serde_json::to_vec(&outcome(1)).unwrap().len() as u64 + 1;
+		let event_log = EventLog::new(path.clone(), line_size * 2);
+
+		for block_number in 1..=3 {
+			event_log.append(&outcome(block_number)).unwrap();
+		}
+		let rotated = fs::read_to_string(event_log.rotated_path()).unwrap();
+		let current = fs::read_to_string(&path).unwrap();
+		assert_eq!(rotated.lines().count(), 2);
+		assert_eq!(current.lines().count(), 1);
+
+		let record: serde_json::Value = serde_json::from_str(current.trim()).unwrap();
+		assert_eq!(record["block_number"], 3);
+		assert_eq!(record["confidence_achieved"], true);
+		assert_eq!(record["cells"]["dht_fetched"], 3);
+
+		fs::remove_file(&path).unwrap();
+		fs::remove_file(event_log.rotated_path()).unwrap();
+	}
+}
@@ -20,8 +20,12 @@ use kate_recovery::{
 };
 use kate_recovery::{data::Cell, matrix::RowIndex};
 use mockall::automock;
+use rand::Rng;
 use sp_core::blake2_256;
-use std::{sync::Arc, time::Instant};
+use std::{
+	sync::Arc,
+	time::{Duration, Instant},
+};
 use tracing::{debug, error, info, warn};
 
 use crate::{
@@ -30,9 +34,9 @@ use crate::{
 		p2p::Client as P2pClient,
 		rpc::{Client as RpcClient, Event},
 	},
-	shutdown::Controller,
+	shutdown::{Controller, ShutdownReason},
 	telemetry::{MetricCounter, MetricValue, Metrics},
-	types::{BlockVerified, ClientChannels, FatClientConfig},
+	types::{BlockVerified, ClientChannels, FatClientConfig, PartitionMode},
 	utils::extract_kate,
 };
 
@@ -72,6 +76,44 @@ impl Client for FatClient {
 	}
 }
 
+/// Returns extended matrix positions of the assigned partition
+pub fn partition_positions(
+	dimensions: Dimensions,
+	partition: &Partition,
+	mode: PartitionMode,
+) -> Vec<Position> {
+	match mode {
+		PartitionMode::Cells => dimensions
+			.iter_extended_partition_positions(partition)
+			.collect(),
+		PartitionMode::Columns => {
+			let cols = dimensions.cols().get() as u32;
+			let start = (partition.number as u32 - 1) * cols / partition.fraction as u32;
+			let end = partition.number as u32 * cols / partition.fraction as u32;
+			(0..dimensions.extended_rows())
+				.flat_map(|row| {
+					(start..end).map(move |col| Position {
+						row,
+						col: col as u16,
+					})
+				})
+				.collect()
+		},
+	}
+}
+
+/// Returns the DHT push delay, with the random jitter added
+fn push_delay(cfg: &FatClientConfig) -> Duration {
+	let jitter = rand::thread_rng().gen_range(0..=cfg.push_jitter.as_millis() as u64);
+	cfg.push_delay + Duration::from_millis(jitter)
+}
+
+/// Returns the pause after the DHT push of given number of records, which keeps pushes within the rate limit
+fn push_pause(cfg: &FatClientConfig, records: usize) -> Option<Duration> {
+	let limit = cfg.push_rate_limit?;
+	Some(Duration::from_secs_f64(records as f64 / limit as f64))
+}
+
 pub async fn process_block(
 	client: &impl Client,
 	db: impl Database,
@@ -120,14 +162,13 @@ pub async fn process_block(
 		.wrap_err("Fat Client failed to store Block Header")?;
 
 	// Fat client partition upload logic
-	let positions: Vec<Position> = dimensions
-		.iter_extended_partition_positions(&partition)
-		.collect();
+	let positions = partition_positions(dimensions, &partition, cfg.block_matrix_partition_mode);
 	let Partition { number, fraction } = partition;
 	info!(
 		block_number,
 		"partition_cells_requested" = positions.len(),
-		"Fetching partition ({number}/{fraction}) from RPC",
+		"Fetching partition ({number}/{fraction}, {:?}) from RPC",
+		cfg.block_matrix_partition_mode,
 	);
 
 	let begin = Instant::now();
@@ -144,14 +185,6 @@ pub async fn process_block(
 		for (i, result) in batch.await.into_iter().enumerate() {
 			let batch_rpc_fetched =
 				result.wrap_err(format!("Failed to fetch cells from node RPC at batch {i}"))?;
-
-			if let Err(e) = client
-				.insert_cells_into_dht(block_number, batch_rpc_fetched.clone())
-				.await
-			{
-				debug!("Error inserting cells into DHT: {e}");
-			}
-
 			rpc_fetched.extend(batch_rpc_fetched);
 		}
 	}
@@ -169,6 +202,34 @@ pub async fn process_block(
 			partition_rpc_retrieve_time_elapsed.as_secs_f64(),
 		))
 		.await;
+	if !positions.is_empty() {
+		let completion = partition_rpc_cells_fetched as f64 / positions.len() as f64;
+		metrics
+			.record(MetricValue::FatClientPartitionCompletion(completion))
+			.await;
+	}
+
+	let delay = push_delay(cfg);
+	if !delay.is_zero() {
+		info!(block_number, "Postponing DHT push for {delay:?}");
+		tokio::time::sleep(delay).await;
+	}
+
+	let (mut pushed, mut push_failed) = (0, 0);
+	for cells in rpc_fetched.chunks(cfg.push_batch_size) {
+		if let Err(e) = client
+			.insert_cells_into_dht(block_number, cells.to_vec())
+			.await
+		{
+			debug!("Error inserting cells into DHT: {e}");
+			push_failed += cells.len();
+		} else {
+			pushed += cells.len();
+		}
+		if let Some(pause) = push_pause(cfg, cells.len()) {
+			tokio::time::sleep(pause).await;
+		}
+	}
 
 	if rpc_fetched.len() >= dimensions.cols().get().into() {
 		let data_cells = rpc_fetched
@@ -178,13 +239,32 @@ pub async fn process_block(
 
 		let data_rows = data::rows(dimensions, &data_cells);
 
-		if let Err(e) = client.insert_rows_into_dht(block_number, data_rows).await {
-			debug!("Error inserting rows into DHT: {e}");
+		for rows in data_rows.chunks(cfg.push_batch_size) {
+			if let Err(e) = client
+				.insert_rows_into_dht(block_number, rows.to_vec())
+				.await
+			{
+				debug!("Error inserting rows into DHT: {e}");
+				push_failed += rows.len();
+			} else {
+				pushed += rows.len();
+			}
+			if let Some(pause) = push_pause(cfg, rows.len()) {
+				tokio::time::sleep(pause).await;
+			}
 		}
 	} else {
 		warn!("No rows has been inserted into DHT since partition size is less than one row.")
 	}
 
+	if pushed + push_failed > 0 {
+		let push_success = pushed as f64 / (pushed + push_failed) as f64;
+		info!(block_number, pushed, push_failed, "Partition pushed to DHT");
+		metrics
+			.record(MetricValue::FatClientPushSuccess(push_success))
+			.await;
+	}
+
 	Ok(())
 }
 
@@ -205,12 +285,22 @@ pub async fn run(
 	metrics: Arc<impl Metrics>,
 	mut channels: ClientChannels,
 	partition: Partition,
-	shutdown: Controller<String>,
+	shutdown: Controller<ShutdownReason>,
 ) {
 	info!("Starting fat client...");
 
 	loop {
-		let (header, received_at) = match channels.rpc_event_receiver.recv().await {
+		let received = tokio::select! {
+			received = channels.rpc_event_receiver.recv() => received,
+			// stop receiving new blocks once the shutdown is triggered,
+			// block being processed is drained in the verification shutdown phase
+			_ = shutdown.triggered_shutdown() => {
+				info!("Shutdown triggered, stopping block verification");
+				return;
+			},
+		};
+
+		let (header, received_at) = match received {
 			Ok(event) => match event {
 				Event::HeaderUpdate {
 					header,
@@ -243,7 +333,9 @@ pub async fn run(
 		.await
 		{
 			error!("Cannot process block: {error}");
-			let _ = shutdown.trigger_shutdown(format!("Cannot process block: {error:#}"));
+			let _ = shutdown.trigger_shutdown(ShutdownReason::Error(format!(
+				"Cannot process block: {error:#}"
+			)));
 			return;
 		};
 
@@ -254,10 +346,7 @@ pub async fn run(
 
 		// notify dht-based application client
 		// that newly mined block has been received
-		if let Err(error) = channels.block_sender.send(client_msg) {
-			error!("Cannot send block verified message: {error}");
-			continue;
-		}
+		channels.block_sender.send(client_msg);
 	}
 }
 
@@ -274,6 +363,7 @@ mod tests {
 		config::substrate::Digest,
 	};
 	use hex_literal::hex;
+	use test_case::test_case;
 
 	fn default_header() -> Header {
 		Header {
@@ -393,4 +483,73 @@ mod tests {
 		.await
 		.unwrap();
 	}
+
+	#[tokio::test]
+	async fn process_block_push_batches() {
+		let db = mem_db::MemoryDB::default();
+		let mut mock_client = MockClient::new();
+		mock_client
+			.expect_get_kate_proof()
+			.returning(move |_, _| Box::pin(async move { Ok(DEFAULT_CELLS.to_vec()) }));
+		mock_client
+			.expect_insert_rows_into_dht()
+			.returning(|_, _| Box::pin(async move { Ok(()) }));
+		mock_client
+			.expect_insert_cells_into_dht()
+			.withf(|_, cells| cells.len() == 1)
+			.times(4)
+			.returning(|_, _| Box::pin(async move { Ok(()) }));
+
+		let mut mock_metrics = telemetry::MockMetrics::new();
+		mock_metrics.expect_count().returning(|_| ());
+		mock_metrics.expect_record().returning(|_| ());
+
+		let cfg = FatClientConfig::from(&RuntimeConfig {
+			fat_client_push_batch_size: 1,
+			fat_client_push_rate_limit: Some(1000),
+			..Default::default()
+		});
+
+		process_block(
+			&mock_client,
+			db,
+			&Arc::new(mock_metrics),
+			&cfg,
+			&default_header(),
+			Instant::now(),
+			entire_block(),
+		)
+		.await
+		.unwrap();
+	}
+
+	#[test_case(PartitionMode::Cells, 1 => vec![(0, 0), (0, 1), (0, 2), (0, 3)] ; "first cells")]
+	#[test_case(PartitionMode::Cells, 2 => vec![(1, 0), (1, 1), (1, 2), (1, 3)] ; "second cells")]
+	#[test_case(PartitionMode::Columns, 1 => vec![(0, 0), (0, 1), (1, 0), (1, 1)] ; "first columns")]
+	#[test_case(PartitionMode::Columns, 2 => vec![(0, 2), (0, 3), (1, 2), (1, 3)] ; "second columns")]
+	fn partition_assignment(mode: PartitionMode, number: u8) -> Vec<(u32, u16)> {
+		let dimensions = Dimensions::new(1, 4).unwrap();
+		let partition = Partition {
+			number,
+			fraction: 2,
+		};
+		partition_positions(dimensions, &partition, mode)
+			.into_iter()
+			.map(|position| (position.row, position.col))
+			.collect()
+	}
+
+	#[test]
+	fn push_schedule() {
+		let mut cfg = FatClientConfig::from(&RuntimeConfig::default());
+		assert_eq!(push_delay(&cfg), Duration::ZERO);
+		assert_eq!(push_pause(&cfg, 100), None);
+
+		cfg.push_delay = Duration::from_secs(2);
+		cfg.push_jitter = Duration::from_secs(1);
+		cfg.push_rate_limit = Some(50);
+		let delay = push_delay(&cfg);
+		assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(3));
+		assert_eq!(push_pause(&cfg, 100), Some(Duration::from_secs(2)));
+	}
 }
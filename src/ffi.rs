@@ -0,0 +1,215 @@
+//! C interface of the light client, for the non-Rust embedders.
+//!
+//! Functions are exported from the `cdylib` build with the `ffi` feature enabled,
+//! and declared in the `include/avail_light.h` header.
+//! Node is started with [`avail_light_init`] and must be stopped and released with [`avail_light_stop`].
+//! On failure, functions return null pointer or negative value, and the error is available with [`avail_light_last_error`].
+
+use color_eyre::{eyre::eyre, Result};
+use std::{
+	cell::RefCell,
+	ffi::{c_char, c_void, CStr, CString},
+	ptr,
+};
+
+use crate::handle::NodeHandle;
+
+thread_local! {
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(error: color_eyre::Report) {
+	let message = CString::new(format!("{error:#}").replace('\0', " ")).unwrap_or_default();
+	LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+unsafe fn to_str<'a>(value: *const c_char, name: &str) -> Result<&'a str> {
+	if value.is_null() {
+		return Err(eyre!("Parameter {name} is null"));
+	}
+	Ok(CStr::from_ptr(value).to_str()?)
+}
+
+fn block_json(handle: &NodeHandle, block_number: u32) -> Result<Option<CString>> {
+	let Some(block) = handle.block(block_number)? else {
+		return Ok(None);
+	};
+	Ok(Some(CString::new(serde_json::to_string(&block)?)?))
+}
+
+/// Verified block callback, called with the block number, confidence (negative if block is not sampled) and user data
+pub type BlockCallback = extern "C" fn(block_number: u32, confidence: f64, user_data: *mut c_void);
+
+struct UserData(*mut c_void);
+
+// SAFETY: Embedder guarantees that user data can be used from the callback thread
+unsafe impl Send for UserData {}
+
+/// Returns the last error which occurred on the calling thread, or null if there was none.
+/// Returned string is valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn avail_light_last_error() -> *const c_char {
+	LAST_ERROR.with(|last_error| {
+		last_error
+			.borrow()
+			.as_ref()
+			.map_or(ptr::null(), |error| error.as_ptr())
+	})
+}
+
+/// Starts the light client with the JSON configuration and identity stored in the given keystore path.
+/// Returns null pointer on failure.
+///
+/// # Safety
+///
+/// Parameters must be null terminated UTF-8 strings.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_init(
+	config_json: *const c_char,
+	identity_path: *const c_char,
+) -> *mut NodeHandle {
+	let handle = to_str(config_json, "config_json").and_then(|config_json| {
+		NodeHandle::start(config_json, to_str(identity_path, "identity_path")?)
+	});
+	match handle {
+		Ok(handle) => Box::into_raw(Box::new(handle)),
+		Err(error) => {
+			set_last_error(error);
+			ptr::null_mut()
+		},
+	}
+}
+
+/// Registers the callback called for each verified block, from the light client thread.
+///
+/// # Safety
+///
+/// Handle must be returned by [`avail_light_init`] and not stopped.
+/// User data must be valid until the handle is stopped.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_on_verified_block(
+	handle: *const NodeHandle,
+	callback: BlockCallback,
+	user_data: *mut c_void,
+) {
+	let Some(handle) = handle.as_ref() else {
+		return;
+	};
+	let user_data = UserData(user_data);
+	handle.on_verified_block(move |block| {
+		let user_data = &user_data;
+		callback(
+			block.block_number,
+			block.confidence.unwrap_or(-1.0),
+			user_data.0,
+		)
+	});
+}
+
+/// Returns confidence of the given block, or negative value if block is not verified or on failure.
+///
+/// # Safety
+///
+/// Handle must be returned by [`avail_light_init`] and not stopped.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_confidence(
+	handle: *const NodeHandle,
+	block_number: u32,
+) -> f64 {
+	let Some(handle) = handle.as_ref() else {
+		return -1.0;
+	};
+	match handle.confidence(block_number) {
+		Ok(confidence) => confidence.unwrap_or(-1.0),
+		Err(error) => {
+			set_last_error(error.into());
+			-1.0
+		},
+	}
+}
+
+/// Returns status and confidence of the given block as JSON string, or null if block is not received yet or on failure.
+/// Returned string must be released with [`avail_light_free_string`].
+///
+/// # Safety
+///
+/// Handle must be returned by [`avail_light_init`] and not stopped.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_block(
+	handle: *const NodeHandle,
+	block_number: u32,
+) -> *mut c_char {
+	let Some(handle) = handle.as_ref() else {
+		return ptr::null_mut();
+	};
+	match block_json(handle, block_number) {
+		Ok(Some(block)) => block.into_raw(),
+		Ok(None) => ptr::null_mut(),
+		Err(error) => {
+			set_last_error(error);
+			ptr::null_mut()
+		},
+	}
+}
+
+/// Releases the string returned by the light client.
+///
+/// # Safety
+///
+/// String must be returned by the light client, and it must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_free_string(value: *mut c_char) {
+	if !value.is_null() {
+		drop(CString::from_raw(value));
+	}
+}
+
+/// Returns the latest block with achieved confidence, or negative value if there is none.
+///
+/// # Safety
+///
+/// Handle must be returned by [`avail_light_init`] and not stopped.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_latest_block(handle: *const NodeHandle) -> i64 {
+	handle
+		.as_ref()
+		.and_then(NodeHandle::latest_block)
+		.map_or(-1, i64::from)
+}
+
+/// Stops the light client and releases the handle.
+///
+/// # Safety
+///
+/// Handle must be returned by [`avail_light_init`], and it must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn avail_light_stop(handle: *mut NodeHandle) {
+	if handle.is_null() {
+		return;
+	}
+	let handle = Box::from_raw(handle);
+	handle.stop();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn init_invalid_config() {
+		let config = CString::new(r#"{"confidence": "high"}"#).unwrap();
+		let identity = CString::new("identity.toml").unwrap();
+		let handle = unsafe { avail_light_init(config.as_ptr(), identity.as_ptr()) };
+		assert!(handle.is_null());
+		let error = unsafe { CStr::from_ptr(avail_light_last_error()) };
+		assert!(error
+			.to_str()
+			.unwrap()
+			.contains("Failed to parse configuration"));
+
+		let handle = unsafe { avail_light_init(ptr::null(), identity.as_ptr()) };
+		assert!(handle.is_null());
+		assert_eq!(unsafe { avail_light_confidence(handle, 1) }, -1.0);
+		assert_eq!(unsafe { avail_light_latest_block(handle) }, -1);
+	}
+}
@@ -0,0 +1,298 @@
+//! Confidence aggregation across the fleet of sibling light clients.
+//!
+//! Each light client serves signed attestation of its confidence for the verified block on `GET /v2/blocks/{block_number}/attestation`
+//! (see [`crate::attestation`]). If `fleet_members` are configured, attestations of each verified block are collected
+//! from the sibling light clients over HTTP, and the aggregated confidence is exposed on `GET /v2/fleet/blocks/{block_number}`.
+//!
+//! # Aggregation
+//!
+//! Light clients sample cells independently, so the block is unavailable, despite being attested,
+//! only if all light clients have missed the unavailable cells. Aggregated confidence is `1 - Π(1 - confidence)`
+//! of the local confidence and the confidence of each valid sibling attestation.
+//!
+//! # Notes
+//!
+//! Attestations are accepted only if signed by the configured peer ID, for the block hash verified by the local light client.
+//! Siblings which haven't verified the block yet, or are not reachable, are retried up to [`MAX_ATTEMPTS`] times.
+//! Aggregates are kept in memory, for the last [`MAX_BLOCKS`] blocks.
+
+use avail_subxt::utils::H256;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use futures::future::join_all;
+use hyper::{body, Body, Request};
+use hyper_rustls::HttpsConnector;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	str::FromStr,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+	attestation::{Attestation, Signer},
+	event_bus::Subscriber,
+	network::proxy::{https_connector, Connector, Proxy},
+	types::{BlockVerified, FleetMember},
+	utils::unix_timestamp_millis,
+};
+
+/// Maximum number of the attestation requests to the sibling light client, per block
+pub const MAX_ATTEMPTS: u32 = 5;
+/// Maximum number of the blocks with the aggregated confidence kept in memory
+pub const MAX_BLOCKS: usize = 1024;
+/// Delay between the attestation requests
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// Timeout of the attestation request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Aggregated confidence of the block
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FleetConfidence {
+	pub block_number: u32,
+	pub block_hash: H256,
+	pub confidence: f64,
+	/// Valid attestations, including the local one
+	pub attestations: Vec<Attestation>,
+}
+
+impl FleetConfidence {
+	fn new(local: Attestation) -> Self {
+		FleetConfidence {
+			block_number: local.block_number,
+			block_hash: local.block_hash,
+			confidence: local.confidence,
+			attestations: vec![local],
+		}
+	}
+
+	fn add(&mut self, attestation: Attestation) {
+		self.attestations.push(attestation);
+		self.confidence = aggregate(self.attestations.iter().map(|a| a.confidence));
+	}
+}
+
+/// Returns confidence (in percents) of the independent samplers
+pub fn aggregate(confidences: impl Iterator<Item = f64>) -> f64 {
+	let missed = confidences.fold(1.0, |missed, confidence| {
+		missed * (1.0 - confidence.clamp(0.0, 100.0) / 100.0)
+	});
+	(1.0 - missed) * 100.0
+}
+
+/// Aggregated confidence of the recent blocks
+#[derive(Clone, Default)]
+pub struct Aggregates(Arc<Mutex<BTreeMap<u32, FleetConfidence>>>);
+
+impl Aggregates {
+	pub fn get(&self, block_number: u32) -> Option<FleetConfidence> {
+		self.0
+			.lock()
+			.expect("Lock acquired")
+			.get(&block_number)
+			.cloned()
+	}
+
+	fn insert(&self, aggregate: FleetConfidence) {
+		let mut aggregates = self.0.lock().expect("Lock acquired");
+		aggregates.insert(aggregate.block_number, aggregate);
+		while aggregates.len() > MAX_BLOCKS {
+			aggregates.pop_first();
+		}
+	}
+}
+
+/// Checks that the attestation is signed by the fleet member, for the locally verified block
+fn check(attestation: &Attestation, member: &PeerId, local: &Attestation) -> Result<()> {
+	let signer = attestation.verify()?;
+	if &signer != member {
+		return Err(eyre!("Attestation is signed by {signer}"));
+	}
+	if (attestation.block_number, attestation.block_hash) != (local.block_number, local.block_hash)
+	{
+		return Err(eyre!(
+			"Attestation is for block {} with hash {:?}",
+			attestation.block_number,
+			attestation.block_hash
+		));
+	}
+	Ok(())
+}
+
+struct Fleet {
+	http_client: hyper::Client<HttpsConnector<Connector>, Body>,
+	members: Vec<(PeerId, String)>,
+	aggregates: Aggregates,
+}
+
+impl Fleet {
+	/// Returns attestation of the sibling, or `None` if sibling hasn't verified the block yet
+	async fn fetch(&self, url: &str, block_number: u32) -> Result<Option<Attestation>> {
+		let url = format!(
+			"{}/v2/blocks/{block_number}/attestation",
+			url.trim_end_matches('/')
+		);
+		let request = Request::get(&url)
+			.body(Body::empty())
+			.wrap_err(format!("Invalid URL {url}"))?;
+		let response = tokio::time::timeout(REQUEST_TIMEOUT, self.http_client.request(request))
+			.await
+			.map_err(|_| eyre!("Request timed out"))??;
+		if response.status() == hyper::StatusCode::NOT_FOUND {
+			return Ok(None);
+		}
+		if !response.status().is_success() {
+			return Err(eyre!("Unexpected response status {}", response.status()));
+		}
+		let body = body::to_bytes(response.into_body()).await?;
+		serde_json::from_slice(&body)
+			.map(Some)
+			.wrap_err("Invalid attestation")
+	}
+
+	/// Collects attestations of the block from the siblings, updating the aggregate after each attempt
+	async fn collect(self: Arc<Self>, local: Attestation) {
+		let block_number = local.block_number;
+		let mut aggregate = FleetConfidence::new(local.clone());
+		let mut pending = self.members.iter().collect::<Vec<_>>();
+		for attempt in 1..=MAX_ATTEMPTS {
+			let fetched =
+				join_all(pending.iter().map(|(_, url)| self.fetch(url, block_number))).await;
+
+			let mut retry = vec![];
+			for (member, result) in pending.into_iter().zip(fetched) {
+				let (peer_id, url) = member;
+				match result {
+					Ok(Some(attestation)) => match check(&attestation, peer_id, &local) {
+						Ok(()) => aggregate.add(attestation),
+						Err(error) => {
+							warn!(block_number, url, "Invalid fleet attestation: {error:#}")
+						},
+					},
+					Ok(None) => retry.push(member),
+					Err(error) => {
+						debug!(
+							block_number,
+							url, "Cannot fetch fleet attestation: {error:#}"
+						);
+						retry.push(member);
+					},
+				}
+			}
+			self.aggregates.insert(aggregate.clone());
+			pending = retry;
+			if pending.is_empty() || attempt == MAX_ATTEMPTS {
+				break;
+			}
+			tokio::time::sleep(RETRY_DELAY).await;
+		}
+		debug!(
+			block_number,
+			confidence = aggregate.confidence,
+			attestations = aggregate.attestations.len(),
+			"Fleet confidence aggregated"
+		);
+	}
+}
+
+/// Parses peer IDs of the fleet members
+pub fn members(members: &[FleetMember]) -> Result<Vec<(PeerId, String)>> {
+	members
+		.iter()
+		.map(|member| {
+			let peer_id = PeerId::from_str(&member.peer_id)
+				.wrap_err(format!("Invalid fleet member peer ID {}", member.peer_id))?;
+			Ok((peer_id, member.url.clone()))
+		})
+		.collect()
+}
+
+/// Aggregates confidence of each verified block from the fleet members
+pub async fn run(
+	members: Vec<(PeerId, String)>,
+	signer: Signer,
+	aggregates: Aggregates,
+	mut blocks: Subscriber<BlockVerified>,
+	proxy: Option<Proxy>,
+) {
+	info!(
+		members = members.len(),
+		"Starting fleet confidence aggregation..."
+	);
+	let fleet = Arc::new(Fleet {
+		http_client: hyper::Client::builder().build(https_connector(proxy)),
+		members,
+		aggregates,
+	});
+	while let Some(block) = blocks.recv().await {
+		// confidence is not set for the skipped blocks
+		let Some(confidence) = block.confidence else {
+			continue;
+		};
+		let local = match signer.sign(
+			block.block_num,
+			block.header_hash,
+			confidence,
+			unix_timestamp_millis(),
+		) {
+			Ok(local) => local,
+			Err(error) => {
+				warn!(block_number = block.block_num, "{error:#}");
+				continue;
+			},
+		};
+		tokio::spawn(fleet.clone().collect(local));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use libp2p::identity::Keypair;
+	use subxt_signer::sr25519::dev;
+
+	fn signer() -> (Signer, PeerId) {
+		let keypair = Keypair::generate_ed25519();
+		let peer_id = keypair.public().to_peer_id();
+		(Signer::new(keypair, dev::alice()), peer_id)
+	}
+
+	#[test]
+	fn checked_attestation() {
+		let (signer, peer_id) = signer();
+		let local = signer.sign(10, H256::repeat_byte(1), 99.0, 1000).unwrap();
+		assert!(check(&local, &peer_id, &local).is_ok());
+
+		let other = Keypair::generate_ed25519().public().to_peer_id();
+		assert!(check(&local, &other, &local).is_err());
+
+		let mut forged = local.clone();
+		forged.confidence = 99.9;
+		assert!(check(&forged, &peer_id, &local).is_err());
+
+		let other_block = signer.sign(10, H256::repeat_byte(2), 99.0, 1000).unwrap();
+		assert!(check(&other_block, &peer_id, &local).is_err());
+	}
+
+	#[test]
+	fn aggregated_confidence() {
+		assert_eq!(aggregate([].into_iter()), 0.0);
+		assert!((aggregate([90.0].into_iter()) - 90.0).abs() < 1e-9);
+		assert!((aggregate([90.0, 90.0].into_iter()) - 99.0).abs() < 1e-9);
+		assert!((aggregate([50.0, 50.0, 50.0].into_iter()) - 87.5).abs() < 1e-9);
+
+		let aggregates = Aggregates::default();
+		let (signer, _) = signer();
+		for block_number in 0..=MAX_BLOCKS as u32 {
+			let local = signer.sign(block_number, H256::zero(), 90.0, 0).unwrap();
+			aggregates.insert(FleetConfidence::new(local));
+		}
+		assert!(aggregates.get(0).is_none());
+		assert_eq!(aggregates.get(1).unwrap().confidence, 90.0);
+	}
+}
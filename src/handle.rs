@@ -0,0 +1,299 @@
+//! Blocking handle of the light client node, for the foreign language bindings.
+//!
+//! Handle owns the tokio runtime on which the [`Node`] is running, and exposes a small synchronous interface
+//! with plain argument types (JSON configuration, block numbers, byte vectors).
+//!
+//! With the `uniffi` feature, handle is exported with [UniFFI](https://mozilla.github.io/uniffi-rs/),
+//! and Kotlin (Android) and Swift (iOS) bindings are generated from the built library with the `uniffi-bindgen` binary
+//! (see `bindings/mobile/generate.sh`).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use avail_light::handle::NodeHandle;
+//! # use std::time::Duration;
+//! # fn example() -> color_eyre::Result<()> {
+//! let handle = NodeHandle::start(r#"{"confidence": 99.9}"#, "identity.toml")?;
+//! if let Some(block) = handle.next_verified_block(Duration::from_secs(30))? {
+//! 	println!("Block {} confidence: {:?}", block.block_number, handle.confidence(block.block_number)?);
+//! }
+//! handle.stop();
+//! # Ok(())
+//! # }
+//! ```
+
+use avail_subxt::primitives::Header;
+use color_eyre::{eyre::WrapErr, Report, Result};
+use std::{
+	fmt::{self, Display, Formatter},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::runtime::Runtime;
+
+use crate::{
+	api::v2::types::{block_status, Block, BlockVerification},
+	data::{Database, Key, VerificationMetadata},
+	event_bus::Subscriber,
+	node::Node,
+	shutdown::{Controller, ShutdownReason},
+	types::{BlockVerified, IdentityConfig, OptionBlockRange, RuntimeConfig, SecretKey},
+	utils::calculate_confidence,
+};
+
+/// Error of the exported handle methods, with the error chain as a message
+#[derive(Debug)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
+pub enum HandleError {
+	Failed(String),
+}
+
+impl Display for HandleError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			HandleError::Failed(message) => f.write_str(message),
+		}
+	}
+}
+
+impl std::error::Error for HandleError {}
+
+impl From<Report> for HandleError {
+	fn from(error: Report) -> Self {
+		HandleError::Failed(format!("{error:#}"))
+	}
+}
+
+/// Block verified by the light client
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct VerifiedBlock {
+	pub block_number: u32,
+	/// Hex encoded block hash
+	pub block_hash: String,
+	/// Confidence, if block was sampled
+	pub confidence: Option<f64>,
+}
+
+impl From<BlockVerified> for VerifiedBlock {
+	fn from(block: BlockVerified) -> Self {
+		VerifiedBlock {
+			block_number: block.block_num,
+			block_hash: format!("{:?}", block.header_hash),
+			confidence: block.confidence,
+		}
+	}
+}
+
+/// Status and confidence of the block
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct BlockStatus {
+	/// Block status, as returned by `GET /v2/blocks/{block_number}` (e.g. `finished`)
+	pub status: String,
+	pub confidence: Option<f64>,
+}
+
+/// Listener of the verified blocks, implemented by the foreign language code
+#[cfg_attr(feature = "uniffi", uniffi::export(callback_interface))]
+pub trait VerifiedBlockListener: Send + Sync {
+	fn on_verified_block(&self, block: VerifiedBlock);
+}
+
+/// Parses runtime configuration from JSON, missing parameters are set to defaults
+pub fn parse_config(config_json: &str) -> Result<RuntimeConfig> {
+	let cfg: RuntimeConfig =
+		serde_json::from_str(config_json).wrap_err("Failed to parse configuration")?;
+	cfg.validate()?;
+	Ok(cfg)
+}
+
+/// Starts the node with the JSON configuration and the identity stored in the given keystore path
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+pub fn start_node(
+	config_json: String,
+	identity_path: String,
+) -> Result<Arc<NodeHandle>, HandleError> {
+	Ok(Arc::new(NodeHandle::start(&config_json, &identity_path)?))
+}
+
+/// Handle of the light client node running on its own runtime
+#[cfg_attr(feature = "uniffi", derive(uniffi::Object))]
+pub struct NodeHandle {
+	// node is dropped before the runtime it is running on
+	node: Node,
+	blocks: Mutex<Subscriber<BlockVerified>>,
+	sync_start_block: Option<u32>,
+	runtime: Runtime,
+}
+
+impl NodeHandle {
+	/// Starts the node with the JSON configuration and the identity stored in the given keystore path
+	pub fn start(config_json: &str, identity_path: &str) -> Result<Self> {
+		let mut cfg = parse_config(config_json)?;
+		let identity_cfg = IdentityConfig::load_or_init(identity_path, None, None)?;
+		if cfg.secret_key.is_none() {
+			if let Some(key) = identity_cfg.libp2p_secret_key.clone() {
+				cfg.secret_key = Some(SecretKey::Key { key });
+			}
+		}
+
+		let sync_start_block = cfg.sync_start_block;
+		let runtime = Runtime::new().wrap_err("Failed to create runtime")?;
+		let node = runtime.block_on(
+			Node::builder(cfg, identity_cfg)
+				.shutdown(Controller::new())
+				.start(),
+		)?;
+		let blocks = Mutex::new(node.subscribe_verified_blocks());
+
+		Ok(NodeHandle {
+			node,
+			blocks,
+			sync_start_block,
+			runtime,
+		})
+	}
+
+	/// Returns status and confidence of the given block, or `None` if block is not received yet
+	pub fn block(&self, block_number: u32) -> Result<Option<Block>> {
+		let header: Option<Header> = self.node.db.get(Key::BlockHeader(block_number))?;
+		let status = {
+			let state = self.node.state.lock().expect("Lock acquired");
+			header.and_then(|header| {
+				block_status(
+					&self.sync_start_block,
+					&state,
+					block_number,
+					header.extension,
+				)
+			})
+		};
+		let Some(status) = status else {
+			return Ok(None);
+		};
+		Ok(Some(Block::new(status, self.confidence(block_number)?)))
+	}
+
+	/// Returns verification metadata of the sampled blocks in the given inclusive range
+	pub fn verification_history(&self, first: u32, last: u32) -> Result<Vec<BlockVerification>> {
+		let mut history = vec![];
+		for block_number in first..=last {
+			let metadata: Option<VerificationMetadata> =
+				self.node.db.get(Key::BlockVerification(block_number))?;
+			history.extend(metadata.map(|metadata| BlockVerification::new(block_number, metadata)));
+		}
+		Ok(history)
+	}
+
+	/// Calls the callback for each verified block, until the node is stopped
+	pub fn on_verified_block(&self, callback: impl Fn(VerifiedBlock) + Send + 'static) {
+		let mut blocks = self.node.subscribe_verified_blocks();
+		self.runtime.spawn(async move {
+			while let Some(block) = blocks.recv().await {
+				callback(block.into());
+			}
+		});
+	}
+}
+
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+impl NodeHandle {
+	/// Stops the node, waiting for the shutdown to complete
+	pub fn stop(&self) {
+		let _ = self
+			.node
+			.shutdown
+			.trigger_shutdown(ShutdownReason::Signal("Stopped by the handle".to_string()));
+		self.runtime.block_on(self.node.stopped());
+	}
+
+	/// Returns `true` if shutdown is triggered
+	pub fn is_stopped(&self) -> bool {
+		self.node.shutdown.is_shutdown_triggered()
+	}
+
+	/// Returns the latest block with achieved confidence
+	pub fn latest_block(&self) -> Option<u32> {
+		self.node
+			.state
+			.lock()
+			.expect("Lock acquired")
+			.confidence_achieved
+			.last()
+	}
+
+	/// Returns confidence of the given block, if block is verified
+	pub fn confidence(&self, block_number: u32) -> Result<Option<f64>, HandleError> {
+		let count: Option<u32> = self.node.db.get(Key::VerifiedCellCount(block_number))?;
+		Ok(count.map(calculate_confidence))
+	}
+
+	/// Returns status and confidence of the given block, or `None` if block is not received yet
+	pub fn block_status(&self, block_number: u32) -> Result<Option<BlockStatus>, HandleError> {
+		let Some(block) = self.block(block_number)? else {
+			return Ok(None);
+		};
+		let status = serde_json::to_value(&block.status)
+			.ok()
+			.and_then(|status| status.as_str().map(String::from))
+			.unwrap_or_default();
+		Ok(Some(BlockStatus {
+			status,
+			confidence: block.confidence,
+		}))
+	}
+
+	/// Returns encoded application extrinsics of the given block, if they are fetched
+	pub fn app_data(
+		&self,
+		app_id: u32,
+		block_number: u32,
+	) -> Result<Option<Vec<Vec<u8>>>, HandleError> {
+		Ok(self.node.db.get(Key::AppData(app_id, block_number))?)
+	}
+
+	/// Calls the listener for each verified block, until the node is stopped
+	pub fn set_verified_block_listener(&self, listener: Box<dyn VerifiedBlockListener>) {
+		self.on_verified_block(move |block| listener.on_verified_block(block));
+	}
+
+	/// Waits for the next verified block, returning `None` on timeout.
+	/// Blocks missed by a slow consumer are skipped.
+	pub fn next_verified_block(
+		&self,
+		timeout: Duration,
+	) -> Result<Option<VerifiedBlock>, HandleError> {
+		let mut blocks = self.blocks.lock().expect("Lock acquired");
+		self.runtime.block_on(async {
+			match tokio::time::timeout(timeout, blocks.recv()).await {
+				Err(_) => Ok(None),
+				Ok(Some(block)) => Ok(Some(block.into())),
+				Ok(None) => Err(HandleError::Failed("Node is stopped".to_string())),
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use color_eyre::eyre::eyre;
+
+	#[test]
+	fn parse_config_defaults() {
+		let cfg = parse_config(r#"{"confidence": 99.9}"#).unwrap();
+		assert_eq!(cfg.confidence, 99.9);
+		assert_eq!(
+			cfg.http_server_port,
+			RuntimeConfig::default().http_server_port
+		);
+		assert!(parse_config(r#"{"confidence": "high"}"#).is_err());
+	}
+
+	#[test]
+	fn handle_error() {
+		let error = HandleError::from(eyre!("Disk is full").wrap_err("Failed to store block"));
+		assert_eq!(error.to_string(), "Failed to store block: Disk is full");
+	}
+}
@@ -0,0 +1,412 @@
+//! Sync of the finalized headers from the peers, during the RPC outages.
+//!
+//! Light and fat clients serve stored headers, with their finality justifications, over the header sync protocol
+//! (see [`crate::network::p2p::header_sync`]). If no finalized header is received from RPC within the timeout,
+//! headers following the last finalized one are requested from the connected peers, and published the same way as RPC headers.
+//!
+//! # Verification
+//!
+//! Synced headers have to extend the last finalized header, and only headers up to the last one finalized with
+//! a justification signed by the supermajority of the validator set are accepted. Validator set changes
+//! scheduled in the synced headers are applied the same way as in the RPC subscription.
+
+use avail_subxt::primitives::Header;
+use codec::Encode;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use libp2p::{request_response::ResponseChannel, PeerId};
+use rand::seq::SliceRandom;
+use sp_core::{blake2_256, ed25519, H256};
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::{
+	sync::{broadcast, mpsc},
+	time::Instant,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+	data::{Database, Key},
+	finality::{check_finality, ValidatorSet},
+	network::{
+		p2p::{
+			header_sync::{HeadersRequest, HeadersResponse, SyncedHeader, MAX_HEADERS},
+			Client as P2pClient,
+		},
+		rpc::Event,
+	},
+	telemetry::{MetricCounter, Metrics},
+	types::{OptionBlockRange, State},
+	utils::filter_auth_set_changes,
+};
+
+/// Maximum number of the peers requested for headers in one sync attempt
+const MAX_PEERS: usize = 5;
+
+pub struct InboundRequest {
+	pub peer_id: PeerId,
+	pub request: HeadersRequest,
+	pub channel: ResponseChannel<HeadersResponse>,
+}
+
+/// Sender of the inbound header requests to the serving task, requests are dropped if the task is not running
+#[derive(Clone, Default)]
+pub struct Requests(Option<mpsc::UnboundedSender<InboundRequest>>);
+
+impl Requests {
+	pub fn new() -> (Self, mpsc::UnboundedReceiver<InboundRequest>) {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		(Requests(Some(sender)), receiver)
+	}
+
+	pub fn send(&self, request: InboundRequest) {
+		let Some(sender) = &self.0 else {
+			debug!(peer_id = %request.peer_id, "Header requests are not served");
+			return;
+		};
+		if sender.send(request).is_err() {
+			debug!("Header requests serving is stopped");
+		}
+	}
+}
+
+/// Returns consecutive stored headers with their justifications, starting with the requested one
+fn stored_headers(db: &impl Database, request: &HeadersRequest) -> Result<HeadersResponse> {
+	let mut headers = vec![];
+	let last = request.from.saturating_add(request.count.min(MAX_HEADERS));
+	for block_number in request.from..last {
+		let Some(header) = db.get(Key::BlockHeader(block_number))? else {
+			break;
+		};
+		let justification = db.get(Key::Justification(block_number))?;
+		headers.push(SyncedHeader {
+			header,
+			justification,
+		});
+	}
+	Ok(HeadersResponse { headers })
+}
+
+/// Serves header requests of the peers from the database
+pub async fn serve(
+	db: impl Database,
+	p2p_client: P2pClient,
+	mut receiver: mpsc::UnboundedReceiver<InboundRequest>,
+) {
+	info!("Serving headers to peers...");
+	while let Some(InboundRequest {
+		peer_id,
+		request,
+		channel,
+	}) = receiver.recv().await
+	{
+		let response = stored_headers(&db, &request).unwrap_or_else(|error| {
+			warn!(%peer_id, "Cannot get requested headers: {error:#}");
+			HeadersResponse::default()
+		});
+		debug!(%peer_id, from = request.from, count = response.headers.len(), "Serving headers");
+		if let Err(error) = p2p_client.send_headers(channel, response).await {
+			debug!(%peer_id, "Cannot send headers: {error:#}");
+		}
+	}
+}
+
+fn hash(header: &Header) -> H256 {
+	Encode::using_encoded(header, blake2_256).into()
+}
+
+/// Returns validator set which is in effect after the header, if the header schedules the change
+fn scheduled_validator_set(header: &Header, current: &ValidatorSet) -> Option<ValidatorSet> {
+	let auths = filter_auth_set_changes(header).pop()?;
+	Some(ValidatorSet {
+		set_id: current.set_id + 1,
+		validator_set: auths
+			.into_iter()
+			.map(|(a, _)| ed25519::Public::from_raw(a.0 .0 .0))
+			.collect(),
+	})
+}
+
+/// Returns the headers following the finalized one, up to the last header finalized with a valid justification,
+/// with the validator set of the last returned header
+fn verify(
+	finalized: &Header,
+	validator_set: &ValidatorSet,
+	response: HeadersResponse,
+) -> Result<(Vec<Header>, ValidatorSet)> {
+	let mut parent_hash = hash(finalized);
+	let mut parent_number = finalized.number;
+	let mut current = validator_set.clone();
+	let mut scheduled = scheduled_validator_set(finalized, &current);
+
+	let mut verified = (vec![], validator_set.clone());
+	let mut unverified = vec![];
+	for SyncedHeader {
+		header,
+		justification,
+	} in response.headers.into_iter().take(MAX_HEADERS as usize)
+	{
+		if header.number != parent_number + 1 || header.parent_hash != parent_hash {
+			return Err(eyre!(
+				"Header {} doesn't extend the header {parent_number}",
+				header.number
+			));
+		}
+		// validator set scheduled by the parent header is in effect from this header
+		if let Some(next) = scheduled.take() {
+			current = next;
+		}
+		parent_hash = hash(&header);
+		parent_number = header.number;
+		scheduled = scheduled_validator_set(&header, &current);
+		unverified.push(header);
+
+		let Some(justification) = justification else {
+			continue;
+		};
+		if justification.commit.target_hash != parent_hash {
+			return Err(eyre!(
+				"Justification of the header {parent_number} is for another block"
+			));
+		}
+		check_finality(&current, &justification)
+			.wrap_err(format!("Header {parent_number} is not finalized"))?;
+		verified.0.append(&mut unverified);
+		verified.1 = current.clone();
+	}
+	Ok(verified)
+}
+
+/// Requests headers following the finalized one from the connected peers, returning the first verified response
+async fn sync(
+	p2p_client: &P2pClient,
+	finalized: &Header,
+	validator_set: &ValidatorSet,
+) -> Result<Option<(Vec<Header>, ValidatorSet)>> {
+	let mut peers = p2p_client
+		.list_connected_peers()
+		.await?
+		.iter()
+		.filter_map(|peer_id| peer_id.parse::<PeerId>().ok())
+		.collect::<Vec<_>>();
+	peers.shuffle(&mut rand::thread_rng());
+
+	let request = HeadersRequest {
+		from: finalized.number + 1,
+		count: MAX_HEADERS,
+	};
+	for peer_id in peers.into_iter().take(MAX_PEERS) {
+		let response = match p2p_client.request_headers(peer_id, request.clone()).await {
+			Ok(response) => response,
+			Err(error) => {
+				debug!(%peer_id, "Header request failed: {error:#}");
+				continue;
+			},
+		};
+		match verify(finalized, validator_set, response) {
+			Ok((headers, _)) if headers.is_empty() => {
+				debug!(%peer_id, "No finalized headers received")
+			},
+			Ok(verified) => return Ok(Some(verified)),
+			Err(error) => warn!(%peer_id, "Invalid headers received: {error:#}"),
+		}
+	}
+	Ok(None)
+}
+
+/// Syncs finalized headers from the peers if no header is received from RPC within the timeout
+pub async fn run(
+	p2p_client: P2pClient,
+	state: Arc<Mutex<State>>,
+	event_sender: broadcast::Sender<Event>,
+	metrics: Arc<impl Metrics>,
+	mut finalized: Header,
+	timeout: Duration,
+) {
+	info!("Starting header sync from peers...");
+	let mut events = event_sender.subscribe();
+	let mut deadline = Instant::now() + timeout;
+	loop {
+		tokio::select! {
+			event = events.recv() => match event {
+				Ok(Event::HeaderUpdate { header, .. }) => {
+					if header.number > finalized.number {
+						finalized = header;
+					}
+					deadline = Instant::now() + timeout;
+				},
+				Err(broadcast::error::RecvError::Lagged(skipped)) => {
+					debug!(skipped, "Headers skipped by header sync");
+				},
+				Err(broadcast::error::RecvError::Closed) => return,
+			},
+			_ = tokio::time::sleep_until(deadline) => {
+				deadline = Instant::now() + timeout;
+				let Some(validator_set) = state.lock().expect("Lock acquired").validator_set.clone() else {
+					continue;
+				};
+				warn!(block_number = finalized.number, "No finalized headers received from RPC in {timeout:?}, syncing from peers");
+				let (headers, validator_set) = match sync(&p2p_client, &finalized, &validator_set).await {
+					Ok(Some(verified)) => verified,
+					Ok(None) => {
+						warn!("No finalized headers synced from peers");
+						continue;
+					},
+					Err(error) => {
+						warn!("Header sync from peers failed: {error:#}");
+						continue;
+					},
+				};
+				info!(count = headers.len(), "Finalized headers synced from peers");
+				for header in headers {
+					{
+						let mut state = state.lock().expect("Lock acquired");
+						state.latest = state.latest.max(header.number);
+						state.header_verified.set(header.number);
+					}
+					metrics.count(MetricCounter::PeerSyncedHeaders).await;
+					if event_sender
+						.send(Event::HeaderUpdate {
+							header,
+							received_at: std::time::Instant::now(),
+						})
+						.is_err()
+					{
+						return;
+					}
+				}
+				state.lock().expect("Lock acquired").validator_set = Some(validator_set);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		data::mem_db::MemoryDB,
+		simulation::Simulation,
+		types::{Commit, GrandpaJustification, Precommit, SignedPrecommit, SignerMessage},
+	};
+	use sp_core::Pair;
+
+	fn chain(finalized: &Header, count: u32) -> Vec<Header> {
+		let mut parent = finalized.clone();
+		(1..=count)
+			.map(|i| {
+				let mut header = Simulation::header(finalized.number + i, 1, 4);
+				header.parent_hash = hash(&parent);
+				parent = header.clone();
+				header
+			})
+			.collect()
+	}
+
+	fn justification(header: &Header, keys: &[ed25519::Pair], set_id: u64) -> GrandpaJustification {
+		let precommit = Precommit {
+			target_hash: hash(header),
+			target_number: header.number,
+		};
+		let round = 1u64;
+		let precommits = keys
+			.iter()
+			.map(|key| {
+				let message = Encode::encode(&(
+					&SignerMessage::PrecommitMessage(precommit.clone()),
+					&round,
+					&set_id,
+				));
+				SignedPrecommit {
+					precommit: precommit.clone(),
+					signature: key.sign(&message),
+					id: key.public(),
+				}
+			})
+			.collect();
+		GrandpaJustification {
+			round,
+			commit: Commit {
+				target_hash: precommit.target_hash,
+				target_number: precommit.target_number,
+				precommits,
+			},
+			votes_ancestries: vec![],
+		}
+	}
+
+	#[test]
+	fn verify_synced_headers() {
+		let keys = (0..3u8)
+			.map(|i| ed25519::Pair::from_seed(&[i; 32]))
+			.collect::<Vec<_>>();
+		let validator_set = ValidatorSet {
+			set_id: 10,
+			validator_set: keys.iter().map(Pair::public).collect(),
+		};
+		let finalized = Simulation::header(1, 1, 4);
+		let headers = chain(&finalized, 3);
+		let synced = |justified: Option<usize>, keys: &[ed25519::Pair]| HeadersResponse {
+			headers: headers
+				.iter()
+				.enumerate()
+				.map(|(i, header)| SyncedHeader {
+					header: header.clone(),
+					justification: (Some(i) == justified).then(|| justification(header, keys, 10)),
+				})
+				.collect(),
+		};
+
+		// headers after the last justified one are not accepted
+		let (verified, verified_set) =
+			verify(&finalized, &validator_set, synced(Some(1), &keys)).unwrap();
+		assert_eq!(
+			verified
+				.iter()
+				.map(|header| header.number)
+				.collect::<Vec<_>>(),
+			vec![2, 3]
+		);
+		assert_eq!(verified_set.set_id, 10);
+
+		let (verified, _) = verify(&finalized, &validator_set, synced(None, &keys)).unwrap();
+		assert!(verified.is_empty());
+
+		// justification has to be signed by the supermajority
+		assert!(verify(&finalized, &validator_set, synced(Some(2), &keys[..1])).is_err());
+
+		// headers have to extend the finalized one
+		let unrelated = Simulation::header(1, 2, 4);
+		assert!(verify(&unrelated, &validator_set, synced(Some(2), &keys)).is_err());
+	}
+
+	#[test]
+	fn serve_stored_headers() {
+		let db = MemoryDB::default();
+		let finalized = Simulation::header(1, 1, 4);
+		for header in chain(&finalized, 3) {
+			db.put(Key::BlockHeader(header.number), header).unwrap();
+		}
+		let response = stored_headers(
+			&db,
+			&HeadersRequest {
+				from: 3,
+				count: 100,
+			},
+		)
+		.unwrap();
+		assert_eq!(
+			response
+				.headers
+				.iter()
+				.map(|synced| synced.header.number)
+				.collect::<Vec<_>>(),
+			vec![3, 4]
+		);
+		assert!(response.headers[0].justification.is_none());
+	}
+}
@@ -0,0 +1,247 @@
+//! Keystore for the light client secrets.
+//!
+//! Keystore is stored in the identity file and holds the Avail account secret URI and the libp2p ed25519 secret key.
+//! When password is given, secrets are stored encrypted with ChaCha20-Poly1305, using a key derived with PBKDF2-HMAC-SHA256.
+
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hmac::Hmac;
+use libp2p::identity;
+use rand::{thread_rng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::Path;
+use subxt_signer::bip39::{Language, Mnemonic};
+use tracing::warn;
+
+/// Environment variable used as a keystore password if `--keystore-password` is not set
+pub const KEYSTORE_PASSWORD_ENV: &str = "AVAIL_KEYSTORE_PASSWORD";
+
+const PBKDF2_ROUNDS: u32 = 100_000;
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct Secrets {
+	/// Avail account secret URI
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub avail_secret_uri: Option<String>,
+	// TODO: Deprecated since 1.9.0, remove it once it is safe
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub avail_secret_seed_phrase: Option<String>,
+	/// Hex encoded ed25519 secret key used for libp2p identity
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub libp2p_secret_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Encrypted {
+	salt: String,
+	nonce: String,
+	ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KeystoreFile {
+	#[serde(flatten)]
+	secrets: Secrets,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	encrypted: Option<Encrypted>,
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32]> {
+	let mut key = [0u8; 32];
+	pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut key)
+		.map_err(|error| eyre!("Failed to derive keystore key: {error}"))?;
+	Ok(key)
+}
+
+fn encrypt(secrets: &Secrets, password: &str) -> Result<Encrypted> {
+	let mut salt = [0u8; SALT_LENGTH];
+	let mut nonce = [0u8; NONCE_LENGTH];
+	thread_rng().fill_bytes(&mut salt);
+	thread_rng().fill_bytes(&mut nonce);
+
+	let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt)?.into());
+	let plaintext = serde_json::to_vec(secrets)?;
+	let ciphertext = cipher
+		.encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+		.map_err(|_| eyre!("Failed to encrypt keystore"))?;
+
+	Ok(Encrypted {
+		salt: hex::encode(salt),
+		nonce: hex::encode(nonce),
+		ciphertext: hex::encode(ciphertext),
+	})
+}
+
+fn decrypt(encrypted: &Encrypted, password: &str) -> Result<Secrets> {
+	let salt = hex::decode(&encrypted.salt).wrap_err("Invalid keystore salt")?;
+	let nonce = hex::decode(&encrypted.nonce).wrap_err("Invalid keystore nonce")?;
+	if nonce.len() != NONCE_LENGTH {
+		return Err(eyre!("Invalid keystore nonce length"));
+	}
+	let ciphertext = hex::decode(&encrypted.ciphertext).wrap_err("Invalid keystore ciphertext")?;
+
+	let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt)?.into());
+	let plaintext = cipher
+		.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+		.map_err(|_| eyre!("Failed to decrypt keystore, password is invalid"))?;
+	serde_json::from_slice(&plaintext).wrap_err("Failed to decode keystore secrets")
+}
+
+/// Generates new 24 words mnemonic, to be used as Avail secret URI
+pub fn generate_avail_secret_uri() -> Result<String> {
+	Ok(Mnemonic::generate_in(Language::English, 24)?.to_string())
+}
+
+/// Generates new hex encoded ed25519 secret key for libp2p identity
+pub fn generate_libp2p_secret_key() -> String {
+	let mut key = [0u8; 32];
+	thread_rng().fill_bytes(&mut key);
+	hex::encode(key)
+}
+
+/// Decodes hex encoded ed25519 secret key into libp2p keypair
+pub fn libp2p_keypair(secret_key: &str) -> Result<identity::Keypair> {
+	let mut key = [0u8; 32];
+	hex::decode_to_slice(secret_key, &mut key).wrap_err("Failed to decode libp2p secret key")?;
+	identity::Keypair::ed25519_from_bytes(key).wrap_err("Invalid libp2p secret key")
+}
+
+pub struct Keystore {
+	path: String,
+	encrypted: bool,
+	pub secrets: Secrets,
+}
+
+impl Keystore {
+	/// Creates empty keystore, stored to the given path
+	pub fn new(path: &str) -> Self {
+		Keystore {
+			path: path.to_string(),
+			encrypted: false,
+			secrets: Secrets::default(),
+		}
+	}
+
+	/// Loads keystore from the file, or creates empty keystore if file doesn't exist
+	pub fn load(path: &str, password: Option<&str>) -> Result<Self> {
+		if !Path::new(path).exists() {
+			return Ok(Keystore::new(path));
+		}
+
+		let file: KeystoreFile =
+			confy::load_path(path).wrap_err(format!("Failed to load keystore from {path}"))?;
+
+		let (secrets, encrypted) = match (file.encrypted, password) {
+			(None, _) => (file.secrets, false),
+			(Some(encrypted), Some(password)) => (decrypt(&encrypted, password)?, true),
+			(Some(_), None) => {
+				return Err(eyre!(
+					"Keystore {path} is password protected, use --keystore-password or {KEYSTORE_PASSWORD_ENV} to unlock it"
+				))
+			},
+		};
+
+		if secrets.avail_secret_seed_phrase.is_some() {
+			warn!("Using deprecated configuration parameter `avail_secret_seed_phrase`, use `avail_secret_uri` instead.");
+		}
+
+		Ok(Keystore {
+			path: path.to_string(),
+			encrypted,
+			secrets,
+		})
+	}
+
+	/// Returns `true` if keystore secrets are stored encrypted
+	pub fn is_encrypted(&self) -> bool {
+		self.encrypted
+	}
+
+	/// Stores keystore to the file, secrets are encrypted if password is given
+	pub fn store(&mut self, password: Option<&str>) -> Result<()> {
+		let file = match password {
+			Some(password) => KeystoreFile {
+				secrets: Secrets::default(),
+				encrypted: Some(encrypt(&self.secrets, password)?),
+			},
+			None => KeystoreFile {
+				secrets: self.secrets.clone(),
+				encrypted: None,
+			},
+		};
+		confy::store_path(&self.path, file)
+			.wrap_err(format!("Failed to store keystore to {}", self.path))?;
+		self.encrypted = password.is_some();
+		Ok(())
+	}
+
+	/// Returns configured Avail secret URI, preferring `avail_secret_uri` over deprecated `avail_secret_seed_phrase`
+	pub fn avail_secret_uri(&self) -> Option<&str> {
+		self.secrets
+			.avail_secret_uri
+			.as_deref()
+			.or(self.secrets.avail_secret_seed_phrase.as_deref())
+	}
+
+	/// Returns libp2p keypair if libp2p secret key is stored in the keystore
+	pub fn libp2p_keypair(&self) -> Result<Option<identity::Keypair>> {
+		self.secrets
+			.libp2p_secret_key
+			.as_deref()
+			.map(libp2p_keypair)
+			.transpose()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn secrets() -> Secrets {
+		Secrets {
+			avail_secret_uri: Some(generate_avail_secret_uri().unwrap()),
+			avail_secret_seed_phrase: None,
+			libp2p_secret_key: Some(generate_libp2p_secret_key()),
+		}
+	}
+
+	#[test]
+	fn encrypt_decrypt() {
+		let secrets = secrets();
+		let encrypted = encrypt(&secrets, "password").unwrap();
+		assert_eq!(decrypt(&encrypted, "password").unwrap(), secrets);
+		assert!(decrypt(&encrypted, "invalid").is_err());
+	}
+
+	#[test]
+	fn store_load() {
+		let dir = std::env::temp_dir().join(format!("avail_light_keystore_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let plain = dir.join("plain.toml");
+		let protected = dir.join("protected.toml");
+
+		let mut keystore = Keystore::load(plain.to_str().unwrap(), None).unwrap();
+		keystore.secrets = secrets();
+		keystore.store(None).unwrap();
+		let loaded = Keystore::load(plain.to_str().unwrap(), None).unwrap();
+		assert_eq!(loaded.secrets, keystore.secrets);
+		assert!(!loaded.is_encrypted());
+		assert!(loaded.libp2p_keypair().unwrap().is_some());
+
+		let mut keystore = Keystore::load(protected.to_str().unwrap(), None).unwrap();
+		keystore.secrets = secrets();
+		keystore.store(Some("password")).unwrap();
+		assert!(Keystore::load(protected.to_str().unwrap(), None).is_err());
+		let loaded = Keystore::load(protected.to_str().unwrap(), Some("password")).unwrap();
+		assert_eq!(loaded.secrets, keystore.secrets);
+		assert!(loaded.is_encrypted());
+
+		let _ = std::fs::remove_dir_all(dir);
+	}
+}
@@ -0,0 +1,199 @@
+//! Persistence of the known peers, to speed up DHT re-entry after restart.
+//!
+//! Connected routing table peers are periodically stored in the database, and once more before the database is flushed on shutdown.
+//! On startup, stored peers are added to the routing table before the bootstrap, so the discovery doesn't start from the bootstrap nodes only.
+//!
+//! Number of stored peers is capped to the most recently seen ones, and peers not seen in the routing table for longer than the maximum age are evicted.
+
+use color_eyre::{eyre::WrapErr, Result};
+use libp2p::{Multiaddr, PeerId};
+use std::{collections::HashMap, str::FromStr, time::Duration};
+use tracing::{debug, info, warn};
+
+use crate::{
+	data::{Database, Key, KnownPeer},
+	network::p2p::Client as P2pClient,
+	types::RuntimeConfig,
+	utils::unix_timestamp_millis,
+};
+
+/// Interval in which connected routing table peers are persisted
+const PERSIST_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+	/// Maximum number of persisted peers, persistence is disabled if 0
+	pub limit: usize,
+	/// Peers not seen for longer than this period are evicted
+	pub max_age: Duration,
+}
+
+impl From<&RuntimeConfig> for Config {
+	fn from(cfg: &RuntimeConfig) -> Self {
+		Config {
+			limit: cfg.known_peers_limit,
+			max_age: Duration::from_secs(cfg.known_peers_max_age),
+		}
+	}
+}
+
+impl Config {
+	pub fn is_enabled(&self) -> bool {
+		self.limit > 0
+	}
+
+	fn is_stale(&self, peer: &KnownPeer, now: u64) -> bool {
+		now.saturating_sub(peer.last_seen) > self.max_age.as_millis() as u64
+	}
+}
+
+/// Merges currently connected peers into the stored ones, evicting stale peers and keeping the most recently seen ones up to the limit
+fn merge(
+	stored: Vec<KnownPeer>,
+	current: Vec<KnownPeer>,
+	now: u64,
+	config: &Config,
+) -> Vec<KnownPeer> {
+	let mut peers = HashMap::new();
+	for peer in stored.into_iter().chain(current) {
+		peers.insert(peer.peer_id.clone(), peer);
+	}
+	let mut peers = peers
+		.into_values()
+		.filter(|peer| !config.is_stale(peer, now))
+		.collect::<Vec<_>>();
+	peers.sort_by(|a, b| {
+		b.last_seen
+			.cmp(&a.last_seen)
+			.then(a.peer_id.cmp(&b.peer_id))
+	});
+	peers.truncate(config.limit);
+	peers
+}
+
+fn parse(peer: &KnownPeer) -> Option<(PeerId, Vec<Multiaddr>)> {
+	let peer_id = PeerId::from_str(&peer.peer_id).ok()?;
+	let addresses = peer
+		.addresses
+		.iter()
+		.filter_map(|address| Multiaddr::from_str(address).ok())
+		.collect::<Vec<_>>();
+	(!addresses.is_empty()).then_some((peer_id, addresses))
+}
+
+/// Returns persisted peers which are not stale, with their addresses
+pub fn load(db: &impl Database, config: &Config) -> Result<Vec<(PeerId, Vec<Multiaddr>)>> {
+	let now = unix_timestamp_millis();
+	let stored: Vec<KnownPeer> = db
+		.get(Key::KnownPeers)
+		.wrap_err("Failed to get known peers from the database")?
+		.unwrap_or_default();
+	Ok(stored
+		.iter()
+		.filter(|peer| !config.is_stale(peer, now))
+		.filter_map(parse)
+		.collect())
+}
+
+/// Adds persisted peers to the routing table, returning the number of added peers
+pub async fn add_to_routing_table(
+	db: &impl Database,
+	p2p_client: &P2pClient,
+	config: &Config,
+) -> Result<usize> {
+	let peers = load(db, config)?;
+	let added = peers.len();
+	for (peer_id, addresses) in peers {
+		for address in addresses {
+			p2p_client.add_address(peer_id, address).await?;
+		}
+	}
+	Ok(added)
+}
+
+/// Persists connected routing table peers, returning the number of persisted peers
+pub async fn persist(db: &impl Database, p2p_client: &P2pClient, config: &Config) -> Result<usize> {
+	let now = unix_timestamp_millis();
+	let current = p2p_client
+		.list_routing_table_peers()
+		.await?
+		.into_iter()
+		.filter(|(_, addresses)| !addresses.is_empty())
+		.map(|(peer_id, addresses)| KnownPeer {
+			peer_id: peer_id.to_string(),
+			addresses: addresses.iter().map(ToString::to_string).collect(),
+			last_seen: now,
+		})
+		.collect();
+	let stored: Vec<KnownPeer> = db
+		.get(Key::KnownPeers)
+		.wrap_err("Failed to get known peers from the database")?
+		.unwrap_or_default();
+	let peers = merge(stored, current, now, config);
+	let persisted = peers.len();
+	db.put(Key::KnownPeers, peers)
+		.wrap_err("Failed to store known peers in the database")?;
+	Ok(persisted)
+}
+
+/// Periodically persists connected routing table peers
+pub async fn run(db: impl Database, p2p_client: P2pClient, config: Config) {
+	info!("Starting known peers persistence...");
+	let mut interval = tokio::time::interval(PERSIST_INTERVAL);
+	// first tick completes immediately, while the routing table is not populated yet
+	interval.tick().await;
+	loop {
+		interval.tick().await;
+		match persist(&db, &p2p_client, &config).await {
+			Ok(persisted) => debug!(persisted, "Known peers persisted"),
+			Err(error) => warn!("Failed to persist known peers: {error:#}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn peer(id: &str, last_seen: u64) -> KnownPeer {
+		KnownPeer {
+			peer_id: id.to_string(),
+			addresses: vec!["/ip4/127.0.0.1/tcp/37000".to_string()],
+			last_seen,
+		}
+	}
+
+	#[test]
+	fn merge_known_peers() {
+		let config = Config {
+			limit: 2,
+			max_age: Duration::from_secs(10),
+		};
+		let stored = vec![peer("a", 1_000), peer("b", 5_000), peer("c", 8_000)];
+		let current = vec![peer("a", 12_000)];
+
+		// "b" is stale, "a" is updated and the most recently seen
+		assert_eq!(
+			merge(stored.clone(), current.clone(), 16_000, &config),
+			vec![peer("a", 12_000), peer("c", 8_000)]
+		);
+
+		let config = Config { limit: 1, ..config };
+		assert_eq!(
+			merge(stored, current, 12_000, &config),
+			vec![peer("a", 12_000)]
+		);
+	}
+
+	#[test]
+	fn parse_known_peer() {
+		let peer_id = PeerId::random();
+		let mut known_peer = peer(&peer_id.to_string(), 0);
+		known_peer.addresses.push("invalid".to_string());
+		let (parsed_id, addresses) = parse(&known_peer).unwrap();
+		assert_eq!(parsed_id, peer_id);
+		assert_eq!(addresses.len(), 1);
+
+		assert!(parse(&peer("invalid", 0)).is_none());
+	}
+}
@@ -1,18 +1,56 @@
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
 pub mod api;
 pub mod app_client;
+pub mod attestation;
+pub mod best_client;
+pub mod channel_metrics;
+pub mod clock_skew;
 pub mod consts;
 #[cfg(feature = "crawl")]
 pub mod crawl_client;
 pub mod data;
+pub mod data_root;
+pub mod dht_retry;
+pub mod doctor;
+#[cfg(feature = "e2e")]
+pub mod e2e;
+pub mod event_bus;
+pub mod event_log;
 pub mod fat_client;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod finality;
+pub mod fleet;
+pub mod handle;
+pub mod header_sync;
+pub mod keystore;
+pub mod known_peers;
+pub mod lifecycle;
 pub mod light_client;
 pub mod maintenance;
 pub mod network;
+pub mod node;
 pub mod proof;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quarantine;
+pub mod randomness;
+pub mod replay;
+pub mod rpc_health;
+pub mod scheduler;
 pub mod shutdown;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
+pub mod status;
+pub mod supervisor;
 pub mod sync_client;
 pub mod sync_finality;
+pub mod systemd;
 pub mod telemetry;
 pub mod types;
 pub mod utils;
+pub mod verification;
+pub mod version_check;
+pub mod webhooks;
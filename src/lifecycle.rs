@@ -0,0 +1,198 @@
+//! Light client lifecycle state machine.
+//!
+//! Lifecycle state is transitioned by the subsystems, stored in the shared [`State`] and published on each change.
+//!
+//! # Transitions
+//!
+//! * `starting` -> `bootstrapping` - once the configuration and database are loaded
+//! * `bootstrapping` -> `syncing` or `running` - once the first finalized header is received and block verification is started
+//! * `syncing` -> `running` - once the historical sync is finished
+//! * `syncing` or `running` <-> `degraded` - while any [`Degradation`] is present (insufficient number of peers detected by the maintenance,
+//!   or stalled RPC header subscription)
+//! * any -> `shutting-down` - once the shutdown is triggered, which is the final state
+
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeSet,
+	sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::types::State;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LifecycleState {
+	#[default]
+	Starting,
+	Bootstrapping,
+	Syncing,
+	Running,
+	Degraded,
+	ShuttingDown,
+}
+
+impl LifecycleState {
+	/// Numeric representation of the lifecycle state, used for the metrics
+	pub fn code(&self) -> u64 {
+		*self as u64
+	}
+}
+
+/// Reason of the `degraded` state
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Degradation {
+	/// Number of peers in the routing table is below minimum
+	InsufficientPeers,
+	/// No header is received from the RPC subscription for longer than expected
+	RpcStalled,
+}
+
+/// Handle for the lifecycle state transitions, clones share the same state
+#[derive(Clone)]
+pub struct Lifecycle {
+	state: Arc<Mutex<State>>,
+	sender: broadcast::Sender<LifecycleState>,
+}
+
+impl Lifecycle {
+	pub fn new(state: Arc<Mutex<State>>) -> Self {
+		let (sender, _) = broadcast::channel(1 << 4);
+		Lifecycle { state, sender }
+	}
+
+	/// Returns current lifecycle state
+	pub fn get(&self) -> LifecycleState {
+		self.state.lock().expect("Lock acquired").lifecycle
+	}
+
+	/// Subscribes to the lifecycle state changes
+	pub fn subscribe(&self) -> broadcast::Receiver<LifecycleState> {
+		self.sender.subscribe()
+	}
+
+	/// Transitions to the given state. Transitions from the `shutting-down` state are ignored.
+	pub fn set(&self, lifecycle: LifecycleState) {
+		{
+			let mut state = self.state.lock().expect("Lock acquired");
+			if state.lifecycle == lifecycle || state.lifecycle == LifecycleState::ShuttingDown {
+				return;
+			}
+			info!(from = ?state.lifecycle, to = ?lifecycle, "Lifecycle state changed");
+			state.lifecycle = lifecycle;
+		}
+		let _ = self.sender.send(lifecycle);
+	}
+
+	/// Sets or clears the reason of the `degraded` state.
+	/// Light client is transitioned only if it is already `syncing`, `running` or `degraded`.
+	pub fn degrade(&self, degradation: Degradation, degraded: bool) {
+		let started = {
+			let mut state = self.state.lock().expect("Lock acquired");
+			if degraded {
+				state.degradations.insert(degradation);
+			} else {
+				state.degradations.remove(&degradation);
+			}
+			matches!(
+				state.lifecycle,
+				LifecycleState::Syncing | LifecycleState::Running | LifecycleState::Degraded
+			)
+		};
+		if started {
+			self.refresh();
+		}
+	}
+
+	/// Returns reasons of the `degraded` state
+	pub fn degradations(&self) -> BTreeSet<Degradation> {
+		self.state
+			.lock()
+			.expect("Lock acquired")
+			.degradations
+			.clone()
+	}
+
+	/// Transitions to the `degraded` state if any degradation is present, or to the `syncing` or `running` state,
+	/// depending on the historical sync progress
+	pub fn refresh(&self) {
+		let (degraded, synced) = {
+			let state = self.state.lock().expect("Lock acquired");
+			(!state.degradations.is_empty(), state.synced)
+		};
+		self.set(match (degraded, synced) {
+			(true, _) => LifecycleState::Degraded,
+			(false, Some(false)) => LifecycleState::Syncing,
+			(false, _) => LifecycleState::Running,
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn transitions() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let lifecycle = Lifecycle::new(state.clone());
+		let mut receiver = lifecycle.subscribe();
+		assert_eq!(lifecycle.get(), LifecycleState::Starting);
+
+		lifecycle.set(LifecycleState::Bootstrapping);
+		state.lock().unwrap().synced = Some(false);
+		lifecycle.refresh();
+		assert_eq!(lifecycle.get(), LifecycleState::Syncing);
+		lifecycle.degrade(Degradation::InsufficientPeers, true);
+		assert_eq!(lifecycle.get(), LifecycleState::Degraded);
+		state.lock().unwrap().synced = Some(true);
+		lifecycle.degrade(Degradation::InsufficientPeers, false);
+		assert_eq!(lifecycle.get(), LifecycleState::Running);
+		lifecycle.refresh();
+
+		lifecycle.set(LifecycleState::ShuttingDown);
+		lifecycle.refresh();
+		assert_eq!(lifecycle.get(), LifecycleState::ShuttingDown);
+
+		let mut changes = vec![];
+		while let Ok(change) = receiver.try_recv() {
+			changes.push(change);
+		}
+		assert_eq!(
+			changes,
+			vec![
+				LifecycleState::Bootstrapping,
+				LifecycleState::Syncing,
+				LifecycleState::Degraded,
+				LifecycleState::Running,
+				LifecycleState::ShuttingDown,
+			]
+		);
+	}
+
+	#[test]
+	fn degradations() {
+		let state = Arc::new(Mutex::new(State::default()));
+		let lifecycle = Lifecycle::new(state.clone());
+
+		// degradation before the start is recorded, without the transition
+		lifecycle.set(LifecycleState::Bootstrapping);
+		lifecycle.degrade(Degradation::RpcStalled, true);
+		assert_eq!(lifecycle.get(), LifecycleState::Bootstrapping);
+		lifecycle.refresh();
+		assert_eq!(lifecycle.get(), LifecycleState::Degraded);
+
+		// light client is degraded until all degradations are cleared
+		lifecycle.degrade(Degradation::InsufficientPeers, true);
+		lifecycle.degrade(Degradation::RpcStalled, false);
+		assert_eq!(lifecycle.get(), LifecycleState::Degraded);
+		assert_eq!(
+			lifecycle.degradations(),
+			BTreeSet::from([Degradation::InsufficientPeers])
+		);
+		lifecycle.degrade(Degradation::InsufficientPeers, false);
+		assert_eq!(lifecycle.get(), LifecycleState::Running);
+	}
+}
@@ -6,7 +6,7 @@
 //!
 //! * Connect to the Avail node WebSocket stream and start listening to finalized headers
 //! * Generate random cells for random data sampling (8 cells currently)
-//! * Retrieve cell proofs from a) DHT and/or b) via RPC call from the node, in that order
+//! * Retrieve cell proofs from a) DHT and/or b) via RPC call from the node and/or c) configured HTTP gateways, in that order
 //! * Verify proof using the received cells
 //! * Calculate block confidence and store it in RocksDB
 //! * Insert cells to to DHT for remote fetch
@@ -15,31 +15,71 @@
 //! # Notes
 //!
 //! In case delay is configured, block processing is delayed for configured time.
+//! In case maximum delay is configured as well, delay of each block is chosen at random from the range, so the sampling time is not predictable.
+//! Actual delay between the header receipt and the sampling start is stored in the block verification metadata.
+//! In case event log is configured, verification outcome of each sampled block is appended to it.
+//! In case verification deadline is configured, sampling which exceeds it is abandoned and partial confidence is stored.
+//! In case sampling boosts are configured, blocks containing data of the boosted apps are sampled with extra cells, or with all cells of the app rows.
 //! In case RPC is disabled, RPC calls will be skipped.
 
+use avail_core::{AppId, DataLookup};
 use avail_subxt::{primitives::Header, utils::H256};
 use codec::Encode;
 use color_eyre::{eyre::WrapErr, Result};
-use kate_recovery::{commitments, matrix::Dimensions};
+use kate_recovery::{
+	com::app_specific_rows,
+	commitments,
+	matrix::{Dimensions, Position},
+};
 use sp_core::blake2_256;
 use std::{
+	collections::{BTreeSet, HashSet},
 	sync::{Arc, Mutex},
 	time::Instant,
 };
-use tracing::{error, info};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
 
 use crate::{
-	data::{Database, Key},
+	data::{Database, Key, VerificationMetadata},
+	event_log::VerificationOutcome,
 	network::{
 		self,
 		rpc::{self, Event},
 	},
-	shutdown::Controller,
+	replay::Recording,
+	shutdown::{Controller, ShutdownReason},
 	telemetry::{MetricCounter, MetricValue, Metrics},
-	types::{self, ClientChannels, LightClientConfig, OptionBlockRange, State},
-	utils::{calculate_confidence, extract_kate},
+	types::{
+		self, ClientChannels, LightClientConfig, OptionBlockRange, ReloadableConfig, SamplingBoost,
+		State,
+	},
+	utils::{calculate_confidence, extract_app_lookup, extract_kate, unix_timestamp_millis},
+	verification,
 };
 
+/// Returns number of the additionally sampled cells, and rows which are fully sampled,
+/// for the block containing data of the boosted applications
+fn sampling_boost(
+	boosts: &[SamplingBoost],
+	lookup: &DataLookup,
+	dimensions: Dimensions,
+) -> (u32, Vec<u32>) {
+	let mut extra_cells = 0;
+	let mut rows = BTreeSet::new();
+	for boost in boosts {
+		let app_id = AppId(boost.app_id);
+		if lookup.range_of(app_id).is_none() {
+			continue;
+		}
+		extra_cells = extra_cells.max(boost.extra_cells);
+		if boost.full_rows {
+			rows.extend(app_specific_rows(lookup, dimensions, app_id));
+		}
+	}
+	(extra_cells, rows.into_iter().collect())
+}
+
 pub async fn process_block(
 	db: impl Database,
 	network_client: &impl network::Client,
@@ -62,7 +102,7 @@ pub async fn process_block(
 		"Processing finalized block",
 	);
 
-	let (required, verified, unverified) = match extract_kate(&header.extension) {
+	let (required, verified, unverified, timed_out) = match extract_kate(&header.extension) {
 		None => {
 			info!("Skipping block without header extension");
 
@@ -89,14 +129,50 @@ pub async fn process_block(
 
 			let commitments = commitments::from_slice(&commitment)?;
 			let cell_count = rpc::cell_count_for_confidence(cfg.confidence);
-			let positions = rpc::generate_random_cells(dimensions, cell_count);
+			let (mut rng, randomness) = cfg.randomness.rng(header_hash).await;
+			let lookup = if cfg.sampling_boosts.is_empty() {
+				None
+			} else {
+				extract_app_lookup(&header.extension).unwrap_or_else(|error| {
+					warn!(block_number, "Sampling is not boosted: {error:#}");
+					None
+				})
+			};
+			let (extra_cells, boosted_rows) = lookup
+				.map(|lookup| sampling_boost(&cfg.sampling_boosts, &lookup, dimensions))
+				.unwrap_or_default();
+			if extra_cells > 0 || !boosted_rows.is_empty() {
+				info!(
+					block_number,
+					extra_cells,
+					?boosted_rows,
+					"Sampling is boosted for the block with the boosted app data"
+				);
+			}
+			let mut positions =
+				verification::select_cells(dimensions, cell_count + extra_cells, &mut rng);
+			let sampled = positions.iter().copied().collect::<HashSet<_>>();
+			positions.extend(
+				boosted_rows
+					.iter()
+					.flat_map(|&row| {
+						(0..dimensions.cols().get()).map(move |col| Position { row, col })
+					})
+					.filter(|position| !sampled.contains(position)),
+			);
 			info!(
 				block_number,
 				"cells_requested" = positions.len(),
+				?randomness,
 				"Random cells generated: {}",
 				positions.len()
 			);
 
+			let started_at = unix_timestamp_millis();
+			let processing_delay = received_at.elapsed().as_millis() as u64;
+			let deadline = cfg
+				.verification_deadline
+				.map(|deadline| tokio::time::Instant::now() + deadline);
 			let (fetched, unfetched, fetch_stats) = network_client
 				.fetch_verified(
 					block_number,
@@ -104,9 +180,58 @@ pub async fn process_block(
 					dimensions,
 					&commitments,
 					&positions,
+					deadline,
 				)
 				.await?;
 
+			if fetch_stats.timed_out {
+				warn!(
+					block_number,
+					cells_verified = fetched.len(),
+					"Block verification deadline of {:?} exceeded",
+					cfg.verification_deadline.unwrap_or_default()
+				);
+				metrics.count(MetricCounter::VerificationTimeouts).await;
+			}
+
+			if let Some(recorder) = cfg
+				.recorder
+				.as_ref()
+				.filter(|recorder| recorder.is_recorded(block_number))
+			{
+				let recording = Recording::new(&header, &positions, &fetched);
+				if let Err(error) = recorder.record(&recording) {
+					warn!(block_number, "Failed to record block: {error:#}");
+				}
+			}
+
+			let verification = VerificationMetadata {
+				started_at,
+				finished_at: unix_timestamp_millis(),
+				processing_delay,
+				cells_requested: positions.len() as u32,
+				dht_fetched: fetch_stats.dht_fetched as u32,
+				rpc_fetched: fetch_stats.rpc_fetched.unwrap_or(0.0) as u32,
+				verified: fetched.len() as u32,
+				randomness,
+			};
+			if let Some(event_log) = &cfg.event_log {
+				let confidence = calculate_confidence(fetched.len() as u32);
+				let outcome = VerificationOutcome::new(
+					block_number,
+					header_hash,
+					confidence,
+					fetch_stats.timed_out,
+					&verification,
+				);
+				if let Err(error) = event_log.append(&outcome) {
+					warn!(block_number, "Failed to append to event log: {error:#}");
+				}
+			}
+
+			db.put(Key::BlockVerification(block_number), verification)
+				.wrap_err("Light Client failed to store Block Verification")?;
+
 			metrics
 				.record(MetricValue::DHTFetched(fetch_stats.dht_fetched))
 				.await;
@@ -123,6 +248,10 @@ pub async fn process_block(
 				))
 				.await;
 
+			metrics
+				.record(MetricValue::DHTBypassed(fetch_stats.dht_bypassed))
+				.await;
+
 			if let Some(rpc_fetched) = fetch_stats.rpc_fetched {
 				metrics.record(MetricValue::RPCFetched(rpc_fetched)).await;
 			}
@@ -132,12 +261,29 @@ pub async fn process_block(
 					.record(MetricValue::RPCFetchDuration(rpc_fetch_duration))
 					.await;
 			}
-			(positions.len(), fetched.len(), unfetched.len())
+			(
+				positions.len(),
+				fetched.len(),
+				unfetched.len(),
+				fetch_stats.timed_out,
+			)
 		},
 	};
 
 	if required > verified {
 		error!(block_number, "Failed to fetch {} cells", unverified);
+		if timed_out {
+			// partial confidence is stored, block status remains incomplete
+			db.put(Key::VerifiedCellCount(block_number), verified as u32)
+				.wrap_err("Light Client failed to store Confidence Factor")?;
+			db.put(Key::BlockHeader(block_number), header)
+				.wrap_err("Light Client failed to store Block Header")?;
+			info!(
+				block_number,
+				"confidence" = calculate_confidence(verified as u32),
+				"Partial confidence factor stored"
+			);
+		}
 		return Ok(None);
 	}
 
@@ -181,20 +327,33 @@ pub async fn process_block(
 /// * `metrics` - Metrics registry
 /// * `state` - Processed blocks state
 /// * `channels` - Communication channels
+/// * `reloadable_config` - Reloaded configuration parameters
 /// * `shutdown` - Shutdown controller
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
 	db: impl Database + Clone,
 	network_client: impl network::Client,
-	cfg: LightClientConfig,
+	mut cfg: LightClientConfig,
 	metrics: Arc<impl Metrics>,
 	state: Arc<Mutex<State>>,
 	mut channels: ClientChannels,
-	shutdown: Controller<String>,
+	reloadable_config: watch::Receiver<ReloadableConfig>,
+	shutdown: Controller<ShutdownReason>,
 ) {
 	info!("Starting light client...");
 
 	loop {
-		let (header, received_at) = match channels.rpc_event_receiver.recv().await {
+		let received = tokio::select! {
+			received = channels.rpc_event_receiver.recv() => received,
+			// stop receiving new blocks once the shutdown is triggered,
+			// block being processed is drained in the verification shutdown phase
+			_ = shutdown.triggered_shutdown() => {
+				info!("Shutdown triggered, stopping block verification");
+				return;
+			},
+		};
+
+		let (header, received_at) = match received {
 			Ok(event) => match event {
 				Event::HeaderUpdate {
 					header,
@@ -207,7 +366,10 @@ pub async fn run(
 			},
 		};
 
-		if let Some(seconds) = cfg.block_processing_delay.sleep_duration(received_at) {
+		let delay = cfg
+			.block_processing_delay
+			.randomized(cfg.block_processing_delay_max);
+		if let Some(seconds) = delay.sleep_duration(received_at) {
 			metrics
 				.record(MetricValue::BlockProcessingDelay(seconds.as_secs_f64()))
 				.await;
@@ -215,6 +377,12 @@ pub async fn run(
 			tokio::time::sleep(seconds).await;
 		}
 
+		{
+			let reloaded = reloadable_config.borrow();
+			cfg.confidence = reloaded.confidence;
+			cfg.sampling_boosts.clone_from(&reloaded.sampling_boosts);
+		}
+
 		let process_block_result = process_block(
 			db.clone(),
 			&network_client,
@@ -229,7 +397,9 @@ pub async fn run(
 			Ok(confidence) => confidence,
 			Err(error) => {
 				error!("Cannot process block: {error}");
-				let _ = shutdown.trigger_shutdown(format!("Cannot process block: {error:#}"));
+				let _ = shutdown.trigger_shutdown(ShutdownReason::Error(format!(
+					"Cannot process block: {error:#}"
+				)));
 				return;
 			},
 		};
@@ -241,10 +411,7 @@ pub async fn run(
 
 		// notify dht-based application client
 		// that newly mined block has been received
-		if let Err(error) = channels.block_sender.send(client_msg) {
-			error!("Cannot send block verified message: {error}");
-			continue;
-		}
+		channels.block_sender.send(client_msg);
 	}
 }
 
@@ -271,6 +438,29 @@ mod tests {
 	use kate_recovery::{data::Cell, matrix::Position};
 	use test_case::test_case;
 
+	#[test]
+	fn sampling_boost_of_app_blocks() {
+		let boost = |app_id, extra_cells, full_rows| SamplingBoost {
+			app_id,
+			extra_cells,
+			full_rows,
+		};
+		let dimensions = Dimensions::new(4, 4).unwrap();
+		let lookup =
+			DataLookup::from_id_and_len_iter([(1u32, 4usize), (2, 8)].into_iter()).unwrap();
+
+		let boosts = [boost(1, 5, false), boost(3, 10, true)];
+		assert_eq!(sampling_boost(&boosts, &lookup, dimensions), (5, vec![]));
+
+		let boosts = [boost(1, 5, false), boost(2, 0, true)];
+		let (extra_cells, rows) = sampling_boost(&boosts, &lookup, dimensions);
+		assert_eq!(extra_cells, 5);
+		assert!(!rows.is_empty());
+		assert_eq!(rows, app_specific_rows(&lookup, dimensions, AppId(2)));
+
+		assert_eq!(sampling_boost(&[], &lookup, dimensions), (0, vec![]));
+	}
+
 	#[test_case(99.9 => 10)]
 	#[test_case(99.99 => CELL_COUNT_99_99)]
 	#[test_case(60.0 => 2)]
@@ -283,8 +473,10 @@ mod tests {
 		cell_count_for_confidence(confidence)
 	}
 
+	#[test_case(false ; "completed")]
+	#[test_case(true ; "timed out")]
 	#[tokio::test]
-	async fn test_process_block_with_rpc() {
+	async fn test_process_block_with_rpc(timed_out: bool) {
 		let mut mock_network_client = network::MockClient::new();
 		let db = mem_db::MemoryDB::default();
 		let cfg = LightClientConfig::from(&RuntimeConfig::default());
@@ -335,15 +527,18 @@ mod tests {
 		let recv = Instant::now();
 		mock_network_client
 			.expect_fetch_verified()
-			.returning(move |_, _, _, _, positions| {
+			.returning(move |_, _, _, _, positions, _| {
 				let fetched = cells_fetched.clone();
 				let unfetched = cells_unfetched.clone();
-				let stats = network::FetchStats::new(
-					positions.len(),
-					fetched.len(),
-					Duration::from_secs(0),
-					None,
-				);
+				let stats = network::FetchStats {
+					timed_out,
+					..network::FetchStats::new(
+						positions.len(),
+						fetched.len(),
+						Duration::from_secs(0),
+						None,
+					)
+				};
 				Box::pin(async move { Ok((fetched, unfetched, stats)) })
 			});
 
@@ -351,7 +546,7 @@ mod tests {
 		mock_metrics.expect_count().returning(|_| ());
 		mock_metrics.expect_record().returning(|_| ());
 		process_block(
-			db,
+			db.clone(),
 			&mock_network_client,
 			&Arc::new(mock_metrics),
 			&cfg,
@@ -361,5 +556,17 @@ mod tests {
 		)
 		.await
 		.unwrap();
+
+		let verification: VerificationMetadata =
+			db.get(Key::BlockVerification(57)).unwrap().unwrap();
+		assert_eq!(verification.cells_requested, 8);
+		assert_eq!(verification.dht_fetched, 0);
+		assert_eq!(verification.rpc_fetched, 0);
+		assert_eq!(verification.verified, 0);
+		assert!(verification.started_at <= verification.finished_at);
+
+		// partial confidence is stored only if the deadline is exceeded
+		let count: Option<u32> = db.get(Key::VerifiedCellCount(57)).unwrap();
+		assert_eq!(count, timed_out.then_some(0));
 	}
 }
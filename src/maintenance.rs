@@ -1,51 +1,375 @@
-use color_eyre::{eyre::WrapErr, Result};
-use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{debug, error, info};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use futures::Future;
+use libp2p::{Multiaddr, PeerId};
+use serde::Serialize;
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
 
 use crate::{
+	data::StoragePause,
+	event_bus::Subscriber,
+	lifecycle::{Degradation, Lifecycle},
 	network::p2p::Client as P2pClient,
-	shutdown::Controller,
+	shutdown::{Controller, ShutdownReason},
+	systemd,
 	telemetry::{MetricValue, Metrics},
-	types::BlockVerified,
+	types::{BlockVerified, ReloadableConfig},
+	utils,
 };
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct StaticConfigParams {
 	pub block_confidence_treshold: f64,
 	pub replication_factor: u16,
 	pub query_timeout: u32,
 	pub pruning_interval: u32,
+	/// Store records threshold, above which expired records are pruned instead of every pruning interval
+	pub pruning_max_records: Option<usize>,
+	/// Store size threshold in bytes, above which expired records are pruned instead of every pruning interval
+	pub pruning_max_bytes: Option<u64>,
 	pub telemetry_flush_interval: u32,
+	/// Resident memory threshold in bytes
+	pub memory_threshold: Option<u64>,
+	pub memory_threshold_restart: bool,
+	pub republish_interval: u32,
+	pub republish_limit: usize,
+	pub republish_before_expiry: Duration,
+	/// Offset in blocks of the republishing pass, so nodes started at the same block don't republish at the same time
+	pub republish_offset: u32,
+	/// Routing table is refreshed if number of peers falls below this value
+	pub min_peers: usize,
+	pub bootstraps: Vec<(PeerId, Multiaddr)>,
+	/// Path of the data directory, used to check available disk space
+	pub avail_path: String,
+	/// Available disk space warning threshold in bytes
+	pub disk_space_warning_threshold: u64,
+	/// Available disk space threshold in bytes, below which storing of app data is paused
+	pub disk_space_critical_threshold: u64,
+}
+
+/// Maintenance tasks with tracked status
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Task {
+	Pruning,
+	Republishing,
+	TelemetryFlush,
+	MemoryCheck,
+	DiskSpaceCheck,
+	KademliaShrink,
+	RoutingTableRefresh,
+	DhtRetry,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskResult {
+	Success,
+	Failure,
+}
+
+/// Status of the last run of the maintenance task
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TaskStatus {
+	pub block_number: u32,
+	/// Unix timestamp of the last run, in milliseconds
+	pub last_run: u64,
+	pub duration_ms: u64,
+	pub result: TaskResult,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// Shared status of the maintenance tasks, updated after each run
+#[derive(Clone, Default, Debug)]
+pub struct MaintenanceStatus(Arc<Mutex<BTreeMap<Task, TaskStatus>>>);
+
+impl MaintenanceStatus {
+	/// Returns status of the last run of each maintenance task
+	pub fn tasks(&self) -> BTreeMap<Task, TaskStatus> {
+		self.0.lock().expect("Lock acquired").clone()
+	}
+
+	fn update(&self, task: Task, status: TaskStatus) {
+		self.0.lock().expect("Lock acquired").insert(task, status);
+	}
+
+	/// Runs the task and stores its start time, duration and result
+	pub(crate) async fn track<T>(
+		&self,
+		task: Task,
+		block_number: u32,
+		future: impl Future<Output = Result<T>>,
+	) -> Result<T> {
+		let last_run = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|duration| duration.as_millis() as u64)
+			.unwrap_or_default();
+		let started = Instant::now();
+		let result = future.await;
+		let (result_status, error) = match &result {
+			Ok(_) => (TaskResult::Success, None),
+			Err(error) => (TaskResult::Failure, Some(format!("{error:#}"))),
+		};
+		self.update(
+			task,
+			TaskStatus {
+				block_number,
+				last_run,
+				duration_ms: started.elapsed().as_millis() as u64,
+				result: result_status,
+				error,
+			},
+		);
+		result
+	}
+}
+
+/// Records available disk space and pauses storing of the app data if it is below critical threshold
+async fn check_disk_space(
+	block_number: u32,
+	static_config_params: &StaticConfigParams,
+	storage_pause: &StoragePause,
+	metrics: &Arc<impl Metrics>,
+) {
+	let Some(available) = utils::available_disk_space(&static_config_params.avail_path) else {
+		return;
+	};
+	metrics
+		.record(MetricValue::DiskSpaceAvailable(available))
+		.await;
+
+	let critical = available < static_config_params.disk_space_critical_threshold;
+	if critical {
+		error!(
+			block_number,
+			available,
+			"Available disk space is below critical threshold, storing of app data is paused"
+		);
+	} else if available < static_config_params.disk_space_warning_threshold {
+		warn!(
+			block_number,
+			available, "Available disk space is below warning threshold"
+		);
+	}
+
+	if storage_pause.set(critical) && !critical {
+		info!(block_number, available, "Storing of app data is resumed");
+	}
+}
+
+/// Error returned if memory threshold is exceeded and restart is configured.
+/// Unlike other maintenance failures, it triggers the shutdown instead of the maintenance restart.
+#[derive(Debug)]
+pub struct MemoryThresholdExceeded {
+	resident_memory: u64,
+	threshold: u64,
+}
+
+impl std::fmt::Display for MemoryThresholdExceeded {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"Memory usage of {} bytes exceeded threshold of {} bytes, restart is required",
+			self.resident_memory, self.threshold
+		)
+	}
+}
+
+impl std::error::Error for MemoryThresholdExceeded {}
+
+/// Records memory usage and shrinks caches if memory threshold is exceeded.
+/// Returns an error if restart on exceeded threshold is configured, to trigger the shutdown.
+async fn check_memory(
+	block_number: u32,
+	p2p_client: &P2pClient,
+	static_config_params: &StaticConfigParams,
+	metrics: &Arc<impl Metrics>,
+) -> Result<()> {
+	let Some(resident_memory) = utils::resident_memory_bytes() else {
+		return Ok(());
+	};
+	metrics
+		.record(MetricValue::MemoryUsage(resident_memory))
+		.await;
+
+	let Some(threshold) = static_config_params.memory_threshold else {
+		return Ok(());
+	};
+	if resident_memory <= threshold {
+		return Ok(());
+	}
+
+	warn!(
+		block_number,
+		resident_memory, threshold, "Memory threshold exceeded, shrinking caches"
+	);
+
+	#[cfg(not(feature = "kademlia-rocksdb"))]
+	match p2p_client.prune_expired_records().await {
+		Ok(pruned) => info!(block_number, pruned, "Pruning finished"),
+		Err(error) => error!(block_number, "Pruning failed: {error:#}"),
+	}
+
+	p2p_client
+		.shrink_kademlia_map()
+		.await
+		.wrap_err("Unable to perform Kademlia map shrink")?;
+
+	if static_config_params.memory_threshold_restart {
+		return Err(MemoryThresholdExceeded {
+			resident_memory,
+			threshold,
+		}
+		.into());
+	}
+	Ok(())
+}
+
+/// Returns `true` if the store size exceeds any of the thresholds
+#[cfg(not(feature = "kademlia-rocksdb"))]
+fn is_store_exceeded(
+	(records, bytes): (usize, u64),
+	max_records: Option<usize>,
+	max_bytes: Option<u64>,
+) -> bool {
+	max_records.is_some_and(|max| records > max) || max_bytes.is_some_and(|max| bytes > max)
+}
+
+/// Returns `true` if pruning is due, on each pruning interval, or if store thresholds are set, when the store exceeds them
+#[cfg(not(feature = "kademlia-rocksdb"))]
+async fn is_pruning_due(
+	block_number: u32,
+	p2p_client: &P2pClient,
+	static_config_params: &StaticConfigParams,
+) -> bool {
+	let (max_records, max_bytes) = (
+		static_config_params.pruning_max_records,
+		static_config_params.pruning_max_bytes,
+	);
+	if max_records.is_none() && max_bytes.is_none() {
+		return block_number % static_config_params.pruning_interval == 0;
+	}
+	match p2p_client.get_kademlia_store_size().await {
+		Ok(size) => {
+			let is_exceeded = is_store_exceeded(size, max_records, max_bytes);
+			if is_exceeded {
+				let (records, bytes) = size;
+				info!(
+					block_number,
+					records, bytes, "Kademlia store threshold exceeded"
+				);
+			}
+			is_exceeded
+		},
+		Err(error) => {
+			error!(block_number, "Unable to get Kademlia store size: {error:#}");
+			false
+		},
+	}
 }
 
 pub async fn process_block(
 	block_number: u32,
 	p2p_client: &P2pClient,
-	static_config_params: StaticConfigParams,
+	static_config_params: &StaticConfigParams,
+	storage_pause: &StoragePause,
+	status: &MaintenanceStatus,
+	lifecycle: &Lifecycle,
 	metrics: &Arc<impl Metrics>,
 ) -> Result<()> {
 	#[cfg(not(feature = "kademlia-rocksdb"))]
-	if block_number % static_config_params.pruning_interval == 0 {
+	if is_pruning_due(block_number, p2p_client, static_config_params).await {
 		info!(block_number, "Pruning...");
-		match p2p_client.prune_expired_records().await {
+		match status
+			.track(
+				Task::Pruning,
+				block_number,
+				p2p_client.prune_expired_records(),
+			)
+			.await
+		{
 			Ok(pruned) => info!(block_number, pruned, "Pruning finished"),
 			Err(error) => error!(block_number, "Pruning failed: {error:#}"),
 		}
 	}
 
+	if static_config_params.republish_interval > 0
+		&& block_number.wrapping_add(static_config_params.republish_offset)
+			% static_config_params.republish_interval
+			== 0
+	{
+		info!(block_number, "Republishing DHT records...");
+		let republish = p2p_client.republish_records(
+			static_config_params.republish_before_expiry,
+			static_config_params.republish_limit,
+		);
+		match status
+			.track(Task::Republishing, block_number, republish)
+			.await
+		{
+			Ok(republished) => info!(block_number, republished, "Republishing finished"),
+			Err(error) => error!(block_number, "Republishing failed: {error:#}"),
+		}
+	}
+
+	match status
+		.track(
+			Task::DhtRetry,
+			block_number,
+			p2p_client.retry_failed_inserts(),
+		)
+		.await
+	{
+		Ok(0) => (),
+		Ok(retried) => info!(block_number, retried, "Retrying failed DHT inserts"),
+		Err(error) => error!(
+			block_number,
+			"Retrying failed DHT inserts failed: {error:#}"
+		),
+	}
+
 	if block_number % static_config_params.telemetry_flush_interval == 0 {
 		info!(block_number, "Flushing metrics...");
-		match metrics.flush().await {
+		match status
+			.track(Task::TelemetryFlush, block_number, metrics.flush())
+			.await
+		{
 			Ok(()) => info!(block_number, "Flushing metrics finished"),
 			Err(error) => error!(block_number, "Flushing metrics failed: {error:#}"),
 		}
 	}
 
-	p2p_client
-		.shrink_kademlia_map()
-		.await
-		.wrap_err("Unable to perform Kademlia map shrink")?;
+	let memory_check = check_memory(block_number, p2p_client, static_config_params, metrics);
+	status
+		.track(Task::MemoryCheck, block_number, memory_check)
+		.await?;
+
+	let disk_space_check = async {
+		check_disk_space(block_number, static_config_params, storage_pause, metrics).await;
+		Ok(())
+	};
+	status
+		.track(Task::DiskSpaceCheck, block_number, disk_space_check)
+		.await?;
+
+	let shrink = async {
+		p2p_client
+			.shrink_kademlia_map()
+			.await
+			.wrap_err("Unable to perform Kademlia map shrink")
+	};
+	status
+		.track(Task::KademliaShrink, block_number, shrink)
+		.await?;
 
 	let map_size = p2p_client
 		.get_kademlia_map_size()
@@ -55,6 +379,27 @@ pub async fn process_block(
 	let (peers_num, pub_peers_num) = p2p_client.count_dht_entries().await?;
 	info!("Number of peers in the routing table: {peers_num}. Number of peers with public IPs: {pub_peers_num}.");
 
+	if peers_num < static_config_params.min_peers {
+		warn!(
+			block_number,
+			peers_num,
+			min_peers = static_config_params.min_peers,
+			"Number of peers in the routing table is below minimum, refreshing routing table"
+		);
+		let refresh = p2p_client.refresh_routing_table(static_config_params.bootstraps.clone());
+		if let Err(error) = status
+			.track(Task::RoutingTableRefresh, block_number, refresh)
+			.await
+		{
+			error!(block_number, "Refreshing routing table failed: {error:#}");
+		}
+	}
+
+	lifecycle.degrade(
+		Degradation::InsufficientPeers,
+		peers_num < static_config_params.min_peers,
+	);
+
 	let connected_peers = p2p_client.list_connected_peers().await?;
 	debug!("Connected peers: {:?}", connected_peers);
 
@@ -76,32 +421,69 @@ pub async fn process_block(
 			static_config_params.query_timeout,
 		))
 		.await;
+	metrics
+		.record(MetricValue::LifecycleState(lifecycle.get().code()))
+		.await;
 	metrics.record(MetricValue::Up()).await;
 
 	info!(block_number, map_size, "Maintenance completed");
 	Ok(())
 }
 
+/// Runs maintenance on each verified block.
+/// Returns an error on maintenance failure, so the maintenance can be restarted by the supervisor.
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
 	p2p_client: P2pClient,
 	metrics: Arc<impl Metrics>,
-	mut block_receiver: broadcast::Receiver<BlockVerified>,
-	static_config_params: StaticConfigParams,
-	shutdown: Controller<String>,
-) {
+	mut block_receiver: Subscriber<BlockVerified>,
+	mut static_config_params: StaticConfigParams,
+	reloadable_config: watch::Receiver<ReloadableConfig>,
+	storage_pause: StoragePause,
+	status: MaintenanceStatus,
+	lifecycle: Lifecycle,
+	shutdown: Controller<ShutdownReason>,
+) -> Result<()> {
 	info!("Starting maintenance...");
 
 	loop {
-		let result = match block_receiver.recv().await {
-			Ok(block) => {
-				process_block(block.block_num, &p2p_client, static_config_params, &metrics).await
-			},
-			Err(error) => Err(error.into()),
+		let Some(block) = block_receiver.recv().await else {
+			return Err(eyre!("Verified blocks channel is closed"));
 		};
 
-		if let Err(error) = result {
-			let _ = shutdown.trigger_shutdown(format!("{error:#}"));
-			break;
+		// events dropped by all verified blocks subscribers, and the longest queue
+		let stats = block_receiver.stats();
+		let dropped = stats.iter().map(|stats| stats.dropped).sum::<u64>();
+		let queued = stats.iter().map(|stats| stats.queued).max().unwrap_or(0);
+		metrics.record(MetricValue::EventsDropped(dropped)).await;
+		metrics
+			.record(MetricValue::EventsQueued(queued as u64))
+			.await;
+		{
+			let reloadable_config = reloadable_config.borrow();
+			static_config_params.block_confidence_treshold = reloadable_config.confidence;
+			static_config_params.telemetry_flush_interval =
+				reloadable_config.ot_flush_block_interval;
+		}
+
+		let result = process_block(
+			block.block_num,
+			&p2p_client,
+			&static_config_params,
+			&storage_pause,
+			&status,
+			&lifecycle,
+			&metrics,
+		)
+		.await;
+
+		match result {
+			Ok(()) => systemd::notify(systemd::WATCHDOG),
+			Err(error) if error.is::<MemoryThresholdExceeded>() => {
+				let _ = shutdown.trigger_shutdown(ShutdownReason::Error(format!("{error:#}")));
+				return Ok(());
+			},
+			Err(error) => return Err(error),
 		}
 	}
 }
@@ -8,18 +8,31 @@ use kate_recovery::{
 };
 use mockall::automock;
 use sp_core::H256;
-use std::{sync::Arc, time::Duration};
+use std::{
+	future::Future,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 use tokio::time::Instant;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::proof;
+use crate::{data::CellSource, proof, quarantine::Quarantine, types::FaultInjectionConfig};
 
+#[cfg(feature = "fault-injection")]
+mod faults;
+pub mod gateway;
 pub mod p2p;
+pub mod proxy;
 pub mod rpc;
 
 #[async_trait]
 #[automock]
 pub trait Client {
+	/// Fetches and verifies cells, falling back from the DHT to the RPC and gateways.
+	/// If the deadline is reached, remaining fetches are abandoned and cells verified so far are returned.
 	async fn fetch_verified(
 		&self,
 		block_number: u32,
@@ -27,6 +40,7 @@ pub trait Client {
 		dimensions: Dimensions,
 		commitments: &[[u8; config::COMMITMENT_SIZE]],
 		positions: &[Position],
+		deadline: Option<Instant>,
 	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)>;
 }
 
@@ -36,6 +50,10 @@ pub struct FetchStats {
 	pub dht_fetch_duration: f64,
 	pub rpc_fetched: Option<f64>,
 	pub rpc_fetch_duration: Option<f64>,
+	/// Fetch is abandoned at the deadline
+	pub timed_out: bool,
+	/// DHT fetch is skipped, due to the low number of the DHT peers
+	pub dht_bypassed: bool,
 }
 
 type RPCFetchStats = (usize, Duration);
@@ -53,7 +71,55 @@ impl FetchStats {
 			dht_fetch_duration: dht_fetch_duration.as_secs_f64(),
 			rpc_fetched: rpc_fetch_stats.map(|(rpc_fetched, _)| rpc_fetched as f64),
 			rpc_fetch_duration: rpc_fetch_stats.map(|(_, duration)| duration.as_secs_f64()),
+			timed_out: false,
+			dht_bypassed: false,
+		}
+	}
+}
+
+/// Awaits the future until the deadline, returning `None` if the deadline is reached
+async fn until<T>(deadline: Option<Instant>, future: impl Future<Output = T>) -> Option<T> {
+	match deadline {
+		Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+		None => Some(future.await),
+	}
+}
+
+/// Bypasses the DHT fetch while the DHT routing table has fewer peers than the threshold,
+/// since DHT queries would time out. Mode is shared between the network clients.
+#[derive(Clone, Default)]
+pub struct PeerFallback {
+	/// Minimum number of the routing table peers, fallback is disabled if 0
+	threshold: usize,
+	bypassed: Arc<AtomicBool>,
+}
+
+impl PeerFallback {
+	pub fn new(threshold: usize) -> Self {
+		PeerFallback {
+			threshold,
+			..Default::default()
+		}
+	}
+
+	fn is_enabled(&self) -> bool {
+		self.threshold > 0
+	}
+
+	/// Updates the mode with the number of the routing table peers, returning `true` if the DHT is bypassed
+	fn update(&self, peers: usize) -> bool {
+		let bypassed = peers < self.threshold;
+		let was_bypassed = self.bypassed.swap(bypassed, Ordering::Relaxed);
+		match (was_bypassed, bypassed) {
+			(false, true) => warn!(
+				peers,
+				threshold = self.threshold,
+				"Not enough DHT peers, fetching cells from RPC only"
+			),
+			(true, false) => info!(peers, "DHT peers recovered, fetching cells from DHT"),
+			_ => (),
 		}
+		bypassed
 	}
 }
 
@@ -62,11 +128,31 @@ struct DHTWithRPCFallbackClient {
 	rpc_client: rpc::Client,
 	pp: Arc<PublicParameters>,
 	disable_rpc: bool,
+	gateway_client: Option<gateway::Client>,
+	quarantine: Quarantine,
+	peer_fallback: PeerFallback,
+	#[cfg(feature = "fault-injection")]
+	faults: Option<faults::FaultInjector>,
 }
 
 type Commitments = [[u8; config::COMMITMENT_SIZE]];
 
 impl DHTWithRPCFallbackClient {
+	/// Returns `true` if the DHT fetch should be skipped due to the low number of the DHT peers
+	async fn is_dht_bypassed(&self) -> bool {
+		// DHT is the only source if neither RPC nor gateways are enabled
+		if !self.peer_fallback.is_enabled() || (self.disable_rpc && self.gateway_client.is_none()) {
+			return false;
+		}
+		match self.p2p_client.count_dht_entries().await {
+			Ok((peers, _)) => self.peer_fallback.update(peers),
+			Err(error) => {
+				debug!("Cannot count DHT peers: {error:#}");
+				self.peer_fallback.bypassed.load(Ordering::Relaxed)
+			},
+		}
+	}
+
 	async fn fetch_verified_from_dht(
 		&self,
 		block_number: u32,
@@ -76,13 +162,30 @@ impl DHTWithRPCFallbackClient {
 	) -> Result<(Vec<Cell>, Vec<Position>, Duration)> {
 		let begin = Instant::now();
 
-		let (mut dht_fetched, mut unfetched) = self
+		#[cfg(feature = "fault-injection")]
+		let (positions, mut dropped) = match &self.faults {
+			Some(faults) => faults.drop_dht_queries(positions),
+			None => (positions.to_vec(), vec![]),
+		};
+		#[cfg(feature = "fault-injection")]
+		let positions = &positions[..];
+
+		let (dht_fetched, mut unfetched) = self
 			.p2p_client
-			.fetch_cells_from_dht(block_number, positions)
+			.fetch_sampled_cells_from_dht(block_number, dimensions, positions)
 			.await;
+		let (mut dht_fetched, peers): (Vec<_>, Vec<_>) = dht_fetched.into_iter().unzip();
 
 		let fetch_elapsed = begin.elapsed();
 
+		#[cfg(feature = "fault-injection")]
+		if let Some(faults) = &self.faults {
+			unfetched.append(&mut dropped);
+			faults.corrupt_cells(&mut dht_fetched);
+		}
+
+		// DHT fetch is scheduled per chunk of cells
+		let _permit = self.p2p_client.schedule().await;
 		let (verified, mut unverified) = proof::verify(
 			block_number,
 			dimensions,
@@ -93,6 +196,21 @@ impl DHTWithRPCFallbackClient {
 		.await
 		.context("Failed to verify fetched cells")?;
 
+		self.quarantine.quarantine(
+			block_number,
+			&dht_fetched,
+			&unverified,
+			commitments,
+			|index| CellSource::Dht {
+				peer_id: peers[index].map(|peer_id| peer_id.to_string()),
+			},
+		);
+		for (cell, peer_id) in dht_fetched.iter().zip(&peers) {
+			if let Some(peer_id) = peer_id.filter(|_| unverified.contains(&cell.position)) {
+				self.p2p_client.peer_stats().record_invalid(peer_id);
+			}
+		}
+
 		info!(
 			block_number,
 			cells_total = positions.len(),
@@ -109,6 +227,38 @@ impl DHTWithRPCFallbackClient {
 		Ok((dht_fetched, unfetched, fetch_elapsed))
 	}
 
+	/// Fetches and verifies cells from DHT chunk by chunk, so the cells verified before the deadline are kept.
+	/// Returns verified cells, unfetched positions, fetch duration, and `true` if the deadline is reached.
+	async fn fetch_verified_from_dht_until(
+		&self,
+		deadline: Option<Instant>,
+		block_number: u32,
+		dimensions: Dimensions,
+		commitments: &Commitments,
+		positions: &[Position],
+	) -> Result<(Vec<Cell>, Vec<Position>, Duration, bool)> {
+		// without the deadline, all cells are fetched and verified at once
+		let chunk_size = match deadline {
+			Some(_) => self.p2p_client.dht_parallelization_limit(),
+			None => positions.len(),
+		}
+		.max(1);
+
+		let (mut fetched, mut unfetched, mut duration) = (vec![], vec![], Duration::ZERO);
+		for (index, chunk) in positions.chunks(chunk_size).enumerate() {
+			let fetch = self.fetch_verified_from_dht(block_number, dimensions, commitments, chunk);
+			let Some(result) = until(deadline, fetch).await else {
+				unfetched.extend(&positions[index * chunk_size..]);
+				return Ok((fetched, unfetched, duration, true));
+			};
+			let (mut chunk_fetched, mut chunk_unfetched, chunk_duration) = result?;
+			fetched.append(&mut chunk_fetched);
+			unfetched.append(&mut chunk_unfetched);
+			duration += chunk_duration;
+		}
+		Ok((fetched, unfetched, duration, false))
+	}
+
 	async fn fetch_verified_from_rpc(
 		&self,
 		block_number: u32,
@@ -117,8 +267,14 @@ impl DHTWithRPCFallbackClient {
 		commitments: &Commitments,
 		positions: &[Position],
 	) -> Result<(Vec<Cell>, Vec<Position>, Duration)> {
+		let _permit = self.p2p_client.schedule().await;
 		let begin = Instant::now();
 
+		#[cfg(feature = "fault-injection")]
+		if let Some(faults) = &self.faults {
+			faults.delay_rpc().await;
+		}
+
 		let mut fetched = self
 			.rpc_client
 			.request_kate_proof(block_hash, positions)
@@ -126,6 +282,11 @@ impl DHTWithRPCFallbackClient {
 
 		let fetch_elapsed = begin.elapsed();
 
+		#[cfg(feature = "fault-injection")]
+		if let Some(faults) = &self.faults {
+			faults.corrupt_cells(&mut fetched);
+		}
+
 		let (verified, unverified) = proof::verify(
 			block_number,
 			dimensions,
@@ -136,6 +297,12 @@ impl DHTWithRPCFallbackClient {
 		.await
 		.context("Failed to verify fetched cells")?;
 
+		let host = self.rpc_client.connected_host();
+		self.quarantine
+			.quarantine(block_number, &fetched, &unverified, commitments, |_| {
+				CellSource::Rpc { host: host.clone() }
+			});
+
 		info!(
 			block_number,
 			cells_total = positions.len(),
@@ -149,6 +316,54 @@ impl DHTWithRPCFallbackClient {
 		fetched.retain(|cell| verified.contains(&cell.position));
 		Ok((fetched, unverified, fetch_elapsed))
 	}
+
+	async fn fetch_verified_from_gateway(
+		&self,
+		gateway_client: &gateway::Client,
+		block_number: u32,
+		block_hash: H256,
+		dimensions: Dimensions,
+		commitments: &Commitments,
+		positions: &[Position],
+	) -> Result<(Vec<Cell>, Vec<Position>)> {
+		let _permit = self.p2p_client.schedule().await;
+		let begin = Instant::now();
+
+		let (mut fetched, mut unfetched) = gateway_client
+			.fetch_cells(block_number, block_hash, positions)
+			.await;
+
+		let fetch_elapsed = begin.elapsed();
+
+		let (verified, mut unverified) = proof::verify(
+			block_number,
+			dimensions,
+			&fetched,
+			commitments,
+			self.pp.clone(),
+		)
+		.await
+		.context("Failed to verify fetched cells")?;
+
+		self.quarantine
+			.quarantine(block_number, &fetched, &unverified, commitments, |_| {
+				CellSource::Gateway
+			});
+
+		info!(
+			block_number,
+			cells_total = positions.len(),
+			cells_fetched = fetched.len(),
+			cells_verified = verified.len(),
+			fetch_elapsed = ?fetch_elapsed,
+			proof_verification_elapsed = ?(begin.elapsed() - fetch_elapsed),
+			"Cells fetched from gateways"
+		);
+
+		fetched.retain(|cell| verified.contains(&cell.position));
+		unfetched.append(&mut unverified);
+		Ok((fetched, unfetched))
+	}
 }
 
 #[async_trait]
@@ -160,60 +375,179 @@ impl Client for DHTWithRPCFallbackClient {
 		dimensions: Dimensions,
 		commitments: &Commitments,
 		positions: &[Position],
+		deadline: Option<Instant>,
 	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)> {
-		let (dht_fetched, unfetched, dht_fetch_duration) = self
-			.fetch_verified_from_dht(block_number, dimensions, commitments, positions)
-			.await?;
+		let begin = Instant::now();
+		let dht_bypassed = self.is_dht_bypassed().await;
+		let (dht_fetched, unfetched, dht_fetch_duration, dht_timed_out) = if dht_bypassed {
+			(vec![], positions.to_vec(), Duration::ZERO, false)
+		} else {
+			self.fetch_verified_from_dht_until(
+				deadline,
+				block_number,
+				dimensions,
+				commitments,
+				positions,
+			)
+			.await?
+		};
+
+		// verified cells fetched from the DHT are kept in the local cell cache, so they can be served to other peers
+		if !dht_fetched.is_empty() {
+			if let Err(error) = self
+				.p2p_client
+				.cache_cells(block_number, &dht_fetched)
+				.await
+			{
+				debug!("Error caching cells fetched from DHT: {error}");
+			}
+		}
+
+		if dht_timed_out {
+			let stats = FetchStats {
+				timed_out: true,
+				..FetchStats::new(positions.len(), dht_fetched.len(), begin.elapsed(), None)
+			};
+			return Ok((dht_fetched, unfetched, stats));
+		}
 
-		if self.disable_rpc {
+		if self.disable_rpc && self.gateway_client.is_none() {
 			let stats =
 				FetchStats::new(positions.len(), dht_fetched.len(), dht_fetch_duration, None);
 			return Ok((dht_fetched, unfetched, stats));
 		};
 
-		let (rpc_fetched, unfetched, rpc_fetch_duration) = self
-			.fetch_verified_from_rpc(
-				block_number,
-				block_hash,
-				dimensions,
-				commitments,
-				&unfetched,
+		let (rpc_fetched, unfetched, rpc_fetch_stats, timed_out) = if self.disable_rpc {
+			(vec![], unfetched, None, false)
+		} else {
+			let rpc_result = until(
+				deadline,
+				self.fetch_verified_from_rpc(
+					block_number,
+					block_hash,
+					dimensions,
+					commitments,
+					&unfetched,
+				),
 			)
-			.await?;
+			.await;
+			match rpc_result {
+				Some(Ok((rpc_fetched, unfetched, duration))) => {
+					let stats = (rpc_fetched.len(), duration);
+					(rpc_fetched, unfetched, Some(stats), false)
+				},
+				// gateways are the last resort when the full node fails
+				Some(Err(error)) if self.gateway_client.is_some() => {
+					warn!(block_number, "Failed to fetch cells from RPC: {error:#}");
+					(vec![], unfetched, None, false)
+				},
+				Some(Err(error)) => return Err(error),
+				None => (vec![], unfetched, None, true),
+			}
+		};
 
-		if let Err(error) = self
-			.p2p_client
-			.insert_cells_into_dht(block_number, rpc_fetched.clone())
-			.await
-		{
-			debug!("Error inserting cells into DHT: {error}");
+		let (gateway_fetched, unfetched, timed_out) = match &self.gateway_client {
+			Some(gateway_client) if !unfetched.is_empty() && !timed_out => {
+				let gateway_result = until(
+					deadline,
+					self.fetch_verified_from_gateway(
+						gateway_client,
+						block_number,
+						block_hash,
+						dimensions,
+						commitments,
+						&unfetched,
+					),
+				)
+				.await;
+				match gateway_result {
+					Some(result) => {
+						let (gateway_fetched, unfetched) = result?;
+						(gateway_fetched, unfetched, false)
+					},
+					None => (vec![], unfetched, true),
+				}
+			},
+			_ => (vec![], unfetched, timed_out),
+		};
+
+		// cells fetched from RPC or gateways are inserted into the DHT, which stores them locally
+		let mut inserted = rpc_fetched;
+		inserted.extend(gateway_fetched);
+		if !inserted.is_empty() {
+			if let Err(error) = self
+				.p2p_client
+				.insert_cells_into_dht(block_number, inserted.clone())
+				.await
+			{
+				debug!("Error inserting cells into DHT: {error}");
+			}
 		}
 
-		let stats = FetchStats::new(
-			positions.len(),
-			dht_fetched.len(),
-			dht_fetch_duration,
-			Some((rpc_fetched.len(), rpc_fetch_duration)),
-		);
+		let stats = FetchStats {
+			timed_out,
+			dht_bypassed,
+			..FetchStats::new(
+				positions.len(),
+				dht_fetched.len(),
+				dht_fetch_duration,
+				rpc_fetch_stats,
+			)
+		};
 
 		let mut fetched = vec![];
 		fetched.extend(dht_fetched);
-		fetched.extend(rpc_fetched);
+		fetched.extend(inserted);
 
 		Ok((fetched, unfetched, stats))
 	}
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn new(
 	p2p_client: p2p::Client,
 	rpc_client: rpc::Client,
 	pp: Arc<PublicParameters>,
 	disable_rpc: bool,
+	gateway_client: Option<gateway::Client>,
+	quarantine: Quarantine,
+	peer_fallback: PeerFallback,
+	fault_injection: Option<FaultInjectionConfig>,
 ) -> impl Client {
+	#[cfg(not(feature = "fault-injection"))]
+	let _ = fault_injection;
 	DHTWithRPCFallbackClient {
 		p2p_client,
 		rpc_client,
 		pp,
 		disable_rpc,
+		gateway_client,
+		quarantine,
+		peer_fallback,
+		#[cfg(feature = "fault-injection")]
+		faults: fault_injection.map(faults::FaultInjector::new),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn peer_fallback_switches() {
+		let fallback = PeerFallback::new(5);
+		assert!(fallback.is_enabled());
+		assert!(fallback.update(0));
+		assert!(fallback.update(4));
+		assert!(!fallback.update(5));
+		assert!(!fallback.update(20));
+		assert!(fallback.update(1));
+
+		// mode is shared between the clones
+		let shared = fallback.clone();
+		assert!(!fallback.update(10));
+		assert!(!shared.bypassed.load(Ordering::Relaxed));
+
+		assert!(!PeerFallback::default().is_enabled());
 	}
 }
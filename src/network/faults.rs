@@ -0,0 +1,102 @@
+//! Fault injection for the chaos testing.
+//!
+//! Faults are injected in the DHT with RPC fallback client, so the light client resilience and alerting can be validated
+//! against the live network. Dropped DHT queries are reported as unfetched cells, and corrupted cells fail the proof verification.
+
+use kate_recovery::{config, data::Cell, matrix::Position};
+use rand::{thread_rng, Rng};
+use std::time::Duration;
+use tracing::debug;
+
+use crate::types::FaultInjectionConfig;
+
+pub struct FaultInjector {
+	cfg: FaultInjectionConfig,
+}
+
+fn sample(rate: f64) -> bool {
+	rate > 0.0 && thread_rng().gen_bool(rate.min(1.0))
+}
+
+impl FaultInjector {
+	pub fn new(cfg: FaultInjectionConfig) -> Self {
+		FaultInjector { cfg }
+	}
+
+	/// Splits positions into queried and dropped ones
+	pub fn drop_dht_queries(&self, positions: &[Position]) -> (Vec<Position>, Vec<Position>) {
+		let (dropped, queried): (Vec<_>, Vec<_>) = positions
+			.iter()
+			.copied()
+			.partition(|_| sample(self.cfg.dht_drop_rate));
+		if !dropped.is_empty() {
+			debug!(
+				dropped = dropped.len(),
+				"Fault injection: DHT queries dropped"
+			);
+		}
+		(queried, dropped)
+	}
+
+	/// Delays the RPC response, if configured
+	pub async fn delay_rpc(&self) {
+		if let Some(delay) = self.cfg.rpc_delay.filter(|&delay| delay > 0) {
+			debug!(delay, "Fault injection: RPC response delayed");
+			tokio::time::sleep(Duration::from_millis(delay)).await;
+		}
+	}
+
+	/// Corrupts data of the sampled cells, so the proof verification fails
+	pub fn corrupt_cells(&self, cells: &mut [Cell]) {
+		let mut corrupted = 0;
+		for cell in cells
+			.iter_mut()
+			.filter(|_| sample(self.cfg.cell_corruption_rate))
+		{
+			cell.content[config::COMMITMENT_SIZE] ^= 0xff;
+			corrupted += 1;
+		}
+		if corrupted > 0 {
+			debug!(corrupted, "Fault injection: cells corrupted");
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn injector(dht_drop_rate: f64, cell_corruption_rate: f64) -> FaultInjector {
+		FaultInjector::new(FaultInjectionConfig {
+			dht_drop_rate,
+			rpc_delay: None,
+			cell_corruption_rate,
+		})
+	}
+
+	#[test]
+	fn drop_dht_queries() {
+		let positions = vec![Position { row: 0, col: 0 }, Position { row: 1, col: 1 }];
+		assert_eq!(
+			injector(0.0, 0.0).drop_dht_queries(&positions),
+			(positions.clone(), vec![])
+		);
+		assert_eq!(
+			injector(1.0, 0.0).drop_dht_queries(&positions),
+			(vec![], positions)
+		);
+	}
+
+	#[test]
+	fn corrupt_cells() {
+		let cell = Cell {
+			position: Position { row: 0, col: 0 },
+			content: [0; 80],
+		};
+		let mut cells = vec![cell.clone()];
+		injector(0.0, 0.0).corrupt_cells(&mut cells);
+		assert_eq!(cells[0].content, cell.content);
+		injector(0.0, 1.0).corrupt_cells(&mut cells);
+		assert_ne!(cells[0].content, cell.content);
+	}
+}
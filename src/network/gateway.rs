@@ -0,0 +1,142 @@
+//! HTTP gateway client, used as the last cell retrieval backend.
+//!
+//! Gateways (e.g. availability bridges or archive services) are queried in the configured order,
+//! for the cells which are fetched neither from the DHT nor from the full node.
+//!
+//! # Gateway API
+//!
+//! `GET {gateway_url}/v1/blocks/{block_hash}/cells?positions={row}:{col},...`
+//!
+//! Response is JSON array of the cells, in the same format as in the verification recordings:
+//! `[{"row": 0, "col": 1, "content": "<hex encoded cell content, with proof>"}]`.
+//! Cells which are not available on the gateway are omitted from the response.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hyper::{body, Body, StatusCode, Uri};
+use hyper_rustls::HttpsConnector;
+use kate_recovery::{data::Cell, matrix::Position};
+use sp_core::H256;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::proxy::{https_connector, Connector, Proxy};
+use crate::replay::RecordedCell;
+
+#[derive(Clone)]
+pub struct Client {
+	http_client: hyper::Client<HttpsConnector<Connector>, Body>,
+	urls: Vec<String>,
+	timeout: Duration,
+}
+
+fn cells_uri(url: &str, block_hash: H256, positions: &[Position]) -> Result<Uri> {
+	let positions = positions
+		.iter()
+		.map(|position| format!("{}:{}", position.row, position.col))
+		.collect::<Vec<_>>()
+		.join(",");
+	let uri = format!(
+		"{}/v1/blocks/{block_hash:?}/cells?positions={positions}",
+		url.trim_end_matches('/')
+	);
+	uri.parse().wrap_err(format!("Invalid gateway URL {url}"))
+}
+
+impl Client {
+	pub fn new(urls: Vec<String>, timeout: Duration, proxy: Option<Proxy>) -> Self {
+		Client {
+			http_client: hyper::Client::builder().build(https_connector(proxy)),
+			urls,
+			timeout,
+		}
+	}
+
+	async fn request_cells(
+		&self,
+		url: &str,
+		block_hash: H256,
+		positions: &[Position],
+	) -> Result<Vec<Cell>> {
+		let uri = cells_uri(url, block_hash, positions)?;
+		let response = tokio::time::timeout(self.timeout, self.http_client.get(uri))
+			.await
+			.map_err(|_| eyre!("Request timed out"))??;
+		if response.status() != StatusCode::OK {
+			return Err(eyre!("Unexpected response status {}", response.status()));
+		}
+		let bytes = tokio::time::timeout(self.timeout, body::to_bytes(response.into_body()))
+			.await
+			.map_err(|_| eyre!("Response timed out"))??;
+		let cells: Vec<RecordedCell> =
+			serde_json::from_slice(&bytes).wrap_err("Failed to parse response")?;
+		cells.iter().map(Cell::try_from).collect()
+	}
+
+	/// Fetches cells from the gateways, in the configured order.
+	/// Returns fetched cells and positions which are not available on any of the gateways, in no particular order.
+	pub async fn fetch_cells(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		positions: &[Position],
+	) -> (Vec<Cell>, Vec<Position>) {
+		let mut fetched: Vec<Cell> = vec![];
+		let mut unfetched = positions.to_vec();
+		for url in &self.urls {
+			if unfetched.is_empty() {
+				break;
+			}
+			match self.request_cells(url, block_hash, &unfetched).await {
+				Ok(cells) => {
+					debug!(
+						block_number,
+						url,
+						fetched = cells.len(),
+						"Cells fetched from gateway"
+					);
+					// cells which are not requested, or are returned more than once, are skipped
+					for cell in cells {
+						if let Some(index) = unfetched
+							.iter()
+							.position(|&position| position == cell.position)
+						{
+							unfetched.swap_remove(index);
+							fetched.push(cell);
+						}
+					}
+				},
+				Err(error) => warn!(
+					block_number,
+					url, "Failed to fetch cells from gateway: {error:#}"
+				),
+			}
+		}
+		(fetched, unfetched)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn gateway_cells_uri() {
+		let positions = [Position { row: 0, col: 1 }, Position { row: 2, col: 3 }];
+		let uri = cells_uri(
+			"https://gateway.example.com/avail/",
+			H256::zero(),
+			&positions,
+		)
+		.unwrap();
+		assert_eq!(
+			uri.to_string(),
+			format!(
+				"https://gateway.example.com/avail/v1/blocks/{:?}/cells?positions=0:1,2:3",
+				H256::zero()
+			)
+		);
+	}
+}
@@ -1,14 +1,26 @@
 use allow_block_list::BlockedPeers;
 use color_eyre::{eyre::WrapErr, Report, Result};
 use libp2p::{
-	autonat, dcutr, identify, identity,
+	autonat,
+	connection_limits::{self, ConnectionLimits},
+	core::upgrade,
+	dcutr, gossipsub, identify, identity,
 	kad::{self, PeerRecord, QueryId},
 	mdns, noise, ping, relay,
-	swarm::NetworkBehaviour,
-	tcp, upnp, yamux, PeerId, Swarm, SwarmBuilder,
+	request_response::{self, OutboundRequestId},
+	swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
+	tcp, upnp, yamux, PeerId, Swarm, SwarmBuilder, Transport,
 };
 use multihash::{self, Hasher};
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::{
+	collections::HashMap,
+	net::Ipv4Addr,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
 use tokio::sync::{
 	mpsc::{self},
 	oneshot,
@@ -18,26 +30,40 @@ use tracing::info;
 #[cfg(feature = "network-analysis")]
 pub mod analyzer;
 mod client;
+mod compression;
 mod event_loop;
+pub mod header_sync;
+mod kad_cached_store;
+mod kad_indexed_store;
 mod kad_mem_providers;
 mod kad_mem_store;
+mod kad_quota_store;
 mod kad_rocksdb_store;
+mod peer_stats;
+mod privacy;
+mod tor;
 
 use crate::types::{LibP2PConfig, SecretKey};
 pub use client::Client;
+pub use compression::RecordCompression;
 pub use event_loop::EventLoop;
 pub use kad_mem_providers::ProvidersConfig;
 pub use kad_mem_store::MemoryStoreConfig;
 pub use kad_rocksdb_store::ExpirationCompactionFilterFactory;
 pub use kad_rocksdb_store::RocksDBStoreConfig;
+pub use peer_stats::{PeerStat, PeerStats};
+use tor::TorTransport;
 
-use self::{client::BlockStat, event_loop::ConnectionEstablishedInfo};
+use self::{
+	client::BlockStat, event_loop::ConnectionEstablishedInfo, header_sync::HeadersResponse,
+};
 use libp2p_allow_block_list as allow_block_list;
 
 #[derive(Debug)]
 pub enum QueryChannel {
 	GetRecord(oneshot::Sender<Result<PeerRecord>>),
 	PutRecord,
+	RepublishRecord,
 	Bootstrap(oneshot::Sender<Result<()>>),
 }
 
@@ -48,6 +74,10 @@ pub struct EventLoopEntries<'a> {
 		&'a mut HashMap<PeerId, oneshot::Sender<Result<ConnectionEstablishedInfo>>>,
 	/// <block_num, (total_cells, result_cell_counter, time_stat)>
 	active_blocks: &'a mut HashMap<u32, BlockStat>,
+	pending_header_requests:
+		&'a mut HashMap<OutboundRequestId, oneshot::Sender<Result<HeadersResponse>>>,
+	/// Agent versions of the connected peers
+	connected_agents: &'a HashMap<PeerId, String>,
 }
 
 impl<'a> EventLoopEntries<'a> {
@@ -59,12 +89,19 @@ impl<'a> EventLoopEntries<'a> {
 			oneshot::Sender<Result<ConnectionEstablishedInfo>>,
 		>,
 		active_blocks: &'a mut HashMap<u32, BlockStat>,
+		pending_header_requests: &'a mut HashMap<
+			OutboundRequestId,
+			oneshot::Sender<Result<HeadersResponse>>,
+		>,
+		connected_agents: &'a HashMap<PeerId, String>,
 	) -> Self {
 		Self {
 			swarm,
 			pending_kad_queries,
 			pending_swarm_events,
 			active_blocks,
+			pending_header_requests,
+			connected_agents,
 		}
 	}
 
@@ -95,6 +132,19 @@ impl<'a> EventLoopEntries<'a> {
 		self.pending_swarm_events.insert(peer_id, result_sender);
 	}
 
+	pub fn insert_header_request(
+		&mut self,
+		request_id: OutboundRequestId,
+		result_sender: oneshot::Sender<Result<HeadersResponse>>,
+	) {
+		self.pending_header_requests
+			.insert(request_id, result_sender);
+	}
+
+	pub fn connected_agents(&self) -> &HashMap<PeerId, String> {
+		self.connected_agents
+	}
+
 	pub fn behavior_mut(&mut self) -> &mut Behaviour {
 		self.swarm.behaviour_mut()
 	}
@@ -110,13 +160,66 @@ pub trait Command {
 }
 
 type SendableCommand = Box<dyn Command + Send + Sync>;
-type CommandSender = mpsc::UnboundedSender<SendableCommand>;
-type CommandReceiver = mpsc::UnboundedReceiver<SendableCommand>;
+
+/// Sender of the event loop commands, counting the commands which are not yet received by the event loop
+#[derive(Clone)]
+pub struct CommandSender {
+	sender: mpsc::UnboundedSender<SendableCommand>,
+	queued: Arc<AtomicUsize>,
+}
+
+impl CommandSender {
+	fn send(
+		&self,
+		command: SendableCommand,
+	) -> Result<(), mpsc::error::SendError<SendableCommand>> {
+		self.queued.fetch_add(1, Ordering::Relaxed);
+		self.sender.send(command).map_err(|error| {
+			self.queued.fetch_sub(1, Ordering::Relaxed);
+			error
+		})
+	}
+
+	/// Returns number of the commands waiting for the event loop
+	fn queued(&self) -> usize {
+		self.queued.load(Ordering::Relaxed)
+	}
+}
+
+pub struct CommandReceiver {
+	receiver: mpsc::UnboundedReceiver<SendableCommand>,
+	queued: Arc<AtomicUsize>,
+}
+
+impl CommandReceiver {
+	async fn recv(&mut self) -> Option<SendableCommand> {
+		let command = self.receiver.recv().await;
+		if command.is_some() {
+			self.queued.fetch_sub(1, Ordering::Relaxed);
+		}
+		command
+	}
+}
+
+/// Creates the channel of the event loop commands
+pub fn command_channel() -> (CommandSender, CommandReceiver) {
+	let (sender, receiver) = mpsc::unbounded_channel();
+	let queued = Arc::new(AtomicUsize::new(0));
+	let sender = CommandSender {
+		sender,
+		queued: queued.clone(),
+	};
+	(sender, CommandReceiver { receiver, queued })
+}
 
 #[cfg(not(feature = "kademlia-rocksdb"))]
-type Store = kad_mem_store::MemoryStore;
+type Store = kad_cached_store::CachedStore<
+	kad_quota_store::QuotaStore<kad_indexed_store::IndexedStore<kad_mem_store::MemoryStore>>,
+>;
 #[cfg(feature = "kademlia-rocksdb")]
-type Store = kad_rocksdb_store::RocksDBStore;
+type Store = kad_cached_store::CachedStore<
+	kad_quota_store::QuotaStore<kad_indexed_store::IndexedStore<kad_rocksdb_store::RocksDBStore>>,
+>;
 
 // Behaviour struct is used to derive delegated Libp2p behaviour implementation
 #[derive(NetworkBehaviour)]
@@ -125,12 +228,15 @@ pub struct Behaviour {
 	kademlia: kad::Behaviour<Store>,
 	identify: identify::Behaviour,
 	ping: ping::Behaviour,
-	mdns: mdns::tokio::Behaviour,
-	auto_nat: autonat::Behaviour,
+	mdns: Toggle<mdns::tokio::Behaviour>,
+	auto_nat: Toggle<autonat::Behaviour>,
 	relay_client: relay::client::Behaviour,
 	dcutr: dcutr::Behaviour,
-	upnp: upnp::tokio::Behaviour,
+	upnp: Toggle<upnp::tokio::Behaviour>,
 	blocked_peers: allow_block_list::Behaviour<BlockedPeers>,
+	connection_limits: connection_limits::Behaviour,
+	header_sync: request_response::Behaviour<header_sync::Codec>,
+	attestation_gossip: Toggle<gossipsub::Behaviour>,
 }
 
 #[derive(Debug)]
@@ -149,6 +255,24 @@ fn generate_config(config: libp2p::swarm::Config, cfg: &LibP2PConfig) -> libp2p:
 		.with_per_connection_event_buffer_size(cfg.per_connection_event_buffer_size)
 }
 
+/// Creates gossipsub behaviour subscribed to the attestations topic, derived from the identify protocol version
+fn attestation_gossip(
+	key: &identity::Keypair,
+	protocol_version: &str,
+) -> Result<gossipsub::Behaviour, Box<dyn std::error::Error + Send + Sync>> {
+	let config = gossipsub::ConfigBuilder::default()
+		.validation_mode(gossipsub::ValidationMode::Strict)
+		.build()?;
+	let mut behaviour =
+		gossipsub::Behaviour::new(gossipsub::MessageAuthenticity::Signed(key.clone()), config)?;
+	let topic = gossipsub::IdentTopic::new(format!("{protocol_version}/attestations/1"));
+	behaviour.subscribe(&topic)?;
+	Ok(behaviour)
+}
+
+/// Timeout of the connection upgrade through Tor, which is slower than the direct one
+const TOR_UPGRADE_TIMEOUT: Duration = Duration::from_secs(60);
+
 async fn build_swarm(
 	cfg: &LibP2PConfig,
 	id_keys: &libp2p::identity::Keypair,
@@ -177,20 +301,52 @@ async fn build_swarm(
 	let mut swarm;
 
 	let behaviour = |key: &identity::Keypair, relay_client| {
+		let gossip = cfg
+			.attestation_gossip
+			.then(|| attestation_gossip(key, &cfg.identify.protocol_version))
+			.transpose()?;
+		// local discovery and NAT traversal would reveal the local addresses, if connections are routed over Tor
+		let is_direct = cfg.tor_proxy.is_none();
+		let mdns = is_direct
+			.then(|| mdns::Behaviour::new(mdns::Config::default(), key.public().to_peer_id()))
+			.transpose()?;
 		Ok(Behaviour {
 			ping: ping::Behaviour::new(ping::Config::new()),
 			identify: identify::Behaviour::new(identify_cfg),
 			relay_client,
 			dcutr: dcutr::Behaviour::new(key.public().to_peer_id()),
 			kademlia: kad::Behaviour::with_config(key.public().to_peer_id(), kad_store, cfg.into()),
-			auto_nat: autonat::Behaviour::new(key.public().to_peer_id(), autonat_cfg),
-			mdns: mdns::Behaviour::new(mdns::Config::default(), key.public().to_peer_id())?,
-			upnp: upnp::tokio::Behaviour::default(),
+			auto_nat: is_direct
+				.then(|| autonat::Behaviour::new(key.public().to_peer_id(), autonat_cfg))
+				.into(),
+			mdns: mdns.into(),
+			upnp: is_direct.then(upnp::tokio::Behaviour::default).into(),
 			blocked_peers: allow_block_list::Behaviour::default(),
+			connection_limits: connection_limits::Behaviour::new(
+				ConnectionLimits::default().with_max_established(cfg.max_established_connections),
+			),
+			header_sync: header_sync::behaviour(&cfg.identify.protocol_version),
+			attestation_gossip: gossip.into(),
 		})
 	};
 
-	if is_ws_transport {
+	if let Some(tor_proxy) = &cfg.tor_proxy {
+		let transport = TorTransport::new(tor_proxy.clone());
+		swarm = tokio_swarm
+			.with_other_transport(|key| {
+				Ok::<_, noise::Error>(
+					transport
+						.upgrade(upgrade::Version::V1)
+						.authenticate(noise::Config::new(key)?)
+						.multiplex(yamux::Config::default())
+						.timeout(TOR_UPGRADE_TIMEOUT),
+				)
+			})?
+			.with_relay_client(noise::Config::new, yamux::Config::default)?
+			.with_behaviour(behaviour)?
+			.with_swarm_config(|c| generate_config(c, cfg))
+			.build();
+	} else if is_ws_transport {
 		swarm = tokio_swarm
 			.with_websocket(noise::Config::new, yamux::Config::default)
 			.await?
@@ -1,6 +1,9 @@
 use super::{
-	event_loop::ConnectionEstablishedInfo, is_global, Command, CommandSender, EventLoopEntries,
-	LocalInfo, QueryChannel, SendableCommand,
+	compression::{self, RecordCompression},
+	event_loop::ConnectionEstablishedInfo,
+	header_sync::{HeadersRequest, HeadersResponse},
+	is_global, privacy, Command, CommandSender, EventLoopEntries, LocalInfo, PeerStats,
+	QueryChannel, SendableCommand,
 };
 use color_eyre::{
 	eyre::{eyre, WrapErr},
@@ -13,21 +16,42 @@ use kate_recovery::{
 	matrix::{Dimensions, Position, RowIndex},
 };
 use libp2p::{
-	kad::{store::RecordStore, PeerRecord, Quorum, Record, RecordKey},
+	kad::{store::RecordStore, NodeStatus, PeerRecord, Quorum, Record, RecordKey},
+	request_response::ResponseChannel,
 	swarm::dial_opts::DialOpts,
 	Multiaddr, PeerId,
 };
-use std::time::{Duration, Instant};
+use std::{
+	collections::BTreeMap,
+	time::{Duration, Instant},
+};
 use tokio::sync::oneshot;
 use tracing::{debug, trace};
 
+use crate::{
+	dht_retry::RetryQueue,
+	scheduler::{Lane, Permit, Scheduler},
+	types::{SamplingPrivacyConfig, TimeToLive},
+};
+
 #[derive(Clone)]
 pub struct Client {
 	command_sender: CommandSender,
 	/// Number of cells to fetch in parallel
 	dht_parallelization_limit: usize,
-	/// Cell time to live in DHT (in seconds)
-	ttl: u64,
+	/// Cell time to live in DHT, with the random jitter
+	ttl: TimeToLive,
+	/// Compression of the published cell and row records
+	compression: RecordCompression,
+	/// Statistics of the peers which served fetched cells
+	peer_stats: PeerStats,
+	scheduler: Scheduler,
+	/// Lane in which DHT fetches of this client are scheduled
+	lane: Lane,
+	/// Decoy queries and query delays of the sampled cells, `None` if disabled
+	sampling_privacy: Option<SamplingPrivacyConfig>,
+	/// Queue of the failed cell and row inserts
+	retry_queue: RetryQueue,
 }
 
 struct DHTCell(Cell);
@@ -37,12 +61,12 @@ impl DHTCell {
 		self.0.reference(block)
 	}
 
-	fn dht_record(&self, block: u32, ttl: u64) -> Record {
+	fn dht_record(&self, block: u32, ttl: &TimeToLive, compression: RecordCompression) -> Record {
 		Record {
 			key: self.0.reference(block).as_bytes().to_vec().into(),
-			value: self.0.content.to_vec(),
+			value: compression.compress(self.0.content.to_vec()),
 			publisher: None,
-			expires: Instant::now().checked_add(Duration::from_secs(ttl)),
+			expires: ttl.expires(),
 		}
 	}
 }
@@ -53,12 +77,12 @@ impl DHTRow {
 		self.0 .0.reference(block)
 	}
 
-	fn dht_record(&self, block: u32, ttl: u64) -> Record {
+	fn dht_record(&self, block: u32, ttl: &TimeToLive, compression: RecordCompression) -> Record {
 		Record {
 			key: self.0 .0.reference(block).as_bytes().to_vec().into(),
-			value: self.0 .1.clone(),
+			value: compression.compress(self.0 .1.clone()),
 			publisher: None,
-			expires: Instant::now().checked_add(Duration::from_secs(ttl)),
+			expires: ttl.expires(),
 		}
 	}
 }
@@ -90,9 +114,9 @@ impl Command for PruneExpiredRecords {
 	fn run(&mut self, mut entries: EventLoopEntries) -> Result<(), Report> {
 		let store = entries.behavior_mut().kademlia.store_mut();
 
-		let before = store.records().count();
+		let (before, _) = store.size();
 		store.retain(|_, record| !record.is_expired(self.now));
-		let after = store.records().count();
+		let (after, _) = store.size();
 
 		self.response_sender
 			.take()
@@ -122,6 +146,57 @@ impl Command for PruneExpiredRecords {
 	fn abort(&mut self, _: Report) {}
 }
 
+struct RepublishRecords {
+	now: Instant,
+	before_expiry: Duration,
+	limit: usize,
+	ttl: TimeToLive,
+	response_sender: Option<oneshot::Sender<Result<usize>>>,
+}
+
+impl Command for RepublishRecords {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<(), Report> {
+		let republish_before = self.now + self.before_expiry;
+		let kademlia = &mut entries.behavior_mut().kademlia;
+
+		// Only records published by the local node are republished, expiring ones first
+		let records =
+			kademlia
+				.store_mut()
+				.expiring_local_records(self.now, republish_before, self.limit);
+
+		let count = records.len();
+		let mut query_ids = Vec::with_capacity(count);
+		for mut record in records {
+			record.expires = self.ttl.expires_from(self.now);
+			let query_id = kademlia
+				.put_record(record, Quorum::One)
+				.wrap_err("Unable to perform Kademlia PUT operation")?;
+			query_ids.push(query_id);
+		}
+
+		for query_id in query_ids {
+			entries.insert_query(query_id, QueryChannel::RepublishRecord);
+		}
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Ok(count))
+			.expect("RepublishRecords receiver dropped");
+
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("RepublishRecords receiver dropped");
+	}
+}
+
 struct StartListening {
 	addr: Multiaddr,
 	response_sender: Option<oneshot::Sender<Result<()>>>,
@@ -193,6 +268,138 @@ impl Command for Bootstrap {
 	}
 }
 
+struct RefreshRoutingTable {
+	bootstraps: Vec<(PeerId, Multiaddr)>,
+	response_sender: Option<oneshot::Sender<Result<()>>>,
+}
+
+impl Command for RefreshRoutingTable {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
+		let kademlia = &mut entries.behavior_mut().kademlia;
+		for (peer_id, address) in &self.bootstraps {
+			kademlia.add_address(peer_id, address.clone());
+		}
+		// Results are not awaited, since peers are added to the routing table during the queries
+		kademlia.bootstrap()?;
+		kademlia.get_closest_peers(PeerId::random());
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Ok(()))
+			.expect("RefreshRoutingTable receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("RefreshRoutingTable receiver dropped");
+	}
+}
+
+struct RequestHeaders {
+	peer_id: PeerId,
+	request: HeadersRequest,
+	response_sender: Option<oneshot::Sender<Result<HeadersResponse>>>,
+}
+
+impl Command for RequestHeaders {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
+		let request_id = entries
+			.behavior_mut()
+			.header_sync
+			.send_request(&self.peer_id, self.request.clone());
+
+		// insert response channel into pending header requests map
+		let response_sender = self.response_sender.take().unwrap();
+		entries.insert_header_request(request_id, response_sender);
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("RequestHeaders receiver dropped");
+	}
+}
+
+struct SendHeaders {
+	channel: Option<ResponseChannel<HeadersResponse>>,
+	response: Option<HeadersResponse>,
+	response_sender: Option<oneshot::Sender<Result<()>>>,
+}
+
+impl Command for SendHeaders {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
+		let (channel, response) = (self.channel.take().unwrap(), self.response.take().unwrap());
+		let result = entries
+			.behavior_mut()
+			.header_sync
+			.send_response(channel, response)
+			.map_err(|_| eyre!("Connection to the peer is closed"));
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(result)
+			.expect("SendHeaders receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("SendHeaders receiver dropped");
+	}
+}
+
+struct PublishAttestation {
+	data: Option<Vec<u8>>,
+	response_sender: Option<oneshot::Sender<Result<()>>>,
+}
+
+impl Command for PublishAttestation {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
+		let data = self.data.take().unwrap();
+		let result = match entries.behavior_mut().attestation_gossip.as_mut() {
+			Some(gossip) => {
+				// attestations topic is the only subscribed one
+				let topic = gossip.topics().next().cloned();
+				match topic {
+					Some(topic) => gossip
+						.publish(topic, data)
+						.map(|_| ())
+						.map_err(|error| eyre!("Publishing failed: {error}")),
+					None => Err(eyre!("Attestations topic is not subscribed")),
+				}
+			},
+			None => Err(eyre!("Attestation gossip is disabled")),
+		};
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(result)
+			.expect("PublishAttestation receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("PublishAttestation receiver dropped");
+	}
+}
+
 struct GetKadRecord {
 	key: RecordKey,
 	response_sender: Option<oneshot::Sender<Result<PeerRecord>>>,
@@ -200,7 +407,11 @@ struct GetKadRecord {
 
 impl Command for GetKadRecord {
 	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
-		let query_id = entries.behavior_mut().kademlia.get_record(self.key.clone());
+		let kademlia = &mut entries.behavior_mut().kademlia;
+		// records found in the local cell cache are not served to other peers
+		kademlia.store_mut().count_cache_hits(false);
+		let query_id = kademlia.get_record(self.key.clone());
+		kademlia.store_mut().count_cache_hits(true);
 
 		// insert response channel into KAD Queries pending map
 		let response_sender = self.response_sender.take().unwrap();
@@ -255,6 +466,22 @@ impl Command for PutKadRecord {
 	fn abort(&mut self, _: Report) {}
 }
 
+struct CacheKadRecords {
+	records: Vec<Record>,
+}
+
+impl Command for CacheKadRecords {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
+		let store = entries.behavior_mut().kademlia.store_mut();
+		for record in self.records.drain(..) {
+			store.cache(record);
+		}
+		Ok(())
+	}
+
+	fn abort(&mut self, _: Report) {}
+}
+
 struct CountKademliaPeers {
 	response_sender: Option<oneshot::Sender<Result<(usize, usize)>>>,
 }
@@ -295,6 +522,39 @@ impl Command for CountKademliaPeers {
 	}
 }
 
+struct ListRoutingTablePeers {
+	response_sender: Option<oneshot::Sender<Result<Vec<(PeerId, Vec<Multiaddr>)>>>>,
+}
+
+impl Command for ListRoutingTablePeers {
+	fn run(&mut self, entries: EventLoopEntries) -> Result<()> {
+		let mut peers = vec![];
+		for bucket in entries.swarm.behaviour_mut().kademlia.kbuckets() {
+			for item in bucket.iter() {
+				if item.status != NodeStatus::Connected {
+					continue;
+				}
+				let addresses = item.node.value.iter().cloned().collect::<Vec<_>>();
+				peers.push((*item.node.key.preimage(), addresses));
+			}
+		}
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Ok(peers))
+			.expect("ListRoutingTablePeers receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("ListRoutingTablePeers receiver dropped");
+	}
+}
+
 struct GetLocalInfo {
 	response_sender: Option<oneshot::Sender<Result<LocalInfo>>>,
 }
@@ -357,6 +617,35 @@ impl Command for ListConnectedPeers {
 	}
 }
 
+struct ListConnectedAgents {
+	response_sender: Option<oneshot::Sender<Result<Vec<(PeerId, String)>>>>,
+}
+
+impl Command for ListConnectedAgents {
+	fn run(&mut self, entries: EventLoopEntries) -> Result<()> {
+		let agents = entries
+			.connected_agents()
+			.iter()
+			.map(|(peer_id, agent_version)| (*peer_id, agent_version.clone()))
+			.collect::<Vec<_>>();
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Ok(agents))
+			.expect("ListConnectedAgents receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, error: Report) {
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Err(error))
+			.expect("ListConnectedAgents receiver dropped");
+	}
+}
+
 struct ReduceKademliaMapSize {
 	response_sender: Option<oneshot::Sender<Result<()>>>,
 }
@@ -387,12 +676,7 @@ struct GetKademliaMapSize {
 
 impl Command for GetKademliaMapSize {
 	fn run(&mut self, mut entries: EventLoopEntries) -> Result<(), Report> {
-		let size = entries
-			.behavior_mut()
-			.kademlia
-			.store_mut()
-			.records()
-			.count();
+		let (size, _) = entries.behavior_mut().kademlia.store_mut().size();
 
 		self.response_sender
 			.take()
@@ -408,6 +692,27 @@ impl Command for GetKademliaMapSize {
 	}
 }
 
+struct GetKademliaStoreSize {
+	response_sender: Option<oneshot::Sender<Result<(usize, u64)>>>,
+}
+
+impl Command for GetKademliaStoreSize {
+	fn run(&mut self, mut entries: EventLoopEntries) -> Result<(), Report> {
+		let (records, bytes) = entries.behavior_mut().kademlia.store_mut().size();
+
+		self.response_sender
+			.take()
+			.unwrap()
+			.send(Ok((records, bytes)))
+			.expect("GetKademliaStoreSize receiver dropped");
+		Ok(())
+	}
+
+	fn abort(&mut self, _: Report) {
+		debug!("No possible errors for GetKademliaStoreSize");
+	}
+}
+
 struct DialPeer {
 	peer_id: PeerId,
 	peer_address: Vec<Multiaddr>,
@@ -445,10 +750,10 @@ struct AddAutonatServer {
 
 impl Command for AddAutonatServer {
 	fn run(&mut self, mut entries: EventLoopEntries) -> Result<()> {
-		entries
-			.behavior_mut()
-			.auto_nat
-			.add_server(self.peer_id, Some(self.address.clone()));
+		// AutoNAT is disabled if connections are routed over Tor
+		if let Some(auto_nat) = entries.behavior_mut().auto_nat.as_mut() {
+			auto_nat.add_server(self.peer_id, Some(self.address.clone()));
+		}
 
 		// send result back
 		// TODO: consider what to do if this results with None
@@ -471,10 +776,77 @@ impl Client {
 		Self {
 			command_sender: sender,
 			dht_parallelization_limit,
-			ttl,
+			ttl: TimeToLive::new(Duration::from_secs(ttl), Duration::ZERO),
+			compression: RecordCompression::None,
+			peer_stats: PeerStats::default(),
+			scheduler: Scheduler::default(),
+			lane: Lane::Head,
+			sampling_privacy: None,
+			retry_queue: RetryQueue::default(),
 		}
 	}
 
+	/// Sets the maximum random jitter added to the TTL of the inserted and republished records
+	pub fn with_ttl_jitter(mut self, jitter: Duration) -> Self {
+		self.ttl.jitter = jitter;
+		self
+	}
+
+	/// Sets the compression of the published cell and row records
+	pub fn with_compression(mut self, compression: RecordCompression) -> Self {
+		self.compression = compression;
+		self
+	}
+
+	/// Sets the scheduler of the head and historical work
+	pub fn with_scheduler(mut self, scheduler: Scheduler) -> Self {
+		self.scheduler = scheduler;
+		self
+	}
+
+	/// Sets the decoy queries and query delays of the sampled cells
+	pub fn with_sampling_privacy(
+		mut self,
+		sampling_privacy: Option<SamplingPrivacyConfig>,
+	) -> Self {
+		self.sampling_privacy = sampling_privacy;
+		self
+	}
+
+	/// Sets the queue of the failed cell and row inserts, retried with [`Client::retry_failed_inserts`]
+	pub fn with_retry_queue(mut self, retry_queue: RetryQueue) -> Self {
+		self.retry_queue = retry_queue;
+		self
+	}
+
+	/// Returns the client which schedules DHT fetches in the given lane
+	pub fn with_lane(&self, lane: Lane) -> Self {
+		Self {
+			lane,
+			..self.clone()
+		}
+	}
+
+	/// Waits until the step in the client lane can run (see [`Scheduler`])
+	pub async fn schedule(&self) -> Permit {
+		self.scheduler.acquire(self.lane).await
+	}
+
+	/// Returns statistics of the peers which served fetched cells
+	pub fn peer_stats(&self) -> &PeerStats {
+		&self.peer_stats
+	}
+
+	/// Returns maximum number of the cells or rows fetched from DHT in parallel
+	pub fn dht_parallelization_limit(&self) -> usize {
+		self.dht_parallelization_limit
+	}
+
+	/// Returns number of the commands waiting for the network event loop
+	pub fn queued_commands(&self) -> usize {
+		self.command_sender.queued()
+	}
+
 	async fn execute_sync<F, T>(&self, command_with_sender: F) -> Result<T>
 	where
 		F: FnOnce(oneshot::Sender<Result<T>>) -> SendableCommand,
@@ -529,6 +901,17 @@ impl Client {
 		.await
 	}
 
+	/// Re-adds bootstrap nodes to the routing table and starts bootstrap and random walk queries, without awaiting results
+	pub async fn refresh_routing_table(&self, bootstraps: Vec<(PeerId, Multiaddr)>) -> Result<()> {
+		self.execute_sync(|response_sender| {
+			Box::new(RefreshRoutingTable {
+				bootstraps,
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
 	pub async fn add_autonat_server(&self, peer_id: PeerId, address: Multiaddr) -> Result<()> {
 		self.execute_sync(|response_sender| {
 			Box::new(AddAutonatServer {
@@ -586,6 +969,16 @@ impl Client {
 		.await
 	}
 
+	/// Returns connected routing table peers, with their addresses
+	pub async fn list_routing_table_peers(&self) -> Result<Vec<(PeerId, Vec<Multiaddr>)>> {
+		self.execute_sync(|response_sender| {
+			Box::new(ListRoutingTablePeers {
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
 	pub async fn list_connected_peers(&self) -> Result<Vec<String>> {
 		self.execute_sync(|response_sender| {
 			Box::new(ListConnectedPeers {
@@ -595,6 +988,59 @@ impl Client {
 		.await
 	}
 
+	/// Returns agent versions of the connected peers, received with the identify protocol
+	pub async fn list_connected_agents(&self) -> Result<Vec<(PeerId, String)>> {
+		self.execute_sync(|response_sender| {
+			Box::new(ListConnectedAgents {
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
+	/// Requests recent finalized headers from the peer
+	pub async fn request_headers(
+		&self,
+		peer_id: PeerId,
+		request: HeadersRequest,
+	) -> Result<HeadersResponse> {
+		self.execute_sync(|response_sender| {
+			Box::new(RequestHeaders {
+				peer_id,
+				request,
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
+	/// Sends headers in response to the peer request
+	pub async fn send_headers(
+		&self,
+		channel: ResponseChannel<HeadersResponse>,
+		response: HeadersResponse,
+	) -> Result<()> {
+		self.execute_sync(|response_sender| {
+			Box::new(SendHeaders {
+				channel: Some(channel),
+				response: Some(response),
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
+	/// Publishes encoded attestation to the attestations gossipsub topic
+	pub async fn publish_attestation(&self, data: Vec<u8>) -> Result<()> {
+		self.execute_sync(|response_sender| {
+			Box::new(PublishAttestation {
+				data: Some(data),
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
 	pub async fn get_local_info(&self) -> Result<LocalInfo> {
 		self.execute_sync(|response_sender| {
 			Box::new(GetLocalInfo {
@@ -623,6 +1069,16 @@ impl Client {
 		.await
 	}
 
+	/// Returns number of records in the Kademlia store and their size in bytes
+	pub async fn get_kademlia_store_size(&self) -> Result<(usize, u64)> {
+		self.execute_sync(|response_sender| {
+			Box::new(GetKademliaStoreSize {
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
 	pub async fn prune_expired_records(&self) -> Result<usize> {
 		self.execute_sync(|response_sender| {
 			Box::new(PruneExpiredRecords {
@@ -633,27 +1089,58 @@ impl Client {
 		.await
 	}
 
+	/// Republishes up to `limit` locally published records, which expire within `before_expiry`.
+	/// Returns number of republished records.
+	pub async fn republish_records(&self, before_expiry: Duration, limit: usize) -> Result<usize> {
+		self.execute_sync(|response_sender| {
+			Box::new(RepublishRecords {
+				now: Instant::now(),
+				before_expiry,
+				limit,
+				ttl: self.ttl,
+				response_sender: Some(response_sender),
+			})
+		})
+		.await
+	}
+
 	// Since callers ignores DHT errors, debug logs are used to observe DHT behavior.
 	// Return type assumes that cell is not found in case when error is present.
-	async fn fetch_cell_from_dht(&self, block_number: u32, position: Position) -> Option<Cell> {
+	/// Fetches cell from DHT, with the peer which returned the record (`None` if record is found in the local store)
+	async fn fetch_cell_from_dht(
+		&self,
+		block_number: u32,
+		position: Position,
+	) -> Option<(Cell, Option<PeerId>)> {
 		let reference = position.reference(block_number);
 		let record_key = RecordKey::from(reference.as_bytes().to_vec());
 
+		if let Some(sampling_privacy) = &self.sampling_privacy {
+			tokio::time::sleep(privacy::query_delay(sampling_privacy)).await;
+		}
+
 		trace!("Getting DHT record for reference {}", reference);
 
+		let started = Instant::now();
 		match self.get_kad_record(record_key).await {
 			Ok(peer_record) => {
 				trace!("Fetched cell {reference} from the DHT");
 
+				if let Some(peer_id) = peer_record.peer {
+					self.peer_stats.record_served(peer_id, started.elapsed());
+				}
+
 				let try_content: Result<[u8; config::COMMITMENT_SIZE + config::CHUNK_SIZE], _> =
-					peer_record.record.value.try_into();
+					compression::decompress(&peer_record.record.value)
+						.as_ref()
+						.try_into();
 
 				let Ok(content) = try_content else {
 					debug!("Cannot convert cell {reference} into 80 bytes");
 					return None;
 				};
 
-				Some(Cell { position, content })
+				Some((Cell { position, content }, peer_record.peer))
 			},
 			Err(error) => {
 				trace!("Cell {reference} not found in the DHT: {error}");
@@ -674,7 +1161,10 @@ impl Client {
 		trace!("Getting DHT record for reference {}", reference);
 
 		match self.get_kad_record(record_key).await {
-			Ok(peer_record) => Some((row_index.0, peer_record.record.value)),
+			Ok(peer_record) => Some((
+				row_index.0,
+				compression::decompress(&peer_record.record.value).into_owned(),
+			)),
 			Err(error) => {
 				debug!("Row {reference} not found in the DHT: {error}");
 				None
@@ -694,9 +1184,24 @@ impl Client {
 		block_number: u32,
 		positions: &[Position],
 	) -> (Vec<Cell>, Vec<Position>) {
-		let mut cells = Vec::<Option<Cell>>::with_capacity(positions.len());
+		let (fetched, unfetched) = self
+			.fetch_cells_with_peers_from_dht(block_number, positions)
+			.await;
+		let fetched = fetched.into_iter().map(|(cell, _)| cell).collect();
+		(fetched, unfetched)
+	}
+
+	/// Fetches cells from DHT, same as [`Client::fetch_cells_from_dht`].
+	/// Each fetched cell is returned with the peer which returned the record (`None` if record is found in the local store).
+	pub async fn fetch_cells_with_peers_from_dht(
+		&self,
+		block_number: u32,
+		positions: &[Position],
+	) -> (Vec<(Cell, Option<PeerId>)>, Vec<Position>) {
+		let mut cells = Vec::<Option<(Cell, Option<PeerId>)>>::with_capacity(positions.len());
 
 		for positions in positions.chunks(self.dht_parallelization_limit) {
+			let _permit = self.schedule().await;
 			let fetch = |&position| self.fetch_cell_from_dht(block_number, position);
 			let results = join_all(positions.iter().map(fetch)).await;
 			cells.extend(results.into_iter().collect::<Vec<_>>());
@@ -714,6 +1219,29 @@ impl Client {
 		(fetched, unfetched)
 	}
 
+	/// Fetches sampled cells from DHT, same as [`Client::fetch_cells_with_peers_from_dht`].
+	/// If sampling privacy is enabled, sampled cells are queried together with the decoy cells, which are discarded.
+	pub async fn fetch_sampled_cells_from_dht(
+		&self,
+		block_number: u32,
+		dimensions: Dimensions,
+		positions: &[Position],
+	) -> (Vec<(Cell, Option<PeerId>)>, Vec<Position>) {
+		let Some(sampling_privacy) = &self.sampling_privacy else {
+			return self
+				.fetch_cells_with_peers_from_dht(block_number, positions)
+				.await;
+		};
+		let (positions, decoys) = privacy::with_decoys(sampling_privacy, dimensions, positions);
+		trace!(block_number, decoys = decoys.len(), "Querying decoy cells");
+		let (mut fetched, mut unfetched) = self
+			.fetch_cells_with_peers_from_dht(block_number, &positions)
+			.await;
+		fetched.retain(|(cell, _)| !decoys.contains(&cell.position));
+		unfetched.retain(|position| !decoys.contains(position));
+		(fetched, unfetched)
+	}
+
 	/// Fetches rows from DHT.
 	/// Returns fetched rows and unfetched row indexes (so we can try RPC fetch).
 	///
@@ -729,6 +1257,7 @@ impl Client {
 	) -> Vec<Option<Vec<u8>>> {
 		let mut rows = vec![None; dimensions.extended_rows() as usize];
 		for row_indexes in row_indexes.chunks(self.dht_parallelization_limit) {
+			let _permit = self.schedule().await;
 			let fetch = |row| self.fetch_row_from_dht(block_number, row);
 			let fetched_rows = join_all(row_indexes.iter().cloned().map(fetch)).await;
 			for (row_index, row) in fetched_rows.into_iter().flatten() {
@@ -750,6 +1279,22 @@ impl Client {
 		.await
 	}
 
+	/// Re-inserts queued failed inserts which are due for the retry into the DHT.
+	/// Returns number of the retried records.
+	pub async fn retry_failed_inserts(&self) -> Result<usize> {
+		let mut blocks = BTreeMap::<u32, Vec<Record>>::new();
+		for (block_number, record) in self.retry_queue.due().await? {
+			blocks.entry(block_number).or_default().push(record);
+		}
+		let mut retried = 0;
+		for (block_number, records) in blocks {
+			retried += records.len();
+			self.put_kad_record(records, Quorum::One, block_number)
+				.await?;
+		}
+		Ok(retried)
+	}
+
 	/// Inserts cells into the DHT.
 	/// There is no rollback, and errors will be logged and skipped,
 	/// which means that we cannot rely on error logs as alert mechanism.
@@ -764,11 +1309,33 @@ impl Client {
 		let records: Vec<_> = cells
 			.into_iter()
 			.map(DHTCell)
-			.map(|cell| (cell.reference(block), cell.dht_record(block, self.ttl)))
+			.map(|cell| {
+				(
+					cell.reference(block),
+					cell.dht_record(block, &self.ttl, self.compression),
+				)
+			})
 			.collect::<Vec<_>>();
 		self.insert_into_dht(records, block).await
 	}
 
+	/// Keeps verified cells in the local cell cache, so they can be served to other peers.
+	/// Cells are not published to the DHT.
+	///
+	/// # Arguments
+	///
+	/// * `block` - Block number
+	/// * `cells` - Verified matrix cells
+	pub async fn cache_cells(&self, block: u32, cells: &[Cell]) -> Result<()> {
+		let records = cells
+			.iter()
+			.map(|cell| DHTCell(cell.clone()).dht_record(block, &self.ttl, self.compression))
+			.collect::<Vec<_>>();
+		self.command_sender
+			.send(Box::new(CacheKadRecords { records }))
+			.context("receiver should not be dropped")
+	}
+
 	/// Inserts rows into the DHT.
 	/// There is no rollback, and errors will be logged and skipped,
 	/// which means that we cannot rely on error logs as alert mechanism.
@@ -787,7 +1354,12 @@ impl Client {
 		let records: Vec<_> = rows
 			.into_iter()
 			.map(DHTRow)
-			.map(|row| (row.reference(block), row.dht_record(block, self.ttl)))
+			.map(|row| {
+				(
+					row.reference(block),
+					row.dht_record(block, &self.ttl, self.compression),
+				)
+			})
 			.collect::<Vec<_>>();
 
 		self.insert_into_dht(records, block).await
@@ -0,0 +1,180 @@
+//! Compression of the DHT record payloads.
+//!
+//! If `dht_record_compression` is enabled, values of the published cell and row records are compressed with LZ4, Zstandard or deflate,
+//! and framed with the header: magic prefix, framing version, algorithm and size of the decompressed value.
+//! Values are published uncompressed (without header) if compression doesn't reduce their size, which is usually the case for cells,
+//! so compression pays off with the highly compressible app data rows.
+//!
+//! Fetched records are always decompressed, regardless of the local configuration, so compressed records published by other nodes can be read.
+//!
+//! # Notes
+//!
+//! Value is decompressed only if the whole header is valid, and decompression yields exactly the declared size,
+//! otherwise it is returned as is. Uncompressed values which start with the magic prefix are always framed (stored uncompressed),
+//! so they cannot be misread as compressed ones.
+//! Nodes without compression support cannot read compressed records, so compression should be enabled once most of the network supports it.
+//! Zstandard is provided by the bindings to the C library, while LZ4 and deflate are implemented in pure Rust.
+
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Magic prefix of the framed record
+const MAGIC: &[u8; 4] = b"\xffAVC";
+/// Version of the record framing
+const VERSION: u8 = 1;
+/// Size of the header: magic prefix, version, algorithm and decompressed size
+const HEADER_SIZE: usize = MAGIC.len() + 2 + 4;
+
+/// Algorithm of the record stored uncompressed
+const STORED: u8 = 0;
+const LZ4: u8 = 1;
+const ZSTD: u8 = 2;
+const DEFLATE: u8 = 3;
+
+/// Zstandard compression level, balancing compression ratio and speed
+const ZSTD_LEVEL: i32 = 3;
+/// Deflate compression level, balancing compression ratio and speed
+const DEFLATE_LEVEL: u8 = 6;
+/// Maximum size of the decompressed record, protecting against decompression bombs
+const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024;
+
+/// Compression of the published DHT records
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordCompression {
+	/// Records are published uncompressed
+	#[default]
+	None,
+	/// Records are compressed with LZ4
+	Lz4,
+	/// Records are compressed with Zstandard
+	Zstd,
+	/// Records are compressed with deflate
+	Deflate,
+}
+
+impl RecordCompression {
+	/// Returns record value, compressed if compression reduces its size
+	pub fn compress(&self, value: Vec<u8>) -> Vec<u8> {
+		let compressed = match self {
+			RecordCompression::None => None,
+			RecordCompression::Lz4 => Some((LZ4, lz4_flex::block::compress(&value))),
+			RecordCompression::Zstd => zstd::bulk::compress(&value, ZSTD_LEVEL)
+				.ok()
+				.map(|compressed| (ZSTD, compressed)),
+			RecordCompression::Deflate => Some((DEFLATE, compress_to_vec(&value, DEFLATE_LEVEL))),
+		};
+		match compressed {
+			Some((algorithm, compressed)) if compressed.len() + HEADER_SIZE < value.len() => {
+				frame(algorithm, value.len(), &compressed)
+			},
+			_ if value.starts_with(MAGIC) => frame(STORED, value.len(), &value),
+			_ => value,
+		}
+	}
+}
+
+fn frame(algorithm: u8, size: usize, payload: &[u8]) -> Vec<u8> {
+	let mut framed = Vec::with_capacity(HEADER_SIZE + payload.len());
+	framed.extend_from_slice(MAGIC);
+	framed.push(VERSION);
+	framed.push(algorithm);
+	framed.extend_from_slice(&(size as u32).to_be_bytes());
+	framed.extend_from_slice(payload);
+	framed
+}
+
+/// Returns decompressed value of the framed record, `None` if the value is not a valid framed record
+fn unframe(value: &[u8]) -> Option<Cow<'_, [u8]>> {
+	let rest = value.strip_prefix(MAGIC.as_slice())?;
+	let (&version, rest) = rest.split_first()?;
+	let (&algorithm, rest) = rest.split_first()?;
+	if version != VERSION || rest.len() < 4 {
+		return None;
+	}
+	let (size, payload) = rest.split_at(4);
+	let size = u32::from_be_bytes(size.try_into().ok()?) as usize;
+	if size > MAX_DECOMPRESSED_SIZE {
+		return None;
+	}
+	let decompressed = match algorithm {
+		STORED => Cow::Borrowed(payload),
+		LZ4 => Cow::Owned(lz4_flex::block::decompress(payload, size).ok()?),
+		ZSTD => Cow::Owned(zstd::bulk::decompress(payload, size).ok()?),
+		DEFLATE => Cow::Owned(decompress_to_vec_with_limit(payload, size).ok()?),
+		_ => return None,
+	};
+	(decompressed.len() == size).then_some(decompressed)
+}
+
+/// Returns decompressed record value, or the value itself if it is not compressed
+pub fn decompress(value: &[u8]) -> Cow<'_, [u8]> {
+	unframe(value).unwrap_or(Cow::Borrowed(value))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn record_compression() {
+		let row = [vec![0u8; 512], vec![1u8; 512]].concat();
+		for (compression, algorithm) in [
+			(RecordCompression::Lz4, LZ4),
+			(RecordCompression::Zstd, ZSTD),
+			(RecordCompression::Deflate, DEFLATE),
+		] {
+			let compressed = compression.compress(row.clone());
+			assert_eq!(&compressed[..MAGIC.len()], MAGIC);
+			assert_eq!(compressed[MAGIC.len() + 1], algorithm);
+			assert!(compressed.len() < row.len());
+			assert_eq!(decompress(&compressed), row);
+		}
+
+		// incompressible and uncompressed records are kept as is
+		let cell = (0..80u8)
+			.map(|i| i.wrapping_mul(97) ^ 0x5a)
+			.collect::<Vec<_>>();
+		assert_eq!(RecordCompression::Lz4.compress(cell.clone()), cell);
+		assert_eq!(RecordCompression::None.compress(row.clone()), row);
+		assert_eq!(decompress(&row), row);
+
+		// uncompressed records starting with the magic prefix are framed
+		let prefixed = [&MAGIC[..], &cell[..]].concat();
+		for compression in [RecordCompression::None, RecordCompression::Zstd] {
+			let framed = compression.compress(prefixed.clone());
+			assert_eq!(framed.len(), prefixed.len() + HEADER_SIZE);
+			assert_eq!(decompress(&framed), prefixed);
+		}
+		let row_0xff = [vec![0xffu8; 4], vec![0u8; 28]].concat();
+		assert_eq!(decompress(&row_0xff), row_0xff);
+	}
+
+	#[test]
+	fn invalid_frames() {
+		let row = [vec![0u8; 512], vec![1u8; 512]].concat();
+		let compressed = RecordCompression::Lz4.compress(row.clone());
+
+		// unknown version and algorithm
+		let mut invalid = compressed.clone();
+		invalid[MAGIC.len()] = VERSION + 1;
+		assert_eq!(decompress(&invalid), invalid);
+		let mut invalid = compressed.clone();
+		invalid[MAGIC.len() + 1] = 0x7f;
+		assert_eq!(decompress(&invalid), invalid);
+
+		// declared size doesn't match, or truncated header and payload
+		let mut invalid = compressed.clone();
+		invalid[HEADER_SIZE - 1] ^= 1;
+		assert_eq!(decompress(&invalid), invalid);
+		let invalid = compressed[..HEADER_SIZE - 1].to_vec();
+		assert_eq!(decompress(&invalid), invalid);
+		let invalid = compressed[..compressed.len() - 1].to_vec();
+		assert_eq!(decompress(&invalid), invalid);
+
+		// oversized compressed records are not decompressed
+		let bomb = RecordCompression::Deflate.compress(vec![0u8; MAX_DECOMPRESSED_SIZE + 1]);
+		assert_eq!(decompress(&bomb), bomb);
+	}
+}
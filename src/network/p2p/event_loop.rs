@@ -3,7 +3,7 @@ use futures::StreamExt;
 use libp2p::{
 	autonat::{self, NatStatus},
 	core::ConnectedPoint,
-	dcutr,
+	dcutr, gossipsub,
 	identify::{self, Info},
 	identity::Keypair,
 	kad::{
@@ -13,6 +13,7 @@ use libp2p::{
 	mdns,
 	multiaddr::Protocol,
 	ping,
+	request_response::{self, OutboundRequestId},
 	swarm::{
 		dial_opts::{DialOpts, PeerCondition},
 		ConnectionError, SwarmEvent,
@@ -20,7 +21,7 @@ use libp2p::{
 	upnp, Multiaddr, PeerId, Swarm,
 };
 use rand::seq::SliceRandom;
-use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use std::{borrow::Cow, collections::HashMap, str::FromStr, sync::Arc, time::Duration};
 use tokio::{
 	sync::oneshot,
 	time::{interval_at, Instant, Interval},
@@ -28,14 +29,21 @@ use tokio::{
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{
-	shutdown::Controller,
+	dht_retry::RetryQueue,
+	header_sync::{self, InboundRequest},
+	shutdown::{Controller, ShutdownReason},
 	telemetry::{MetricCounter, MetricValue, Metrics},
 	types::{AgentVersion, IdentifyConfig, KademliaMode, LibP2PConfig, TimeToLive},
 };
 
 use super::{
-	build_swarm, client::BlockStat, Behaviour, BehaviourEvent, CommandReceiver, EventLoopEntries,
-	QueryChannel, SendableCommand,
+	build_swarm,
+	client::BlockStat,
+	header_sync::HeadersResponse,
+	kad_cached_store::CachedStore,
+	kad_indexed_store::IndexedStore,
+	kad_quota_store::{PublisherQuota, QuotaStore},
+	Behaviour, BehaviourEvent, CommandReceiver, EventLoopEntries, QueryChannel, SendableCommand,
 };
 
 // RelayState keeps track of all things relay related
@@ -102,8 +110,16 @@ pub struct EventLoop {
 	bootstrap: BootstrapState,
 	/// Blocks we monitor for PUT success rate
 	active_blocks: HashMap<u32, BlockStat>,
-	shutdown: Controller<String>,
+	shutdown: Controller<ShutdownReason>,
 	event_loop_config: EventLoopConfig,
+	/// Queue of the failed cell and row inserts
+	retry_queue: RetryQueue,
+	// Tracking header requests sent to the peers
+	pending_header_requests: HashMap<OutboundRequestId, oneshot::Sender<Result<HeadersResponse>>>,
+	/// Header requests of the peers, served from the database
+	header_requests: header_sync::Requests,
+	/// Agent versions of the connected peers, received with the identify protocol
+	connected_agents: HashMap<PeerId, String>,
 }
 
 #[derive(PartialEq, Debug)]
@@ -140,7 +156,7 @@ impl EventLoop {
 		id_keys: &Keypair,
 		is_fat_client: bool,
 		is_ws_transport: bool,
-		shutdown: Controller<String>,
+		shutdown: Controller<ShutdownReason>,
 		#[cfg(feature = "kademlia-rocksdb")] db: Arc<rocksdb::DB>,
 	) -> Self {
 		let bootstrap_interval = cfg.bootstrap_interval;
@@ -151,6 +167,14 @@ impl EventLoop {
 			#[cfg(feature = "kademlia-rocksdb")]
 			db,
 		);
+		let quota = PublisherQuota {
+			max_records: cfg.kademlia.max_kad_records_per_publisher,
+			max_bytes: cfg.kademlia.max_kad_bytes_per_publisher,
+		};
+		let store = CachedStore::new(
+			QuotaStore::new(IndexedStore::new(store, peer_id), quota),
+			cfg.kademlia.cell_cache_size,
+		);
 
 		let swarm = build_swarm(&cfg, id_keys, store, is_ws_transport)
 			.await
@@ -175,11 +199,30 @@ impl EventLoop {
 			event_loop_config: EventLoopConfig {
 				identity_data: cfg.identify,
 				is_fat_client,
-				kad_record_ttl: TimeToLive(cfg.kademlia.kad_record_ttl),
+				kad_record_ttl: TimeToLive::new(
+					cfg.kademlia.kad_record_ttl,
+					cfg.kademlia.kad_record_ttl_jitter,
+				),
 			},
+			retry_queue: RetryQueue::default(),
+			pending_header_requests: Default::default(),
+			header_requests: header_sync::Requests::default(),
+			connected_agents: Default::default(),
 		}
 	}
 
+	/// Sets the queue to which failed cell and row inserts are sent for the retry
+	pub fn with_retry_queue(mut self, retry_queue: RetryQueue) -> Self {
+		self.retry_queue = retry_queue;
+		self
+	}
+
+	/// Sets the sender to which header requests of the peers are sent to be served
+	pub fn with_header_requests(mut self, header_requests: header_sync::Requests) -> Self {
+		self.header_requests = header_requests;
+		self
+	}
+
 	pub async fn run(mut self, metrics: Arc<impl Metrics>, mut command_receiver: CommandReceiver) {
 		// shutdown will wait as long as this token is not dropped
 		let _delay_token = self
@@ -248,6 +291,15 @@ impl EventLoop {
 					kad::Event::InboundRequest { request } => match request {
 						InboundRequest::GetRecord { .. } => {
 							metrics.count(MetricCounter::IncomingGetRecord).await;
+							let hits = self
+								.swarm
+								.behaviour_mut()
+								.kademlia
+								.store_mut()
+								.take_cache_hits();
+							for _ in 0..hits {
+								metrics.count(MetricCounter::DHTCellCacheHits).await;
+							}
 						},
 						InboundRequest::PutRecord { source, record, .. } => {
 							metrics.count(MetricCounter::IncomingPutRecord).await;
@@ -256,9 +308,13 @@ impl EventLoop {
 									let ttl = &self.event_loop_config.kad_record_ttl;
 
 									// Set TTL for all incoming records
-									// TTL will be set to a lower value between the local TTL and incoming record TTL
+									// TTL will be set to a lower value between the local TTL (with jitter) and incoming record TTL
 									record.expires = record.expires.min(ttl.expires());
-									_ = self.swarm.behaviour_mut().kademlia.store_mut().put(record);
+									let store = self.swarm.behaviour_mut().kademlia.store_mut();
+									_ = store.put_from(source, record);
+									for _ in 0..store.take_evictions() {
+										metrics.count(MetricCounter::DHTQuotaEvictions).await;
+									}
 								},
 								None => {
 									debug!("Received empty cell record from: {source:?}");
@@ -293,8 +349,13 @@ impl EventLoop {
 							_ => (),
 						},
 						QueryResult::PutRecord(Err(error)) => {
-							if self.pending_kad_queries.remove(&id).is_none() {
-								return;
+							match self.pending_kad_queries.remove(&id) {
+								None => return,
+								Some(QueryChannel::RepublishRecord) => {
+									debug!("Republishing record failed: {error}");
+									return;
+								},
+								Some(_) => (),
 							};
 
 							match error {
@@ -308,8 +369,13 @@ impl EventLoop {
 						},
 
 						QueryResult::PutRecord(Ok(record)) => {
-							if self.pending_kad_queries.remove(&id).is_none() {
-								return;
+							match self.pending_kad_queries.remove(&id) {
+								None => return,
+								Some(QueryChannel::RepublishRecord) => {
+									trace!("Record republished: {:?}", record.key);
+									return;
+								},
+								Some(_) => (),
 							};
 							self.handle_put_result(record.key.clone(), stats, false, metrics)
 								.await;
@@ -357,6 +423,7 @@ impl EventLoop {
 					trace!(
 						"Identity Received from: {peer_id:?} on listen address: {listen_addrs:?}"
 					);
+					self.connected_agents.insert(peer_id, agent_version.clone());
 
 					let incoming_peer_agent_version = match AgentVersion::from_str(&agent_version) {
 						Ok(agent) => agent,
@@ -423,8 +490,8 @@ impl EventLoop {
 							.swarm
 							.behaviour_mut()
 							.mdns
-							.discovered_nodes()
-							.any(|&p| p == peer_id)
+							.as_mut()
+							.is_some_and(|mdns| mdns.discovered_nodes().any(|&p| p == peer_id))
 						{
 							self.swarm
 								.behaviour_mut()
@@ -474,6 +541,56 @@ impl EventLoop {
 						.await;
 				}
 			},
+			SwarmEvent::Behaviour(BehaviourEvent::HeaderSync(event)) => match event {
+				request_response::Event::Message { peer, message } => match message {
+					request_response::Message::Request {
+						request, channel, ..
+					} => {
+						trace!("Header request from {peer}: {request:?}");
+						self.header_requests.send(InboundRequest {
+							peer_id: peer,
+							request,
+							channel,
+						});
+					},
+					request_response::Message::Response {
+						request_id,
+						response,
+					} => {
+						if let Some(ch) = self.pending_header_requests.remove(&request_id) {
+							_ = ch.send(Ok(response));
+						}
+					},
+				},
+				request_response::Event::OutboundFailure {
+					peer,
+					request_id,
+					error,
+				} => {
+					if let Some(ch) = self.pending_header_requests.remove(&request_id) {
+						_ = ch.send(Err(eyre!("Header request to {peer} failed: {error}")));
+					}
+				},
+				request_response::Event::InboundFailure { peer, error, .. } => {
+					trace!("Header request from {peer} failed: {error}");
+				},
+				request_response::Event::ResponseSent { peer, .. } => {
+					trace!("Headers sent to {peer}");
+				},
+			},
+			SwarmEvent::Behaviour(BehaviourEvent::AttestationGossip(event)) => match event {
+				gossipsub::Event::Message {
+					propagation_source,
+					message,
+					..
+				} => {
+					trace!(
+						"Attestation gossiped by {propagation_source}, {} bytes",
+						message.data.len()
+					);
+				},
+				event => trace!("Attestation gossip event: {event:?}"),
+			},
 			SwarmEvent::Behaviour(BehaviourEvent::Upnp(event)) => match event {
 				upnp::Event::NewExternalAddr(addr) => {
 					trace!("[UPnP] New external address: {addr}");
@@ -506,6 +623,9 @@ impl EventLoop {
 							// remove peer with failed connection
 							self.swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
 						}
+						if num_established == 0 {
+							self.connected_agents.remove(&peer_id);
+						}
 					},
 					SwarmEvent::IncomingConnection { .. } => {
 						metrics.count(MetricCounter::IncomingConnections).await;
@@ -582,6 +702,8 @@ impl EventLoop {
 			&mut self.pending_kad_queries,
 			&mut self.pending_swarm_events,
 			&mut self.active_blocks,
+			&mut self.pending_header_requests,
+			&self.connected_agents,
 		)) {
 			command.abort(eyre!(err));
 		}
@@ -659,6 +781,16 @@ impl EventLoop {
 				return;
 			},
 		};
+		if !is_error {
+			self.retry_queue.inserted(key.clone());
+		} else {
+			let store = self.swarm.behaviour_mut().kademlia.store_mut();
+			store.count_cache_hits(false);
+			if let Some(record) = store.get(&key).map(Cow::into_owned) {
+				self.retry_queue.failed(record, block_num);
+			}
+			store.count_cache_hits(true);
+		}
 		if let Some(block) = self.active_blocks.get_mut(&block_num) {
 			// Decrement record counter for this block
 			block.remaining_counter -= 1;
@@ -0,0 +1,152 @@
+//! Request-response protocol for the recent finalized headers, served by the light and fat clients.
+//!
+//! Request contains the first block number and number of the requested headers, and the response contains
+//! the consecutive stored headers, each with its finality justification, if it is stored.
+//! Messages are SCALE encoded, one message per stream.
+
+use async_trait::async_trait;
+use avail_subxt::primitives::Header;
+use codec::{Decode, DecodeAll, Encode};
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use libp2p::{
+	request_response::{self, ProtocolSupport},
+	StreamProtocol,
+};
+use std::{io, time::Duration};
+
+use crate::types::GrandpaJustification;
+
+/// Maximum number of headers in the response
+pub const MAX_HEADERS: u32 = 32;
+/// Maximum size of the encoded request
+const MAX_REQUEST_SIZE: u64 = 1024;
+/// Maximum size of the encoded response
+const MAX_RESPONSE_SIZE: u64 = 8 * 1024 * 1024;
+/// Timeout of the outbound request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct HeadersRequest {
+	/// Block number of the first requested header
+	pub from: u32,
+	/// Number of the requested headers, limited to [`MAX_HEADERS`]
+	pub count: u32,
+}
+
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct SyncedHeader {
+	pub header: Header,
+	/// Justification of the header, if the header is finalized with it
+	pub justification: Option<GrandpaJustification>,
+}
+
+#[derive(Clone, Debug, Default, Encode, Decode)]
+pub struct HeadersResponse {
+	pub headers: Vec<SyncedHeader>,
+}
+
+#[derive(Clone, Default)]
+pub struct Codec;
+
+async fn read<T, M>(io: &mut T, limit: u64) -> io::Result<M>
+where
+	T: AsyncRead + Unpin + Send,
+	M: DecodeAll,
+{
+	let mut buffer = vec![];
+	io.take(limit).read_to_end(&mut buffer).await?;
+	M::decode_all(&mut buffer.as_slice())
+		.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+async fn write<T, M>(io: &mut T, message: M) -> io::Result<()>
+where
+	T: AsyncWrite + Unpin + Send,
+	M: Encode,
+{
+	io.write_all(&message.encode()).await?;
+	io.close().await
+}
+
+#[async_trait]
+impl request_response::Codec for Codec {
+	type Protocol = StreamProtocol;
+	type Request = HeadersRequest;
+	type Response = HeadersResponse;
+
+	async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<Self::Request>
+	where
+		T: AsyncRead + Unpin + Send,
+	{
+		read(io, MAX_REQUEST_SIZE).await
+	}
+
+	async fn read_response<T>(
+		&mut self,
+		_: &StreamProtocol,
+		io: &mut T,
+	) -> io::Result<Self::Response>
+	where
+		T: AsyncRead + Unpin + Send,
+	{
+		read(io, MAX_RESPONSE_SIZE).await
+	}
+
+	async fn write_request<T>(
+		&mut self,
+		_: &StreamProtocol,
+		io: &mut T,
+		request: Self::Request,
+	) -> io::Result<()>
+	where
+		T: AsyncWrite + Unpin + Send,
+	{
+		write(io, request).await
+	}
+
+	async fn write_response<T>(
+		&mut self,
+		_: &StreamProtocol,
+		io: &mut T,
+		response: Self::Response,
+	) -> io::Result<()>
+	where
+		T: AsyncWrite + Unpin + Send,
+	{
+		write(io, response).await
+	}
+}
+
+/// Creates header sync behaviour, with the protocol name derived from the identify protocol version (which contains the genesis hash)
+pub fn behaviour(protocol_version: &str) -> request_response::Behaviour<Codec> {
+	let protocol = StreamProtocol::try_from_owned(format!("{protocol_version}/header-sync/1"))
+		.expect("Invalid header sync protocol name");
+	request_response::Behaviour::new(
+		[(protocol, ProtocolSupport::Full)],
+		request_response::Config::default().with_request_timeout(REQUEST_TIMEOUT),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::io::Cursor;
+	use request_response::Codec as _;
+
+	#[tokio::test]
+	async fn codec_roundtrip() {
+		let protocol = StreamProtocol::new("/header-sync");
+		let mut io = Cursor::new(vec![]);
+		let request = HeadersRequest { from: 10, count: 5 };
+		Codec
+			.write_request(&protocol, &mut io, request.clone())
+			.await
+			.unwrap();
+		io.set_position(0);
+		let decoded = Codec.read_request(&protocol, &mut io).await.unwrap();
+		assert_eq!(decoded, request);
+
+		let mut io = Cursor::new(vec![0u8; 3]);
+		assert!(Codec.read_response(&protocol, &mut io).await.is_err());
+	}
+}
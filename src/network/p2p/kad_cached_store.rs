@@ -0,0 +1,185 @@
+//! Record store with the in-memory LRU cache of the recently fetched cells.
+//!
+//! Cells fetched and verified by the local node are not stored in the DHT store, so they cannot be served to other peers.
+//! Keeping them in the bounded cache, which is checked if record is not found in the underlying store,
+//! increases the number of peers which can answer incoming queries for the popular cells, shortly after the block is produced.
+//!
+//! Cached records are not returned from [`RecordStore::records`], so they are neither replicated nor republished.
+
+use libp2p::{
+	identity::PeerId,
+	kad::{
+		store::{RecordStore, Result},
+		ProviderRecord, Record, RecordKey,
+	},
+};
+use lru::LruCache;
+use std::{
+	borrow::Cow,
+	num::NonZeroUsize,
+	ops::{Deref, DerefMut},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+};
+
+pub struct CachedStore<S> {
+	store: S,
+	/// Recently fetched cells, `None` if cache is disabled
+	cache: Option<Mutex<LruCache<RecordKey, Record>>>,
+	/// Number of records returned from the cache, since the last [`CachedStore::take_cache_hits`] call
+	hits: AtomicU64,
+	/// Cache hits are not counted during the local lookups, so only the records served to other peers are counted
+	count_hits: bool,
+}
+
+impl<S> CachedStore<S> {
+	/// Creates store with the cache of the given capacity. Cache is disabled if capacity is 0.
+	pub fn new(store: S, capacity: usize) -> Self {
+		CachedStore {
+			store,
+			cache: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+			hits: AtomicU64::new(0),
+			count_hits: true,
+		}
+	}
+
+	/// Caches the record, evicting the least recently used one if cache is full
+	pub fn cache(&mut self, record: Record) {
+		if let Some(cache) = self.cache.as_mut() {
+			let cache = cache.get_mut().expect("Lock acquired");
+			cache.put(record.key.clone(), record);
+		}
+	}
+
+	/// Enables or disables counting of the cache hits, disabled around the local lookups
+	pub fn count_cache_hits(&mut self, enabled: bool) {
+		self.count_hits = enabled;
+	}
+
+	/// Returns number of records returned from the cache, and resets the counter
+	pub fn take_cache_hits(&self) -> u64 {
+		self.hits.swap(0, Ordering::Relaxed)
+	}
+
+	fn cached(&self, key: &RecordKey) -> Option<Record> {
+		let mut cache = self.cache.as_ref()?.lock().expect("Lock acquired");
+		let record = cache.get(key).cloned()?;
+		if self.count_hits {
+			self.hits.fetch_add(1, Ordering::Relaxed);
+		}
+		Some(record)
+	}
+}
+
+impl<S> Deref for CachedStore<S> {
+	type Target = S;
+
+	fn deref(&self) -> &Self::Target {
+		&self.store
+	}
+}
+
+impl<S> DerefMut for CachedStore<S> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.store
+	}
+}
+
+impl<S: RecordStore> RecordStore for CachedStore<S> {
+	type RecordsIter<'a>
+		= S::RecordsIter<'a>
+	where
+		Self: 'a;
+
+	type ProvidedIter<'a>
+		= S::ProvidedIter<'a>
+	where
+		Self: 'a;
+
+	fn get(&self, key: &RecordKey) -> Option<Cow<'_, Record>> {
+		match self.store.get(key) {
+			Some(record) => Some(record),
+			None => self.cached(key).map(Cow::Owned),
+		}
+	}
+
+	fn put(&mut self, record: Record) -> Result<()> {
+		self.store.put(record)
+	}
+
+	fn remove(&mut self, key: &RecordKey) {
+		// expired records are removed on lookup, so they are removed from the cache as well
+		if let Some(cache) = self.cache.as_mut() {
+			cache.get_mut().expect("Lock acquired").pop(key);
+		}
+		self.store.remove(key)
+	}
+
+	fn records(&self) -> Self::RecordsIter<'_> {
+		self.store.records()
+	}
+
+	fn add_provider(&mut self, record: ProviderRecord) -> Result<()> {
+		self.store.add_provider(record)
+	}
+
+	fn providers(&self, key: &RecordKey) -> Vec<ProviderRecord> {
+		self.store.providers(key)
+	}
+
+	fn provided(&self) -> Self::ProvidedIter<'_> {
+		self.store.provided()
+	}
+
+	fn remove_provider(&mut self, key: &RecordKey, provider: &PeerId) {
+		self.store.remove_provider(key, provider)
+	}
+}
+
+#[cfg(all(test, not(feature = "kademlia-rocksdb")))]
+mod tests {
+	use super::*;
+	use crate::network::p2p::kad_mem_store::MemoryStore;
+
+	fn record(key: &str) -> Record {
+		Record::new(RecordKey::new(&key), vec![1, 2, 3])
+	}
+
+	#[test]
+	fn cache_eviction() {
+		let mut store = CachedStore::new(MemoryStore::new(PeerId::random()), 2);
+		store.cache(record("1:0:0"));
+		store.cache(record("1:0:1"));
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_some());
+		store.cache(record("1:0:2"));
+
+		assert!(store.get(&RecordKey::new(&"1:0:1")).is_none());
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_some());
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_some());
+		assert_eq!(store.take_cache_hits(), 3);
+		assert_eq!(store.take_cache_hits(), 0);
+
+		// local lookups are not counted
+		store.count_cache_hits(false);
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_some());
+		store.count_cache_hits(true);
+		assert_eq!(store.take_cache_hits(), 0);
+		assert_eq!(store.records().count(), 0);
+
+		store.remove(&RecordKey::new(&"1:0:2"));
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_none());
+	}
+
+	#[test]
+	fn disabled_cache() {
+		let mut store = CachedStore::new(MemoryStore::new(PeerId::random()), 0);
+		store.cache(record("1:0:0"));
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_none());
+
+		store.put(record("1:0:0")).unwrap();
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_some());
+		assert_eq!(store.take_cache_hits(), 0);
+	}
+}
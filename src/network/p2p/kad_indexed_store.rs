@@ -0,0 +1,308 @@
+//! Record store with the incrementally tracked size, and the expiry index of the locally published records.
+//!
+//! Number and size of the stored records are updated on each put and remove, so the store size can be reported
+//! without iterating the store. Records published by the local node are indexed by their expiry,
+//! so the records expiring first can be republished without iterating the store, which would block the event loop on large stores.
+//!
+//! # Notes
+//!
+//! Existing records are counted and indexed once, when the store is created.
+//! With the RocksDB store, records removed during the TTL compaction are counted until they are looked up and removed,
+//! and their index entries are dropped once they are found missing.
+
+use libp2p::{
+	identity::PeerId,
+	kad::{
+		store::{RecordStore, Result},
+		ProviderRecord, Record, RecordKey,
+	},
+};
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, HashMap},
+	ops::{Deref, DerefMut},
+	time::Instant,
+};
+
+#[cfg(not(feature = "kademlia-rocksdb"))]
+use super::kad_mem_store::MemoryStore;
+
+fn size(record: &Record) -> u64 {
+	(record.key.as_ref().len() + record.value.len()) as u64
+}
+
+/// Locally published records, ordered by expiry
+#[derive(Default)]
+struct ExpiryIndex {
+	/// Sequence number of the next index entry, so entries with the same expiry are unique
+	sequence: u64,
+	by_expiry: BTreeMap<(Instant, u64), RecordKey>,
+	by_key: HashMap<RecordKey, (Instant, u64)>,
+}
+
+impl ExpiryIndex {
+	fn insert(&mut self, key: RecordKey, expires: Instant) {
+		self.remove(&key);
+		let entry = (expires, self.sequence);
+		self.sequence += 1;
+		self.by_expiry.insert(entry, key.clone());
+		self.by_key.insert(key, entry);
+	}
+
+	fn remove(&mut self, key: &RecordKey) {
+		if let Some(entry) = self.by_key.remove(key) {
+			self.by_expiry.remove(&entry);
+		}
+	}
+}
+
+pub struct IndexedStore<S> {
+	store: S,
+	local_peer_id: PeerId,
+	records: usize,
+	bytes: u64,
+	index: ExpiryIndex,
+}
+
+impl<S: RecordStore> IndexedStore<S> {
+	/// Creates store, counting and indexing the records already stored in the underlying store
+	pub fn new(store: S, local_peer_id: PeerId) -> Self {
+		let mut indexed = IndexedStore {
+			store,
+			local_peer_id,
+			records: 0,
+			bytes: 0,
+			index: ExpiryIndex::default(),
+		};
+		let existing = indexed
+			.store
+			.records()
+			.map(|record| (record.key.clone(), size(&record), indexed.expiry(&record)))
+			.collect::<Vec<_>>();
+		for (key, bytes, expires) in existing {
+			indexed.added(key, bytes, expires);
+		}
+		indexed
+	}
+
+	/// Returns number of the stored records and their size in bytes
+	pub fn size(&self) -> (usize, u64) {
+		(self.records, self.bytes)
+	}
+
+	/// Returns up to `limit` locally published records which expire before the given time, expiring ones first.
+	/// Already expired records are skipped, and dropped from the index.
+	pub fn expiring_local_records(
+		&mut self,
+		now: Instant,
+		before: Instant,
+		limit: usize,
+	) -> Vec<Record> {
+		let mut records = Vec::with_capacity(limit);
+		let mut stale = vec![];
+		for ((expires, _), key) in self.index.by_expiry.iter() {
+			if records.len() >= limit || *expires > before {
+				break;
+			}
+			match self.store.get(key) {
+				Some(record) if *expires > now => records.push(record.into_owned()),
+				_ => stale.push(key.clone()),
+			}
+		}
+		for key in stale {
+			self.index.remove(&key);
+		}
+		records
+	}
+
+	/// Returns expiry of the locally published record, `None` for the records of other publishers or records without expiry
+	fn expiry(&self, record: &Record) -> Option<Instant> {
+		(record.publisher == Some(self.local_peer_id))
+			.then_some(record.expires)
+			.flatten()
+	}
+
+	fn added(&mut self, key: RecordKey, bytes: u64, expires: Option<Instant>) {
+		self.records += 1;
+		self.bytes += bytes;
+		match expires {
+			Some(expires) => self.index.insert(key, expires),
+			None => self.index.remove(&key),
+		}
+	}
+
+	fn removed(&mut self, key: &RecordKey, bytes: u64) {
+		self.records = self.records.saturating_sub(1);
+		self.bytes = self.bytes.saturating_sub(bytes);
+		self.index.remove(key);
+	}
+}
+
+#[cfg(not(feature = "kademlia-rocksdb"))]
+impl IndexedStore<MemoryStore> {
+	/// Retains the records satisfying a predicate, updating size and index of the removed ones
+	pub fn retain<F>(&mut self, mut f: F)
+	where
+		F: FnMut(&RecordKey, &mut Record) -> bool,
+	{
+		let mut removed = vec![];
+		self.store.retain(|key, record| {
+			let retain = f(key, record);
+			if !retain {
+				removed.push((key.clone(), size(record)));
+			}
+			retain
+		});
+		for (key, bytes) in removed {
+			self.removed(&key, bytes);
+		}
+	}
+}
+
+impl<S> Deref for IndexedStore<S> {
+	type Target = S;
+
+	fn deref(&self) -> &Self::Target {
+		&self.store
+	}
+}
+
+impl<S> DerefMut for IndexedStore<S> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.store
+	}
+}
+
+impl<S: RecordStore> RecordStore for IndexedStore<S> {
+	type RecordsIter<'a>
+		= S::RecordsIter<'a>
+	where
+		Self: 'a;
+
+	type ProvidedIter<'a>
+		= S::ProvidedIter<'a>
+	where
+		Self: 'a;
+
+	fn get(&self, key: &RecordKey) -> Option<Cow<'_, Record>> {
+		self.store.get(key)
+	}
+
+	fn put(&mut self, record: Record) -> Result<()> {
+		let replaced = self.store.get(&record.key).map(|record| size(&record));
+		let (key, bytes, expires) = (record.key.clone(), size(&record), self.expiry(&record));
+		self.store.put(record)?;
+		if let Some(replaced) = replaced {
+			self.removed(&key, replaced);
+		}
+		self.added(key, bytes, expires);
+		Ok(())
+	}
+
+	fn remove(&mut self, key: &RecordKey) {
+		if let Some(bytes) = self.store.get(key).map(|record| size(&record)) {
+			self.removed(key, bytes);
+		}
+		self.store.remove(key)
+	}
+
+	fn records(&self) -> Self::RecordsIter<'_> {
+		self.store.records()
+	}
+
+	fn add_provider(&mut self, record: ProviderRecord) -> Result<()> {
+		self.store.add_provider(record)
+	}
+
+	fn providers(&self, key: &RecordKey) -> Vec<ProviderRecord> {
+		self.store.providers(key)
+	}
+
+	fn provided(&self) -> Self::ProvidedIter<'_> {
+		self.store.provided()
+	}
+
+	fn remove_provider(&mut self, key: &RecordKey, provider: &PeerId) {
+		self.store.remove_provider(key, provider)
+	}
+}
+
+#[cfg(all(test, not(feature = "kademlia-rocksdb")))]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn record(key: &str, publisher: PeerId, expires: Instant) -> Record {
+		Record {
+			key: RecordKey::new(&key),
+			value: vec![1, 2, 3],
+			publisher: Some(publisher),
+			expires: Some(expires),
+		}
+	}
+
+	#[test]
+	fn store_size() {
+		let peer_id = PeerId::random();
+		let mut store = IndexedStore::new(MemoryStore::new(peer_id), peer_id);
+		let expires = Instant::now() + Duration::from_secs(60);
+		store.put(record("1:0:0", peer_id, expires)).unwrap();
+		store.put(record("1:0:1", peer_id, expires)).unwrap();
+		assert_eq!(store.size(), (2, 16));
+
+		// replaced records are counted once
+		store
+			.put(record("1:0:1", PeerId::random(), expires))
+			.unwrap();
+		assert_eq!(store.size(), (2, 16));
+
+		store.remove(&RecordKey::new(&"1:0:0"));
+		store.remove(&RecordKey::new(&"1:0:2"));
+		assert_eq!(store.size(), (1, 8));
+
+		store.retain(|_, _| false);
+		assert_eq!(store.size(), (0, 0));
+		assert_eq!(store.records().count(), 0);
+	}
+
+	#[test]
+	fn expiring_local_records() {
+		let peer_id = PeerId::random();
+		let mut store = IndexedStore::new(MemoryStore::new(peer_id), peer_id);
+		let now = Instant::now();
+		let after = |secs| now + Duration::from_secs(secs);
+		store.put(record("1:0:0", peer_id, after(30))).unwrap();
+		store.put(record("1:0:1", peer_id, after(10))).unwrap();
+		store.put(record("1:0:2", peer_id, after(20))).unwrap();
+		store
+			.put(record("1:0:3", PeerId::random(), after(5)))
+			.unwrap();
+		store.put(record("1:0:4", peer_id, after(100))).unwrap();
+
+		let keys = |records: Vec<Record>| {
+			records
+				.into_iter()
+				.map(|record| record.key)
+				.collect::<Vec<_>>()
+		};
+		let expiring = store.expiring_local_records(now, after(50), 2);
+		assert_eq!(
+			keys(expiring),
+			vec![RecordKey::new(&"1:0:1"), RecordKey::new(&"1:0:2")]
+		);
+
+		// republished records are moved to the back of the index
+		store.put(record("1:0:1", peer_id, after(60))).unwrap();
+		let expiring = store.expiring_local_records(now, after(50), 10);
+		assert_eq!(
+			keys(expiring),
+			vec![RecordKey::new(&"1:0:2"), RecordKey::new(&"1:0:0")]
+		);
+
+		// expired and removed records are dropped from the index
+		store.remove(&RecordKey::new(&"1:0:2"));
+		let expiring = store.expiring_local_records(after(40), after(70), 10);
+		assert_eq!(keys(expiring), vec![RecordKey::new(&"1:0:1")]);
+		assert_eq!(store.index.by_key.len(), 2);
+	}
+}
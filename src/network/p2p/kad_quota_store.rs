@@ -0,0 +1,419 @@
+//! Record store with the per-publisher quotas of the stored records.
+//!
+//! Records received with the incoming `PUT_VALUE` requests are accounted to the peer which sent them,
+//! so a single publisher cannot exhaust the store. If the publisher exceeds its quota of records or bytes,
+//! its oldest records are evicted. If the store is full, the oldest record of the publisher which is the most over its quota
+//! (relative to the quota) is evicted, so records of the well-behaved publishers are kept.
+//!
+//! # Notes
+//!
+//! Accounting is kept in memory, so records stored before the restart are not accounted.
+//! Records stored by the local node or by the Kademlia behaviour are not accounted.
+//! Records expired in the RocksDB store are removed during compaction; their accounting is released once they are evicted.
+
+use libp2p::{
+	identity::PeerId,
+	kad::{
+		store::{Error, RecordStore, Result},
+		ProviderRecord, Record, RecordKey,
+	},
+};
+use std::{
+	borrow::Cow,
+	collections::{HashMap, VecDeque},
+	ops::{Deref, DerefMut},
+};
+
+#[cfg(not(feature = "kademlia-rocksdb"))]
+use super::{kad_indexed_store::IndexedStore, kad_mem_store::MemoryStore};
+
+/// Maximum number of records and bytes stored per publisher. Limit is disabled if set to 0.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublisherQuota {
+	pub max_records: usize,
+	pub max_bytes: usize,
+}
+
+impl PublisherQuota {
+	fn is_enabled(&self) -> bool {
+		self.max_records > 0 || self.max_bytes > 0
+	}
+
+	/// Returns usage relative to the quota, greater than 1 if publisher is over quota
+	fn ratio(&self, usage: &Usage) -> f64 {
+		let ratio = |used: usize, max: usize| match max {
+			0 => 0.0,
+			max => used as f64 / max as f64,
+		};
+		ratio(usage.records, self.max_records).max(ratio(usage.bytes, self.max_bytes))
+	}
+
+	fn is_exceeded(&self, usage: &Usage) -> bool {
+		(self.max_records > 0 && usage.records > self.max_records)
+			|| (self.max_bytes > 0 && usage.bytes > self.max_bytes)
+	}
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+	records: usize,
+	bytes: usize,
+	/// Keys of the publisher records with their generation, oldest first
+	/// (may contain stale entries of the removed or replaced records)
+	keys: VecDeque<(RecordKey, u64)>,
+}
+
+struct Owner {
+	publisher: PeerId,
+	bytes: usize,
+	/// Generation of the stored record, so the stale entries of the replaced records are skipped
+	generation: u64,
+}
+
+impl Owner {
+	fn is_current(&self, publisher: &PeerId, generation: u64) -> bool {
+		&self.publisher == publisher && self.generation == generation
+	}
+}
+
+#[derive(Default)]
+struct Quotas {
+	quota: PublisherQuota,
+	owners: HashMap<RecordKey, Owner>,
+	usage: HashMap<PeerId, Usage>,
+	/// Generation of the next tracked record
+	generation: u64,
+}
+
+fn size(record: &Record) -> usize {
+	record.key.as_ref().len() + record.value.len()
+}
+
+impl Quotas {
+	fn track(&mut self, publisher: PeerId, key: RecordKey, bytes: usize) {
+		self.untrack(&key);
+		let generation = self.generation;
+		self.generation += 1;
+		let owner = Owner {
+			publisher,
+			bytes,
+			generation,
+		};
+		self.owners.insert(key.clone(), owner);
+		let usage = self.usage.entry(publisher).or_default();
+		usage.records += 1;
+		usage.bytes += bytes;
+		usage.keys.push_back((key, generation));
+		// drop stale entries, so the queue doesn't grow unbounded
+		if usage.keys.len() > 2 * usage.records {
+			let owners = &self.owners;
+			usage.keys.retain(|(key, generation)| {
+				owners
+					.get(key)
+					.is_some_and(|owner| owner.is_current(&publisher, *generation))
+			});
+		}
+	}
+
+	fn untrack(&mut self, key: &RecordKey) {
+		let Some(owner) = self.owners.remove(key) else {
+			return;
+		};
+		if let Some(usage) = self.usage.get_mut(&owner.publisher) {
+			usage.records -= 1;
+			usage.bytes -= owner.bytes;
+			if usage.records == 0 {
+				self.usage.remove(&owner.publisher);
+			}
+		}
+	}
+
+	fn is_exceeded(&self, publisher: &PeerId) -> bool {
+		self.usage
+			.get(publisher)
+			.is_some_and(|usage| self.quota.is_exceeded(usage))
+	}
+
+	fn most_over_quota(&self) -> Option<PeerId> {
+		self.usage
+			.iter()
+			.max_by(|(_, a), (_, b)| self.quota.ratio(a).total_cmp(&self.quota.ratio(b)))
+			.map(|(publisher, _)| *publisher)
+	}
+
+	/// Returns key of the oldest record of the publisher, releasing its accounting
+	fn evict(&mut self, publisher: &PeerId) -> Option<RecordKey> {
+		loop {
+			let (key, generation) = self.usage.get_mut(publisher)?.keys.pop_front()?;
+			if self
+				.owners
+				.get(&key)
+				.is_some_and(|owner| owner.is_current(publisher, generation))
+			{
+				self.untrack(&key);
+				return Some(key);
+			}
+		}
+	}
+}
+
+pub struct QuotaStore<S> {
+	store: S,
+	quotas: Quotas,
+	/// Number of records evicted due to the quotas, since the last [`QuotaStore::take_evictions`] call
+	evictions: u64,
+}
+
+impl<S: RecordStore> QuotaStore<S> {
+	/// Creates store with the given quota. Quotas are not enforced if both limits are 0.
+	pub fn new(store: S, quota: PublisherQuota) -> Self {
+		QuotaStore {
+			store,
+			quotas: Quotas {
+				quota,
+				..Default::default()
+			},
+			evictions: 0,
+		}
+	}
+
+	/// Returns number of records evicted due to the quotas, and resets the counter
+	pub fn take_evictions(&mut self) -> u64 {
+		std::mem::take(&mut self.evictions)
+	}
+
+	fn remove_evicted(&mut self, key: &RecordKey) {
+		self.store.remove(key);
+		self.evictions += 1;
+	}
+
+	/// Stores the record received from the publisher, evicting records of the over-quota publishers
+	pub fn put_from(&mut self, publisher: PeerId, record: Record) -> Result<()> {
+		let quota = self.quotas.quota;
+		if !quota.is_enabled() {
+			return self.store.put(record);
+		}
+		let bytes = size(&record);
+		if quota.max_bytes > 0 && bytes > quota.max_bytes {
+			return Err(Error::ValueTooLarge);
+		}
+
+		let key = record.key.clone();
+		match self.store.put(record.clone()) {
+			Err(Error::MaxRecords) => {
+				let Some(evicted) = self
+					.quotas
+					.most_over_quota()
+					.and_then(|most_over_quota| self.quotas.evict(&most_over_quota))
+				else {
+					return Err(Error::MaxRecords);
+				};
+				self.remove_evicted(&evicted);
+				self.store.put(record)?;
+			},
+			result => result?,
+		}
+
+		self.quotas.track(publisher, key, bytes);
+		while self.quotas.is_exceeded(&publisher) {
+			let Some(evicted) = self.quotas.evict(&publisher) else {
+				break;
+			};
+			self.remove_evicted(&evicted);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(not(feature = "kademlia-rocksdb"))]
+impl QuotaStore<IndexedStore<MemoryStore>> {
+	/// Retains the records satisfying a predicate, releasing accounting of the removed ones
+	pub fn retain<F>(&mut self, mut f: F)
+	where
+		F: FnMut(&RecordKey, &mut Record) -> bool,
+	{
+		let quotas = &mut self.quotas;
+		self.store.retain(|key, record| {
+			let retain = f(key, record);
+			if !retain {
+				quotas.untrack(key);
+			}
+			retain
+		});
+	}
+}
+
+impl<S> Deref for QuotaStore<S> {
+	type Target = S;
+
+	fn deref(&self) -> &Self::Target {
+		&self.store
+	}
+}
+
+impl<S> DerefMut for QuotaStore<S> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.store
+	}
+}
+
+impl<S: RecordStore> RecordStore for QuotaStore<S> {
+	type RecordsIter<'a>
+		= S::RecordsIter<'a>
+	where
+		Self: 'a;
+
+	type ProvidedIter<'a>
+		= S::ProvidedIter<'a>
+	where
+		Self: 'a;
+
+	fn get(&self, key: &RecordKey) -> Option<Cow<'_, Record>> {
+		self.store.get(key)
+	}
+
+	fn put(&mut self, record: Record) -> Result<()> {
+		// records stored by the local node are not accounted
+		self.quotas.untrack(&record.key);
+		self.store.put(record)
+	}
+
+	fn remove(&mut self, key: &RecordKey) {
+		self.quotas.untrack(key);
+		self.store.remove(key)
+	}
+
+	fn records(&self) -> Self::RecordsIter<'_> {
+		self.store.records()
+	}
+
+	fn add_provider(&mut self, record: ProviderRecord) -> Result<()> {
+		self.store.add_provider(record)
+	}
+
+	fn providers(&self, key: &RecordKey) -> Vec<ProviderRecord> {
+		self.store.providers(key)
+	}
+
+	fn provided(&self) -> Self::ProvidedIter<'_> {
+		self.store.provided()
+	}
+
+	fn remove_provider(&mut self, key: &RecordKey, provider: &PeerId) {
+		self.store.remove_provider(key, provider)
+	}
+}
+
+#[cfg(all(test, not(feature = "kademlia-rocksdb")))]
+mod tests {
+	use super::*;
+	use crate::network::p2p::kad_mem_store::MemoryStoreConfig;
+
+	fn record(key: &str) -> Record {
+		Record::new(RecordKey::new(&key), vec![1, 2, 3])
+	}
+
+	fn quota_store(
+		max_records: usize,
+		quota: PublisherQuota,
+	) -> QuotaStore<IndexedStore<MemoryStore>> {
+		let config = MemoryStoreConfig {
+			max_records,
+			..Default::default()
+		};
+		let peer_id = PeerId::random();
+		let store = IndexedStore::new(MemoryStore::with_config(peer_id, config), peer_id);
+		QuotaStore::new(store, quota)
+	}
+
+	#[test]
+	fn publisher_quota() {
+		let quota = PublisherQuota {
+			max_records: 2,
+			max_bytes: 0,
+		};
+		let mut store = quota_store(100, quota);
+		let (publisher, other) = (PeerId::random(), PeerId::random());
+		store.put_from(publisher, record("1:0:0")).unwrap();
+		store.put_from(publisher, record("1:0:1")).unwrap();
+		store.put_from(other, record("1:0:2")).unwrap();
+		store.put_from(publisher, record("1:0:3")).unwrap();
+
+		// the oldest record of the over-quota publisher is evicted
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_none());
+		assert!(store.get(&RecordKey::new(&"1:0:1")).is_some());
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_some());
+		assert!(store.get(&RecordKey::new(&"1:0:3")).is_some());
+		assert_eq!(store.take_evictions(), 1);
+		assert_eq!(store.take_evictions(), 0);
+
+		// removed records are released from the quota
+		store.remove(&RecordKey::new(&"1:0:1"));
+		store.put_from(publisher, record("1:0:4")).unwrap();
+		assert!(store.get(&RecordKey::new(&"1:0:3")).is_some());
+		assert_eq!(store.take_evictions(), 0);
+
+		let quota = PublisherQuota {
+			max_records: 0,
+			max_bytes: 10,
+		};
+		let mut store = quota_store(100, quota);
+		let large = Record::new(RecordKey::new(&"1:0:0"), vec![0; 16]);
+		assert!(matches!(
+			store.put_from(publisher, large),
+			Err(Error::ValueTooLarge)
+		));
+	}
+
+	#[test]
+	fn refreshed_record_eviction() {
+		let quota = PublisherQuota {
+			max_records: 2,
+			max_bytes: 0,
+		};
+		let mut store = quota_store(100, quota);
+		let publisher = PeerId::random();
+		store.put_from(publisher, record("1:0:0")).unwrap();
+		store.put_from(publisher, record("1:0:1")).unwrap();
+
+		// refreshed record becomes the newest one of the publisher
+		store.put_from(publisher, record("1:0:0")).unwrap();
+		store.put_from(publisher, record("1:0:2")).unwrap();
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_some());
+		assert!(store.get(&RecordKey::new(&"1:0:1")).is_none());
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_some());
+		assert_eq!(store.take_evictions(), 1);
+	}
+
+	#[test]
+	fn full_store_eviction() {
+		let quota = PublisherQuota {
+			max_records: 2,
+			max_bytes: 0,
+		};
+		let mut store = quota_store(3, quota);
+		let (publisher, other) = (PeerId::random(), PeerId::random());
+		store.put_from(publisher, record("1:0:0")).unwrap();
+		store.put_from(publisher, record("1:0:1")).unwrap();
+		store.put_from(other, record("1:0:2")).unwrap();
+
+		// publisher with the largest share of its quota is evicted, when store is full
+		store.put_from(other, record("1:0:3")).unwrap();
+		assert!(store.get(&RecordKey::new(&"1:0:0")).is_none());
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_some());
+		assert_eq!(store.records().count(), 3);
+
+		store.retain(|key, _| key != &RecordKey::new(&"1:0:3"));
+		store.put_from(other, record("1:0:4")).unwrap();
+		assert!(store.get(&RecordKey::new(&"1:0:2")).is_some());
+		assert_eq!(store.take_evictions(), 1);
+
+		// without quotas, store rejects the records when full
+		let mut store = quota_store(1, PublisherQuota::default());
+		store.put_from(publisher, record("1:0:0")).unwrap();
+		assert!(matches!(
+			store.put_from(publisher, record("1:0:1")),
+			Err(Error::MaxRecords)
+		));
+	}
+}
@@ -0,0 +1,130 @@
+//! Statistics of the DHT cells served by each peer.
+//!
+//! Each cell fetched from the DHT is attributed to the peer which returned the record, together with the query latency.
+//! Cells which fail proof verification are counted as invalid ones, so the peer score is the fraction of the valid cells served by the peer.
+//!
+//! Only the [`MAX_TRACKED_PEERS`] most recently serving peers are tracked.
+
+use libp2p::PeerId;
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use crate::utils::unix_timestamp_millis;
+
+/// Maximum number of peers with the collected statistics
+pub const MAX_TRACKED_PEERS: usize = 1024;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PeerStat {
+	/// Number of cells returned by the peer
+	pub cells_served: u64,
+	/// Number of returned cells which failed proof verification
+	pub invalid_cells: u64,
+	/// Sum of the DHT query durations of the returned cells
+	pub total_latency: Duration,
+	/// Last time the peer returned a cell (milliseconds since UNIX epoch)
+	pub last_served: u64,
+}
+
+impl PeerStat {
+	/// Returns average DHT query duration of the returned cells
+	pub fn average_latency(&self) -> Duration {
+		match u32::try_from(self.cells_served) {
+			Ok(0) => Duration::ZERO,
+			Ok(served) => self.total_latency / served,
+			Err(_) => {
+				Duration::from_secs_f64(self.total_latency.as_secs_f64() / self.cells_served as f64)
+			},
+		}
+	}
+
+	/// Returns fraction of the valid cells in the returned cells, 1.0 if no cells are returned
+	pub fn score(&self) -> f64 {
+		if self.cells_served == 0 {
+			return 1.0;
+		}
+		self.cells_served.saturating_sub(self.invalid_cells) as f64 / self.cells_served as f64
+	}
+}
+
+/// Shared statistics of the peers which served DHT cells
+#[derive(Clone, Default, Debug)]
+pub struct PeerStats(Arc<Mutex<HashMap<PeerId, PeerStat>>>);
+
+impl PeerStats {
+	/// Records the cell returned by the peer, evicting the least recently serving peer if limit is reached
+	pub fn record_served(&self, peer_id: PeerId, latency: Duration) {
+		let mut stats = self.0.lock().expect("Lock acquired");
+		if !stats.contains_key(&peer_id) && stats.len() >= MAX_TRACKED_PEERS {
+			let evicted = stats
+				.iter()
+				.min_by_key(|(_, stat)| stat.last_served)
+				.map(|(&peer_id, _)| peer_id);
+			if let Some(evicted) = evicted {
+				stats.remove(&evicted);
+			}
+		}
+		let stat = stats.entry(peer_id).or_default();
+		stat.cells_served += 1;
+		stat.total_latency += latency;
+		stat.last_served = unix_timestamp_millis();
+	}
+
+	/// Records the returned cell which failed proof verification
+	pub fn record_invalid(&self, peer_id: PeerId) {
+		if let Some(stat) = self.0.lock().expect("Lock acquired").get_mut(&peer_id) {
+			stat.invalid_cells += 1;
+		}
+	}
+
+	/// Returns statistics of the tracked peers, ordered by the number of served cells
+	pub fn get(&self) -> Vec<(PeerId, PeerStat)> {
+		let stats = self.0.lock().expect("Lock acquired");
+		let mut stats = stats
+			.iter()
+			.map(|(&peer_id, stat)| (peer_id, stat.clone()))
+			.collect::<Vec<_>>();
+		stats.sort_by(|(_, a), (_, b)| b.cells_served.cmp(&a.cells_served));
+		stats
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn peer_stats() {
+		let stats = PeerStats::default();
+		let (first, second) = (PeerId::random(), PeerId::random());
+		stats.record_served(first, Duration::from_millis(100));
+		stats.record_served(first, Duration::from_millis(300));
+		stats.record_served(second, Duration::from_millis(50));
+		stats.record_invalid(first);
+		// invalid cells of the untracked peers are ignored
+		stats.record_invalid(PeerId::random());
+
+		let stats = stats.get();
+		assert_eq!(stats.len(), 2);
+		let (peer_id, stat) = &stats[0];
+		assert_eq!(*peer_id, first);
+		assert_eq!(stat.cells_served, 2);
+		assert_eq!(stat.invalid_cells, 1);
+		assert_eq!(stat.average_latency(), Duration::from_millis(200));
+		assert_eq!(stat.score(), 0.5);
+		assert_eq!(stats[1].1.score(), 1.0);
+		assert_eq!(PeerStat::default().average_latency(), Duration::ZERO);
+	}
+
+	#[test]
+	fn tracked_peers_limit() {
+		let stats = PeerStats::default();
+		for _ in 0..MAX_TRACKED_PEERS + 1 {
+			stats.record_served(PeerId::random(), Duration::ZERO);
+		}
+		assert_eq!(stats.get().len(), MAX_TRACKED_PEERS);
+	}
+}
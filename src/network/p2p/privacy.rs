@@ -0,0 +1,75 @@
+//! Obfuscation of the sampled cells, so the DHT peers cannot infer which cells are sampled by the light client.
+//!
+//! Sampled cells are queried together with the decoy cells at random positions of the same block,
+//! in random order and with random delays before each query. Decoy cells are fetched the same way as the sampled ones,
+//! but they are neither verified nor returned to the caller.
+//!
+//! # Notes
+//!
+//! Decoy queries increase the DHT load and the fetch duration proportionally to the configured decoy ratio.
+
+use kate_recovery::matrix::{Dimensions, Position};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+use std::{collections::HashSet, time::Duration};
+
+use crate::types::SamplingPrivacyConfig;
+
+/// Returns sampled positions mixed with the decoy positions, in random order, and set of the decoy positions
+pub fn with_decoys(
+	cfg: &SamplingPrivacyConfig,
+	dimensions: Dimensions,
+	positions: &[Position],
+) -> (Vec<Position>, HashSet<Position>) {
+	let sampled = positions.iter().copied().collect::<HashSet<_>>();
+	let available = (dimensions.extended_size() as usize).saturating_sub(sampled.len());
+	let count = ((positions.len() as f64 * cfg.decoy_ratio).ceil() as usize).min(available);
+
+	let mut rng = thread_rng();
+	let mut decoys = HashSet::with_capacity(count);
+	while decoys.len() < count {
+		let position = Position {
+			row: rng.gen_range(0..dimensions.extended_rows()),
+			col: rng.gen_range(0..dimensions.cols().into()),
+		};
+		if !sampled.contains(&position) {
+			decoys.insert(position);
+		}
+	}
+
+	let mut mixed = positions.iter().chain(&decoys).copied().collect::<Vec<_>>();
+	mixed.shuffle(&mut rng);
+	(mixed, decoys)
+}
+
+/// Returns random delay before the DHT query, up to the configured maximum
+pub fn query_delay(cfg: &SamplingPrivacyConfig) -> Duration {
+	if cfg.max_query_delay == 0 {
+		return Duration::ZERO;
+	}
+	Duration::from_millis(thread_rng().gen_range(0..=cfg.max_query_delay))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decoys() {
+		let cfg = SamplingPrivacyConfig {
+			decoy_ratio: 2.0,
+			max_query_delay: 100,
+		};
+		let dimensions = Dimensions::new(4, 4).unwrap();
+		let positions = [Position { row: 0, col: 0 }, Position { row: 1, col: 1 }];
+		let (mixed, decoys) = with_decoys(&cfg, dimensions, &positions);
+		assert_eq!((mixed.len(), decoys.len()), (6, 4));
+		assert!(positions.iter().all(|position| mixed.contains(position)));
+		assert!(positions.iter().all(|position| !decoys.contains(position)));
+		assert!(query_delay(&cfg) <= Duration::from_millis(100));
+
+		// decoys are limited by the matrix size
+		let dimensions = Dimensions::new(1, 2).unwrap();
+		let (mixed, decoys) = with_decoys(&cfg, dimensions, &positions);
+		assert_eq!((mixed.len(), decoys.len()), (4, 2));
+	}
+}
@@ -0,0 +1,144 @@
+//! Transport which dials peers through the Tor SOCKS5 proxy.
+//!
+//! Peers are dialed on their `/ip4`, `/ip6`, `/dns*` or `/onion3` TCP addresses, and host names are resolved by Tor,
+//! so the peers and DNS resolvers don't see the operator's IP address.
+//!
+//! # Notes
+//!
+//! Transport doesn't listen, inbound P2P connections are not accepted with Tor enabled.
+
+use futures::{future::BoxFuture, FutureExt};
+use libp2p::{
+	core::transport::{ListenerId, TransportError, TransportEvent},
+	multiaddr::Protocol,
+	Multiaddr, Transport,
+};
+use std::{
+	io,
+	pin::Pin,
+	task::{Context, Poll},
+};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::network::proxy::Proxy;
+
+#[derive(Clone, Debug)]
+pub struct TorTransport {
+	proxy: Proxy,
+}
+
+impl TorTransport {
+	pub fn new(proxy: Proxy) -> Self {
+		TorTransport { proxy }
+	}
+}
+
+/// Returns host and port to dial through the proxy, `None` if the address is not supported
+fn dial_target(addr: &Multiaddr) -> Option<(String, u16)> {
+	let mut protocols = addr.iter();
+	let target = match protocols.next()? {
+		Protocol::Onion3(onion) => {
+			let encoded = Protocol::Onion3(onion.clone()).to_string();
+			let (hash, _) = encoded.strip_prefix("/onion3/")?.split_once(':')?;
+			(format!("{hash}.onion"), onion.port())
+		},
+		host => {
+			let host = match host {
+				Protocol::Ip4(ip) => ip.to_string(),
+				Protocol::Ip6(ip) => ip.to_string(),
+				Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => {
+					host.to_string()
+				},
+				_ => return None,
+			};
+			let Protocol::Tcp(port) = protocols.next()? else {
+				return None;
+			};
+			(host, port)
+		},
+	};
+	// only the peer ID can follow the TCP address
+	protocols
+		.all(|protocol| matches!(protocol, Protocol::P2p(_)))
+		.then_some(target)
+}
+
+impl Transport for TorTransport {
+	type Output = Compat<TcpStream>;
+	type Error = io::Error;
+	type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+	type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn listen_on(
+		&mut self,
+		_id: ListenerId,
+		addr: Multiaddr,
+	) -> Result<(), TransportError<Self::Error>> {
+		Err(TransportError::MultiaddrNotSupported(addr))
+	}
+
+	fn remove_listener(&mut self, _id: ListenerId) -> bool {
+		false
+	}
+
+	fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+		let Some((host, port)) = dial_target(&addr) else {
+			return Err(TransportError::MultiaddrNotSupported(addr));
+		};
+		let proxy = self.proxy.clone();
+		Ok(async move {
+			let stream = proxy
+				.connect(&host, port)
+				.await
+				.map_err(|error| io::Error::other(format!("{error:#}")))?;
+			Ok(stream.compat())
+		}
+		.boxed())
+	}
+
+	fn dial_as_listener(
+		&mut self,
+		addr: Multiaddr,
+	) -> Result<Self::Dial, TransportError<Self::Error>> {
+		self.dial(addr)
+	}
+
+	fn poll(
+		self: Pin<&mut Self>,
+		_cx: &mut Context<'_>,
+	) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+		Poll::Pending
+	}
+
+	fn address_translation(&self, _listen: &Multiaddr, _observed: &Multiaddr) -> Option<Multiaddr> {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dial_targets() {
+		let target = |addr: &str| dial_target(&addr.parse().unwrap());
+		assert_eq!(
+			target("/ip4/127.0.0.1/tcp/37000"),
+			Some(("127.0.0.1".to_string(), 37000))
+		);
+		assert_eq!(
+			target("/dns/bootnode.avail.so/tcp/37000/p2p/12D3KooWStAKPADXqJ7cngPYXd2mSANpdgh1xQ34aouufHA2xShz"),
+			Some(("bootnode.avail.so".to_string(), 37000))
+		);
+		assert_eq!(
+			target("/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:1234"),
+			Some((
+				"vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd.onion".to_string(),
+				1234
+			))
+		);
+		assert_eq!(target("/ip4/127.0.0.1/udp/37000/quic-v1"), None);
+		assert_eq!(target("/ip4/127.0.0.1/tcp/37000/ws"), None);
+	}
+}
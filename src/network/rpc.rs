@@ -1,12 +1,10 @@
 use avail_subxt::{primitives::Header, utils::H256};
 use codec::Decode;
 use color_eyre::{eyre::eyre, Result};
-use kate_recovery::matrix::{Dimensions, Position};
-use rand::{seq::SliceRandom, thread_rng, Rng};
-use serde::{de, Deserialize};
+use rand::{seq::SliceRandom, thread_rng};
+use serde::{de, Deserialize, Serialize};
 use sp_core::bytes::from_hex;
 use std::{
-	collections::HashSet,
 	fmt::Display,
 	sync::{Arc, Mutex},
 };
@@ -14,12 +12,11 @@ use tokio::{
 	sync::broadcast,
 	time::{self, timeout},
 };
-use tracing::{debug, info};
 
 use crate::{
 	data::Database,
-	network::rpc,
-	shutdown::Controller,
+	network::{proxy::Proxy, rpc},
+	shutdown::{Controller, ShutdownReason},
 	types::{GrandpaJustification, RetryConfig, State},
 };
 
@@ -32,6 +29,7 @@ const PROOF_SIZE: usize = 48;
 pub const CELL_WITH_PROOF_SIZE: usize = CELL_SIZE + PROOF_SIZE;
 pub use subscriptions::Event;
 
+pub use crate::verification::{cell_count_for_confidence, generate_random_cells, CELL_COUNT_99_99};
 pub use client::Client;
 
 pub enum Subscription {
@@ -124,6 +122,15 @@ impl Display for Node {
 	}
 }
 
+/// Runtime spec version change, detected on the runtime upgrade or on the RPC failover
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RuntimeUpgraded {
+	/// Latest block received at the time of the change
+	pub block_number: u32,
+	pub previous_spec_version: u32,
+	pub spec_version: u32,
+}
+
 #[derive(Clone)]
 pub struct Nodes {
 	list: Vec<Node>,
@@ -195,13 +202,17 @@ pub async fn init<T: Database>(
 	nodes: &[String],
 	genesis_hash: &str,
 	retry_config: RetryConfig,
-	shutdown: Controller<String>,
+	proxy: Option<Proxy>,
+	user_agent: Option<String>,
+	shutdown: Controller<ShutdownReason>,
 ) -> Result<(Client, broadcast::Sender<Event>, SubscriptionLoop<T>)> {
 	let rpc_client = Client::new(
 		state.clone(),
 		Nodes::new(nodes),
 		genesis_hash,
 		retry_config,
+		proxy,
+		user_agent,
 		shutdown,
 	)
 	.await?;
@@ -213,60 +224,6 @@ pub async fn init<T: Database>(
 	Ok((rpc_client, event_sender, subscriptions))
 }
 
-/// Generates random cell positions for sampling
-pub fn generate_random_cells(dimensions: Dimensions, cell_count: u32) -> Vec<Position> {
-	let max_cells = dimensions.extended_size();
-	let count = if max_cells < cell_count {
-		debug!("Max cells count {max_cells} is lesser than cell_count {cell_count}");
-		max_cells
-	} else {
-		cell_count
-	};
-	let mut rng = thread_rng();
-	let mut indices = HashSet::new();
-	while (indices.len() as u16) < count as u16 {
-		let col = rng.gen_range(0..dimensions.cols().into());
-		let row = rng.gen_range(0..dimensions.extended_rows());
-		indices.insert(Position { row, col });
-	}
-
-	indices.into_iter().collect::<Vec<_>>()
-}
-
-/* @note: fn to take the number of cells needs to get equal to or greater than
-the percentage of confidence mentioned in config file */
-
-pub const CELL_COUNT_99_99: u32 = 14;
-
-/// Calculates number of cells required to achieve given confidence
-pub fn cell_count_for_confidence(confidence: f64) -> u32 {
-	let mut cell_count: u32;
-	if !(50.0..=100f64).contains(&confidence) {
-		//in this default of 8 cells will be taken
-		info!(
-			"confidence is {} invalid so taking default confidence of 99",
-			confidence
-		);
-		cell_count = (-((1f64 - (99.3f64 / 100f64)).log2())).ceil() as u32;
-	} else {
-		cell_count = (-((1f64 - (confidence / 100f64)).log2())).ceil() as u32;
-	}
-	if cell_count <= 1 {
-		info!(
-			"confidence of {} is too low so taking confidence of 50.0",
-			confidence
-		);
-		cell_count = 1;
-	} else if cell_count > CELL_COUNT_99_99 {
-		info!(
-			"confidence of {} is invalid so taking confidence of 99.99",
-			confidence
-		);
-		cell_count = CELL_COUNT_99_99;
-	}
-	cell_count
-}
-
 pub async fn wait_for_finalized_header(
 	mut rpc_events_receiver: broadcast::Receiver<Event>,
 	timeout_seconds: u64,
@@ -20,6 +20,7 @@ use std::sync::{Arc, Mutex};
 use subxt::{
 	backend::{
 		legacy::rpc_methods::{BlockNumber, StorageKey},
+		rpc::RpcClient,
 		RuntimeVersion,
 	},
 	rpc_params,
@@ -32,11 +33,14 @@ use tokio_retry::Retry;
 use tokio_stream::StreamExt;
 use tracing::{info, warn};
 
-use super::{Node, Nodes, Subscription, WrappedProof};
+use super::{Node, Nodes, RuntimeUpgraded, Subscription, WrappedProof};
 use crate::{
 	api::v2::types::Base64,
 	consts::ExpectedNodeVariant,
-	shutdown::Controller,
+	event_bus::EventBus,
+	network::proxy::{self, Proxy},
+	rpc_health,
+	shutdown::{Controller, ShutdownReason},
 	types::{RetryConfig, State, DEV_FLAG_GENHASH},
 };
 
@@ -44,10 +48,14 @@ use crate::{
 pub struct Client {
 	subxt_client: Arc<RwLock<Arc<AvailClient>>>,
 	state: Arc<Mutex<State>>,
-	nodes: Nodes,
+	nodes: Arc<Mutex<Nodes>>,
 	retry_config: RetryConfig,
 	expected_genesis_hash: String,
-	shutdown: Controller<String>,
+	runtime_upgrades: EventBus<RuntimeUpgraded>,
+	proxy: Option<Proxy>,
+	/// User agent of the RPC connections, default one is used if not set
+	user_agent: Option<String>,
+	shutdown: Controller<ShutdownReason>,
 }
 
 impl Client {
@@ -56,7 +64,9 @@ impl Client {
 		nodes: Nodes,
 		expected_genesis_hash: &str,
 		retry_config: RetryConfig,
-		shutdown: Controller<String>,
+		proxy: Option<Proxy>,
+		user_agent: Option<String>,
+		shutdown: Controller<ShutdownReason>,
 	) -> Result<Self> {
 		// try and connect appropriate Node from the provided list
 		// will do retries with the provided Retry Config
@@ -66,6 +76,8 @@ impl Client {
 					nodes.shuffle(Default::default()),
 					ExpectedNodeVariant::default(),
 					expected_genesis_hash,
+					proxy.as_ref(),
+					user_agent.as_deref(),
 					|_| futures::future::ok(()),
 				)
 				.await
@@ -86,21 +98,85 @@ impl Client {
 		Ok(Self {
 			subxt_client: Arc::new(RwLock::new(client)),
 			state,
-			nodes,
+			nodes: Arc::new(Mutex::new(nodes)),
 			retry_config,
 			expected_genesis_hash: expected_genesis_hash.to_string(),
+			runtime_upgrades: EventBus::new(),
+			proxy,
+			user_agent,
 			shutdown,
 		})
 	}
 
+	/// Returns the bus on which runtime spec version changes are published
+	pub fn runtime_upgrades(&self) -> &EventBus<RuntimeUpgraded> {
+		&self.runtime_upgrades
+	}
+
+	/// Updates the connected node, publishing the runtime upgrade if spec version is changed
+	fn set_connected_node(&self, node: Node) {
+		let mut state = self.state.lock().unwrap();
+		let previous_spec_version = state.connected_node.spec_version;
+		let spec_version = node.spec_version;
+		state.connected_node = node;
+		let block_number = state.latest;
+		drop(state);
+
+		if previous_spec_version == spec_version {
+			return;
+		}
+		info!(
+			block_number,
+			previous_spec_version, spec_version, "Runtime spec version changed"
+		);
+		self.runtime_upgrades.send(RuntimeUpgraded {
+			block_number,
+			previous_spec_version,
+			spec_version,
+		});
+	}
+
+	/// Reconnects to the currently connected node, refreshing the cached runtime version and metadata.
+	/// Used after the runtime upgrade, so the extrinsics and storage are decoded with the upgraded metadata.
+	pub async fn refresh_runtime(&self) -> Result<()> {
+		let host = self.state.lock().unwrap().connected_node.host.clone();
+		let (client, node) = Self::create_subxt_client(
+			&host,
+			ExpectedNodeVariant::default(),
+			&self.expected_genesis_hash,
+			self.proxy.as_ref(),
+			self.user_agent.as_deref(),
+		)
+		.await
+		.wrap_err(format!("Failed to refresh runtime from {host}"))?;
+		*self.subxt_client.write().await = Arc::new(client);
+		self.set_connected_node(node);
+		Ok(())
+	}
+
+	/// Replaces the list of nodes used for reconnecting, currently connected node is kept
+	pub fn set_nodes(&self, nodes: &[String]) {
+		*self.nodes.lock().unwrap() = Nodes::new(nodes);
+	}
+
 	async fn create_subxt_client(
 		host: &str,
 		expected_node: ExpectedNodeVariant,
 		expected_genesis_hash: &str,
+		proxy: Option<&Proxy>,
+		user_agent: Option<&str>,
 	) -> Result<(AvailClient, Node)> {
-		let client = AvailClient::new_insecure(host)
-			.await
-			.map_err(|e| eyre!(e))?;
+		let client = match (proxy, user_agent) {
+			(None, None) => AvailClient::new_insecure(host)
+				.await
+				.map_err(|e| eyre!(e))?,
+			(proxy, user_agent) => {
+				let ws_client = proxy::ws_client(proxy, host, user_agent).await?;
+				AvailClient::from_rpc_client(RpcClient::new(ws_client))
+					.await
+					.map_err(|e| eyre!(e))?
+			},
+		};
 
 		// check genesis hash
 		let genesis_hash = client.genesis_hash();
@@ -145,10 +221,42 @@ impl Client {
 		Ok((client, variant))
 	}
 
+	/// Connects to the node, checking its genesis hash and system version.
+	/// Returns the node and the timestamp of its best block, in milliseconds.
+	pub async fn probe(
+		host: &str,
+		expected_genesis_hash: &str,
+		proxy: Option<&Proxy>,
+		user_agent: Option<&str>,
+	) -> Result<(Node, u64)> {
+		let (client, node) = Self::create_subxt_client(
+			host,
+			ExpectedNodeVariant::default(),
+			expected_genesis_hash,
+			proxy,
+			user_agent,
+		)
+		.await?;
+		let best_hash = client
+			.legacy_rpc()
+			.chain_get_block_hash(None)
+			.await?
+			.ok_or_else(|| eyre!("Best block not found"))?;
+		let timestamp = client
+			.storage()
+			.at(best_hash)
+			.fetch(&api::storage().timestamp().now())
+			.await?
+			.ok_or_else(|| eyre!("Best block timestamp not found"))?;
+		Ok((node, timestamp))
+	}
+
 	async fn try_connect_and_execute<T, F, Fut>(
 		nodes: Vec<Node>,
 		expected_node: ExpectedNodeVariant,
 		expected_genesis_hash: &str,
+		proxy: Option<&Proxy>,
+		user_agent: Option<&str>,
 		mut f: F,
 	) -> Result<(Arc<AvailClient>, Node, T)>
 	where
@@ -158,13 +266,18 @@ impl Client {
 		// go through the provided list of Nodes to try and find and appropriate one,
 		// after a successful connection, try to execute passed function call
 		for Node { host, .. } in nodes.iter() {
-			let result =
-				Self::create_subxt_client(host, expected_node.clone(), expected_genesis_hash)
-					.and_then(move |(client, node)| {
-						let client = Arc::new(client);
-						f(client.clone()).map_ok(move |res| (client, node, res))
-					})
-					.await;
+			let result = Self::create_subxt_client(
+				host,
+				expected_node.clone(),
+				expected_genesis_hash,
+				proxy,
+				user_agent,
+			)
+			.and_then(move |(client, node)| {
+				let client = Arc::new(client);
+				f(client.clone()).map_ok(move |res| (client, node, res))
+			})
+			.await;
 
 			match result {
 				Err(error) => warn!(host, %error, "Skipping connection with this node"),
@@ -207,7 +320,7 @@ impl Client {
 			connected_node.host
 		);
 		// shuffle nodes, if possible
-		let nodes = self.nodes.shuffle(connected_node.host);
+		let nodes = self.nodes.lock().unwrap().shuffle(connected_node.host);
 		// go through available Nodes, try to connect, Retry connecting if needed
 		let (client, node, result) = match self
 			.shutdown
@@ -217,6 +330,8 @@ impl Client {
 					nodes,
 					ExpectedNodeVariant::default(),
 					&self.expected_genesis_hash,
+					self.proxy.as_ref(),
+					self.user_agent.as_deref(),
 					move |client| f(client).map_err(Report::from),
 				)
 				.await
@@ -233,7 +348,7 @@ impl Client {
 
 		// retries gave results, update currently connected Node and created Client
 		*self.subxt_client.write().await = client;
-		self.state.lock().unwrap().connected_node = node;
+		self.set_connected_node(node);
 
 		Ok(result)
 	}
@@ -278,6 +393,7 @@ impl Client {
 					// no more subscriptions left on stream, we have to try and create a new stream
 					let Some(result) = stream.next().await else {
 						warn!("No more items on Subscriptions Stream. Trying to create a new one.");
+						rpc_health::restarted(&self.state);
 						continue 'outer
 					};
 					match result {
@@ -285,6 +401,7 @@ impl Client {
 						// if Error was received, we need to switch to another RPC Client
 						Err(err)=> {
 							warn!(%err, "Received Error on stream. Trying to create a new one.");
+							rpc_health::restarted(&self.state);
 							continue 'outer
 						}
 					}
@@ -293,6 +410,11 @@ impl Client {
 		}
 	}
 
+	/// Returns host of the connected node
+	pub fn connected_host(&self) -> String {
+		self.state.lock().unwrap().connected_node.host.clone()
+	}
+
 	pub async fn current_client(&self) -> Arc<AvailClient> {
 		self.subxt_client.read().await.clone()
 	}
@@ -312,6 +434,13 @@ impl Client {
 		Ok(hash)
 	}
 
+	/// Subscribes to the best block headers of the current RPC node
+	pub async fn best_headers_stream(&self) -> Result<impl Stream<Item = Result<Header>>> {
+		let client = self.current_client().await;
+		let headers = client.backend().stream_best_block_headers().await?;
+		Ok(headers.map(|result| result.map(|(header, _)| header).map_err(Into::into)))
+	}
+
 	pub async fn get_header_by_hash(&self, block_hash: H256) -> Result<Header> {
 		self.with_retries(|client| async move {
 			client
@@ -332,6 +461,20 @@ impl Client {
 		))
 	}
 
+	/// Returns the block timestamp, in milliseconds
+	pub async fn get_timestamp_by_hash(&self, block_hash: H256) -> Result<u64> {
+		self.with_retries(|client| async move {
+			client
+				.storage()
+				.at(block_hash)
+				.fetch(&api::storage().timestamp().now())
+				.await
+				.map_err(Into::into)
+		})
+		.await?
+		.ok_or_else(|| eyre!("Block timestamp with hash {block_hash:?} not found"))
+	}
+
 	pub async fn get_validator_set_by_hash(&self, block_hash: H256) -> Result<Vec<Public>> {
 		let res = self
 			.with_retries(|client| async move {
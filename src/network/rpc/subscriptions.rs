@@ -11,15 +11,16 @@ use std::{
 };
 use tokio::sync::broadcast::Sender;
 use tokio_stream::StreamExt;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 use super::{Client, Subscription};
 use crate::{
 	data::Database,
 	data::{FinalitySyncCheckpoint, Key},
 	finality::{check_finality, ValidatorSet},
+	rpc_health,
 	types::{GrandpaJustification, OptionBlockRange, State},
-	utils::filter_auth_set_changes,
+	utils::{filter_auth_set_changes, has_runtime_upgrade, unix_timestamp_millis},
 };
 
 #[derive(Clone, Debug)]
@@ -71,6 +72,12 @@ impl<T: Database> SubscriptionLoop<T> {
 			.get_header_by_hash(last_finalized_block_hash)
 			.await?;
 
+		let current_valset = ValidatorSet {
+			set_id,
+			validator_set,
+		};
+		state.lock().unwrap().validator_set = Some(current_valset.clone());
+
 		Ok(Self {
 			rpc_client,
 			event_sender,
@@ -79,17 +86,15 @@ impl<T: Database> SubscriptionLoop<T> {
 			block_data: BlockData {
 				justifications: Default::default(),
 				unverified_headers: Default::default(),
-				current_valset: ValidatorSet {
-					set_id,
-					validator_set,
-				},
+				current_valset,
 				next_valset: None,
 				last_finalized_block_header: Some(last_finalized_block_header),
 			},
 		})
 	}
 
-	pub async fn run(mut self) -> Result<()> {
+	pub async fn run(&mut self) -> Result<()> {
+		rpc_health::started(&self.state);
 		// create subscriptions stream
 		let subscriptions = self.rpc_client.clone().subscription_stream().await;
 		futures::pin_mut!(subscriptions);
@@ -110,8 +115,28 @@ impl<T: Database> SubscriptionLoop<T> {
 		match subscription {
 			Subscription::Header(header) => {
 				let received_at = Instant::now();
-				self.state.lock().unwrap().latest = header.clone().number;
+				let missing = {
+					let mut state = self.state.lock().unwrap();
+					state.latest = header.number;
+					state.rpc_subscription.as_mut().map_or(0, |health| {
+						health.header_received(header.number, unix_timestamp_millis())
+					})
+				};
 				info!("Header no.: {}", header.number);
+				if missing > 0 {
+					warn!(
+						block_number = header.number,
+						missing, "Headers are missing from the RPC subscription"
+					);
+				}
+
+				// upgraded runtime is in effect after the finalized upgrade block, so the cached metadata is refreshed
+				if has_runtime_upgrade(&header) {
+					info!(block_number = header.number, "Runtime upgrade detected");
+					if let Err(error) = self.rpc_client.refresh_runtime().await {
+						warn!("Cannot refresh runtime after upgrade: {error:#}");
+					}
+				}
 
 				// if new validator set becomes active, replace the current one
 				if self.block_data.next_valset.is_some() {
@@ -179,6 +204,17 @@ impl<T: Database> SubscriptionLoop<T> {
 
 				is_final.expect("Finality check failed");
 
+				// justifications are served to the peers syncing headers over P2P
+				if let Err(error) = self
+					.db
+					.put(Key::Justification(header.number), justification)
+				{
+					warn!(
+						block_number = header.number,
+						"Failed to store justification: {error:#}"
+					);
+				}
+
 				// To avoid locking the global state all the time, after finality is synced, it will not be necessary to read the state
 				if !finality_synced {
 					finality_synced = self.state.lock().unwrap().finality_synced;
@@ -239,11 +275,10 @@ impl<T: Database> SubscriptionLoop<T> {
 				self.block_data.last_finalized_block_header = Some(header.clone());
 
 				// finally, send the Verified Block Header
-				self.state
-					.lock()
-					.unwrap()
-					.header_verified
-					.set(header.number);
+				let mut state = self.state.lock().unwrap();
+				state.header_verified.set(header.number);
+				state.validator_set = Some(valset);
+				drop(state);
 				self.event_sender
 					.send(Event::HeaderUpdate {
 						header,
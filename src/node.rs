@@ -0,0 +1,1094 @@
+//! Light client node, embeddable into other Rust programs.
+//!
+//! Node is constructed with the [`NodeBuilder`], from the injected configuration and the shutdown controller.
+//! Once started, all subsystems are running in the background, until the shutdown is triggered.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use avail_light::{node::Node, shutdown::{Controller, ShutdownReason}, types::{IdentityConfig, RuntimeConfig}};
+//! # async fn example() -> color_eyre::Result<()> {
+//! let cfg = RuntimeConfig::default();
+//! let identity_cfg = IdentityConfig::load_or_init("identity.toml", None, None)?;
+//! let shutdown = Controller::new();
+//!
+//! let node = Node::builder(cfg, identity_cfg)
+//! 	.shutdown(shutdown.clone())
+//! 	.start()
+//! 	.await?;
+//!
+//! let mut blocks = node.subscribe_verified_blocks();
+//! while let Some(block) = blocks.recv().await {
+//! 	println!("Block {} confidence: {:?}", block.block_num, block.confidence);
+//! }
+//!
+//! let _ = shutdown.trigger_shutdown(ShutdownReason::Signal("Done".to_string()));
+//! node.stopped().await;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Individual subsystems can be run without the node, the same way they are wired in [`NodeBuilder::start`]:
+//! P2P client with [`init_p2p`], RPC client with [`rpc::init`], and light client core with [`crate::light_client::run`].
+
+use avail_core::AppId;
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use kate_recovery::com::AppData;
+use libp2p::{multiaddr::Protocol, Multiaddr};
+use rand::Rng;
+use std::{
+	net::Ipv4Addr,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{error, info, trace, warn, Level};
+
+#[cfg(feature = "network-analysis")]
+use crate::network::p2p::analyzer;
+use crate::{
+	api, attestation,
+	best_client::{BlockReorged, ChainReorg},
+	channel_metrics, clock_skew,
+	consts::EXPECTED_SYSTEM_VERSION,
+	data::{recovery, rocks_db::RocksDB, StoragePause},
+	dht_retry::{self, RetryQueue},
+	event_bus::{EventBus, OverflowPolicy, Subscriber},
+	fleet, header_sync, known_peers,
+	lifecycle::{Lifecycle, LifecycleState},
+	maintenance::{MaintenanceStatus, StaticConfigParams},
+	network::{self, p2p, rpc},
+	proof,
+	quarantine::Quarantine,
+	rpc_health,
+	scheduler::{Lane, Scheduler},
+	shutdown::{Controller, Phase, ShutdownReason},
+	supervisor::{supervise, Supervisor},
+	sync_client::SyncClient,
+	sync_finality::SyncFinality,
+	systemd,
+	telemetry::{
+		otlp::{self, MetricAttributes},
+		MetricCounter, MetricValue, Metrics,
+	},
+	types::{
+		AppClientConfig, BlockVerified, ClientChannels, ConfigReloadSender, IdentityConfig,
+		LibP2PConfig, Network, OtelConfig, ReloadableConfig, RuntimeConfig, State,
+	},
+	version_check, webhooks,
+};
+
+/// Capacity of the internal event subscriber queues
+const EVENT_QUEUE_CAPACITY: usize = 1 << 7;
+
+/// Default role of the node, reported in the metrics
+pub const CLIENT_ROLE: &str = if cfg!(feature = "crawl") {
+	"crawler"
+} else {
+	"lightnode"
+};
+
+/// Reloads the log level of the embedding program's tracing subscriber
+pub type LogLevelReload = Box<dyn Fn(Level) -> Result<()> + Send + Sync>;
+
+/// Loads the configuration on reload request, e.g. from the configuration file
+pub type ConfigLoader = Box<dyn Fn() -> Result<RuntimeConfig> + Send + Sync>;
+
+/// Builder of the light client node
+pub struct NodeBuilder {
+	cfg: RuntimeConfig,
+	identity_cfg: IdentityConfig,
+	shutdown: Controller<ShutdownReason>,
+	client_role: Option<String>,
+	config_loader: Option<ConfigLoader>,
+	log_level_reload: Option<LogLevelReload>,
+}
+
+impl NodeBuilder {
+	/// Sets the shutdown controller, which is used to stop the node (default: new controller)
+	pub fn shutdown(mut self, shutdown: Controller<ShutdownReason>) -> Self {
+		self.shutdown = shutdown;
+		self
+	}
+
+	/// Sets the role reported in the metrics (default: "fatnode" for the fat client, [`CLIENT_ROLE`] otherwise)
+	pub fn client_role(mut self, client_role: &str) -> Self {
+		self.client_role = Some(client_role.to_string());
+		self
+	}
+
+	/// Sets the configuration loader, used on configuration reload requests.
+	/// If not set, configuration reload is not supported.
+	pub fn config_loader(mut self, config_loader: ConfigLoader) -> Self {
+		self.config_loader = Some(config_loader);
+		self
+	}
+
+	/// Sets the log level reload, applied on configuration reload
+	pub fn log_level_reload(mut self, log_level_reload: LogLevelReload) -> Self {
+		self.log_level_reload = Some(log_level_reload);
+		self
+	}
+
+	/// Starts all the node subsystems in the background, returning once the first finalized header is received.
+	/// On failure, shutdown is triggered with the reason matching the failure.
+	pub async fn start(self) -> Result<Node> {
+		let NodeBuilder {
+			cfg,
+			identity_cfg,
+			shutdown,
+			client_role,
+			config_loader,
+			log_level_reload,
+		} = self;
+
+		if cfg.bootstraps.is_empty() {
+			let error = eyre!("Bootstrap node list must not be empty. Either use a '--network' flag or add a list of bootstrap nodes in the configuration file");
+			return Err(shutdown.fail(ShutdownReason::ConfigError, error));
+		}
+
+		let client_role = client_role.unwrap_or_else(|| {
+			if cfg.is_fat_client() {
+				"fatnode".to_string()
+			} else {
+				CLIENT_ROLE.to_string()
+			}
+		});
+
+		let (db, _rocks_db, database_recovery) =
+			recovery::open(&cfg.avail_path).map_err(|error| {
+				let reason = match error
+					.downcast_ref::<rocksdb::Error>()
+					.map(rocksdb::Error::kind)
+				{
+					Some(rocksdb::ErrorKind::Corruption) => ShutdownReason::DbCorruption,
+					_ => ShutdownReason::Error,
+				};
+				let error = error.wrap_err("Avail Light could not initialize database");
+				shutdown.fail(reason, error)
+			})?;
+
+		let cfg_libp2p: LibP2PConfig = (&cfg).into();
+		let (id_keys, peer_id) = p2p::keypair(&cfg_libp2p)?;
+
+		let metric_attributes = MetricAttributes {
+			role: client_role,
+			peer_id,
+			origin: cfg.origin.clone(),
+			avail_address: identity_cfg.avail_public_key.clone(),
+			operating_mode: cfg.operation_mode.to_string(),
+			partition_size: cfg
+				.block_matrix_partition
+				.map(|partition| format!("{}/{}", partition.number, partition.fraction))
+				.unwrap_or("n/a".to_string()),
+			network: Network::name(&cfg.genesis_hash),
+		};
+
+		let cfg_otel: OtelConfig = (&cfg).into();
+		let ot_metrics = Arc::new(
+			otlp::initialize(
+				cfg.ot_collector_endpoint.clone(),
+				metric_attributes,
+				cfg.origin.clone(),
+				cfg_otel,
+			)
+			.wrap_err("Unable to initialize OpenTelemetry service")?,
+		);
+
+		// Run shutdown phases once the shutdown is triggered, delaying the shutdown completion until they are done
+		let timed_out_phases = Arc::new(Mutex::new(vec![]));
+		let phase_timeout = Duration::from_secs(cfg.shutdown_phase_timeout);
+		tokio::spawn(shutdown.with_delay({
+			let (shutdown, timed_out_phases) = (shutdown.clone(), timed_out_phases.clone());
+			async move {
+				let (reason, timed_out) = shutdown.phased_shutdown(phase_timeout).await;
+				if timed_out.is_empty() {
+					info!("Shutdown phases completed");
+					return;
+				}
+				error!(?timed_out, "Shutdown phases exceeded deadline: {reason}");
+				*timed_out_phases.lock().expect("Lock acquired") = timed_out;
+			}
+		})?);
+
+		let telemetry_shutdown = shutdown.phase(Phase::Telemetry);
+		tokio::spawn(telemetry_shutdown.with_delay({
+			let signal = telemetry_shutdown.triggered_shutdown();
+			let ot_metrics = ot_metrics.clone();
+			async move {
+				signal.await;
+				if let Err(error) = ot_metrics.flush().await {
+					error!("Flushing metrics on shutdown failed: {error:#}");
+				}
+			}
+		})?);
+
+		let retry_queue = if cfg.dht_retry_queue_limit > 0 {
+			let (retry_queue, retry_receiver) = RetryQueue::new();
+			tokio::task::spawn(shutdown.with_cancel(dht_retry::run(
+				db.clone(),
+				cfg.dht_retry_queue_limit,
+				retry_receiver,
+			)));
+			retry_queue
+		} else {
+			RetryQueue::default()
+		};
+
+		let (header_requests, header_requests_receiver) = header_sync::Requests::new();
+		let p2p_client = init_p2p(
+			&cfg,
+			cfg_libp2p,
+			&id_keys,
+			ot_metrics.clone(),
+			shutdown.clone(),
+			retry_queue,
+			header_requests,
+			#[cfg(feature = "kademlia-rocksdb")]
+			_rocks_db,
+		)
+		.await?;
+		tokio::task::spawn(shutdown.with_cancel(header_sync::serve(
+			db.clone(),
+			p2p_client.clone(),
+			header_requests_receiver,
+		)));
+
+		let known_peers_config = known_peers::Config::from(&cfg);
+
+		// P2P is closed after the database, so connected peers are persisted before the flush
+		let database_shutdown = shutdown.phase(Phase::Database);
+		tokio::spawn(database_shutdown.with_delay({
+			let signal = database_shutdown.triggered_shutdown();
+			let (db, p2p_client) = (db.clone(), p2p_client.clone());
+			async move {
+				signal.await;
+				if known_peers_config.is_enabled() {
+					if let Err(error) =
+						known_peers::persist(&db, &p2p_client, &known_peers_config).await
+					{
+						error!("Persisting known peers on shutdown failed: {error:#}");
+					}
+				}
+				if let Err(error) = db.flush() {
+					error!("Flushing database on shutdown failed: {error:#}");
+				}
+			}
+		})?);
+
+		if known_peers_config.is_enabled() {
+			match known_peers::add_to_routing_table(&db, &p2p_client, &known_peers_config).await {
+				Ok(added) => info!(added, "Known peers added to the routing table"),
+				Err(error) => warn!("Failed to add known peers to the routing table: {error:#}"),
+			}
+			tokio::spawn(shutdown.with_cancel(known_peers::run(
+				db.clone(),
+				p2p_client.clone(),
+				known_peers_config,
+			)));
+		}
+
+		let p2p_clone = p2p_client.to_owned();
+		let cfg_clone = cfg.to_owned();
+		tokio::spawn(shutdown.with_cancel(async move {
+			info!("Bootstraping the DHT with bootstrap nodes...");
+			let bs_result = p2p_clone
+				.bootstrap_on_startup(cfg_clone.bootstraps.iter().map(Into::into).collect())
+				.await;
+			match bs_result {
+				Ok(_) => {
+					info!("Bootstrap done.");
+				},
+				Err(e) => {
+					warn!("Bootstrap process: {e:?}.");
+				},
+			}
+		}));
+
+		#[cfg(feature = "network-analysis")]
+		tokio::task::spawn(shutdown.with_cancel(analyzer::start_traffic_analyzer(cfg.port, 10)));
+
+		let pp = Arc::new(kate_recovery::couscous::public_params());
+		let raw_pp = pp.to_raw_var_bytes();
+		let public_params_hash = hex::encode(sp_core::blake2_128(&raw_pp));
+		let public_params_len = hex::encode(raw_pp).len();
+		trace!("Public params ({public_params_len}): hash: {public_params_hash}");
+
+		let verification =
+			proof::configure(cfg.verification_threads, cfg.verification_cpus.clone());
+		let state = Arc::new(Mutex::new(State {
+			database_recovery,
+			verification: Some(verification),
+			..Default::default()
+		}));
+		let lifecycle = Lifecycle::new(state.clone());
+		lifecycle.set(LifecycleState::Bootstrapping);
+		tokio::spawn({
+			let (lifecycle, signal) = (lifecycle.clone(), shutdown.triggered_shutdown());
+			async move {
+				signal.await;
+				lifecycle.set(LifecycleState::ShuttingDown);
+			}
+		});
+
+		let (rpc_client, rpc_events, rpc_subscriptions) = rpc::init(
+			db.clone(),
+			state.clone(),
+			&cfg.full_node_ws,
+			&cfg.genesis_hash,
+			cfg.retry_config.clone(),
+			cfg.outbound_proxy(),
+			cfg.rpc_user_agent(),
+			shutdown.clone(),
+		)
+		.await
+		.map_err(|error| shutdown.fail(ShutdownReason::RpcUnreachable, error))?;
+
+		// Subscribing to RPC events before first event is published
+		let publish_rpc_event_receiver = rpc_events.subscribe();
+		let first_header_rpc_event_receiver = rpc_events.subscribe();
+		let client_rpc_event_receiver = rpc_events.subscribe();
+		#[cfg(feature = "crawl")]
+		let crawler_rpc_event_receiver = rpc_events.subscribe();
+		tokio::task::spawn(shutdown.with_cancel(clock_skew::run(
+			rpc_client.clone(),
+			rpc_events.subscribe(),
+			state.clone(),
+			ot_metrics.clone(),
+			Duration::from_secs(cfg.clock_skew_threshold),
+		)));
+		tokio::task::spawn(shutdown.with_cancel(rpc_health::run(
+			state.clone(),
+			lifecycle.clone(),
+			ot_metrics.clone(),
+			Duration::from_secs(cfg.rpc_block_time),
+			cfg.rpc_stall_blocks,
+		)));
+
+		// spawn the RPC Network task for Event Loop to run in the background
+		// and shut it down, without delays
+		let mut rpc_subscriptions_supervisor =
+			Supervisor::new("RPC subscriptions", cfg.restart_config.clone());
+		let rpc_subscriptions_handle = tokio::spawn(shutdown.with_cancel(shutdown.with_trigger(
+			ShutdownReason::RpcUnreachable(
+				"Subscription loop failure triggered shutdown".to_string(),
+			),
+			async move {
+				loop {
+					let Err(error) = rpc_subscriptions.run().await else {
+						return Ok(());
+					};
+					if let Err(err) = rpc_subscriptions_supervisor.restart(error).await {
+						error!(%err, "Subscription loop ended with error");
+						return Err(err);
+					}
+				}
+			},
+		)));
+
+		info!("Waiting for first finalized header...");
+		let block_header = match shutdown
+			.with_cancel(rpc::wait_for_finalized_header(
+				first_header_rpc_event_receiver,
+				360,
+			))
+			.await
+		{
+			Ok(Err(report)) => {
+				let report = shutdown.fail(ShutdownReason::RpcUnreachable, report);
+				if !rpc_subscriptions_handle.is_finished() {
+					return Err(report);
+				}
+				let Ok(Ok(Err(subscriptions_error))) = rpc_subscriptions_handle.await else {
+					return Err(report);
+				};
+				return Err(eyre!(subscriptions_error));
+			},
+			Ok(Ok(num)) => num,
+			Err(shutdown_reason) => {
+				if !rpc_subscriptions_handle.is_finished() {
+					return Err(eyre!(shutdown_reason));
+				}
+				let Ok(Ok(Err(event_loop_error))) = rpc_subscriptions_handle.await else {
+					return Err(eyre!(shutdown_reason));
+				};
+				return Err(eyre!(event_loop_error));
+			},
+		};
+
+		state.lock().unwrap().latest = block_header.number;
+
+		if let Some(timeout) = cfg.peer_header_sync_timeout {
+			tokio::task::spawn(shutdown.with_cancel(header_sync::run(
+				p2p_client.clone(),
+				state.clone(),
+				rpc_events.clone(),
+				ot_metrics.clone(),
+				block_header.clone(),
+				Duration::from_secs(timeout),
+			)));
+		}
+		let sync_range = cfg.sync_range(block_header.number);
+
+		let ws_clients = api::v2::types::WsClients::default();
+
+		let (reloadable_config_sender, reloadable_config) =
+			watch::channel(ReloadableConfig::from(&cfg));
+		let (config_reload_sender, config_reload_receiver) = mpsc::unbounded_channel();
+		tokio::task::spawn(shutdown.with_cancel(reload_config(
+			config_reload_receiver,
+			reloadable_config_sender,
+			rpc_client.clone(),
+			config_loader,
+			log_level_reload,
+		)));
+
+		let maintenance_status = MaintenanceStatus::default();
+		let fleet_aggregates = fleet::Aggregates::default();
+		let attestation_signer =
+			attestation::Signer::new(id_keys, identity_cfg.avail_key_pair.clone());
+
+		// Spawn tokio task which runs one http server for handling RPC
+		let server = api::server::Server {
+			db: db.clone(),
+			cfg: cfg.clone(),
+			identity_cfg,
+			state: state.clone(),
+			version: format!("v{}", clap::crate_version!()),
+			network_version: EXPECTED_SYSTEM_VERSION[0].to_string(),
+			node_client: rpc_client.clone(),
+			ws_clients: ws_clients.clone(),
+			shutdown: shutdown.phase(Phase::Api),
+			p2p_client: p2p_client.clone(),
+			config_reload_sender: config_reload_sender.clone(),
+			maintenance_status: maintenance_status.clone(),
+			pp: pp.clone(),
+			attestation_signer: attestation_signer.clone(),
+			fleet_aggregates: fleet_aggregates.clone(),
+		};
+		if cfg.http_server_enable {
+			let api_shutdown = shutdown.phase(Phase::Api);
+			tokio::task::spawn(api_shutdown.with_delay(server.bind()?)?);
+		} else {
+			info!("HTTP server is disabled, running headless");
+		}
+
+		// Block verification stops receiving new blocks on shutdown trigger,
+		// and blocks being processed are drained in the verification shutdown phase
+		let verification_delay = shutdown.phase(Phase::Verification).delay_token()?;
+
+		let block_tx = EventBus::<BlockVerified>::new();
+
+		let storage_pause = StoragePause::default();
+
+		// event buses with the API subscribers, other than the verified blocks bus
+		let mut event_monitors = vec![rpc_client.runtime_upgrades().monitor()];
+
+		let data_rx = cfg.app_id.map(AppId).map(|app_id| {
+			let data_tx = EventBus::<(u32, AppData)>::new();
+			event_monitors.push(data_tx.monitor());
+			let data_rx = data_tx.subscribe(
+				"api_data_verified",
+				EVENT_QUEUE_CAPACITY,
+				OverflowPolicy::DropOldest,
+			);
+			let app_client_config: AppClientConfig = (&cfg).into();
+			let block_tx = block_tx.clone();
+			let (db, p2p_client, rpc_client, pp, state, sync_range, storage_pause, shutdown) = (
+				db.clone(),
+				p2p_client.clone(),
+				rpc_client.clone(),
+				pp.clone(),
+				state.clone(),
+				sync_range.clone(),
+				storage_pause.clone(),
+				shutdown.clone(),
+			);
+			tokio::task::spawn(verification_delay.clone().with_future(supervise(
+				"App client",
+				cfg.restart_config.clone(),
+				shutdown.clone(),
+				move || {
+					crate::app_client::run(
+						app_client_config.clone(),
+						db.clone(),
+						p2p_client.clone(),
+						rpc_client.clone(),
+						app_id,
+						block_tx.subscribe(
+							"app_client",
+							EVENT_QUEUE_CAPACITY,
+							OverflowPolicy::DropOldest,
+						),
+						pp.clone(),
+						state.clone(),
+						sync_range.clone(),
+						data_tx.clone(),
+						storage_pause.clone(),
+						shutdown.clone(),
+					)
+				},
+			)));
+			data_rx
+		});
+
+		tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+			api::v2::types::Topic::HeaderVerified,
+			publish_rpc_event_receiver,
+			ws_clients.clone(),
+		)));
+
+		// Lifecycle changes are published until the API shutdown phase, to include the shutting down state
+		tokio::task::spawn(shutdown.phase(Phase::Api).with_cancel(api::v2::publish(
+			api::v2::types::Topic::LifecycleChanged,
+			lifecycle.subscribe(),
+			ws_clients.clone(),
+		)));
+
+		tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+			api::v2::types::Topic::ConfidenceAchieved,
+			block_tx.subscribe(
+				"api_confidence_achieved",
+				EVENT_QUEUE_CAPACITY,
+				OverflowPolicy::DropOldest,
+			),
+			ws_clients.clone(),
+		)));
+
+		tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+			api::v2::types::Topic::RuntimeUpgraded,
+			rpc_client.runtime_upgrades().subscribe(
+				"api_runtime_upgraded",
+				EVENT_QUEUE_CAPACITY,
+				OverflowPolicy::DropOldest,
+			),
+			ws_clients.clone(),
+		)));
+
+		let spec_version = state
+			.lock()
+			.expect("Lock acquired")
+			.connected_node
+			.spec_version;
+		ot_metrics
+			.record(MetricValue::RPCSpecVersion(spec_version))
+			.await;
+		tokio::task::spawn(shutdown.with_cancel({
+			let mut runtime_upgrades = rpc_client.runtime_upgrades().subscribe(
+				"runtime_upgrade_metrics",
+				EVENT_QUEUE_CAPACITY,
+				OverflowPolicy::DropOldest,
+			);
+			let ot_metrics = ot_metrics.clone();
+			async move {
+				while let Some(upgrade) = runtime_upgrades.recv().await {
+					ot_metrics.count(MetricCounter::RuntimeUpgrades).await;
+					ot_metrics
+						.record(MetricValue::RPCSpecVersion(upgrade.spec_version))
+						.await;
+				}
+			}
+		}));
+
+		if let Some(url) = cfg.version_check_url.clone() {
+			tokio::task::spawn(shutdown.with_cancel(version_check::run(
+				url,
+				cfg.outbound_proxy(),
+				state.clone(),
+				ot_metrics.clone(),
+			)));
+		}
+
+		if !cfg.fleet_members.is_empty() {
+			tokio::task::spawn(shutdown.with_cancel(fleet::run(
+				fleet::members(&cfg.fleet_members)?,
+				attestation_signer.clone(),
+				fleet_aggregates,
+				block_tx.subscribe("fleet", EVENT_QUEUE_CAPACITY, OverflowPolicy::DropOldest),
+				cfg.outbound_proxy(),
+			)));
+		}
+
+		if cfg.attestation_gossip {
+			tokio::task::spawn(shutdown.with_cancel(attestation::gossip(
+				attestation_signer,
+				block_tx.subscribe(
+					"attestation_gossip",
+					EVENT_QUEUE_CAPACITY,
+					OverflowPolicy::DropOldest,
+				),
+				p2p_client.clone(),
+			)));
+		}
+
+		// Shutdown event is posted before the telemetry shutdown phase completes
+		if !cfg.webhook_urls.is_empty() {
+			let webhooks_client = webhooks::Client::new(
+				cfg.webhook_urls.clone(),
+				Duration::from_secs(cfg.webhook_timeout),
+				cfg.outbound_proxy(),
+			);
+			tokio::task::spawn(shutdown.phase(Phase::Telemetry).with_delay(webhooks::run(
+				webhooks_client,
+				state.clone(),
+				lifecycle.subscribe(),
+				block_tx.subscribe("webhooks", EVENT_QUEUE_CAPACITY, OverflowPolicy::DropOldest),
+				reloadable_config.clone(),
+				cfg.dht_min_peers,
+				shutdown.clone(),
+			))?);
+		}
+
+		if let Some(data_rx) = data_rx {
+			tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+				api::v2::types::Topic::DataVerified,
+				data_rx,
+				ws_clients.clone(),
+			)));
+		}
+
+		#[cfg(feature = "crawl")]
+		if cfg.crawl.crawl_block {
+			tokio::task::spawn(shutdown.with_cancel(crate::crawl_client::run(
+				crawler_rpc_event_receiver,
+				p2p_client.with_lane(Lane::Historical),
+				db.clone(),
+				ot_metrics.clone(),
+				cfg.crawl.clone(),
+			)));
+		}
+
+		let sync_client = SyncClient::new(db.clone(), rpc_client.clone());
+
+		let gateway_client = (!cfg.http_gateways.is_empty()).then(|| {
+			network::gateway::Client::new(
+				cfg.http_gateways.clone(),
+				Duration::from_secs(cfg.http_gateway_timeout),
+				cfg.outbound_proxy(),
+			)
+		});
+
+		let (quarantine, quarantine_receiver) = Quarantine::new();
+		let peer_fallback = network::PeerFallback::new(cfg.dht_fallback_peers);
+		tokio::task::spawn(
+			shutdown.with_cancel(crate::quarantine::run(db.clone(), quarantine_receiver)),
+		);
+
+		// sync client work is preempted by the head blocks verification
+		let sync_network_client = network::new(
+			p2p_client.with_lane(Lane::Historical),
+			rpc_client.clone(),
+			pp.clone(),
+			cfg.disable_rpc,
+			gateway_client.clone(),
+			quarantine.clone(),
+			peer_fallback.clone(),
+			cfg.fault_injection.clone(),
+		);
+
+		if cfg.sync_start_block.is_some() {
+			state.lock().unwrap().synced.replace(false);
+			tokio::task::spawn(shutdown.with_cancel(crate::sync_client::run(
+				sync_client,
+				sync_network_client,
+				(&cfg).into(),
+				sync_range,
+				block_tx.clone(),
+				state.clone(),
+			)));
+		}
+
+		if cfg.sync_finality_enable {
+			let sync_finality = SyncFinality::new(db.clone(), rpc_client.clone());
+			tokio::task::spawn(shutdown.with_cancel(crate::sync_finality::run(
+				sync_finality,
+				shutdown.clone(),
+				state.clone(),
+				block_header.clone(),
+			)));
+		} else {
+			let mut s = state
+				.lock()
+				.map_err(|e| eyre!("State mutex is poisoned: {e:#}"))?;
+			warn!("Finality sync is disabled! Implicitly, blocks before LC startup will be considered verified as final");
+			s.finality_synced = true;
+		}
+
+		let static_config_params = StaticConfigParams {
+			block_confidence_treshold: cfg.confidence,
+			replication_factor: cfg.replication_factor,
+			query_timeout: cfg.query_timeout,
+			pruning_interval: cfg.store_pruning_interval,
+			telemetry_flush_interval: cfg.ot_flush_block_interval,
+			memory_threshold: cfg
+				.memory_threshold
+				.map(|megabytes| megabytes * 1024 * 1024),
+			memory_threshold_restart: cfg.memory_threshold_restart,
+			pruning_max_records: cfg.store_pruning_max_records,
+			pruning_max_bytes: cfg
+				.store_pruning_max_size
+				.map(|megabytes| megabytes * 1024 * 1024),
+			republish_interval: cfg.dht_republish_interval,
+			republish_limit: cfg.dht_republish_limit,
+			republish_before_expiry: Duration::from_secs(cfg.dht_republish_before_expiry),
+			republish_offset: republish_offset(
+				cfg.dht_republish_interval,
+				cfg.kad_record_ttl_jitter,
+			),
+			min_peers: cfg.dht_min_peers,
+			bootstraps: cfg.bootstraps.iter().map(Into::into).collect(),
+			avail_path: cfg.avail_path.clone(),
+			disk_space_warning_threshold: cfg.disk_space_warning_threshold * 1024 * 1024,
+			disk_space_critical_threshold: cfg.disk_space_critical_threshold * 1024 * 1024,
+		};
+
+		tokio::task::spawn(shutdown.with_cancel(supervise(
+			"Maintenance",
+			cfg.restart_config.clone(),
+			shutdown.clone(),
+			{
+				let (p2p_client, ot_metrics, reloadable_config, lifecycle, shutdown, block_tx) = (
+					p2p_client.clone(),
+					ot_metrics.clone(),
+					reloadable_config.clone(),
+					lifecycle.clone(),
+					shutdown.clone(),
+					block_tx.clone(),
+				);
+				move || {
+					crate::maintenance::run(
+						p2p_client.clone(),
+						ot_metrics.clone(),
+						block_tx.subscribe(
+							"maintenance",
+							EVENT_QUEUE_CAPACITY,
+							OverflowPolicy::DropOldest,
+						),
+						static_config_params.clone(),
+						reloadable_config.clone(),
+						storage_pause.clone(),
+						maintenance_status.clone(),
+						lifecycle.clone(),
+						shutdown.clone(),
+					)
+				}
+			},
+		)));
+
+		let channels = ClientChannels {
+			block_sender: block_tx.clone(),
+			rpc_event_receiver: client_rpc_event_receiver,
+		};
+
+		if let Some(partition) = cfg.block_matrix_partition {
+			let fat_client = crate::fat_client::new(p2p_client.clone(), rpc_client.clone());
+
+			tokio::task::spawn(verification_delay.with_future(crate::fat_client::run(
+				fat_client,
+				db.clone(),
+				(&cfg).into(),
+				ot_metrics.clone(),
+				channels,
+				partition,
+				shutdown.clone(),
+			)));
+		} else {
+			if cfg.best_block_sampling {
+				let reorgs = EventBus::<BlockReorged>::new();
+				event_monitors.push(reorgs.monitor());
+				tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+					api::v2::types::Topic::BlockReorged,
+					reorgs.subscribe(
+						"api_block_reorged",
+						EVENT_QUEUE_CAPACITY,
+						OverflowPolicy::DropOldest,
+					),
+					ws_clients.clone(),
+				)));
+				let chain_reorgs = EventBus::<ChainReorg>::new();
+				event_monitors.push(chain_reorgs.monitor());
+				tokio::task::spawn(shutdown.with_cancel(api::v2::publish(
+					api::v2::types::Topic::Reorg,
+					chain_reorgs.subscribe(
+						"api_reorg",
+						EVENT_QUEUE_CAPACITY,
+						OverflowPolicy::DropOldest,
+					),
+					ws_clients,
+				)));
+
+				let best_network_client = network::new(
+					p2p_client.clone(),
+					rpc_client.clone(),
+					pp.clone(),
+					cfg.disable_rpc,
+					gateway_client.clone(),
+					quarantine.clone(),
+					peer_fallback.clone(),
+					cfg.fault_injection.clone(),
+				);
+				tokio::task::spawn(shutdown.with_cancel(crate::best_client::run(
+					db.clone(),
+					rpc_client.clone(),
+					best_network_client,
+					ot_metrics.clone(),
+					reloadable_config.clone(),
+					block_tx.subscribe(
+						"best_client",
+						EVENT_QUEUE_CAPACITY,
+						OverflowPolicy::DropOldest,
+					),
+					reorgs,
+					chain_reorgs,
+					shutdown.clone(),
+				)));
+			}
+
+			let light_network_client = network::new(
+				p2p_client.clone(),
+				rpc_client.clone(),
+				pp,
+				cfg.disable_rpc,
+				gateway_client,
+				quarantine,
+				peer_fallback,
+				cfg.fault_injection.clone(),
+			);
+
+			tokio::task::spawn(verification_delay.with_future(crate::light_client::run(
+				db.clone(),
+				light_network_client,
+				(&cfg).into(),
+				ot_metrics.clone(),
+				state.clone(),
+				channels,
+				reloadable_config,
+				shutdown.clone(),
+			)));
+		}
+
+		tokio::task::spawn(shutdown.with_cancel(channel_metrics::run(
+			block_tx.monitor(),
+			event_monitors,
+			p2p_client.clone(),
+			ot_metrics.clone(),
+		)));
+
+		lifecycle.refresh();
+		ot_metrics.count(MetricCounter::Starts).await;
+
+		systemd::notify(systemd::READY);
+		tokio::task::spawn({
+			let shutdown = shutdown.clone();
+			async move {
+				shutdown.triggered_shutdown().await;
+				systemd::notify(systemd::STOPPING);
+			}
+		});
+
+		Ok(Node {
+			shutdown,
+			state,
+			lifecycle,
+			db,
+			p2p_client,
+			rpc_client,
+			config_reload_sender,
+			block_sender: block_tx,
+			timed_out_phases,
+		})
+	}
+}
+
+/// Handle of the running light client node
+pub struct Node {
+	pub shutdown: Controller<ShutdownReason>,
+	pub state: Arc<Mutex<State>>,
+	pub lifecycle: Lifecycle,
+	pub db: RocksDB,
+	pub p2p_client: p2p::Client,
+	pub rpc_client: rpc::Client,
+	pub config_reload_sender: ConfigReloadSender,
+	block_sender: EventBus<BlockVerified>,
+	timed_out_phases: Arc<Mutex<Vec<Phase>>>,
+}
+
+impl Node {
+	pub fn builder(cfg: RuntimeConfig, identity_cfg: IdentityConfig) -> NodeBuilder {
+		NodeBuilder {
+			cfg,
+			identity_cfg,
+			shutdown: Controller::new(),
+			client_role: None,
+			config_loader: None,
+			log_level_reload: None,
+		}
+	}
+
+	/// Subscribes to the blocks verified by the light client, or processed by the fat client.
+	/// If subscriber lags, the oldest blocks are dropped.
+	pub fn subscribe_verified_blocks(&self) -> Subscriber<BlockVerified> {
+		self.block_sender.subscribe(
+			"verified_blocks",
+			EVENT_QUEUE_CAPACITY,
+			OverflowPolicy::DropOldest,
+		)
+	}
+
+	/// Waits until the node is stopped, returning the shutdown reason
+	pub async fn stopped(&self) -> ShutdownReason {
+		self.shutdown.completed_shutdown().await
+	}
+
+	/// Returns shutdown phases which exceeded their deadline
+	pub fn timed_out_phases(&self) -> Vec<Phase> {
+		self.timed_out_phases.lock().expect("Lock acquired").clone()
+	}
+}
+
+/// Starts the P2P event loop in the background, and the listener on the configured port.
+/// Event loop stops on its own in the network shutdown phase.
+pub async fn init_p2p(
+	cfg: &RuntimeConfig,
+	cfg_libp2p: LibP2PConfig,
+	id_keys: &libp2p::identity::Keypair,
+	metrics: Arc<otlp::Metrics>,
+	shutdown: Controller<ShutdownReason>,
+	retry_queue: RetryQueue,
+	header_requests: header_sync::Requests,
+	#[cfg(feature = "kademlia-rocksdb")] rocks_db: Arc<rocksdb::DB>,
+) -> Result<p2p::Client> {
+	// Create sender channel for P2P event loop commands
+	let (p2p_event_loop_sender, p2p_event_loop_receiver) = p2p::command_channel();
+
+	let p2p_event_loop = p2p::EventLoop::new(
+		cfg_libp2p,
+		id_keys,
+		cfg.is_fat_client(),
+		cfg.ws_transport_enable,
+		shutdown.phase(Phase::Network),
+		#[cfg(feature = "kademlia-rocksdb")]
+		rocks_db,
+	);
+
+	tokio::spawn(
+		p2p_event_loop
+			.await
+			.with_retry_queue(retry_queue.clone())
+			.with_header_requests(header_requests)
+			.run(metrics, p2p_event_loop_receiver),
+	);
+
+	let p2p_client = p2p::Client::new(
+		p2p_event_loop_sender,
+		cfg.dht_parallelization_limit,
+		cfg.kad_record_ttl,
+	)
+	.with_ttl_jitter(Duration::from_secs(cfg.kad_record_ttl_jitter))
+	.with_compression(cfg.dht_record_compression)
+	.with_scheduler(Scheduler::new(Some(cfg.historical_lane_weight)))
+	.with_sampling_privacy(cfg.sampling_privacy.clone())
+	.with_retry_queue(retry_queue);
+
+	if cfg.tor_proxy.is_some() {
+		info!("P2P connections are routed over Tor, listener is disabled");
+		return Ok(p2p_client);
+	}
+
+	if !cfg.p2p_listen_addresses.is_empty() {
+		for address in &cfg.p2p_listen_addresses {
+			let address: Multiaddr = address
+				.parse()
+				.wrap_err(format!("Invalid P2P listen address {address}"))?;
+			p2p_client
+				.start_listening(address.clone())
+				.await
+				.wrap_err(format!("Listening on {address} not to fail."))?;
+			info!("P2P listener started on {address}");
+		}
+		return Ok(p2p_client);
+	}
+
+	// Start listening on provided port
+	p2p_client
+		.start_listening(construct_multiaddress(cfg.ws_transport_enable, cfg.port))
+		.await
+		.wrap_err("Listening on TCP not to fail.")?;
+	info!("TCP listener started on port {}", cfg.port);
+
+	Ok(p2p_client)
+}
+
+fn construct_multiaddress(is_websocket: bool, port: u16) -> Multiaddr {
+	let tcp_multiaddress = Multiaddr::empty()
+		.with(Protocol::from(Ipv4Addr::UNSPECIFIED))
+		.with(Protocol::Tcp(port));
+
+	if is_websocket {
+		return tcp_multiaddress.with(Protocol::Ws(std::borrow::Cow::Borrowed("avail-light")));
+	}
+
+	tcp_multiaddress
+}
+
+/// Returns random offset of the republishing pass within the republish interval, if TTL jitter is enabled
+fn republish_offset(republish_interval: u32, ttl_jitter: u64) -> u32 {
+	if republish_interval == 0 || ttl_jitter == 0 {
+		return 0;
+	}
+	rand::thread_rng().gen_range(0..republish_interval)
+}
+
+/// Reloads the reloadable subset of the configuration, loaded with the configuration loader
+fn reload(
+	reloadable_config: &watch::Sender<ReloadableConfig>,
+	rpc_client: &rpc::Client,
+	config_loader: &ConfigLoader,
+	log_level_reload: Option<&LogLevelReload>,
+) -> Result<ReloadableConfig> {
+	let runtime_config = config_loader()?;
+	// reload is rejected if any parameter is invalid, including the parameters which are not reloaded
+	runtime_config.validate()?;
+	let config = ReloadableConfig::from(&runtime_config);
+
+	let log_level = config
+		.log_level
+		.to_uppercase()
+		.parse::<Level>()
+		.map_err(|error| eyre!("Invalid log level {}: {error}", config.log_level))?;
+
+	if let Some(log_level_reload) = log_level_reload {
+		log_level_reload(log_level)?;
+	}
+	rpc_client.set_nodes(&config.full_node_ws);
+	reloadable_config.send_replace(config.clone());
+	Ok(config)
+}
+
+async fn reload_config(
+	mut config_reload_receiver: mpsc::UnboundedReceiver<oneshot::Sender<Result<ReloadableConfig>>>,
+	reloadable_config: watch::Sender<ReloadableConfig>,
+	rpc_client: rpc::Client,
+	config_loader: Option<ConfigLoader>,
+	log_level_reload: Option<LogLevelReload>,
+) {
+	while let Some(response_sender) = config_reload_receiver.recv().await {
+		let result = match &config_loader {
+			Some(config_loader) => reload(
+				&reloadable_config,
+				&rpc_client,
+				config_loader,
+				log_level_reload.as_ref(),
+			),
+			None => Err(eyre!("Configuration reload is not supported")),
+		};
+		match &result {
+			Ok(config) => info!("Configuration reloaded: {config:?}"),
+			Err(error) => error!("Configuration reload failed: {error:#}"),
+		}
+		let _ = response_sender.send(result);
+	}
+}
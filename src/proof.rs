@@ -1,6 +1,11 @@
 //! Parallelized proof verification
+//!
+//! Proofs are verified on the dedicated thread pool, so the CPU heavy verification doesn't block the async runtime.
+//! Number of the verification threads can be capped, and threads can be pinned to the CPU cores,
+//! so the light client can share the machine with the other services (e.g. a validator).
+//! Pool is configured once per process, on startup; if it is not configured, one thread per CPU is used.
 
-use color_eyre::eyre;
+use color_eyre::eyre::{self, eyre};
 use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
 use itertools::{Either, Itertools};
 use kate_recovery::{
@@ -8,18 +13,110 @@ use kate_recovery::{
 	matrix::{Dimensions, Position},
 	proof,
 };
-use std::sync::Arc;
-use tokio::{task::JoinSet, time::Instant};
-use tracing::debug;
+use serde::{Deserialize, Serialize};
+use std::{
+	sync::{mpsc, Arc, Mutex, OnceLock},
+	thread,
+};
+use tokio::{sync::oneshot, time::Instant};
+use tracing::{debug, info, warn};
 
-async fn verify_proof(
-	public_parameters: Arc<PublicParameters>,
-	dimensions: Dimensions,
-	commitment: [u8; 48],
-	cell: Cell,
-) -> Result<(Position, bool), proof::Error> {
-	proof::verify(&public_parameters, dimensions, &commitment, &cell)
-		.map(|verified| (cell.position, verified))
+use crate::utils::pin_current_thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Effective parallelism of the proof verification
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Parallelism {
+	/// Number of the verification threads
+	pub threads: usize,
+	/// CPU cores to which the threads are pinned
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub cpus: Vec<usize>,
+}
+
+struct Pool {
+	sender: Mutex<mpsc::Sender<Job>>,
+	parallelism: Parallelism,
+}
+
+static POOL: OnceLock<Pool> = OnceLock::new();
+
+impl Pool {
+	/// Starts the verification threads, one per CPU if `threads` is 0.
+	/// Threads are pinned to the `cpus` round-robin.
+	fn start(threads: usize, cpus: Vec<usize>) -> Self {
+		let threads = if threads == 0 {
+			num_cpus::get()
+		} else {
+			threads
+		};
+		let (sender, receiver) = mpsc::channel::<Job>();
+		let receiver = Arc::new(Mutex::new(receiver));
+		let mut pinned = vec![];
+		for index in 0..threads {
+			let receiver = receiver.clone();
+			let cpu = (!cpus.is_empty()).then(|| cpus[index % cpus.len()]);
+			let spawned = thread::Builder::new()
+				.name(format!("verification-{index}"))
+				.spawn(move || {
+					if let Some(cpu) = cpu {
+						if let Err(error) = pin_current_thread(cpu) {
+							warn!("Cannot pin verification thread to CPU {cpu}: {error}");
+						}
+					}
+					loop {
+						let job = receiver.lock().expect("Lock acquired").recv();
+						match job {
+							Ok(job) => job(),
+							// pool is dropped
+							Err(_) => return,
+						}
+					}
+				});
+			match spawned {
+				Ok(_) => pinned.extend(cpu),
+				Err(error) => warn!("Cannot spawn verification thread: {error}"),
+			}
+		}
+		pinned.sort_unstable();
+		pinned.dedup();
+		Pool {
+			sender: Mutex::new(sender),
+			parallelism: Parallelism {
+				threads,
+				cpus: pinned,
+			},
+		}
+	}
+
+	fn execute(&self, job: impl FnOnce() + Send + 'static) {
+		// receiver is not dropped while the pool exists
+		let _ = self
+			.sender
+			.lock()
+			.expect("Lock acquired")
+			.send(Box::new(job));
+	}
+}
+
+/// Configures the verification thread pool, returning its parallelism.
+/// Pool is configured only once, and the parallelism of the existing pool is returned on subsequent calls.
+pub fn configure(threads: usize, cpus: Vec<usize>) -> Parallelism {
+	let parallelism = POOL
+		.get_or_init(|| Pool::start(threads, cpus))
+		.parallelism
+		.clone();
+	info!(
+		threads = parallelism.threads,
+		cpus = ?parallelism.cpus,
+		"Proof verification threads started"
+	);
+	parallelism
+}
+
+fn pool() -> &'static Pool {
+	POOL.get_or_init(|| Pool::start(0, vec![]))
 }
 
 /// Verifies proofs for given block, cells and commitments
@@ -36,20 +133,29 @@ pub async fn verify(
 
 	let start_time = Instant::now();
 
-	let mut tasks = JoinSet::new();
-
-	for cell in cells {
-		tasks.spawn(verify_proof(
-			public_parameters.clone(),
-			dimensions,
-			commitments[cell.position.row as usize],
-			cell.clone(),
-		));
-	}
+	let pool = pool();
+	let receivers = cells
+		.iter()
+		.map(|cell| {
+			let (sender, receiver) = oneshot::channel();
+			let public_parameters = public_parameters.clone();
+			let commitment = commitments[cell.position.row as usize];
+			let cell = cell.clone();
+			pool.execute(move || {
+				let result = proof::verify(&public_parameters, dimensions, &commitment, &cell)
+					.map(|verified| (cell.position, verified));
+				let _ = sender.send(result);
+			});
+			receiver
+		})
+		.collect::<Vec<_>>();
 
 	let mut results = Vec::with_capacity(cells.len());
-	while let Some(result) = tasks.join_next().await {
-		results.push(result??)
+	for receiver in receivers {
+		let result = receiver
+			.await
+			.map_err(|_| eyre!("Verification thread stopped"))?;
+		results.push(result?)
 	}
 
 	debug!(block_num, duration = ?start_time.elapsed(), "Proof verification completed");
@@ -61,3 +167,35 @@ pub async fn verify(
 			false => Either::Right(position),
 		}))
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn pool_execution() {
+		let pool = Pool::start(2, vec![]);
+		assert_eq!(
+			pool.parallelism,
+			Parallelism {
+				threads: 2,
+				cpus: vec![]
+			}
+		);
+		let receivers = (0..10)
+			.map(|job| {
+				let (sender, receiver) = oneshot::channel();
+				pool.execute(move || {
+					let _ = sender.send(job * 2);
+				});
+				receiver
+			})
+			.collect::<Vec<_>>();
+		let mut results = vec![];
+		for receiver in receivers {
+			results.push(receiver.await.unwrap());
+		}
+		assert_eq!(results, (0..10).map(|job| job * 2).collect::<Vec<_>>());
+		assert!(Pool::start(0, vec![]).parallelism.threads > 0);
+	}
+}
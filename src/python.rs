@@ -0,0 +1,144 @@
+//! Python extension module of the light client, built with [maturin](https://www.maturin.rs) from `bindings/python`.
+//!
+//! Module wraps the [`NodeHandle`], and releases the GIL while the handle is blocked on the node.
+//! Asynchronous iteration over the verified blocks is implemented in the `avail_light` Python package, on top of this module.
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use std::{fmt::Display, time::Duration};
+
+use crate::{api::v2::types::BlockVerification, handle::NodeHandle};
+
+create_exception!(
+	_native,
+	LightClientError,
+	PyException,
+	"Error returned by the light client"
+);
+
+fn to_py_err(error: impl Display) -> PyErr {
+	LightClientError::new_err(format!("{error:#}"))
+}
+
+/// Verification metadata of the sampled block, with the sampling timestamps
+#[pyclass(get_all, frozen, module = "avail_light")]
+pub struct VerificationEntry {
+	block_number: u32,
+	/// Sampling start time (milliseconds since UNIX epoch)
+	started_at: u64,
+	/// Sampling end time (milliseconds since UNIX epoch)
+	finished_at: u64,
+	duration_ms: u64,
+	/// Delay between the header receipt and the sampling start, in milliseconds
+	processing_delay_ms: u64,
+	cells_requested: u32,
+	dht_fetched: u32,
+	rpc_fetched: u32,
+	verified: u32,
+	confidence: f64,
+}
+
+#[pymethods]
+impl VerificationEntry {
+	fn __repr__(&self) -> String {
+		format!(
+			"VerificationEntry(block_number={}, started_at={}, finished_at={}, confidence={})",
+			self.block_number, self.started_at, self.finished_at, self.confidence
+		)
+	}
+}
+
+impl From<BlockVerification> for VerificationEntry {
+	fn from(verification: BlockVerification) -> Self {
+		VerificationEntry {
+			block_number: verification.block_number,
+			started_at: verification.started_at,
+			finished_at: verification.finished_at,
+			duration_ms: verification.duration_ms,
+			processing_delay_ms: verification.processing_delay_ms,
+			cells_requested: verification.cells.requested,
+			dht_fetched: verification.cells.dht_fetched,
+			rpc_fetched: verification.cells.rpc_fetched,
+			verified: verification.cells.verified,
+			confidence: verification.confidence,
+		}
+	}
+}
+
+/// Light client node running in the background, until it is stopped
+#[pyclass(frozen, module = "avail_light")]
+pub struct Node {
+	handle: NodeHandle,
+}
+
+#[pymethods]
+impl Node {
+	#[new]
+	fn new(py: Python<'_>, config_json: &str, identity_path: &str) -> PyResult<Self> {
+		let handle = py
+			.allow_threads(|| NodeHandle::start(config_json, identity_path))
+			.map_err(to_py_err)?;
+		Ok(Node { handle })
+	}
+
+	/// Stops the node, waiting for the shutdown to complete
+	fn stop(&self, py: Python<'_>) {
+		py.allow_threads(|| self.handle.stop())
+	}
+
+	fn is_stopped(&self) -> bool {
+		self.handle.is_stopped()
+	}
+
+	fn latest_block(&self) -> Option<u32> {
+		self.handle.latest_block()
+	}
+
+	fn confidence(&self, block_number: u32) -> PyResult<Option<f64>> {
+		self.handle.confidence(block_number).map_err(to_py_err)
+	}
+
+	/// Returns status and confidence of the given block as JSON, in the `/v2/blocks/{block_number}` format
+	fn block_json(&self, block_number: u32) -> PyResult<Option<String>> {
+		let Some(block) = self.handle.block(block_number).map_err(to_py_err)? else {
+			return Ok(None);
+		};
+		serde_json::to_string(&block).map(Some).map_err(to_py_err)
+	}
+
+	/// Returns verification metadata of the sampled blocks in the given inclusive range
+	fn verification_history(
+		&self,
+		py: Python<'_>,
+		first: u32,
+		last: u32,
+	) -> PyResult<Vec<VerificationEntry>> {
+		let history = py
+			.allow_threads(|| self.handle.verification_history(first, last))
+			.map_err(to_py_err)?;
+		Ok(history.into_iter().map(VerificationEntry::from).collect())
+	}
+
+	/// Waits for the next verified block, returning block number and confidence, or `None` on timeout
+	fn next_verified_block(
+		&self,
+		py: Python<'_>,
+		timeout: f64,
+	) -> PyResult<Option<(u32, Option<f64>)>> {
+		let timeout = Duration::try_from_secs_f64(timeout).map_err(to_py_err)?;
+		let block = py
+			.allow_threads(|| self.handle.next_verified_block(timeout))
+			.map_err(to_py_err)?;
+		Ok(block.map(|block| (block.block_number, block.confidence)))
+	}
+}
+
+#[pymodule]
+fn _native(module: &Bound<'_, PyModule>) -> PyResult<()> {
+	module.add_class::<Node>()?;
+	module.add_class::<VerificationEntry>()?;
+	module.add(
+		"LightClientError",
+		module.py().get_type_bound::<LightClientError>(),
+	)?;
+	Ok(())
+}
@@ -0,0 +1,160 @@
+//! Quarantine of the cells which failed proof verification.
+//!
+//! Failed cells are stored with the commitment they are verified against and with the source they are fetched from
+//! (DHT peer, RPC node or HTTP gateway), so malicious or buggy data sources can be identified.
+//! Quarantined cells are exposed with `GET /v2/admin/failures`.
+//!
+//! # Notes
+//!
+//! Failures are sent to the single task which stores them, so concurrent verifications don't overwrite each other.
+//! Only the most recent [`QUARANTINE_LIMIT`] failures are kept.
+
+use color_eyre::{eyre::WrapErr, Result};
+use kate_recovery::{config, data::Cell, matrix::Position};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::{
+	data::{CellSource, Database, Key, ProofFailure},
+	utils::unix_timestamp_millis,
+};
+
+/// Maximum number of quarantined proof failures
+pub const QUARANTINE_LIMIT: usize = 256;
+
+/// Sender of the failed cells to the quarantine, noop if quarantine is not running
+#[derive(Clone, Default)]
+pub struct Quarantine(Option<mpsc::UnboundedSender<ProofFailure>>);
+
+impl Quarantine {
+	pub fn new() -> (Self, mpsc::UnboundedReceiver<ProofFailure>) {
+		let (sender, receiver) = mpsc::unbounded_channel();
+		(Quarantine(Some(sender)), receiver)
+	}
+
+	/// Quarantines fetched cells with the unverified positions.
+	/// Source of the cell is resolved by its index in the fetched cells.
+	pub fn quarantine(
+		&self,
+		block_number: u32,
+		fetched: &[Cell],
+		unverified: &[Position],
+		commitments: &[[u8; config::COMMITMENT_SIZE]],
+		source: impl Fn(usize) -> CellSource,
+	) {
+		let Some(sender) = &self.0 else {
+			return;
+		};
+		let quarantined_at = unix_timestamp_millis();
+		for (index, cell) in fetched.iter().enumerate() {
+			if !unverified.contains(&cell.position) {
+				continue;
+			}
+			let failure = ProofFailure {
+				block_number,
+				row: cell.position.row,
+				col: cell.position.col,
+				content: cell.content.to_vec(),
+				commitment: commitments
+					.get(cell.position.row as usize)
+					.map(|commitment| commitment.to_vec())
+					.unwrap_or_default(),
+				source: source(index),
+				quarantined_at,
+			};
+			if sender.send(failure).is_err() {
+				warn!(block_number, "Quarantine is closed, failed cell is dropped");
+			}
+		}
+	}
+}
+
+/// Returns quarantined proof failures, from the oldest to the most recent one
+pub fn failures(db: &impl Database) -> Result<Vec<ProofFailure>> {
+	db.get(Key::ProofFailures)
+		.wrap_err("Failed to get proof failures from the database")
+		.map(Option::unwrap_or_default)
+}
+
+fn store(db: &impl Database, failure: ProofFailure) -> Result<()> {
+	let mut failures = failures(db)?;
+	failures.push(failure);
+	let evicted = failures.len().saturating_sub(QUARANTINE_LIMIT);
+	failures.drain(..evicted);
+	db.put(Key::ProofFailures, failures)
+		.wrap_err("Failed to store proof failures in the database")
+}
+
+/// Stores failed cells received from the quarantine senders
+pub async fn run(db: impl Database, mut receiver: mpsc::UnboundedReceiver<ProofFailure>) {
+	info!("Starting proof failures quarantine...");
+	while let Some(failure) = receiver.recv().await {
+		warn!(
+			block_number = failure.block_number,
+			row = failure.row,
+			col = failure.col,
+			source = ?failure.source,
+			"Cell failed proof verification, quarantined"
+		);
+		if let Err(error) = store(&db, failure) {
+			error!("Failed to quarantine cell: {error:#}");
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data::mem_db::MemoryDB;
+
+	fn cell(row: u32, col: u16) -> Cell {
+		Cell {
+			position: Position { row, col },
+			content: [1; config::COMMITMENT_SIZE + config::CHUNK_SIZE],
+		}
+	}
+
+	#[test]
+	fn quarantine_unverified() {
+		let (quarantine, mut receiver) = Quarantine::new();
+		let fetched = [cell(0, 0), cell(1, 1)];
+		let commitments = [[2; config::COMMITMENT_SIZE], [3; config::COMMITMENT_SIZE]];
+		quarantine.quarantine(
+			1,
+			&fetched,
+			&[Position { row: 1, col: 1 }],
+			&commitments,
+			|_| CellSource::Gateway,
+		);
+
+		let failure = receiver.try_recv().unwrap();
+		assert_eq!((failure.block_number, failure.row, failure.col), (1, 1, 1));
+		assert_eq!(failure.commitment, vec![3; config::COMMITMENT_SIZE]);
+		assert_eq!(failure.source, CellSource::Gateway);
+		assert!(receiver.try_recv().is_err());
+
+		// disabled quarantine is noop
+		Quarantine::default().quarantine(1, &fetched, &[], &commitments, |_| CellSource::Gateway);
+	}
+
+	#[test]
+	fn store_limit() {
+		let db = MemoryDB::default();
+		let (quarantine, mut receiver) = Quarantine::new();
+		let fetched = (0..QUARANTINE_LIMIT as u32 + 1)
+			.map(|row| cell(row, 0))
+			.collect::<Vec<_>>();
+		let unverified = fetched.iter().map(|cell| cell.position).collect::<Vec<_>>();
+		quarantine.quarantine(1, &fetched, &unverified, &[], |index| CellSource::Dht {
+			peer_id: Some(index.to_string()),
+		});
+		while let Ok(failure) = receiver.try_recv() {
+			store(&db, failure).unwrap();
+		}
+
+		let failures = failures(&db).unwrap();
+		assert_eq!(failures.len(), QUARANTINE_LIMIT);
+		assert_eq!(failures[0].row, 1);
+		assert!(failures[0].commitment.is_empty());
+	}
+}
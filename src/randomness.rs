@@ -0,0 +1,157 @@
+//! Randomness of the sampled cell positions.
+//!
+//! By default, cell positions are chosen using the operating system entropy. If `randomness_beacon_url` is configured,
+//! RNG of each block is seeded with the latest round of the public randomness beacon (e.g. drand), mixed with the block hash
+//! and the local entropy, so the sampled cells cannot be predicted in advance, even if the local entropy is compromised.
+//!
+//! # Notes
+//!
+//! Beacon signatures are not verified, so the beacon randomness is only mixed into the seed, and never replaces the local entropy.
+//! If the beacon is not reachable, operating system entropy is used. Source used for each block is recorded in the block verification metadata.
+
+use async_trait::async_trait;
+use avail_subxt::utils::H256;
+use codec::{Decode, Encode};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hyper::{body, Body, Request};
+use hyper_rustls::HttpsConnector;
+use rand::{rngs::OsRng, rngs::StdRng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sp_core::blake2_256;
+use std::{sync::Arc, time::Duration};
+use tracing::warn;
+
+use crate::network::proxy::{https_connector, Connector, Proxy};
+
+/// Timeout of the beacon request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Source of the sampled cell positions randomness
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Decode, Encode, PartialEq)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum RandomnessSource {
+	/// Operating system entropy
+	Os,
+	/// Randomness beacon round, mixed with the operating system entropy
+	Beacon { round: u64 },
+}
+
+/// Source of the RNG used to choose the sampled cell positions
+#[async_trait]
+pub trait Randomness: Send + Sync {
+	/// Returns RNG for the cell positions of the block, with the source of its seed
+	async fn rng(&self, block_hash: H256) -> (StdRng, RandomnessSource);
+}
+
+/// Operating system entropy
+pub struct OsRandomness;
+
+#[async_trait]
+impl Randomness for OsRandomness {
+	async fn rng(&self, _: H256) -> (StdRng, RandomnessSource) {
+		(StdRng::from_entropy(), RandomnessSource::Os)
+	}
+}
+
+#[derive(Deserialize)]
+struct BeaconRound {
+	round: u64,
+	/// Hex encoded randomness of the round
+	randomness: String,
+}
+
+/// Returns seed derived from the beacon randomness, block hash and local entropy
+fn seed(randomness: &[u8], block_hash: H256, local: [u8; 32]) -> [u8; 32] {
+	blake2_256(&[randomness, block_hash.as_bytes(), &local].concat())
+}
+
+/// Public randomness beacon, with the drand HTTP API (`GET {url}/public/latest`)
+pub struct Beacon {
+	url: String,
+	http_client: hyper::Client<HttpsConnector<Connector>, Body>,
+}
+
+impl Beacon {
+	pub fn new(url: &str, proxy: Option<Proxy>) -> Self {
+		Beacon {
+			url: url.trim_end_matches('/').to_string(),
+			http_client: hyper::Client::builder().build(https_connector(proxy)),
+		}
+	}
+
+	async fn latest(&self) -> Result<(u64, Vec<u8>)> {
+		let url = format!("{}/public/latest", self.url);
+		let request = Request::get(&url)
+			.body(Body::empty())
+			.wrap_err(format!("Invalid URL {url}"))?;
+		let response = tokio::time::timeout(REQUEST_TIMEOUT, self.http_client.request(request))
+			.await
+			.map_err(|_| eyre!("Request timed out"))??;
+		if !response.status().is_success() {
+			return Err(eyre!("Unexpected response status {}", response.status()));
+		}
+		let body = body::to_bytes(response.into_body()).await?;
+		let BeaconRound { round, randomness } =
+			serde_json::from_slice(&body).wrap_err("Invalid beacon round")?;
+		let randomness = hex::decode(randomness).wrap_err("Invalid beacon randomness")?;
+		if randomness.is_empty() {
+			return Err(eyre!("Empty beacon randomness"));
+		}
+		Ok((round, randomness))
+	}
+}
+
+#[async_trait]
+impl Randomness for Beacon {
+	async fn rng(&self, block_hash: H256) -> (StdRng, RandomnessSource) {
+		match self.latest().await {
+			Ok((round, randomness)) => {
+				let mut local = [0u8; 32];
+				OsRng.fill_bytes(&mut local);
+				let seed = seed(&randomness, block_hash, local);
+				(StdRng::from_seed(seed), RandomnessSource::Beacon { round })
+			},
+			Err(error) => {
+				warn!("Randomness beacon is not available, using local entropy: {error:#}");
+				OsRandomness.rng(block_hash).await
+			},
+		}
+	}
+}
+
+/// Creates randomness of the configured source
+pub fn new(beacon_url: Option<&str>, proxy: Option<Proxy>) -> Arc<dyn Randomness> {
+	match beacon_url {
+		Some(url) => Arc::new(Beacon::new(url, proxy)),
+		None => Arc::new(OsRandomness),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::verification::select_cells;
+	use kate_recovery::matrix::Dimensions;
+
+	#[test]
+	fn beacon_seed() {
+		let block_hash = H256::repeat_byte(1);
+		let seed_1 = seed(&[1, 2, 3], block_hash, [0; 32]);
+		assert_eq!(seed_1, seed(&[1, 2, 3], block_hash, [0; 32]));
+		assert_ne!(seed_1, seed(&[1, 2, 4], block_hash, [0; 32]));
+		assert_ne!(seed_1, seed(&[1, 2, 3], H256::repeat_byte(2), [0; 32]));
+		assert_ne!(seed_1, seed(&[1, 2, 3], block_hash, [1; 32]));
+
+		let dimensions = Dimensions::new(16, 64).unwrap();
+		let cells = |seed| select_cells(dimensions, 10, &mut StdRng::from_seed(seed));
+		let mut positions = cells(seed_1);
+		let mut same_positions = cells(seed_1);
+		positions.sort_by_key(|position| (position.row, position.col));
+		same_positions.sort_by_key(|position| (position.row, position.col));
+		assert_eq!(positions.len(), 10);
+		assert_eq!(positions, same_positions);
+	}
+}
@@ -0,0 +1,251 @@
+//! Recording and deterministic replay of the block verification.
+//!
+//! Recorder stores verification inputs of each block (header, sampled positions and cells fetched from DHT or RPC) as JSON lines,
+//! with hex encoded SCALE header and cell contents.
+//! Replay re-runs proof verification from the recording, without network access,
+//! so verification failures of a specific block can be reproduced offline.
+
+use avail_subxt::primitives::Header;
+use codec::{Decode, Encode};
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use kate_recovery::{data::Cell, matrix::Position};
+use serde::{Deserialize, Serialize};
+use std::{
+	fs::OpenOptions,
+	io::{BufRead, Write},
+	sync::Mutex,
+};
+
+use crate::{
+	utils::calculate_confidence,
+	verification::{parse_header, verify_cells},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordedCell {
+	pub row: u32,
+	pub col: u16,
+	/// Hex encoded cell content, with proof
+	pub content: String,
+}
+
+impl From<&Cell> for RecordedCell {
+	fn from(cell: &Cell) -> Self {
+		RecordedCell {
+			row: cell.position.row,
+			col: cell.position.col,
+			content: hex::encode(cell.content),
+		}
+	}
+}
+
+impl TryFrom<&RecordedCell> for Cell {
+	type Error = color_eyre::Report;
+
+	fn try_from(cell: &RecordedCell) -> Result<Self> {
+		let content = hex::decode(&cell.content).wrap_err("Failed to decode cell content")?;
+		Ok(Cell {
+			position: Position {
+				row: cell.row,
+				col: cell.col,
+			},
+			content: content
+				.try_into()
+				.map_err(|_| eyre!("Invalid cell content length"))?,
+		})
+	}
+}
+
+/// Verification inputs of a single block
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recording {
+	pub block_number: u32,
+	/// Hex encoded SCALE header
+	pub header: String,
+	/// Positions sampled for the verification
+	pub positions: Vec<(u32, u16)>,
+	/// Cells fetched from the DHT or RPC
+	pub cells: Vec<RecordedCell>,
+}
+
+impl Recording {
+	pub fn new(header: &Header, positions: &[Position], cells: &[Cell]) -> Self {
+		Recording {
+			block_number: header.number,
+			header: hex::encode(header.encode()),
+			positions: positions
+				.iter()
+				.map(|position| (position.row, position.col))
+				.collect(),
+			cells: cells.iter().map(From::from).collect(),
+		}
+	}
+}
+
+/// Appends recordings of the configured block range to the file
+pub struct Recorder {
+	path: String,
+	start_block: Option<u32>,
+	end_block: Option<u32>,
+	lock: Mutex<()>,
+}
+
+impl Recorder {
+	pub fn new(path: String, start_block: Option<u32>, end_block: Option<u32>) -> Self {
+		Recorder {
+			path,
+			start_block,
+			end_block,
+			lock: Mutex::new(()),
+		}
+	}
+
+	/// Returns `true` if block is in the recorded range
+	pub fn is_recorded(&self, block_number: u32) -> bool {
+		self.start_block.is_none_or(|start| block_number >= start)
+			&& self.end_block.is_none_or(|end| block_number <= end)
+	}
+
+	pub fn record(&self, recording: &Recording) -> Result<()> {
+		let _lock = self.lock.lock().expect("Lock acquired");
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.wrap_err(format!("Failed to open recording file {}", self.path))?;
+		let mut line = serde_json::to_vec(recording)?;
+		line.push(b'\n');
+		file.write_all(&line)?;
+		Ok(())
+	}
+}
+
+/// Result of the replayed block verification
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Replay {
+	pub block_number: u32,
+	pub requested: usize,
+	pub fetched: usize,
+	pub verified: usize,
+	/// Positions of the cells which failed the proof verification
+	pub failed: Vec<(u32, u16)>,
+	pub confidence: Option<f64>,
+}
+
+/// Re-runs verification of the recorded block
+pub fn replay(public_parameters: &PublicParameters, recording: &Recording) -> Result<Replay> {
+	let header_bytes = hex::decode(&recording.header).wrap_err("Failed to decode header")?;
+	let header = Header::decode(&mut &header_bytes[..]).wrap_err("Failed to decode header")?;
+	let cells = recording
+		.cells
+		.iter()
+		.map(Cell::try_from)
+		.collect::<Result<Vec<_>>>()?;
+
+	let mut replay = Replay {
+		block_number: recording.block_number,
+		requested: recording.positions.len(),
+		fetched: cells.len(),
+		verified: 0,
+		failed: vec![],
+		confidence: None,
+	};
+	let Some(block) = parse_header(&header.extension)? else {
+		return Ok(replay);
+	};
+	let (verified, failed) = verify_cells(public_parameters, &block, &cells)?;
+	replay.verified = verified.len();
+	replay.failed = failed
+		.iter()
+		.map(|position| (position.row, position.col))
+		.collect();
+	replay.confidence = Some(calculate_confidence(verified.len() as u32));
+	Ok(replay)
+}
+
+/// Reads recordings from the reader, skipping empty lines
+pub fn read(reader: impl BufRead) -> impl Iterator<Item = Result<Recording>> {
+	reader
+		.lines()
+		.enumerate()
+		.filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+		.map(|(index, line)| {
+			serde_json::from_str(&line?)
+				.wrap_err(format!("Failed to parse recording on line {}", index + 1))
+		})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use avail_subxt::{
+		api::runtime_types::avail_core::{
+			data_lookup::compact::CompactDataLookup,
+			header::extension::{v3, HeaderExtension::V3},
+			kate_commitment::v3::KateCommitment,
+		},
+		config::substrate::Digest,
+	};
+
+	fn header(number: u32) -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Digest { logs: vec![] },
+			extension: V3(v3::HeaderExtension {
+				commitment: KateCommitment {
+					rows: 1,
+					cols: 4,
+					data_root: Default::default(),
+					commitment: vec![0; 48],
+				},
+				app_lookup: CompactDataLookup {
+					size: 1,
+					index: vec![],
+				},
+			}),
+		}
+	}
+
+	#[test]
+	fn recording_roundtrip() {
+		let cell = Cell {
+			position: Position { row: 1, col: 2 },
+			content: [7; 80],
+		};
+		let recording = Recording::new(&header(5), &[cell.position], &[cell.clone()]);
+		let line = serde_json::to_string(&recording).unwrap();
+		let recordings = read(format!("{line}\n\n{line}\n").as_bytes())
+			.collect::<Result<Vec<_>>>()
+			.unwrap();
+		assert_eq!(recordings, vec![recording.clone(), recording]);
+		let decoded = Cell::try_from(&recordings[0].cells[0]).unwrap();
+		assert_eq!(decoded.position, cell.position);
+		assert_eq!(decoded.content, cell.content);
+	}
+
+	#[test]
+	fn replay_unfetched() {
+		let pp = kate_recovery::couscous::public_params();
+		let recording = Recording::new(&header(5), &[Position { row: 0, col: 0 }], &[]);
+		let replay = replay(&pp, &recording).unwrap();
+		assert_eq!(replay.requested, 1);
+		assert_eq!(replay.fetched, 0);
+		assert_eq!(replay.confidence, Some(0.0));
+	}
+
+	#[test]
+	fn recorder_range() {
+		let recorder = Recorder::new("recording.jsonl".to_string(), Some(10), Some(20));
+		assert!(!recorder.is_recorded(9));
+		assert!(recorder.is_recorded(10));
+		assert!(recorder.is_recorded(20));
+		assert!(!recorder.is_recorded(21));
+	}
+}
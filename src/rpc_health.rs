@@ -0,0 +1,170 @@
+//! Health of the RPC header subscription.
+//!
+//! Time of the last received header, number of the subscription restarts and number of the gaps between the received headers
+//! are tracked in the shared state, recorded with the `avail.light.rpc.*` metrics, and exposed with the `rpc_subscription` field of `GET /v2/status`.
+//!
+//! If no header is received for longer than `rpc_stall_blocks` expected block times (`rpc_block_time`), the subscription is reported as stalled,
+//! and the light client transitions to the `degraded` state, until the next header is received.
+
+use serde::{Deserialize, Serialize};
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::{
+	lifecycle::{Degradation, Lifecycle},
+	telemetry::{MetricValue, Metrics},
+	types::State,
+	utils::unix_timestamp_millis,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionHealth {
+	/// Time at which the subscription is started (milliseconds since UNIX epoch)
+	#[serde(skip)]
+	pub started_at: u64,
+	/// Time of the last received header (milliseconds since UNIX epoch)
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_header_at: Option<u64>,
+	/// Number of the last received header
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_header: Option<u32>,
+	/// Number of the subscription restarts, after the stream has ended or failed
+	pub restarts: u64,
+	/// Number of the gaps between the received headers
+	pub header_gaps: u64,
+	/// No header is received for longer than expected
+	pub stalled: bool,
+}
+
+impl SubscriptionHealth {
+	pub fn new(started_at: u64) -> Self {
+		SubscriptionHealth {
+			started_at,
+			..Default::default()
+		}
+	}
+
+	/// Records the received header, returning number of the missing headers since the previous one
+	pub fn header_received(&mut self, block_number: u32, received_at: u64) -> u32 {
+		let missing = self
+			.last_header
+			.map(|last| block_number.saturating_sub(last).saturating_sub(1))
+			.unwrap_or(0);
+		if missing > 0 {
+			self.header_gaps += 1;
+		}
+		self.last_header = Some(
+			self.last_header
+				.map_or(block_number, |last| last.max(block_number)),
+		);
+		self.last_header_at = Some(received_at);
+		missing
+	}
+
+	/// Returns time since the last received header, or since the subscription start
+	pub fn since_last_header(&self, now: u64) -> Duration {
+		let last = self.last_header_at.unwrap_or(self.started_at);
+		Duration::from_millis(now.saturating_sub(last))
+	}
+}
+
+/// Records subscription start in the shared state, counting it as restart if the subscription was already started
+pub fn started(state: &Mutex<State>) {
+	let mut state = state.lock().expect("Lock acquired");
+	match state.rpc_subscription.as_mut() {
+		Some(health) => health.restarts += 1,
+		None => state.rpc_subscription = Some(SubscriptionHealth::new(unix_timestamp_millis())),
+	}
+}
+
+/// Records subscription restart in the shared state
+pub fn restarted(state: &Mutex<State>) {
+	if let Some(health) = state
+		.lock()
+		.expect("Lock acquired")
+		.rpc_subscription
+		.as_mut()
+	{
+		health.restarts += 1;
+	}
+}
+
+/// Checks the subscription health each expected block time, degrading the light client while the subscription is stalled
+pub async fn run(
+	state: Arc<Mutex<State>>,
+	lifecycle: Lifecycle,
+	metrics: Arc<impl Metrics>,
+	block_time: Duration,
+	stall_blocks: u32,
+) {
+	info!("Starting RPC subscription health monitoring...");
+	let max_delay = block_time * stall_blocks;
+	let mut interval = tokio::time::interval(block_time);
+	loop {
+		interval.tick().await;
+		let (health, since_last_header, was_stalled) = {
+			let mut state = state.lock().expect("Lock acquired");
+			let Some(health) = state.rpc_subscription.as_mut() else {
+				continue;
+			};
+			let since_last_header = health.since_last_header(unix_timestamp_millis());
+			let was_stalled = std::mem::replace(&mut health.stalled, since_last_header > max_delay);
+			(health.clone(), since_last_header, was_stalled)
+		};
+
+		// stall is logged as warning once, until the next header is received
+		match (was_stalled, health.stalled) {
+			(false, true) => warn!(
+				last_header = health.last_header,
+				"No header received from RPC for {since_last_header:?} (maximum {max_delay:?}), subscription is stalled"
+			),
+			(true, false) => info!(
+				last_header = health.last_header,
+				"RPC subscription is recovered"
+			),
+			_ => (),
+		}
+		lifecycle.degrade(Degradation::RpcStalled, health.stalled);
+
+		metrics
+			.record(MetricValue::RPCSecondsSinceLastHeader(
+				since_last_header.as_secs(),
+			))
+			.await;
+		metrics
+			.record(MetricValue::RPCSubscriptionRestarts(health.restarts))
+			.await;
+		metrics
+			.record(MetricValue::RPCHeaderGaps(health.header_gaps))
+			.await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn subscription_health() {
+		let mut health = SubscriptionHealth::new(1_000);
+		assert_eq!(health.since_last_header(4_000), Duration::from_secs(3));
+
+		assert_eq!(health.header_received(10, 5_000), 0);
+		assert_eq!(health.header_received(11, 6_000), 0);
+		assert_eq!(health.header_gaps, 0);
+		assert_eq!(health.since_last_header(8_000), Duration::from_secs(2));
+
+		// missed headers are counted as one gap
+		assert_eq!(health.header_received(14, 7_000), 2);
+		assert_eq!(health.header_gaps, 1);
+
+		// repeated and older headers are not gaps
+		assert_eq!(health.header_received(14, 8_000), 0);
+		assert_eq!(health.header_received(12, 9_000), 0);
+		assert_eq!(health.header_gaps, 1);
+		assert_eq!(health.last_header, Some(14));
+	}
+}
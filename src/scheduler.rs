@@ -0,0 +1,161 @@
+//! Two-lane scheduler of the head-of-chain and historical work.
+//!
+//! Work on the new blocks (light client, best chain and app client verification) runs in the head lane, and is never delayed.
+//! Work on the past blocks (sync client and crawler) runs in the historical lane, and is preempted by the head lane:
+//! while any head work is running, only the configured number of historical steps (historical lane weight) can run concurrently.
+//! Once head work completes, historical work runs without limits.
+//!
+//! # Notes
+//!
+//! Lane permits are acquired for each DHT fetch chunk, RPC fetch and proof verification, so the preemption granularity is a single step,
+//! and the head block doesn't wait for the whole historical block to be processed.
+
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lane {
+	/// Work on the new blocks
+	Head,
+	/// Work on the past blocks, preempted by the head lane
+	Historical,
+}
+
+#[derive(Debug, Default)]
+struct Running {
+	head: usize,
+	historical: usize,
+}
+
+#[derive(Debug)]
+struct Inner {
+	running: Mutex<Running>,
+	notify: Notify,
+	/// Maximum number of concurrent historical steps while head work is running, `None` if historical work is not limited
+	historical_weight: Option<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scheduler(Arc<Inner>);
+
+impl Default for Scheduler {
+	/// Scheduler which doesn't limit historical work
+	fn default() -> Self {
+		Self::new(None)
+	}
+}
+
+impl Scheduler {
+	pub fn new(historical_weight: Option<usize>) -> Self {
+		Scheduler(Arc::new(Inner {
+			running: Mutex::new(Running::default()),
+			notify: Notify::new(),
+			historical_weight,
+		}))
+	}
+
+	fn try_acquire(&self, lane: Lane) -> bool {
+		let mut running = self.0.running.lock().expect("Lock acquired");
+		match lane {
+			Lane::Head => running.head += 1,
+			Lane::Historical => {
+				let limited = running.head > 0
+					&& self
+						.0
+						.historical_weight
+						.is_some_and(|weight| running.historical >= weight);
+				if limited {
+					return false;
+				}
+				running.historical += 1;
+			},
+		}
+		true
+	}
+
+	/// Waits until the step in the given lane can run, returning the permit which is released on drop.
+	/// Head lane permits are acquired immediately.
+	pub async fn acquire(&self, lane: Lane) -> Permit {
+		loop {
+			// notification is registered before checking, so the release between the check and the wait is not missed
+			let notified = self.0.notify.notified();
+			if self.try_acquire(lane) {
+				return Permit {
+					scheduler: self.clone(),
+					lane,
+				};
+			}
+			notified.await;
+		}
+	}
+
+	fn release(&self, lane: Lane) {
+		let mut running = self.0.running.lock().expect("Lock acquired");
+		match lane {
+			Lane::Head => running.head -= 1,
+			Lane::Historical => running.historical -= 1,
+		}
+		drop(running);
+		self.0.notify.notify_waiters();
+	}
+}
+
+/// Permit to run the step in the lane, released on drop
+#[derive(Debug)]
+pub struct Permit {
+	scheduler: Scheduler,
+	lane: Lane,
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		self.scheduler.release(self.lane);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[tokio::test]
+	async fn head_preempts_historical() {
+		let scheduler = Scheduler::new(Some(1));
+		let first = scheduler.acquire(Lane::Historical).await;
+		let _second = scheduler.acquire(Lane::Historical).await;
+
+		let head = scheduler.acquire(Lane::Head).await;
+		drop(first);
+		// only one historical step runs while head work is running
+		let historical = tokio::time::timeout(
+			Duration::from_millis(50),
+			scheduler.acquire(Lane::Historical),
+		);
+		assert!(historical.await.is_err());
+
+		let waiting = tokio::spawn({
+			let scheduler = scheduler.clone();
+			async move { scheduler.acquire(Lane::Historical).await }
+		});
+		drop(head);
+		assert!(tokio::time::timeout(Duration::from_secs(1), waiting)
+			.await
+			.is_ok());
+	}
+
+	#[tokio::test]
+	async fn historical_paused() {
+		let scheduler = Scheduler::new(Some(0));
+		let _head = scheduler.acquire(Lane::Head).await;
+		let _other_head = scheduler.acquire(Lane::Head).await;
+		let historical = tokio::time::timeout(
+			Duration::from_millis(50),
+			scheduler.acquire(Lane::Historical),
+		);
+		assert!(historical.await.is_err());
+
+		let unlimited = Scheduler::default();
+		let _head = unlimited.acquire(Lane::Head).await;
+		let _historical = unlimited.acquire(Lane::Historical).await;
+	}
+}
@@ -2,21 +2,75 @@ use std::{
 	fmt::Debug,
 	future::Future,
 	mem,
+	ops::Index,
 	sync::{Arc, Mutex},
 	task::Waker,
+	time::Duration,
 };
 
+pub use self::reason::ShutdownReason;
 use self::{
 	completed::Completed, signal::Signal, with_cancel::WithCancel, with_delay::WithDelay,
 	with_trigger::WithTrigger,
 };
 
 mod completed;
+mod reason;
 mod signal;
 mod with_cancel;
 mod with_delay;
 mod with_trigger;
 
+/// Ordered phases of the graceful shutdown.
+///
+/// Once the shutdown is triggered, each phase is triggered after the previous one completes,
+/// or after the previous one exceeds its deadline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+	/// Stop accepting API requests
+	Api,
+	/// Drain in-flight block verification
+	Verification,
+	/// Flush metrics
+	Telemetry,
+	/// Close database
+	Database,
+	/// Close swarm
+	Network,
+}
+
+impl Phase {
+	/// All phases, in the order of the enum variants
+	pub const ALL: &'static [Phase] = &[
+		Phase::Api,
+		Phase::Verification,
+		Phase::Telemetry,
+		Phase::Database,
+		Phase::Network,
+	];
+}
+
+const PHASES: usize = Phase::ALL.len();
+
+/// Controllers of the shutdown phases, indexed by the phase
+struct PhaseControllers<T>([Arc<Mutex<ControllerInner<T>>>; PHASES]);
+
+impl<T: Clone> PhaseControllers<T> {
+	fn new() -> Self {
+		PhaseControllers(std::array::from_fn(|_| {
+			Arc::new(Mutex::new(ControllerInner::new()))
+		}))
+	}
+}
+
+impl<T> Index<Phase> for PhaseControllers<T> {
+	type Output = Arc<Mutex<ControllerInner<T>>>;
+
+	fn index(&self, phase: Phase) -> &Self::Output {
+		&self.0[phase as usize]
+	}
+}
+
 #[derive(Clone)]
 /// Shutdown controller for graceful shutdowns in async code.
 ///
@@ -32,6 +86,7 @@ mod with_trigger;
 /// Take care to wrap futures before spawning to avoid potential data loss on shutdown.
 pub struct Controller<T: Clone> {
 	inner: Arc<Mutex<ControllerInner<T>>>,
+	phases: Arc<PhaseControllers<T>>,
 }
 
 impl<T: Clone> Controller<T> {
@@ -40,7 +95,40 @@ impl<T: Clone> Controller<T> {
 	pub fn new() -> Self {
 		Self {
 			inner: Arc::new(Mutex::new(ControllerInner::new())),
+			phases: Arc::new(PhaseControllers::new()),
+		}
+	}
+
+	/// Returns the controller of the given shutdown phase.
+	///
+	/// Phase controller is triggered by [`Self::phased_shutdown()`], once all previous phases are completed.
+	/// Futures which need to be stopped or drained in this phase should be wrapped with the phase controller,
+	/// so the phase completes when they are done.
+	pub fn phase(&self, phase: Phase) -> Self {
+		Self {
+			inner: self.phases[phase].clone(),
+			phases: self.phases.clone(),
+		}
+	}
+
+	/// Awaits the triggering of the shutdown and runs shutdown phases in order.
+	///
+	/// Each phase is triggered with the shutdown reason, and awaited until completed or until the timeout expires.
+	/// Returns the shutdown reason and the list of phases which didn't complete before the timeout.
+	pub async fn phased_shutdown(&self, timeout: Duration) -> (T, Vec<Phase>) {
+		let reason = self.triggered_shutdown().await;
+		let mut timed_out = vec![];
+		for &phase in Phase::ALL {
+			let controller = self.phase(phase);
+			let _ = controller.trigger_shutdown(reason.clone());
+			if tokio::time::timeout(timeout, controller.completed_shutdown())
+				.await
+				.is_err()
+			{
+				timed_out.push(phase);
+			}
 		}
+		(reason, timed_out)
 	}
 
 	/// Checks if the shutdown has been triggered.
@@ -374,7 +462,7 @@ mod tests {
 		time::{sleep, timeout},
 	};
 
-	use crate::shutdown::{Controller, ShutdownHasCompleted, ShutdownHasStarted};
+	use crate::shutdown::{Controller, Phase, ShutdownHasCompleted, ShutdownHasStarted};
 
 	// using custom runtime to create non-blocking promises instead of `[tokio::test]`,
 	// ensuring predictable asynchronous operations without indefinite blocking
@@ -635,4 +723,58 @@ mod tests {
 			assert!(shutdown.completed_shutdown().await == "you shall not pass");
 		});
 	}
+
+	#[test]
+	fn phases_order() {
+		for (index, phase) in Phase::ALL.iter().enumerate() {
+			assert_eq!(*phase as usize, index);
+		}
+	}
+
+	#[test]
+	fn phased_shutdown_in_order() {
+		test_runtime(async {
+			let shutdown = Controller::new();
+			let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+			for &phase in Phase::ALL.iter().rev() {
+				let controller = shutdown.phase(phase);
+				let sender = sender.clone();
+				let signal = controller.triggered_shutdown();
+				tokio::spawn(
+					controller
+						.with_delay(async move {
+							signal.await;
+							sleep(Duration::from_millis(5)).await;
+							sender.send(phase).unwrap();
+						})
+						.unwrap(),
+				);
+			}
+
+			assert!(shutdown.trigger_shutdown("phased").is_ok());
+			let (reason, timed_out) = shutdown.phased_shutdown(Duration::from_millis(50)).await;
+			assert_eq!(reason, "phased");
+			assert!(timed_out.is_empty());
+
+			let mut completed = vec![];
+			while let Ok(phase) = receiver.try_recv() {
+				completed.push(phase);
+			}
+			assert_eq!(completed, Phase::ALL);
+		});
+	}
+
+	#[test]
+	fn phased_shutdown_timeout() {
+		test_runtime(async {
+			let shutdown = Controller::new();
+			let _token = shutdown.phase(Phase::Verification).delay_token().unwrap();
+
+			assert!(shutdown.trigger_shutdown("phased").is_ok());
+			let (_, timed_out) = shutdown.phased_shutdown(Duration::from_millis(10)).await;
+			assert_eq!(timed_out, vec![Phase::Verification]);
+			assert!(shutdown.phase(Phase::Network).is_shutdown_completed());
+		});
+	}
 }
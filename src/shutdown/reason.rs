@@ -0,0 +1,59 @@
+use color_eyre::Report;
+use std::fmt::{self, Display};
+
+use super::Controller;
+
+/// Cause of the light client shutdown, mapped to the process exit code.
+///
+/// Exit codes follow `sysexits.h` conventions where applicable, so orchestrators and scripts
+/// can distinguish configuration errors (which shouldn't be retried) from transient failures.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShutdownReason {
+	/// Shutdown requested with a signal
+	Signal(String),
+	/// Configuration is invalid or cannot be loaded
+	ConfigError(String),
+	/// None of the configured RPC nodes are reachable
+	RpcUnreachable(String),
+	/// Database is corrupted
+	DbCorruption(String),
+	/// Panic occurred in one of the tasks
+	InternalPanic(String),
+	/// Other runtime failure
+	Error(String),
+}
+
+impl ShutdownReason {
+	/// Returns process exit code for the shutdown reason
+	pub fn exit_code(&self) -> i32 {
+		match self {
+			ShutdownReason::Signal(_) => 0,
+			ShutdownReason::Error(_) => 1,
+			ShutdownReason::RpcUnreachable(_) => 69,
+			ShutdownReason::InternalPanic(_) => 70,
+			ShutdownReason::DbCorruption(_) => 74,
+			ShutdownReason::ConfigError(_) => 78,
+		}
+	}
+}
+
+impl Display for ShutdownReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ShutdownReason::Signal(message) => write!(f, "Signal: {message}"),
+			ShutdownReason::ConfigError(message) => write!(f, "Configuration error: {message}"),
+			ShutdownReason::RpcUnreachable(message) => write!(f, "RPC unreachable: {message}"),
+			ShutdownReason::DbCorruption(message) => write!(f, "Database corruption: {message}"),
+			ShutdownReason::InternalPanic(message) => write!(f, "Internal panic: {message}"),
+			ShutdownReason::Error(message) => write!(f, "{message}"),
+		}
+	}
+}
+
+impl Controller<ShutdownReason> {
+	/// Triggers the shutdown with the reason created from the error, and returns the error
+	pub fn fail(&self, reason: fn(String) -> ShutdownReason, error: Report) -> Report {
+		let _ = self.trigger_shutdown(reason(format!("{error:#}")));
+		error
+	}
+}
@@ -0,0 +1,311 @@
+//! In-process simulation of the light client network.
+//!
+//! Simulation runs N light clients and a fake fat client against an in-memory DHT, without sockets or a live chain,
+//! so integration tests can assert confidence propagation under the configured faults.
+//! Light clients run the real block processing ([`light_client::process_block`]) with the in-memory databases,
+//! and fetch cells with the simulated network client.
+//!
+//! # Notes
+//!
+//! * Cell proofs are not generated, so cells published by the fat client are considered valid unless corrupted by the fault injection
+//! * Network latency is accounted on the [`VirtualClock`], so simulations don't sleep
+//! * All random decisions are made with the seeded RNG, so simulations are deterministic
+
+use async_trait::async_trait;
+use avail_subxt::{
+	api::runtime_types::avail_core::{
+		data_lookup::compact::CompactDataLookup,
+		header::extension::{v3, HeaderExtension},
+		kate_commitment::v3::KateCommitment,
+	},
+	config::substrate::Digest,
+	primitives::Header,
+	utils::H256,
+};
+use color_eyre::Result;
+use kate_recovery::{
+	config,
+	data::Cell,
+	matrix::{Dimensions, Position},
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use crate::{
+	data::mem_db::MemoryDB,
+	light_client,
+	network::{self, FetchStats},
+	telemetry::NoopMetrics,
+	types::{LightClientConfig, RuntimeConfig, State},
+};
+
+/// Faults injected into the simulated network
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+	/// Fraction of the DHT queries which are dropped
+	pub dht_drop_rate: f64,
+	/// Fraction of the published cells which are corrupted, and fail the verification
+	pub cell_corruption_rate: f64,
+	/// Latency of each DHT query
+	pub dht_latency: Duration,
+}
+
+/// Clock advanced by the simulated network latency
+#[derive(Clone, Default)]
+pub struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+	/// Returns elapsed virtual time
+	pub fn now(&self) -> Duration {
+		Duration::from_micros(self.0.load(Ordering::SeqCst))
+	}
+
+	pub fn advance(&self, duration: Duration) {
+		self.0
+			.fetch_add(duration.as_micros() as u64, Ordering::SeqCst);
+	}
+}
+
+struct StoredCell {
+	cell: Cell,
+	corrupted: bool,
+}
+
+#[derive(Clone)]
+struct Dht {
+	cells: Arc<Mutex<HashMap<(u32, Position), StoredCell>>>,
+	rng: Arc<Mutex<StdRng>>,
+	faults: Faults,
+	clock: VirtualClock,
+}
+
+impl Dht {
+	fn sample(&self, rate: f64) -> bool {
+		rate > 0.0
+			&& self
+				.rng
+				.lock()
+				.expect("Lock acquired")
+				.gen_bool(rate.min(1.0))
+	}
+}
+
+/// Network client of the simulated light client, fetching cells from the in-memory DHT
+#[derive(Clone)]
+struct SimulatedClient {
+	dht: Dht,
+}
+
+#[async_trait]
+impl network::Client for SimulatedClient {
+	async fn fetch_verified(
+		&self,
+		block_number: u32,
+		_: H256,
+		_: Dimensions,
+		_: &[[u8; config::COMMITMENT_SIZE]],
+		positions: &[Position],
+		_: Option<tokio::time::Instant>,
+	) -> Result<(Vec<Cell>, Vec<Position>, FetchStats)> {
+		let (mut fetched, mut unfetched) = (vec![], vec![]);
+		for position in positions {
+			self.dht.clock.advance(self.dht.faults.dht_latency);
+			if self.dht.sample(self.dht.faults.dht_drop_rate) {
+				unfetched.push(*position);
+				continue;
+			}
+			let cells = self.dht.cells.lock().expect("Lock acquired");
+			match cells.get(&(block_number, *position)) {
+				Some(stored) if !stored.corrupted => fetched.push(stored.cell.clone()),
+				_ => unfetched.push(*position),
+			}
+		}
+		let duration = self.dht.faults.dht_latency * positions.len() as u32;
+		let stats = FetchStats::new(positions.len(), fetched.len(), duration, None);
+		Ok((fetched, unfetched, stats))
+	}
+}
+
+/// Simulated light client
+pub struct LightNode {
+	pub db: MemoryDB,
+	pub state: Arc<Mutex<State>>,
+	client: SimulatedClient,
+}
+
+/// Simulated network with N light clients and the fake fat client
+pub struct Simulation {
+	pub nodes: Vec<LightNode>,
+	pub clock: VirtualClock,
+	dht: Dht,
+	cfg: LightClientConfig,
+}
+
+impl Simulation {
+	/// Creates simulation with the given number of light clients, faults and RNG seed
+	pub fn new(nodes: usize, faults: Faults, seed: u64) -> Self {
+		let clock = VirtualClock::default();
+		let dht = Dht {
+			cells: Default::default(),
+			rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+			faults,
+			clock: clock.clone(),
+		};
+		let nodes = (0..nodes)
+			.map(|_| LightNode {
+				db: MemoryDB::default(),
+				state: Arc::new(Mutex::new(State::default())),
+				client: SimulatedClient { dht: dht.clone() },
+			})
+			.collect();
+		Simulation {
+			nodes,
+			clock,
+			dht,
+			cfg: LightClientConfig::from(&RuntimeConfig::default()),
+		}
+	}
+
+	/// Creates header of the block with the given matrix dimensions
+	pub fn header(block_number: u32, rows: u16, cols: u16) -> Header {
+		Header {
+			parent_hash: Default::default(),
+			number: block_number,
+			state_root: Default::default(),
+			extrinsics_root: Default::default(),
+			digest: Digest { logs: vec![] },
+			extension: HeaderExtension::V3(v3::HeaderExtension {
+				commitment: KateCommitment {
+					rows,
+					cols,
+					data_root: Default::default(),
+					commitment: vec![0; config::COMMITMENT_SIZE * rows as usize],
+				},
+				app_lookup: CompactDataLookup {
+					size: 1,
+					index: vec![],
+				},
+			}),
+		}
+	}
+
+	/// Publishes cells of the extended matrix to the DHT, as the fat client does.
+	/// Only the given fraction of the cells is published, to simulate partial data availability.
+	pub fn publish(&self, header: &Header, fraction: f64) {
+		let HeaderExtension::V3(extension) = &header.extension;
+		let Some(dimensions) =
+			Dimensions::new(extension.commitment.rows, extension.commitment.cols)
+		else {
+			return;
+		};
+		let positions = (0..dimensions.extended_rows())
+			.flat_map(|row| (0..dimensions.cols().get()).map(move |col| Position { row, col }))
+			.collect::<Vec<_>>();
+		let published = (positions.len() as f64 * fraction.clamp(0.0, 1.0)).ceil() as usize;
+		for position in positions.into_iter().take(published) {
+			let stored = StoredCell {
+				cell: Cell {
+					position,
+					content: [0u8; config::COMMITMENT_SIZE + 32],
+				},
+				corrupted: self.dht.sample(self.dht.faults.cell_corruption_rate),
+			};
+			self.dht
+				.cells
+				.lock()
+				.expect("Lock acquired")
+				.insert((header.number, position), stored);
+		}
+	}
+
+	/// Processes block on each light client, returning achieved confidences
+	pub async fn process_block(&self, header: &Header) -> Result<Vec<Option<f64>>> {
+		let metrics = Arc::new(NoopMetrics);
+		let mut confidences = Vec::with_capacity(self.nodes.len());
+		for node in &self.nodes {
+			let confidence = light_client::process_block(
+				node.db.clone(),
+				&node.client,
+				&metrics,
+				&self.cfg,
+				header.clone(),
+				Instant::now(),
+				node.state.clone(),
+			)
+			.await?;
+			confidences.push(confidence);
+		}
+		Ok(confidences)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		data::{Database, Key},
+		utils::calculate_confidence,
+		verification::cell_count_for_confidence,
+	};
+
+	fn cell_count() -> u32 {
+		cell_count_for_confidence(RuntimeConfig::default().confidence)
+	}
+
+	#[tokio::test]
+	async fn confidence_propagation() {
+		let simulation = Simulation::new(5, Faults::default(), 1);
+		let header = Simulation::header(1, 4, 4);
+		simulation.publish(&header, 1.0);
+
+		let confidences = simulation.process_block(&header).await.unwrap();
+		let confidence = calculate_confidence(cell_count());
+		assert_eq!(confidences, vec![Some(confidence); 5]);
+		for node in &simulation.nodes {
+			let count: Option<u32> = node.db.get(Key::VerifiedCellCount(1)).unwrap();
+			assert_eq!(count, Some(cell_count()));
+		}
+	}
+
+	#[tokio::test]
+	async fn unavailable_block() {
+		let simulation = Simulation::new(3, Faults::default(), 1);
+		let header = Simulation::header(1, 4, 4);
+
+		let confidences = simulation.process_block(&header).await.unwrap();
+		assert_eq!(confidences, vec![None; 3]);
+	}
+
+	#[tokio::test]
+	async fn faults() {
+		let faults = Faults {
+			dht_drop_rate: 1.0,
+			dht_latency: Duration::from_millis(100),
+			..Default::default()
+		};
+		let simulation = Simulation::new(2, faults, 1);
+		let header = Simulation::header(1, 4, 4);
+		simulation.publish(&header, 1.0);
+
+		let confidences = simulation.process_block(&header).await.unwrap();
+		assert_eq!(confidences, vec![None; 2]);
+		let latency = Duration::from_millis(100) * cell_count() * 2;
+		assert_eq!(simulation.clock.now(), latency);
+
+		let faults = Faults {
+			cell_corruption_rate: 1.0,
+			..Default::default()
+		};
+		let simulation = Simulation::new(2, faults, 1);
+		simulation.publish(&header, 1.0);
+		let confidences = simulation.process_block(&header).await.unwrap();
+		assert_eq!(confidences, vec![None; 2]);
+	}
+}
@@ -0,0 +1,217 @@
+//! Status summary of the running light client, for the `status` subcommand.
+//!
+//! Summary is collected from the light client HTTP API (`GET /v2/status`, `GET /v2/blocks/{block_number}` and `GET /v2/p2p/local/info`),
+//! using the Unix domain socket if `http_server_socket` is configured.
+//! Sync lag is the number of the received blocks, after the last block with the achieved confidence.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hyper::{body, header, Body, Request, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+	fmt::{self, Display, Formatter},
+	time::Duration,
+};
+use tokio::{
+	io::{AsyncRead, AsyncWrite},
+	net::TcpStream,
+};
+
+use crate::{
+	api::v2::types::{Block, Status},
+	lifecycle::LifecycleState,
+	types::RuntimeConfig,
+};
+
+/// Timeout of the single API request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct PeerInfo {
+	routing_table_peers_count: usize,
+}
+
+/// Status summary of the running light client
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+	pub prefix: Option<String>,
+	pub state: LifecycleState,
+	pub latest_block: u32,
+	/// Last block with the achieved confidence
+	pub available_block: Option<u32>,
+	pub confidence: Option<f64>,
+	/// Number of peers in the routing table
+	pub peers: Option<usize>,
+	pub rpc_host: String,
+}
+
+impl Summary {
+	pub fn sync_lag(&self) -> Option<u32> {
+		self.available_block
+			.map(|available| self.latest_block.saturating_sub(available))
+	}
+}
+
+fn format_option<T: Display>(value: Option<T>) -> String {
+	value
+		.map(|value| value.to_string())
+		.unwrap_or_else(|| "n/a".to_string())
+}
+
+impl Display for Summary {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let state = serde_json::to_value(self.state)
+			.ok()
+			.and_then(|state| state.as_str().map(ToString::to_string))
+			.unwrap_or_default();
+		let confidence = self
+			.confidence
+			.map(|confidence| format!("{confidence:.2}%"));
+		let sync_lag = self.sync_lag().map(|lag| format!("{lag} blocks"));
+
+		if let Some(prefix) = &self.prefix {
+			writeln!(f, "{:<16}{prefix}", "Network")?;
+		}
+		writeln!(f, "{:<16}{state}", "State")?;
+		writeln!(f, "{:<16}{}", "Latest block", self.latest_block)?;
+		writeln!(
+			f,
+			"{:<16}{}",
+			"Available block",
+			format_option(self.available_block)
+		)?;
+		writeln!(f, "{:<16}{}", "Confidence", format_option(confidence))?;
+		writeln!(f, "{:<16}{}", "Sync lag", format_option(sync_lag))?;
+		writeln!(f, "{:<16}{}", "Peers", format_option(self.peers))?;
+		write!(f, "{:<16}{}", "RPC host", self.rpc_host)
+	}
+}
+
+/// Returns the RPC host from the `network` field of the status (`{host}/{system_version}/{spec_version}`)
+fn rpc_host(network: &str) -> &str {
+	network.rsplitn(3, '/').last().unwrap_or(network)
+}
+
+async fn send<S>(stream: S, request: Request<Body>) -> Result<(StatusCode, body::Bytes)>
+where
+	S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+	let (mut sender, connection) = hyper::client::conn::handshake(stream).await?;
+	tokio::spawn(connection);
+	let response = sender.send_request(request).await?;
+	let status = response.status();
+	Ok((status, body::to_bytes(response.into_body()).await?))
+}
+
+async fn get<T: DeserializeOwned>(cfg: &RuntimeConfig, path: &str) -> Result<T> {
+	let path = match &cfg.http_server_prefix {
+		Some(prefix) => format!("/{prefix}{path}"),
+		None => path.to_string(),
+	};
+	// light client listening on all interfaces is reached on the loopback
+	let host = match cfg.http_server_host.as_str() {
+		"0.0.0.0" => "127.0.0.1",
+		host => host,
+	};
+	let address = format!("{host}:{}", cfg.http_server_port);
+	let request = Request::get(&path)
+		.header(header::HOST, &address)
+		.body(Body::empty())?;
+
+	let response = async {
+		match &cfg.http_server_socket {
+			#[cfg(unix)]
+			Some(socket) => {
+				let stream = tokio::net::UnixStream::connect(socket)
+					.await
+					.wrap_err(format!("Cannot connect to unix:{socket}"))?;
+				send(stream, request).await
+			},
+			#[cfg(not(unix))]
+			Some(_) => Err(eyre!("Unix domain sockets are not supported")),
+			None => {
+				let stream = TcpStream::connect(&address)
+					.await
+					.wrap_err(format!("Cannot connect to {address}"))?;
+				send(stream, request).await
+			},
+		}
+	};
+	let (status, body) = tokio::time::timeout(REQUEST_TIMEOUT, response)
+		.await
+		.map_err(|_| eyre!("Request {path} timed out after {REQUEST_TIMEOUT:?}"))??;
+	if !status.is_success() {
+		return Err(eyre!("Request {path} failed with status {status}"));
+	}
+	serde_json::from_slice(&body).wrap_err(format!("Invalid response to {path}"))
+}
+
+/// Collects the status summary from the running light client API
+pub async fn run(cfg: &RuntimeConfig) -> Result<Summary> {
+	if !cfg.http_server_enable {
+		return Err(eyre!("HTTP server is not enabled"));
+	}
+	let status: Status = get(cfg, "/v2/status")
+		.await
+		.wrap_err("Light client is not reachable")?;
+
+	let available_block = status.blocks.available.as_ref().map(|range| range.last);
+	let confidence = match available_block {
+		Some(block_number) => {
+			get::<Block>(cfg, &format!("/v2/blocks/{block_number}"))
+				.await?
+				.confidence
+		},
+		None => None,
+	};
+	// P2P API is not available in the fat client mode
+	let peers = get::<PeerInfo>(cfg, "/v2/p2p/local/info")
+		.await
+		.ok()
+		.map(|info| info.routing_table_peers_count);
+
+	Ok(Summary {
+		prefix: cfg.http_server_prefix.clone(),
+		state: status.state,
+		latest_block: status.blocks.latest,
+		available_block,
+		confidence,
+		peers,
+		rpc_host: rpc_host(&status.network).to_string(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn summary() {
+		assert_eq!(
+			rpc_host("wss://rpc.avail.so/ws/Avail Node 2.1.0/22"),
+			"wss://rpc.avail.so/ws"
+		);
+
+		let mut summary = Summary {
+			prefix: None,
+			state: LifecycleState::Running,
+			latest_block: 105,
+			available_block: Some(100),
+			confidence: Some(99.951171875),
+			peers: Some(25),
+			rpc_host: "wss://rpc.avail.so/ws".to_string(),
+		};
+		assert_eq!(summary.sync_lag(), Some(5));
+		assert_eq!(
+			summary.to_string(),
+			"State           running\nLatest block    105\nAvailable block 100\nConfidence      99.95%\nSync lag        5 blocks\nPeers           25\nRPC host        wss://rpc.avail.so/ws"
+		);
+
+		summary.available_block = None;
+		summary.confidence = None;
+		assert_eq!(summary.sync_lag(), None);
+		assert!(summary.to_string().contains("Sync lag        n/a\n"));
+	}
+}
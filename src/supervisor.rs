@@ -0,0 +1,137 @@
+//! Supervisor for restarting failed subsystems.
+//!
+//! Failed subsystem is restarted after the backoff delay defined by the restart configuration.
+//! Once restarts are exhausted, failure is escalated to the process shutdown.
+//! Restarts are counted from the beginning if subsystem was running for longer than [`STABLE_RUN_DURATION`].
+
+use color_eyre::{Report, Result};
+use std::{
+	future::Future,
+	time::{Duration, Instant},
+};
+use tracing::{error, warn};
+
+use crate::{
+	shutdown::{Controller, ShutdownReason},
+	types::RetryConfig,
+};
+
+/// Subsystem running for longer than this duration before failure is considered stable
+pub const STABLE_RUN_DURATION: Duration = Duration::from_secs(600);
+
+pub struct Supervisor {
+	name: String,
+	restart_config: RetryConfig,
+	delays: std::vec::IntoIter<Duration>,
+	started_at: Instant,
+}
+
+impl Supervisor {
+	pub fn new(name: &str, restart_config: RetryConfig) -> Self {
+		Supervisor {
+			name: name.to_string(),
+			delays: restart_config.clone().into_iter(),
+			restart_config,
+			started_at: Instant::now(),
+		}
+	}
+
+	/// Handles subsystem failure, waiting for the backoff delay before the restart.
+	/// Returns an error if restarts are exhausted.
+	pub async fn restart(&mut self, error: Report) -> Result<()> {
+		if self.started_at.elapsed() >= STABLE_RUN_DURATION {
+			self.delays = self.restart_config.clone().into_iter();
+		}
+
+		let Some(delay) = self.delays.next() else {
+			return Err(error.wrap_err(format!("{} failed, restarts are exhausted", self.name)));
+		};
+
+		warn!(
+			subsystem = self.name,
+			?delay,
+			"Subsystem failed, restarting: {error:#}"
+		);
+		tokio::time::sleep(delay).await;
+		self.started_at = Instant::now();
+		Ok(())
+	}
+}
+
+/// Runs the subsystem created with the `start` function, restarting it on failure.
+/// Shutdown is triggered if restarts are exhausted.
+pub async fn supervise<F, Fut>(
+	name: &str,
+	restart_config: RetryConfig,
+	shutdown: Controller<ShutdownReason>,
+	mut start: F,
+) where
+	F: FnMut() -> Fut,
+	Fut: Future<Output = Result<()>>,
+{
+	let mut supervisor = Supervisor::new(name, restart_config);
+	loop {
+		let Err(error) = start().await else {
+			return;
+		};
+		if let Err(error) = supervisor.restart(error).await {
+			error!("{error:#}");
+			let _ = shutdown.trigger_shutdown(ShutdownReason::Error(format!("{error:#}")));
+			return;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::FibonacciConfig;
+	use color_eyre::eyre::eyre;
+	use std::sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	};
+
+	fn restart_config(retries: usize) -> RetryConfig {
+		RetryConfig::Fibonacci(FibonacciConfig {
+			base: 0,
+			max_delay: 0,
+			retries,
+		})
+	}
+
+	#[tokio::test]
+	async fn supervise_restarts_exhausted() {
+		let shutdown = Controller::new();
+		let starts = Arc::new(AtomicUsize::new(0));
+
+		supervise("test", restart_config(2), shutdown.clone(), || {
+			starts.fetch_add(1, Ordering::SeqCst);
+			async { Err(eyre!("failure")) }
+		})
+		.await;
+
+		assert_eq!(starts.load(Ordering::SeqCst), 3);
+		assert!(shutdown.is_shutdown_triggered());
+	}
+
+	#[tokio::test]
+	async fn supervise_recovers() {
+		let shutdown = Controller::new();
+		let starts = Arc::new(AtomicUsize::new(0));
+
+		supervise("test", restart_config(2), shutdown.clone(), || {
+			let start = starts.fetch_add(1, Ordering::SeqCst);
+			async move {
+				match start {
+					0 => Err(eyre!("failure")),
+					_ => Ok(()),
+				}
+			}
+		})
+		.await;
+
+		assert_eq!(starts.load(Ordering::SeqCst), 2);
+		assert!(!shutdown.is_shutdown_triggered());
+	}
+}
@@ -17,6 +17,7 @@
 
 use crate::{
 	data::{Database, Key},
+	event_bus::EventBus,
 	network::{
 		self,
 		rpc::{self, Client as RpcClient},
@@ -40,7 +41,6 @@ use std::{
 	sync::{Arc, Mutex},
 	time::Instant,
 };
-use tokio::sync::broadcast;
 use tracing::{error, info, warn};
 
 #[async_trait]
@@ -115,7 +115,7 @@ async fn process_block(
 	header: DaHeader,
 	header_hash: H256,
 	cfg: &SyncClientConfig,
-	block_verified_sender: broadcast::Sender<BlockVerified>,
+	block_verified_sender: EventBus<BlockVerified>,
 ) -> Result<()> {
 	let block_number = header.number;
 	let begin = Instant::now();
@@ -145,6 +145,7 @@ async fn process_block(
 					dimensions,
 					&commitments,
 					&positions,
+					None,
 				)
 				.await?;
 			(positions.len(), fetched.len(), unfetched.len())
@@ -163,9 +164,7 @@ async fn process_block(
 	let client_msg =
 		BlockVerified::try_from((header, confidence)).wrap_err("converting to message failed")?;
 
-	if let Err(error) = block_verified_sender.send(client_msg) {
-		error!("Cannot send block verified message: {error}");
-	}
+	block_verified_sender.send(client_msg);
 
 	Ok(())
 }
@@ -183,7 +182,7 @@ pub async fn run(
 	network_client: impl network::Client,
 	cfg: SyncClientConfig,
 	sync_range: Range<u32>,
-	block_verified_sender: broadcast::Sender<BlockVerified>,
+	block_verified_sender: EventBus<BlockVerified>,
 	state: Arc<Mutex<State>>,
 ) {
 	if sync_range.is_empty() {
@@ -306,7 +305,7 @@ mod tests {
 
 	#[tokio::test]
 	pub async fn test_process_blocks_without_rpc() {
-		let (block_tx, _) = broadcast::channel::<types::BlockVerified>(10);
+		let block_tx = EventBus::<types::BlockVerified>::new();
 		let mut cfg = SyncClientConfig::from(&RuntimeConfig::default());
 		cfg.disable_rpc = true;
 		let mut mock_network_client = network::MockClient::new();
@@ -317,7 +316,7 @@ mod tests {
 
 		mock_network_client
 			.expect_fetch_verified()
-			.returning(move |_, _, _, _, positions| {
+			.returning(move |_, _, _, _, positions, _| {
 				let unfetched = vec![];
 				let fetched: Vec<Cell> = vec![
 					Cell {
@@ -395,7 +394,7 @@ mod tests {
 
 	#[tokio::test]
 	pub async fn test_process_blocks_with_rpc() {
-		let (block_tx, _) = broadcast::channel::<types::BlockVerified>(10);
+		let block_tx = EventBus::<types::BlockVerified>::new();
 		let cfg = SyncClientConfig::from(&RuntimeConfig::default());
 		let mut mock_network_client = network::MockClient::new();
 		let mut mock_client = MockClient::new();
@@ -406,7 +405,7 @@ mod tests {
 		mock_network_client
 			.expect_fetch_verified()
 			.withf(|&x, _, _, _, _| x == 2)
-			.returning(move |_, _, _, _, positions| {
+			.returning(move |_, _, _, _, positions, _| {
 				let unfetched = vec![Position { row: 0, col: 3 }];
 				let dht_fetched: Vec<Cell> = vec![
 					Cell {
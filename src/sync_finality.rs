@@ -22,7 +22,7 @@ use crate::{
 	data::{Database, FinalitySyncCheckpoint, Key},
 	finality::{check_finality, ValidatorSet},
 	network::rpc::{self, WrappedProof},
-	shutdown::Controller,
+	shutdown::{Controller, ShutdownReason},
 	types::State,
 	utils::filter_auth_set_changes,
 };
@@ -212,13 +212,15 @@ async fn get_valset_at_genesis(
 
 pub async fn run(
 	client: impl Client,
-	shutdown: Controller<String>,
+	shutdown: Controller<ShutdownReason>,
 	state: Arc<Mutex<State>>,
 	from_header: Header,
 ) {
 	if let Err(error) = sync(client, state, from_header).await {
 		error!("Cannot sync finality {error}");
-		let _ = shutdown.trigger_shutdown(format!("Cannot sync finality {error:#}"));
+		let _ = shutdown.trigger_shutdown(ShutdownReason::Error(format!(
+			"Cannot sync finality {error:#}"
+		)));
 	};
 }
 
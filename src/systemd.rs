@@ -0,0 +1,78 @@
+//! systemd service manager notifications.
+//!
+//! Notifications are sent to the socket set in the `NOTIFY_SOCKET` environment variable, which is set by systemd for `Type=notify` services.
+//! If variable is not set, or on non-Unix platforms, notifications are skipped.
+//!
+//! # Notifications
+//!
+//! * `READY=1` - once the node is bootstrapped and connected to the RPC node
+//! * `WATCHDOG=1` - on each block processed by the maintenance
+//! * `STOPPING=1` - once the shutdown is triggered
+
+use tracing::{debug, warn};
+
+pub const READY: &str = "READY=1";
+pub const WATCHDOG: &str = "WATCHDOG=1";
+pub const STOPPING: &str = "STOPPING=1";
+
+#[cfg(unix)]
+fn send(socket: &str, state: &str) -> std::io::Result<()> {
+	use std::os::unix::net::UnixDatagram;
+
+	let datagram = UnixDatagram::unbound()?;
+	match socket.strip_prefix('@') {
+		#[cfg(target_os = "linux")]
+		Some(name) => {
+			use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+			let address = SocketAddr::from_abstract_name(name)?;
+			datagram.send_to_addr(state.as_bytes(), &address)?;
+		},
+		#[cfg(not(target_os = "linux"))]
+		Some(_) => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::Unsupported,
+				"abstract sockets are not supported",
+			))
+		},
+		None => {
+			datagram.send_to(state.as_bytes(), socket)?;
+		},
+	}
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_: &str, _: &str) -> std::io::Result<()> {
+	Ok(())
+}
+
+/// Sends the state notification to the service manager, if the notification socket is configured
+pub fn notify(state: &str) {
+	let Ok(socket) = std::env::var("NOTIFY_SOCKET") else {
+		return;
+	};
+	match send(&socket, state) {
+		Ok(()) => debug!(state, "Service manager notified"),
+		Err(error) => warn!(state, "Failed to notify service manager: {error}"),
+	}
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use std::os::unix::net::UnixDatagram;
+
+	#[test]
+	fn send_notification() {
+		let path = std::env::temp_dir().join(format!("avail_light_notify_{}", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		let socket = UnixDatagram::bind(&path).unwrap();
+
+		send(path.to_str().unwrap(), READY).unwrap();
+		let mut buffer = [0u8; 16];
+		let len = socket.recv(&mut buffer).unwrap();
+		assert_eq!(&buffer[..len], READY.as_bytes());
+
+		std::fs::remove_file(&path).unwrap();
+	}
+}
@@ -2,9 +2,24 @@ use crate::types::Origin;
 use async_trait::async_trait;
 use color_eyre::Result;
 use mockall::automock;
+use serde::{Deserialize, Serialize};
 
 pub mod otlp;
 
+/// Category of the exported telemetry, which can be disabled by the operator
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MetricCategory {
+	/// P2P connections, DHT and RPC operations
+	Network,
+	/// Block confidence, sampling and reorgs
+	Verification,
+	/// Process state and host resources
+	System,
+	/// Peer ID and Avail address attributes, which are omitted from all exported data if disabled (anonymous mode)
+	IdentityRevealing,
+}
+
 #[derive(Debug)]
 pub enum MetricCounter {
 	Starts,
@@ -15,6 +30,24 @@ pub enum MetricCounter {
 	EstablishedConnections,
 	IncomingPutRecord,
 	IncomingGetRecord,
+	DHTCellCacheHits,
+	DHTQuotaEvictions,
+	BlockReorgs,
+	ChainReorgs,
+	RuntimeUpgrades,
+	VerificationTimeouts,
+	PeerSyncedHeaders,
+}
+
+/// Internal channel with the queue depth and dropped events metrics
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+	/// Verified blocks, consumed by the maintenance, clients and integrations
+	Blocks,
+	/// Events published to the WebSocket API clients
+	ApiEvents,
+	/// Commands of the network event loop
+	DhtCommands,
 }
 
 pub trait MetricName {
@@ -33,11 +66,38 @@ impl MetricName for MetricCounter {
 			EstablishedConnections => "avail.light.established_connections",
 			IncomingPutRecord => "avail.light.incoming_put_record",
 			IncomingGetRecord => "avail.light.incoming_get_record",
+			DHTCellCacheHits => "avail.light.dht.cell_cache_hits",
+			DHTQuotaEvictions => "avail.light.dht.quota_evictions",
+			BlockReorgs => "avail.light.block.reorgs",
+			ChainReorgs => "avail.light.chain.reorgs",
+			RuntimeUpgrades => "avail.light.rpc.runtime_upgrades",
+			VerificationTimeouts => "avail.light.block.verification_timeouts",
+			PeerSyncedHeaders => "avail.light.header_sync.peer_headers",
 		}
 	}
 }
 
 impl MetricCounter {
+	fn category(&self) -> MetricCategory {
+		use MetricCounter::*;
+		match self {
+			Starts => MetricCategory::System,
+			SessionBlocks | BlockReorgs | ChainReorgs | VerificationTimeouts => {
+				MetricCategory::Verification
+			},
+			OutgoingConnectionErrors
+			| IncomingConnectionErrors
+			| IncomingConnections
+			| EstablishedConnections
+			| IncomingPutRecord
+			| IncomingGetRecord
+			| DHTCellCacheHits
+			| DHTQuotaEvictions
+			| RuntimeUpgrades
+			| PeerSyncedHeaders => MetricCategory::Network,
+		}
+	}
+
 	fn is_buffered(&self) -> bool {
 		!matches!(self, MetricCounter::Starts)
 	}
@@ -57,6 +117,8 @@ pub enum MetricValue {
 	BlockConfidence(f64),
 	BlockConfidenceThreshold(f64),
 	BlockProcessingDelay(f64),
+	BestBlockHeight(u32),
+	BestBlockConfidence(f64),
 
 	DHTReplicationFactor(u16),
 
@@ -69,12 +131,29 @@ pub enum MetricValue {
 	DHTConnectedPeers(usize),
 	DHTQueryTimeout(u32),
 	DHTPingLatency(f64),
+	DHTBypassed(bool),
 
 	RPCFetched(f64),
 	RPCFetchDuration(f64),
 	RPCCallDuration(f64),
+	RPCSpecVersion(u32),
+	RPCSecondsSinceLastHeader(u64),
+	RPCSubscriptionRestarts(u64),
+	RPCHeaderGaps(u64),
+
+	FatClientPartitionCompletion(f64),
+	FatClientPushSuccess(f64),
 
 	Up(),
+	MemoryUsage(u64),
+	DiskSpaceAvailable(u64),
+	LifecycleState(u64),
+	EventsDropped(u64),
+	EventsQueued(u64),
+	ChannelQueued(Channel, u64),
+	ChannelDropped(Channel, u64),
+	UpdateAvailable(bool),
+	ClockSkew(f64),
 
 	#[cfg(feature = "crawl")]
 	CrawlCellsSuccessRate(f64),
@@ -93,6 +172,8 @@ impl MetricName for MetricValue {
 			BlockConfidence(_) => "avail.light.block.confidence",
 			BlockConfidenceThreshold(_) => "avail.light.block.confidence_threshold",
 			BlockProcessingDelay(_) => "avail.light.block.processing_delay",
+			BestBlockHeight(_) => "avail.light.best_block.height",
+			BestBlockConfidence(_) => "avail.light.best_block.confidence",
 
 			DHTReplicationFactor(_) => "avail.light.dht.replication_factor",
 			DHTFetched(_) => "avail.light.dht.fetched",
@@ -104,12 +185,33 @@ impl MetricName for MetricValue {
 			DHTConnectedPeers(_) => "avail.light.dht.connected_peers",
 			DHTQueryTimeout(_) => "avail.light.dht.query_timeout",
 			DHTPingLatency(_) => "avail.light.dht.ping_latency",
+			DHTBypassed(_) => "avail.light.dht.bypassed",
 
 			RPCFetched(_) => "avail.light.rpc.fetched",
 			RPCFetchDuration(_) => "avail.light.rpc.fetch_duration",
 			RPCCallDuration(_) => "avail.light.rpc.call_duration",
+			RPCSpecVersion(_) => "avail.light.rpc.spec_version",
+			RPCSecondsSinceLastHeader(_) => "avail.light.rpc.seconds_since_last_header",
+			RPCSubscriptionRestarts(_) => "avail.light.rpc.subscription_restarts",
+			RPCHeaderGaps(_) => "avail.light.rpc.header_gaps",
+
+			FatClientPartitionCompletion(_) => "avail.light.fat_client.partition_completion",
+			FatClientPushSuccess(_) => "avail.light.fat_client.push_success",
 
 			Up() => "avail.light.up",
+			MemoryUsage(_) => "avail.light.memory_usage",
+			DiskSpaceAvailable(_) => "avail.light.disk_space_available",
+			LifecycleState(_) => "avail.light.lifecycle_state",
+			EventsDropped(_) => "avail.light.events.dropped",
+			EventsQueued(_) => "avail.light.events.queued",
+			ChannelQueued(Channel::Blocks, _) => "avail.light.channel.blocks.queued",
+			ChannelQueued(Channel::ApiEvents, _) => "avail.light.channel.api_events.queued",
+			ChannelQueued(Channel::DhtCommands, _) => "avail.light.channel.dht_commands.queued",
+			ChannelDropped(Channel::Blocks, _) => "avail.light.channel.blocks.dropped",
+			ChannelDropped(Channel::ApiEvents, _) => "avail.light.channel.api_events.dropped",
+			ChannelDropped(Channel::DhtCommands, _) => "avail.light.channel.dht_commands.dropped",
+			UpdateAvailable(_) => "avail.light.update_available",
+			ClockSkew(_) => "avail.light.clock_skew",
 
 			#[cfg(feature = "crawl")]
 			CrawlCellsSuccessRate(_) => "avail.light.crawl.cells_success_rate",
@@ -122,6 +224,54 @@ impl MetricName for MetricValue {
 }
 
 impl MetricValue {
+	fn category(&self) -> MetricCategory {
+		use MetricValue::*;
+		match self {
+			BlockHeight(_)
+			| BlockConfidence(_)
+			| BlockConfidenceThreshold(_)
+			| BlockProcessingDelay(_)
+			| BestBlockHeight(_)
+			| BestBlockConfidence(_) => MetricCategory::Verification,
+
+			DHTReplicationFactor(_)
+			| DHTFetched(_)
+			| DHTFetchedPercentage(_)
+			| DHTFetchDuration(_)
+			| DHTPutDuration(_)
+			| DHTPutSuccess(_)
+			| DHTConnectedPeers(_)
+			| DHTQueryTimeout(_)
+			| DHTPingLatency(_)
+			| DHTBypassed(_)
+			| RPCFetched(_)
+			| RPCFetchDuration(_)
+			| RPCCallDuration(_)
+			| RPCSpecVersion(_)
+			| RPCSecondsSinceLastHeader(_)
+			| RPCSubscriptionRestarts(_)
+			| RPCHeaderGaps(_)
+			| FatClientPartitionCompletion(_)
+			| FatClientPushSuccess(_) => MetricCategory::Network,
+
+			Up()
+			| MemoryUsage(_)
+			| DiskSpaceAvailable(_)
+			| LifecycleState(_)
+			| EventsDropped(_)
+			| EventsQueued(_)
+			| ChannelQueued(..)
+			| ChannelDropped(..)
+			| UpdateAvailable(_)
+			| ClockSkew(_) => MetricCategory::System,
+
+			#[cfg(feature = "crawl")]
+			CrawlCellsSuccessRate(_) | CrawlRowsSuccessRate(_) | CrawlBlockDelay(_) => {
+				MetricCategory::Network
+			},
+		}
+	}
+
 	// Metric filter for external peers
 	// Only the metrics we wish to send to OTel should be in this list
 	fn is_allowed(&self, origin: &Origin) -> bool {
@@ -144,3 +294,15 @@ pub trait Metrics {
 	async fn record(&self, value: MetricValue);
 	async fn flush(&self) -> Result<()>;
 }
+
+/// Metrics which are discarded
+pub struct NoopMetrics;
+
+#[async_trait]
+impl Metrics for NoopMetrics {
+	async fn count(&self, _: MetricCounter) {}
+	async fn record(&self, _: MetricValue) {}
+	async fn flush(&self) -> Result<()> {
+		Ok(())
+	}
+}
@@ -1,10 +1,11 @@
-use super::{MetricCounter, MetricValue};
+use super::{MetricCategory, MetricCounter, MetricValue};
 use crate::{
+	network::proxy::Connector,
 	telemetry::MetricName,
 	types::{Origin, OtelConfig},
 };
 use async_trait::async_trait;
-use color_eyre::Result;
+use color_eyre::{eyre::WrapErr, Result};
 use opentelemetry_api::{
 	global,
 	metrics::{Counter, Meter},
@@ -13,8 +14,7 @@ use opentelemetry_api::{
 use opentelemetry_otlp::{ExportConfig, Protocol, WithExportConfig};
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
-
-const ATTRIBUTE_NUMBER: usize = 8;
+use tonic::transport::Endpoint;
 
 // NOTE: Buffers are less space efficient, as opposed to the solution with in place compute.
 // That can be optimized by using dedicated data structure with proper bounds.
@@ -25,6 +25,7 @@ pub struct Metrics {
 	attributes: MetricAttributes,
 	metric_buffer: Arc<Mutex<Vec<MetricValue>>>,
 	counter_buffer: Arc<Mutex<Vec<MetricCounter>>>,
+	disabled_categories: Vec<MetricCategory>,
 }
 
 #[derive(Debug)]
@@ -39,17 +40,27 @@ pub struct MetricAttributes {
 }
 
 impl Metrics {
-	fn attributes(&self) -> [KeyValue; ATTRIBUTE_NUMBER] {
-		[
+	fn is_enabled(&self, category: MetricCategory) -> bool {
+		!self.disabled_categories.contains(&category)
+	}
+
+	/// Returns attributes of the exported metrics, omitting identity revealing ones in the anonymous mode
+	fn attributes(&self) -> Vec<KeyValue> {
+		let mut attributes = vec![
 			KeyValue::new("version", clap::crate_version!()),
 			KeyValue::new("role", self.attributes.role.clone()),
 			KeyValue::new("origin", self.attributes.origin.to_string()),
-			KeyValue::new("peerID", self.attributes.peer_id.clone()),
-			KeyValue::new("avail_address", self.attributes.avail_address.clone()),
 			KeyValue::new("partition_size", self.attributes.partition_size.clone()),
 			KeyValue::new("operating_mode", self.attributes.operating_mode.clone()),
 			KeyValue::new("network", self.attributes.network.clone()),
-		]
+		];
+		if self.is_enabled(MetricCategory::IdentityRevealing) {
+			attributes.extend([
+				KeyValue::new("peerID", self.attributes.peer_id.clone()),
+				KeyValue::new("avail_address", self.attributes.avail_address.clone()),
+			]);
+		}
+		attributes
 	}
 
 	async fn record_u64(&self, name: &'static str, value: u64) -> Result<()> {
@@ -90,6 +101,8 @@ impl From<MetricValue> for Record {
 			BlockConfidence(number) => AvgF64(name, number),
 			BlockConfidenceThreshold(number) => AvgF64(name, number),
 			BlockProcessingDelay(number) => AvgF64(name, number),
+			BestBlockHeight(number) => MaxU64(name, number as u64),
+			BestBlockConfidence(number) => AvgF64(name, number),
 
 			DHTReplicationFactor(number) => AvgF64(name, number as f64),
 
@@ -102,12 +115,29 @@ impl From<MetricValue> for Record {
 			DHTConnectedPeers(number) => AvgF64(name, number as f64),
 			DHTQueryTimeout(number) => AvgF64(name, number as f64),
 			DHTPingLatency(number) => AvgF64(name, number),
+			DHTBypassed(bypassed) => MaxU64(name, bypassed as u64),
 
 			RPCFetched(number) => AvgF64(name, number),
 			RPCFetchDuration(number) => AvgF64(name, number),
 			RPCCallDuration(number) => AvgF64(name, number),
+			RPCSpecVersion(number) => MaxU64(name, number as u64),
+			RPCSecondsSinceLastHeader(seconds) => MaxU64(name, seconds),
+			RPCSubscriptionRestarts(count) => MaxU64(name, count),
+			RPCHeaderGaps(count) => MaxU64(name, count),
+
+			FatClientPartitionCompletion(number) => AvgF64(name, number),
+			FatClientPushSuccess(number) => AvgF64(name, number),
 
 			Up() => MaxU64(name, 1),
+			MemoryUsage(bytes) => MaxU64(name, bytes),
+			DiskSpaceAvailable(bytes) => MaxU64(name, bytes),
+			LifecycleState(code) => MaxU64(name, code),
+			EventsDropped(count) => MaxU64(name, count),
+			EventsQueued(count) => MaxU64(name, count),
+			ChannelQueued(_, count) => MaxU64(name, count),
+			ChannelDropped(_, count) => MaxU64(name, count),
+			UpdateAvailable(available) => MaxU64(name, available as u64),
+			ClockSkew(seconds) => AvgF64(name, seconds),
 
 			#[cfg(feature = "crawl")]
 			CrawlCellsSuccessRate(number) => AvgF64(name, number),
@@ -164,10 +194,10 @@ fn flatten_metrics(
 
 #[async_trait]
 impl super::Metrics for Metrics {
-	/// Puts counter to the counter buffer if it is allowed.
+	/// Puts counter to the counter buffer if it is allowed and its category is enabled.
 	/// If counter is not buffered, counter is incremented.
 	async fn count(&self, counter: super::MetricCounter) {
-		if !counter.is_allowed(&self.attributes.origin) {
+		if !counter.is_allowed(&self.attributes.origin) || !self.is_enabled(counter.category()) {
 			return;
 		}
 		if !counter.is_buffered() {
@@ -178,9 +208,9 @@ impl super::Metrics for Metrics {
 		counter_buffer.push(counter);
 	}
 
-	/// Puts metric to the metric buffer if it is allowed.
+	/// Puts metric to the metric buffer if it is allowed and its category is enabled.
 	async fn record(&self, value: super::MetricValue) {
-		if !value.is_allowed(&self.attributes.origin) {
+		if !value.is_allowed(&self.attributes.origin) || !self.is_enabled(value.category()) {
 			return;
 		}
 
@@ -215,7 +245,11 @@ impl super::Metrics for Metrics {
 	}
 }
 
-fn init_counters(meter: Meter, origin: Origin) -> HashMap<&'static str, Counter<u64>> {
+fn init_counters(
+	meter: Meter,
+	origin: Origin,
+	disabled_categories: &[MetricCategory],
+) -> HashMap<&'static str, Counter<u64>> {
 	[
 		MetricCounter::Starts,
 		MetricCounter::SessionBlocks,
@@ -225,9 +259,17 @@ fn init_counters(meter: Meter, origin: Origin) -> HashMap<&'static str, Counter<
 		MetricCounter::EstablishedConnections,
 		MetricCounter::IncomingPutRecord,
 		MetricCounter::IncomingGetRecord,
+		MetricCounter::DHTCellCacheHits,
+		MetricCounter::DHTQuotaEvictions,
+		MetricCounter::BlockReorgs,
+		MetricCounter::ChainReorgs,
+		MetricCounter::RuntimeUpgrades,
+		MetricCounter::VerificationTimeouts,
+		MetricCounter::PeerSyncedHeaders,
 	]
 	.iter()
 	.filter(|counter| MetricCounter::is_allowed(counter, &origin))
+	.filter(|counter| !disabled_categories.contains(&counter.category()))
 	.map(|counter| (counter.name(), meter.u64_counter(counter.name()).init()))
 	.collect()
 }
@@ -239,17 +281,24 @@ pub fn initialize(
 	ot_config: OtelConfig,
 ) -> Result<Metrics> {
 	let export_config = ExportConfig {
-		endpoint,
+		endpoint: endpoint.clone(),
 		timeout: Duration::from_secs(10),
 		protocol: Protocol::Grpc,
 	};
+	let mut exporter = opentelemetry_otlp::new_exporter()
+		.tonic()
+		.with_export_config(export_config);
+	if let Some(proxy) = ot_config.proxy {
+		// channel is connected lazily, on the first export
+		let channel = Endpoint::from_shared(endpoint)
+			.wrap_err("Invalid OpenTelemetry collector endpoint")?
+			.timeout(Duration::from_secs(10))
+			.connect_with_connector_lazy(Connector::new(Some(proxy)));
+		exporter = exporter.with_channel(channel);
+	}
 	let provider = opentelemetry_otlp::new_pipeline()
 		.metrics(opentelemetry_sdk::runtime::Tokio)
-		.with_exporter(
-			opentelemetry_otlp::new_exporter()
-				.tonic()
-				.with_export_config(export_config),
-		)
+		.with_exporter(exporter)
 		.with_period(Duration::from_secs(ot_config.ot_export_period)) // Configures the intervening time between exports
 		.with_timeout(Duration::from_secs(ot_config.ot_export_timeout)) // Configures the time a OT waits for an export to complete before canceling it.
 		.build()?;
@@ -258,13 +307,14 @@ pub fn initialize(
 	let meter = global::meter("avail_light_client");
 
 	// Initialize counters - they need to persist unlike Gauges that are recreated on every record
-	let counters = init_counters(meter.clone(), origin);
+	let counters = init_counters(meter.clone(), origin, &ot_config.ot_disabled_categories);
 	Ok(Metrics {
 		meter,
 		attributes,
 		counters,
 		metric_buffer: Arc::new(Mutex::new(vec![])),
 		counter_buffer: Arc::new(Mutex::new(vec![])),
+		disabled_categories: ot_config.ot_disabled_categories,
 	})
 }
 
@@ -310,6 +360,42 @@ mod tests {
 		assert_eq!(result, expected);
 	}
 
+	#[tokio::test]
+	async fn disabled_categories() {
+		use crate::telemetry::Metrics as _;
+
+		let metrics = Metrics {
+			meter: global::meter("test"),
+			counters: HashMap::new(),
+			attributes: MetricAttributes {
+				role: "lightnode".to_string(),
+				peer_id: "peer".to_string(),
+				origin: Origin::Internal,
+				avail_address: "address".to_string(),
+				operating_mode: "client".to_string(),
+				partition_size: "n/a".to_string(),
+				network: "local".to_string(),
+			},
+			metric_buffer: Arc::new(Mutex::new(vec![])),
+			counter_buffer: Arc::new(Mutex::new(vec![])),
+			disabled_categories: vec![MetricCategory::Network, MetricCategory::IdentityRevealing],
+		};
+		metrics.record(MetricValue::DHTConnectedPeers(10)).await;
+		metrics.record(MetricValue::BlockConfidence(99.0)).await;
+		metrics.count(MetricCounter::IncomingConnections).await;
+		assert_eq!(metrics.metric_buffer.lock().await.len(), 1);
+		assert!(metrics.counter_buffer.lock().await.is_empty());
+
+		let keys = metrics
+			.attributes()
+			.into_iter()
+			.map(|attribute| attribute.key.as_str().to_string())
+			.collect::<Vec<_>>();
+		assert!(!keys.contains(&"peerID".to_string()));
+		assert!(!keys.contains(&"avail_address".to_string()));
+		assert!(keys.contains(&"network".to_string()));
+	}
+
 	#[test]
 	fn test_flatten_metrics() {
 		let (m_u64, m_f64) = flatten_metrics(&[] as &[MetricValue]);
@@ -0,0 +1,665 @@
+//! Generation of the commented configuration file.
+//!
+//! Values are taken from the serialized [`RuntimeConfig`], so the generated file always reflects the current defaults
+//! (or the selected network preset). Every serialized parameter must be documented in [`PARAMETERS`], which is enforced by tests.
+
+use super::RuntimeConfig;
+use color_eyre::{eyre::eyre, Result};
+use std::fmt::Write;
+
+/// Configuration parameter documentation, with an example for the parameters which are not set by default
+struct Parameter {
+	name: &'static str,
+	description: &'static str,
+	example: Option<&'static str>,
+}
+
+const fn parameter(name: &'static str, description: &'static str) -> Parameter {
+	Parameter {
+		name,
+		description,
+		example: None,
+	}
+}
+
+const fn optional(
+	name: &'static str,
+	description: &'static str,
+	example: &'static str,
+) -> Parameter {
+	Parameter {
+		name,
+		description,
+		example: Some(example),
+	}
+}
+
+const PARAMETERS: &[Parameter] = &[
+	parameter("http_server_host", "Light client HTTP server host name."),
+	parameter("http_server_port", "Light client HTTP server port."),
+	parameter(
+		"http_server_enable",
+		"Enables HTTP and WebSocket API server. If disabled, client runs headless, with sampling and DHT participation only.",
+	),
+	optional(
+		"http_server_prefix",
+		"Path prefix of the HTTP server routes, e.g. `mainnet` for `/mainnet/v2/status`.",
+		r#""mainnet""#,
+	),
+	optional(
+		"ws_server_port",
+		"Port of the separate WebSocket API server. If set, `/v2/subscriptions` and `/v2/ws` are served on this port of `http_server_host`, instead of `http_server_port`.",
+		"7008",
+	),
+	optional(
+		"http_server_socket",
+		"Path of the Unix domain socket on which HTTP API is served, instead of `http_server_host` and `http_server_port`.",
+		r#""/run/avail-light/api.sock""#,
+	),
+	parameter(
+		"http_server_cache_ttl",
+		"Time in milliseconds for which `/v2/status`, `/v2/blocks/{block_number}` and `/v2/metrics/confidence` responses are cached. If 0, responses are not cached.",
+	),
+	parameter(
+		"http_server_drain_timeout",
+		"Grace period in seconds for the in-flight HTTP requests on shutdown, after WebSocket subscribers are closed. Must be lower than `shutdown_phase_timeout`.",
+	),
+	optional(
+		"secret_key",
+		"Secret key for libp2p keypair. Can be either set to `seed` or to `key`.\nIf set to seed, keypair will be generated from that seed.\nIf set to key, a valid ed25519 private key must be provided, else the client will fail.\nIf `secret_key` is not set, keystore key or random seed will be used.",
+		r#"{ seed = "avail" }"#,
+	),
+	parameter("port", "P2P service port."),
+	parameter(
+		"p2p_listen_addresses",
+		"P2P listen multiaddresses, e.g. `/ip4/10.0.0.1/tcp/37000`. If empty, P2P service listens on all interfaces on `port`.",
+	),
+	parameter("ws_transport_enable", "Enables websocket transport."),
+	parameter(
+		"autonat_only_global_ips",
+		"Configures AutoNAT behaviour to reject probes as a server for clients that are observed at a non-global ip address.",
+	),
+	parameter(
+		"autonat_throttle",
+		"AutoNat throttle period for re-using a peer as server for a dial-request, in seconds.",
+	),
+	parameter(
+		"autonat_retry_interval",
+		"Interval in which the NAT status should be re-tried if it is currently unknown or max confidence was not reached yet, in seconds.",
+	),
+	parameter(
+		"autonat_refresh_interval",
+		"Interval in which the NAT should be tested again if max confidence was reached in a status, in seconds.",
+	),
+	parameter(
+		"autonat_boot_delay",
+		"AutoNat on init delay before starting the first probe, in seconds.",
+	),
+	parameter(
+		"bootstraps",
+		"Vector of Light Client bootstrap nodes, used to bootstrap DHT. If not set, light client acts as a bootstrap node, waiting for first peer to connect for DHT bootstrap.",
+	),
+	parameter(
+		"bootstrap_period",
+		"Period of time in which periodic bootstraps will be repeated, in seconds.",
+	),
+	parameter(
+		"operation_mode",
+		"Kademlia operation mode. Possible values are `client` and `server`.",
+	),
+	parameter(
+		"relays",
+		"Vector of Relay nodes, which are used for hole punching.",
+	),
+	parameter(
+		"full_node_ws",
+		"WebSocket endpoints of full nodes for subscribing to latest header, etc.",
+	),
+	parameter(
+		"genesis_hash",
+		"Genesis hash of the network to be connected to. Set to a string beginning with \"DEV\" to connect to any network.",
+	),
+	optional(
+		"agent_version_suffix",
+		"Suffix appended to the client type of the libp2p identify agent version, and to the RPC user agent, e.g. operator or fleet name.\nSuffix can contain up to 32 alphanumeric, `-`, `_` or `.` characters.",
+		r#""fleet-1""#,
+	),
+	optional(
+		"app_id",
+		"If set, application client is started with given app_id.",
+		"1",
+	),
+	parameter(
+		"confidence",
+		"Confidence threshold, used to calculate how many cells need to be sampled to achieve desired confidence.",
+	),
+	parameter(
+		"avail_path",
+		"File system path where RocksDB used by light client, stores its data.",
+	),
+	parameter(
+		"log_level",
+		"Log level. Possible values are `trace`, `debug`, `info`, `warn` and `error`.",
+	),
+	parameter("origin", "Origin of the light client, used in the telemetry."),
+	parameter(
+		"log_format_json",
+		"If set to true, logs are displayed in JSON format, which is used for structured logging. Otherwise, plain text format is used.",
+	),
+	parameter("ot_collector_endpoint", "OpenTelemetry Collector endpoint."),
+	parameter(
+		"ot_export_period",
+		"OpenTelemetry metrics export period, in seconds.",
+	),
+	parameter(
+		"ot_export_timeout",
+		"OpenTelemetry metrics export timeout, in seconds.",
+	),
+	parameter(
+		"ot_flush_block_interval",
+		"Interval in blocks in which aggregated telemetry counters are flushed.",
+	),
+	parameter(
+		"ot_disabled_categories",
+		"Telemetry categories which are not exported: `network` (P2P connections, DHT and RPC operations), `verification` (block confidence, sampling and reorgs), `system` (process state and host resources) or `identity-revealing`.\nIf `identity-revealing` is disabled (anonymous mode), peer ID and Avail address attributes are omitted from all exported data.",
+	),
+	parameter(
+		"disable_rpc",
+		"Disables fetching of cells from RPC, set to true if client expects cells to be available in DHT.",
+	),
+	parameter(
+		"dht_fallback_peers",
+		"Minimum number of the DHT routing table peers for fetching cells from the DHT. While there are fewer peers, cells are fetched from RPC or gateways only. If 0, cells are always fetched from the DHT first.",
+	),
+	parameter(
+		"dht_parallelization_limit",
+		"Maximum number of parallel tasks spawned for GET and PUT operations on DHT.",
+	),
+	parameter(
+		"query_proof_rpc_parallel_tasks",
+		"Number of parallel queries for cell fetching via RPC from node.",
+	),
+	parameter(
+		"historical_lane_weight",
+		"Maximum number of concurrent historical steps (sync client and crawler DHT fetches, RPC fetches and proof verifications) while head blocks are verified. Head blocks verification is never delayed, and historical work is paused while it runs if set to 0.",
+	),
+	optional(
+		"block_processing_delay",
+		"Number of seconds to postpone block processing after block finalized message arrives.",
+		"0",
+	),
+	optional(
+		"block_processing_delay_max",
+		"Maximum number of seconds to postpone block sampling. If set, delay of each block is chosen at random between `block_processing_delay` and this value, so the sampling time is not predictable.",
+		"30",
+	),
+	optional(
+		"verification_deadline",
+		"Maximum number of seconds to sample the block, counted from the sampling start. If exceeded, remaining fetches are abandoned, partial confidence of the cells verified so far is stored, and the next block is processed.\nTimeouts are counted with the `avail.light.block.verification_timeouts` metric.",
+		"20",
+	),
+	parameter(
+		"verification_threads",
+		"Number of the proof verification threads. If 0, one thread per CPU is used.",
+	),
+	parameter(
+		"verification_cpus",
+		"CPU cores to which the proof verification threads are pinned, round-robin, e.g. `[2, 3]`. Supported on Linux only. If empty, threads are not pinned.",
+	),
+	optional(
+		"block_matrix_partition",
+		"Fraction and number of the block matrix part to fetch (e.g. 2/20 means second 1/20 part of a matrix). If set, client behaves as a fat client.",
+		r#""1/20""#,
+	),
+	parameter(
+		"block_matrix_partition_mode",
+		"Block matrix partitioning mode. With \"cells\" mode, partition is a range of cells. With \"columns\" mode, partition is a range of columns (e.g. 1/4 of a matrix with 256 columns means columns 0-63).",
+	),
+	optional(
+		"sync_start_block",
+		"Starting block of the syncing process. Omitting it will disable syncing.",
+		"0",
+	),
+	parameter(
+		"sync_finality_enable",
+		"Enable or disable synchronizing finality. If disabled, finality is assumed to be verified until the starting block at the point the LC is started and is only checked for new blocks.",
+	),
+	optional(
+		"max_cells_per_rpc",
+		"Maximum number of cells per request for proof queries.",
+		"30",
+	),
+	parameter(
+		"threshold",
+		"Threshold for the number of cells fetched via DHT for the app client.",
+	),
+	parameter(
+		"kad_record_ttl",
+		"Time-to-live for DHT entries in seconds.\nRecord TTL, publication and replication intervals are co-dependent, meaning that TTL >> publication_interval >> replication_interval.",
+	),
+	parameter(
+		"kad_record_ttl_jitter",
+		"Maximum random jitter in seconds added to the TTL of each DHT record, so records inserted at the same block don't expire and get republished at the same time.\nRepublishing pass is also shifted by a random number of blocks within `dht_republish_interval`. If set to 0, jitter is disabled.",
+	),
+	parameter(
+		"publication_interval",
+		"(Re-)publication interval of stored records in seconds.",
+	),
+	parameter(
+		"replication_interval",
+		"(Re-)replication interval for stored records in seconds.",
+	),
+	parameter(
+		"replication_factor",
+		"The replication factor determines to how many closest peers a record is replicated.",
+	),
+	parameter(
+		"connection_idle_timeout",
+		"Amount of time to keep connections alive when they're idle, in seconds.",
+	),
+	parameter(
+		"max_negotiating_inbound_streams",
+		"Maximum number of inbound streams concurrently negotiating on a connection.",
+	),
+	parameter(
+		"task_command_buffer_size",
+		"Size of the buffer for commands sent from the swarm to connection handlers.",
+	),
+	parameter(
+		"per_connection_event_buffer_size",
+		"Size of the buffer for events sent from connection handlers to the swarm.",
+	),
+	parameter(
+		"dial_concurrency_factor",
+		"Number of addresses concurrently dialed for a single outbound connection attempt.",
+	),
+	parameter(
+		"store_pruning_interval",
+		"Kademlia record store pruning interval in blocks.",
+	),
+	parameter(
+		"query_timeout",
+		"Timeout for a single Kademlia query, in seconds.",
+	),
+	parameter(
+		"query_parallelism",
+		"Allowed level of parallelism for iterative Kademlia queries.",
+	),
+	parameter(
+		"caching_max_peers",
+		"Kademlia caching strategy to use for successful lookups. If set to 0, caching is disabled.",
+	),
+	parameter(
+		"disjoint_query_paths",
+		"Require iterative queries to use disjoint paths for increased resiliency in the presence of potentially adversarial nodes.",
+	),
+	parameter("max_kad_record_number", "The maximum number of records."),
+	parameter(
+		"max_kad_record_size",
+		"The maximum size of record values, in bytes.",
+	),
+	parameter(
+		"max_kad_provided_keys",
+		"The maximum number of provider records for which the local node is the provider.",
+	),
+	parameter(
+		"max_kad_records_per_publisher",
+		"The maximum number of records stored per publisher (peer which sent the records). If exceeded, the oldest records of the publisher are evicted. If set to 0, limit is disabled.",
+	),
+	parameter(
+		"max_kad_bytes_per_publisher",
+		"The maximum size of records stored per publisher, in bytes. If exceeded, the oldest records of the publisher are evicted. If set to 0, limit is disabled.",
+	),
+	parameter(
+		"dht_record_compression",
+		"Compression of the published cell and row DHT records: \"none\", \"lz4\", \"zstd\" or \"deflate\". Records are published uncompressed if compression doesn't reduce their size.\nCompressed records published by other nodes are read regardless of this option.",
+	),
+	parameter(
+		"dht_cell_cache_size",
+		"The maximum number of recently fetched and verified cells kept in memory, to answer incoming Kademlia queries. If set to 0, cell cache is disabled.",
+	),
+	parameter(
+		"disk_space_warning_threshold",
+		"Available disk space threshold in megabytes on the `avail_path` filesystem, below which warning is logged.",
+	),
+	parameter(
+		"disk_space_critical_threshold",
+		"Available disk space threshold in megabytes on the `avail_path` filesystem, below which storing of app data is paused.",
+	),
+	parameter(
+		"dht_min_peers",
+		"Routing table is refreshed with bootstrap and random walk queries if number of peers in the routing table falls below this value. If set to 0, refresh is disabled.",
+	),
+	parameter(
+		"dht_republish_interval",
+		"Interval in blocks in which locally published DHT records are republished before they expire. If set to 0, republishing is disabled.",
+	),
+	parameter(
+		"dht_republish_limit",
+		"Maximum number of DHT records republished in a single pass.",
+	),
+	parameter(
+		"dht_republish_before_expiry",
+		"DHT records expiring within this period in seconds are republished.",
+	),
+	parameter(
+		"known_peers_limit",
+		"Maximum number of connected routing table peers persisted in the database, to speed up DHT re-entry after restart. If set to 0, known peers are not persisted.",
+	),
+	parameter(
+		"known_peers_max_age",
+		"Persisted peers not seen in the routing table for longer than this period in seconds are evicted.",
+	),
+	parameter(
+		"shutdown_phase_timeout",
+		"Deadline in seconds for each of the graceful shutdown phases, after which the next phase is started. If any phase exceeds its deadline, process exit is forced.",
+	),
+	parameter(
+		"shutdown_grace_period",
+		"Grace period in seconds for the graceful shutdown after SIGTERM or SIGINT. Process exit is forced if shutdown doesn't complete within the grace period, or if another signal is received.",
+	),
+	parameter(
+		"fat_client_push_delay",
+		"Number of seconds to postpone fat client DHT pushes after the partition is fetched.",
+	),
+	parameter(
+		"fat_client_push_jitter",
+		"Maximum random delay in seconds added to the fat client push delay, so fat clients don't push at the same time.",
+	),
+	parameter(
+		"fat_client_push_batch_size",
+		"Maximum number of cells or rows inserted into the DHT in a single fat client push.",
+	),
+	optional(
+		"fat_client_push_rate_limit",
+		"Maximum number of cells and rows per second inserted into the DHT by the fat client. If not set, pushes are not rate limited.",
+		"1000",
+	),
+	parameter(
+		"networks",
+		"Networks run in the same process, each with its own RPC endpoints, DHT, data directory and API prefix.\nEach network inherits base configuration, and can override `network` preset, `full_node_ws`, `bootstraps`, `genesis_hash`, `http_server_port`, `ws_server_port` and `port`.\nIf empty, single network is run with the base configuration.",
+	),
+	optional(
+		"record_path",
+		"Path to the file to which block verification inputs (headers, sampled positions and fetched cells) are recorded, for the offline replay with the `replay` command.",
+		r#""recording.jsonl""#,
+	),
+	optional(
+		"record_start_block",
+		"First recorded block, if recording is enabled. All blocks are recorded if range is not set.",
+		"1000",
+	),
+	optional(
+		"record_end_block",
+		"Last recorded block, if recording is enabled.",
+		"2000",
+	),
+	optional(
+		"event_log_path",
+		"Path to the file to which verification outcome of each sampled block (confidence, timing and the number of cells fetched from DHT and RPC) is appended as JSON line.",
+		r#""events.jsonl""#,
+	),
+	parameter(
+		"event_log_max_size",
+		"Maximum size of the event log in megabytes. If exceeded, event log is rotated to `<event_log_path>.1`, replacing the previously rotated log.",
+	),
+	optional(
+		"fault_injection",
+		"Faults injected into the cell retrieval, for the chaos testing: fraction of dropped DHT queries, RPC response delay in milliseconds, and fraction of corrupted cells.\nRequires the light client compiled with the `fault-injection` feature. Never enable it in production.",
+		"{ dht_drop_rate = 0.1, rpc_delay = 500, cell_corruption_rate = 0.01 }",
+	),
+	parameter(
+		"http_gateways",
+		"HTTP gateways (e.g. availability bridges or archive services) from which cells are fetched if they are fetched neither from the DHT nor from the full node.\nGateways are queried in the configured order, and fetched cells are verified and inserted into the DHT. If empty, gateway retrieval is disabled.",
+	),
+	parameter(
+		"http_gateway_timeout",
+		"Timeout in seconds of the gateway requests.",
+	),
+	parameter(
+		"best_block_sampling",
+		"Sample best chain blocks before the finality, storing their confidence separately from the finalized chain confidence.\nIf sampled best block is reorged out, `block-reorged` event is published.",
+	),
+	parameter(
+		"webhook_urls",
+		"URLs to which operational events are posted as JSON: confidence below threshold, RPC failover, DHT peers collapse and shutdown.\nDelivery is best effort, failed requests are not retried. If empty, webhook notifications are disabled.",
+	),
+	parameter(
+		"webhook_timeout",
+		"Timeout in seconds of the webhook requests.",
+	),
+	optional(
+		"api_admin_token",
+		"Admin token required by the `/v2/admin/*` endpoints in the `Authorization: Bearer {token}` header.\nIf not set, admin endpoints are disabled, and configuration can be reloaded only with `SIGHUP`.",
+		r#""{secret}""#,
+	),
+	parameter(
+		"api_tokens",
+		"API tokens which limit the data endpoints (block data and app data proof) to the specific apps, for the nodes serving several applications,\ne.g. `[{ name = \"rollup-a\", token = \"{secret}\", app_ids = [1] }]`.\nIf set, data endpoints and `data-verified` WebSocket subscriptions require `Authorization: Bearer {token}` header, and usage of each token is exposed on `GET /v2/admin/tokens`.\nUnauthenticated `/v1/appdata` endpoint is disabled.",
+	),
+	optional(
+		"proxy",
+		"HTTP or SOCKS5 proxy of the outbound HTTP and WebSocket connections (RPC nodes, OpenTelemetry collector, webhooks and HTTP gateways). P2P connections are not proxied.\nHTTP proxy is used with the CONNECT method, and SOCKS5 proxy resolves the target host names. Credentials can be set in the URL.",
+		r#""socks5://127.0.0.1:1080""#,
+	),
+	optional(
+		"tor_proxy",
+		"Tor SOCKS5 proxy, so sampling patterns can't be linked to the operator's IP address.\nIf set, P2P peers are dialed over Tor, P2P listening, mDNS, UPnP and AutoNAT are disabled, and RPC nodes, OpenTelemetry collector, webhooks and HTTP gateways are connected over Tor (proxy cannot be set).\nHTTP API has to be bound to a loopback address. Light client doesn't publish it as an onion service,\nit has to be configured in the Tor daemon, e.g. with `HiddenServicePort 80 127.0.0.1:7007` in torrc.",
+		r#""socks5://127.0.0.1:9050""#,
+	),
+	optional(
+		"sampling_privacy",
+		"Obfuscation of the sampled cells: number of the decoy DHT queries per sampled cell, and maximum random delay in milliseconds before each DHT query.\nDecoy cells are queried at random positions of the same block, in random order together with the sampled cells, and discarded. Decoy queries increase the DHT load and the fetch duration.",
+		"{ decoy_ratio = 1.0, max_query_delay = 200 }",
+	),
+	parameter(
+		"sampling_boosts",
+		"Stronger sampling of the blocks containing data of the given apps, e.g. `[{ app_id = 1, extra_cells = 10 }, { app_id = 2, full_rows = true }]`.\nBoosted blocks are sampled with `extra_cells` additional random cells (maximum of the matching boosts), and if `full_rows` is set, with all cells of the rows containing the app data.\nOther blocks are sampled with the number of cells required for the configured confidence.",
+	),
+	optional(
+		"randomness_beacon_url",
+		"URL of the public randomness beacon with the drand HTTP API (`GET {url}/public/latest`).\nIf set, RNG of the sampled cell positions is seeded with the latest beacon round, mixed with the block hash and the local entropy,\nso sampling is unpredictable even if the local entropy is compromised. If the beacon is not reachable, local entropy is used.",
+		r#""https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971""#,
+	),
+	optional(
+		"version_check_url",
+		"URL of the release manifest (JSON with the `version` and optional release `url`), checked on startup and daily.\nIf the released version is newer than the running one, update is reported with the warning log, the `avail.light.update_available` metric, and the `update_available` field of `/v2/status`.",
+		r#""https://example.com/avail-light/release.json""#,
+	),
+	parameter(
+		"clock_skew_threshold",
+		"Maximum difference in seconds between the local clock and the timestamps of the received finalized blocks, before the clock skew is reported with the warning log and the `clock_skewed` field of `/v2/status`.\nFinalized blocks are received after the finality delay, so the threshold has to be greater than the usual finality lag. Skew is recorded with the `avail.light.clock_skew` metric.",
+	),
+	parameter(
+		"dht_retry_queue_limit",
+		"Maximum number of the failed DHT cell and row inserts queued for the retry, 0 disables the retry queue.\nQueued inserts are persisted and retried from the maintenance with exponential backoff, oldest blocks are evicted once the limit is reached.",
+	),
+	optional(
+		"peer_header_sync_timeout",
+		"Time in seconds without new finalized headers from RPC, after which headers are synced from the connected peers.\nSynced headers have to be finalized with the justification signed by the known validator set. If not set, headers are not synced from peers.",
+		"60",
+	),
+	parameter(
+		"rpc_block_time",
+		"Expected block time in seconds, used to detect the stalled RPC header subscription.",
+	),
+	parameter(
+		"rpc_stall_blocks",
+		"Number of the expected block times without new headers from the RPC subscription, after which the subscription is reported as stalled.\nStalled subscription is reported with the warning log and the `rpc_subscription` field of `/v2/status`, and the light client transitions to the `degraded` state.",
+	),
+	parameter(
+		"fleet_members",
+		"Sibling light clients, which signed confidence attestations of the verified blocks are collected over HTTP,\ne.g. `[{ peer_id = \"12D3KooW...\", url = \"http://10.0.0.2:7007\" }]`.\nAggregated confidence is exposed on `GET /v2/fleet/blocks/{block_number}`. If empty, fleet confidence aggregation is disabled.",
+	),
+	parameter(
+		"attestation_gossip",
+		"Publish attestation of each verified block, signed with the identity and keystore keys, to the `{protocol_version}/attestations/1` gossipsub topic.",
+	),
+	parameter(
+		"low_resource",
+		"Run in the low-resource mode, for Raspberry Pi class devices. Confidence is capped to 99 (7 sampled cells), cells per RPC request to 10,\nKademlia store to 100000 records and established connections to 32. Pruning and republishing intervals are at least 360 blocks,\nand the app client is disabled. Not supported in the fat client mode.",
+	),
+	parameter(
+		"retry_config",
+		"Configuration based on which the retries will be orchestrated. Type can be `exponential` or `fibonacci`.",
+	),
+	parameter(
+		"restart_config",
+		"Configuration based on which failed subsystems (RPC subscriptions, app client and maintenance) are restarted, before the failure triggers the shutdown. Type can be `exponential` or `fibonacci`.",
+	),
+	optional(
+		"memory_threshold",
+		"Resident memory threshold in megabytes. If exceeded, caches are shrunk and warning is logged.",
+		"4096",
+	),
+	parameter(
+		"memory_threshold_restart",
+		"Shut down the light client if memory threshold is exceeded, expecting that it is restarted by the supervisor.",
+	),
+	optional(
+		"store_pruning_max_records",
+		"Maximum number of records in the Kademlia store. If set, store size is checked on each block, and expired records are pruned whenever the store exceeds it, instead of every `store_pruning_interval` blocks.",
+		"1000000",
+	),
+	optional(
+		"store_pruning_max_size",
+		"Maximum size of the records in the Kademlia store in megabytes. If set, store size is checked on each block, and expired records are pruned whenever the store exceeds it, instead of every `store_pruning_interval` blocks.",
+		"2048",
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_block",
+		"Crawl block periodically to ensure availability.",
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_block_delay",
+		"Crawl block delay. Increment to ensure large block crawling.",
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_block_mode",
+		"Crawl block mode. Available modes are \"cells\", \"rows\" and \"both\".",
+	),
+	#[cfg(feature = "crawl")]
+	optional(
+		"crawl_block_matrix_partition",
+		"Fraction and number of the block matrix part to crawl (e.g. 2/20 means second 1/20 part of a matrix).",
+		r#""1/20""#,
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_block_sampling_fraction",
+		"Fraction of the partition cells and rows which are randomly sampled and crawled per block, in range (0, 1].",
+	),
+	#[cfg(feature = "crawl")]
+	optional(
+		"crawl_report_path",
+		"Path of the coverage report file, which is written periodically if set.",
+		r#""crawl_report.json""#,
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_report_format",
+		"Coverage report format. Available formats are \"json\" and \"csv\".",
+	),
+	#[cfg(feature = "crawl")]
+	parameter(
+		"crawl_report_interval",
+		"Number of crawled blocks after which the coverage report is written.",
+	),
+];
+
+fn write_value(output: &mut String, value: &toml::Value) -> std::fmt::Result {
+	match value {
+		toml::Value::String(value) => write!(output, "{}", serde_json::Value::from(value.as_str())),
+		toml::Value::Integer(value) => write!(output, "{value}"),
+		toml::Value::Float(value) => write!(output, "{value:?}"),
+		toml::Value::Boolean(value) => write!(output, "{value}"),
+		toml::Value::Datetime(value) => write!(output, "{value}"),
+		toml::Value::Array(values) => {
+			write!(output, "[")?;
+			for (index, value) in values.iter().enumerate() {
+				if index > 0 {
+					write!(output, ", ")?;
+				}
+				write_value(output, value)?;
+			}
+			write!(output, "]")
+		},
+		toml::Value::Table(table) => {
+			write!(output, "{{ ")?;
+			for (index, (key, value)) in table.iter().enumerate() {
+				if index > 0 {
+					write!(output, ", ")?;
+				}
+				write!(output, "{key} = ")?;
+				write_value(output, value)?;
+			}
+			write!(output, " }}")
+		},
+	}
+}
+
+/// Renders configuration file with all parameters, each preceded by its description.
+/// Parameters which are not set are rendered commented out, with an example value.
+pub fn commented_config(cfg: &RuntimeConfig) -> Result<String> {
+	let toml::Value::Table(mut values) = toml::Value::try_from(cfg)? else {
+		return Err(eyre!("Configuration is not serialized as a table"));
+	};
+
+	let mut output = String::new();
+	for parameter in PARAMETERS {
+		for line in parameter.description.lines() {
+			writeln!(output, "# {line}")?;
+		}
+		match (values.remove(parameter.name), parameter.example) {
+			(Some(value), _) => {
+				write!(output, "{} = ", parameter.name)?;
+				write_value(&mut output, &value)?;
+				writeln!(output)?;
+			},
+			(None, Some(example)) => writeln!(output, "# {} = {example}", parameter.name)?,
+			(None, None) => return Err(eyre!("Parameter {} is not set", parameter.name)),
+		}
+		writeln!(output)?;
+	}
+
+	if let Some(name) = values.keys().next() {
+		return Err(eyre!("Parameter {name} is not documented"));
+	}
+	Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::types::Network;
+
+	#[test]
+	fn all_parameters_documented() {
+		let cfg = RuntimeConfig::default();
+		let serialized = serde_json::to_value(&cfg).unwrap();
+		let names = serialized.as_object().unwrap().keys().collect::<Vec<_>>();
+		for name in &names {
+			assert!(
+				PARAMETERS.iter().any(|parameter| parameter.name == *name),
+				"{name} is not documented"
+			);
+		}
+		assert_eq!(names.len(), PARAMETERS.len());
+	}
+
+	#[test]
+	fn generated_config_loads() {
+		let mut cfg = RuntimeConfig::default();
+		cfg.apply_network_preset(&Network::Turing).unwrap();
+		let generated = commented_config(&cfg).unwrap();
+		let loaded: RuntimeConfig = toml::from_str(&generated).unwrap();
+		assert_eq!(
+			serde_json::to_value(&loaded).unwrap(),
+			serde_json::to_value(&cfg).unwrap()
+		);
+		assert!(generated.contains("# app_id = 1\n"));
+	}
+}
@@ -21,6 +21,7 @@ use kate_recovery::{
 	data::Cell,
 	matrix::{Dimensions, Position},
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn decode_app_data(data: &[u8]) -> Result<Option<Vec<u8>>> {
 	let extrisic: AppUncheckedExtrinsic =
@@ -32,9 +33,73 @@ pub fn decode_app_data(data: &[u8]) -> Result<Option<Vec<u8>>> {
 	}
 }
 
-/// Calculates confidence from given number of verified cells
-pub fn calculate_confidence(count: u32) -> f64 {
-	100f64 * (1f64 - 1f64 / 2u32.pow(count) as f64)
+pub use avail_light_verification::calculate_confidence;
+
+/// Returns current time in milliseconds since UNIX epoch
+pub fn unix_timestamp_millis() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_millis() as u64)
+		.unwrap_or_default()
+}
+
+/// Parses resident set size in bytes from the `/proc/self/statm` content
+fn parse_statm(statm: &str, page_size: u64) -> Option<u64> {
+	let resident_pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+	Some(resident_pages * page_size)
+}
+
+/// Returns resident memory of the current process in bytes, if supported by the platform
+pub fn resident_memory_bytes() -> Option<u64> {
+	#[cfg(target_os = "linux")]
+	{
+		let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+		let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+		parse_statm(&statm, u64::try_from(page_size).ok()?)
+	}
+	#[cfg(not(target_os = "linux"))]
+	{
+		None
+	}
+}
+
+/// Returns available disk space in bytes on the filesystem containing the given path, if supported by the platform
+pub fn available_disk_space(path: &str) -> Option<u64> {
+	#[cfg(unix)]
+	{
+		let path = std::ffi::CString::new(path).ok()?;
+		let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+		if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+			return None;
+		}
+		let stat = unsafe { stat.assume_init() };
+		#[allow(clippy::useless_conversion)]
+		Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+	}
+	#[cfg(not(unix))]
+	{
+		let _ = path;
+		None
+	}
+}
+
+/// Pins the current thread to the CPU core, if supported by the platform
+pub fn pin_current_thread(cpu: usize) -> std::io::Result<()> {
+	#[cfg(target_os = "linux")]
+	{
+		let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+		unsafe { libc::CPU_SET(cpu, &mut set) };
+		let size = std::mem::size_of::<libc::cpu_set_t>();
+		if unsafe { libc::sched_setaffinity(0, size, &set) } != 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+		Ok(())
+	}
+	#[cfg(not(target_os = "linux"))]
+	{
+		let _ = cpu;
+		Err(std::io::ErrorKind::Unsupported.into())
+	}
 }
 
 pub trait OptionalExtension {
@@ -105,6 +170,16 @@ pub fn filter_auth_set_changes(header: &DaHeader) -> Vec<Vec<(AuthorityId, u64)>
 	new_auths
 }
 
+/// Checks if the runtime (code or heap pages) is updated in the block, based on the header digest
+pub fn has_runtime_upgrade(header: &DaHeader) -> bool {
+	header.digest.logs.iter().any(|item| {
+		matches!(
+			item,
+			avail_subxt::config::substrate::DigestItem::RuntimeEnvironmentUpdated
+		)
+	})
+}
+
 // TODO: Remove unused functions if not needed after next iteration
 
 #[allow(dead_code)]
@@ -129,7 +204,9 @@ fn diff_positions(positions: &[Position], cells: &[Cell]) -> Vec<Position> {
 
 #[cfg(test)]
 mod tests {
-	use super::{can_reconstruct, diff_positions};
+	use super::{can_reconstruct, diff_positions, has_runtime_upgrade, parse_statm};
+	use crate::simulation::Simulation;
+	use avail_subxt::config::substrate::DigestItem;
 	use kate_recovery::{
 		data::Cell,
 		matrix::{Dimensions, Position},
@@ -193,4 +270,25 @@ mod tests {
 		assert_eq!(diff_positions(&positions, &cells)[0], position(0, 0));
 		assert_eq!(diff_positions(&positions, &cells)[1], position(1, 1));
 	}
+
+	#[test]
+	fn test_parse_statm() {
+		assert_eq!(
+			parse_statm("1000 250 100 10 0 300 0", 4096),
+			Some(250 * 4096)
+		);
+		assert_eq!(parse_statm("1000", 4096), None);
+		assert_eq!(parse_statm("", 4096), None);
+	}
+
+	#[test]
+	fn test_has_runtime_upgrade() {
+		let mut header = Simulation::header(1, 1, 4);
+		assert!(!has_runtime_upgrade(&header));
+		header
+			.digest
+			.logs
+			.push(DigestItem::RuntimeEnvironmentUpdated);
+		assert!(has_runtime_upgrade(&header));
+	}
 }
@@ -0,0 +1,124 @@
+//! Verification core, with the header extension parsing.
+//!
+//! Re-exports the `avail-light-verification` crate, which contains cell selection and KZG proof verification,
+//! without dependencies on the tokio runtime, libp2p, RocksDB or jemalloc, so it can be compiled for the `wasm32-unknown-unknown` target.
+//! Cells are fetched using the pluggable [`Transport`], which can be implemented on top of the WebSocket RPC, HTTP gateway or WebRTC peers.
+//!
+//! # Flow
+//!
+//! * Parse dimensions and commitments from the header extension
+//! * Generate random cell positions required for the configured confidence (see [`select_cells`] for the custom RNG)
+//! * Fetch cells using the transport
+//! * Verify cell proofs and calculate the block confidence
+
+use avail_subxt::{
+	api::runtime_types::avail_core::header::extension::HeaderExtension, utils::H256,
+};
+use color_eyre::Result;
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+
+pub use avail_light_verification::{
+	calculate_confidence, cell_count_for_confidence, generate_random_cells, select_cells,
+	verify_cells, BlockCommitments, Transport, Verification, CELL_COUNT_99_99,
+};
+
+use crate::utils::extract_kate;
+
+/// Parses dimensions and commitments from the header extension.
+/// Returns `None` if block has no commitments or dimensions are invalid.
+pub fn parse_header(extension: &HeaderExtension) -> Result<Option<BlockCommitments>> {
+	let Some((rows, cols, _, commitment)) = extract_kate(extension) else {
+		return Ok(None);
+	};
+	BlockCommitments::new(rows, cols, &commitment)
+}
+
+/// Samples and verifies the block with the given confidence, fetching cells using the transport.
+/// Returns `None` if block has no commitments.
+pub async fn verify_block(
+	transport: &impl Transport,
+	public_parameters: &PublicParameters,
+	block_number: u32,
+	block_hash: H256,
+	extension: &HeaderExtension,
+	confidence: f64,
+) -> Result<Option<Verification>> {
+	let Some(block) = parse_header(extension)? else {
+		return Ok(None);
+	};
+	avail_light_verification::verify_block(
+		transport,
+		public_parameters,
+		block_number,
+		block_hash,
+		&block,
+		confidence,
+	)
+	.await
+	.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use async_trait::async_trait;
+	use avail_subxt::api::runtime_types::avail_core::{
+		data_lookup::compact::CompactDataLookup,
+		header::extension::{v3, HeaderExtension::V3},
+		kate_commitment::v3::KateCommitment,
+	};
+	use kate_recovery::{data::Cell, matrix::Position};
+
+	struct EmptyTransport;
+
+	#[async_trait]
+	impl Transport for EmptyTransport {
+		async fn fetch_cells(&self, _: u32, _: H256, _: &[Position]) -> Result<Vec<Cell>> {
+			Ok(vec![])
+		}
+	}
+
+	fn extension(rows: u16, cols: u16) -> HeaderExtension {
+		V3(v3::HeaderExtension {
+			commitment: KateCommitment {
+				rows,
+				cols,
+				data_root: H256::zero(),
+				commitment: vec![0; 48 * rows as usize],
+			},
+			app_lookup: CompactDataLookup {
+				size: 1,
+				index: vec![],
+			},
+		})
+	}
+
+	#[test]
+	fn parse_header_invalid_dimensions() {
+		assert!(parse_header(&extension(0, 4)).unwrap().is_none());
+		let block = parse_header(&extension(2, 4)).unwrap().unwrap();
+		assert_eq!(block.dimensions.rows().get(), 2);
+		assert_eq!(block.dimensions.cols().get(), 4);
+		assert_eq!(block.commitments.len(), 2);
+	}
+
+	#[tokio::test]
+	async fn verify_block_unfetched() {
+		let pp = kate_recovery::couscous::public_params();
+		let verification = verify_block(
+			&EmptyTransport,
+			&pp,
+			1,
+			H256::zero(),
+			&extension(2, 4),
+			99.9,
+		)
+		.await
+		.unwrap()
+		.unwrap();
+		assert_eq!(verification.requested.len(), 10);
+		assert!(verification.verified.is_empty());
+		assert_eq!(verification.unverified.len(), 10);
+		assert_eq!(verification.confidence, 0.0);
+	}
+}
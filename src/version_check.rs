@@ -0,0 +1,125 @@
+//! Check of the new light client releases.
+//!
+//! Release manifest is fetched from the configured URL on startup and daily, and its version is compared with the running one.
+//! Newer release is surfaced with the warning log, the `avail.light.update_available` metric, and the `update_available` field of `GET /v2/status`.
+//!
+//! # Manifest
+//!
+//! `{"version": "1.10.0", "url": "https://github.com/availproject/avail-light/releases/tag/v1.10.0"}`
+//!
+//! Release URL is optional. Check is best effort: failed requests are logged and retried on the next check.
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hyper::{body, Body, Request};
+use hyper_rustls::HttpsConnector;
+use semver::Version;
+use serde::Deserialize;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tracing::{debug, info, warn};
+
+use crate::{
+	network::proxy::{https_connector, Connector, Proxy},
+	telemetry::{MetricValue, Metrics},
+	types::State,
+};
+
+/// Interval in which the release manifest is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Timeout of the release manifest request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ReleaseManifest {
+	/// Latest released version
+	pub version: String,
+	/// Release notes or download URL
+	#[serde(default)]
+	pub url: Option<String>,
+}
+
+/// Returns released version if it is newer than the running one
+fn newer_version(manifest: &ReleaseManifest, running: &str) -> Result<Option<Version>> {
+	let released = Version::parse(manifest.version.trim_start_matches('v'))
+		.wrap_err(format!("Invalid released version {}", manifest.version))?;
+	let running = Version::parse(running).wrap_err("Invalid running version")?;
+	Ok((released > running).then_some(released))
+}
+
+async fn fetch_manifest(
+	http_client: &hyper::Client<HttpsConnector<Connector>, Body>,
+	url: &str,
+) -> Result<ReleaseManifest> {
+	let request = Request::get(url)
+		.body(Body::empty())
+		.wrap_err(format!("Invalid release manifest URL {url}"))?;
+	let response = tokio::time::timeout(REQUEST_TIMEOUT, http_client.request(request))
+		.await
+		.map_err(|_| eyre!("Request timed out"))??;
+	if !response.status().is_success() {
+		return Err(eyre!("Unexpected response status {}", response.status()));
+	}
+	let body = body::to_bytes(response.into_body()).await?;
+	serde_json::from_slice(&body).wrap_err("Invalid release manifest")
+}
+
+/// Periodically checks the release manifest, surfacing the newer release
+pub async fn run(
+	url: String,
+	proxy: Option<Proxy>,
+	state: Arc<Mutex<State>>,
+	metrics: Arc<impl Metrics>,
+) {
+	info!("Starting version check...");
+	let http_client = hyper::Client::builder().build(https_connector(proxy));
+	let running = clap::crate_version!();
+	let mut interval = tokio::time::interval(CHECK_INTERVAL);
+	loop {
+		interval.tick().await;
+		let newer = fetch_manifest(&http_client, &url)
+			.await
+			.and_then(|manifest| Ok((newer_version(&manifest, running)?, manifest)));
+		match newer {
+			Ok((Some(version), manifest)) => {
+				let release_url = manifest.url.unwrap_or_default();
+				warn!(running, available = %version, release_url, "Update available");
+				state.lock().expect("Lock acquired").update_available = Some(version.to_string());
+				metrics.record(MetricValue::UpdateAvailable(true)).await;
+			},
+			Ok((None, _)) => {
+				debug!(running, "Running version is up to date");
+				state.lock().expect("Lock acquired").update_available = None;
+				metrics.record(MetricValue::UpdateAvailable(false)).await;
+			},
+			Err(error) => warn!(url, "Failed to check for updates: {error:#}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn newer_versions() {
+		let manifest = |version: &str| ReleaseManifest {
+			version: version.to_string(),
+			url: None,
+		};
+		assert_eq!(
+			newer_version(&manifest("v1.10.0"), "1.9.2").unwrap(),
+			Some(Version::new(1, 10, 0))
+		);
+		assert_eq!(newer_version(&manifest("1.9.2"), "1.9.2").unwrap(), None);
+		assert_eq!(newer_version(&manifest("1.9.1"), "1.9.2").unwrap(), None);
+		assert!(newer_version(&manifest("latest"), "1.9.2").is_err());
+
+		let manifest: ReleaseManifest = serde_json::from_str(r#"{"version": "1.10.0"}"#).unwrap();
+		assert_eq!(manifest.url, None);
+	}
+}
@@ -0,0 +1,264 @@
+//! Webhook notifications for the operational events.
+//!
+//! Events are posted as JSON to each configured webhook URL, so operators without the metrics stack still get alerted.
+//! Delivery is best effort: failed requests are logged and not retried.
+//!
+//! # Events
+//!
+//! * `confidence_below_threshold` - confidence is not achieved, or it is below the configured confidence (only blocks with app data are checked)
+//! * `rpc_failover` - connected RPC node is replaced, after the requests to the previous one failed
+//! * `dht_peers_collapsed` - number of peers in the routing table fell below the minimum
+//! * `shutdown_triggered` - shutdown is triggered, with the shutdown reason
+//!
+//! # Payload
+//!
+//! `{"event": "rpc_failover", "timestamp": 1700000000000, "from": "wss://rpc-1", "to": "wss://rpc-2"}`
+
+use color_eyre::{
+	eyre::{eyre, WrapErr},
+	Result,
+};
+use hyper::{header, Body, Method, Request};
+use hyper_rustls::HttpsConnector;
+use serde::Serialize;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::sync::{broadcast, watch};
+use tracing::{debug, info, warn};
+
+use crate::{
+	event_bus::{Receiver, Subscriber},
+	lifecycle::{Degradation, LifecycleState},
+	network::proxy::{https_connector, Connector, Proxy},
+	shutdown::{Controller, ShutdownReason},
+	types::{BlockVerified, ReloadableConfig, State},
+	utils::unix_timestamp_millis,
+};
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+	ConfidenceBelowThreshold {
+		block_number: u32,
+		/// Achieved confidence, `None` if confidence is not achieved
+		confidence: Option<f64>,
+		threshold: f64,
+	},
+	RpcFailover {
+		from: String,
+		to: String,
+	},
+	DhtPeersCollapsed {
+		min_peers: usize,
+	},
+	ShutdownTriggered {
+		reason: String,
+	},
+}
+
+#[derive(Serialize)]
+struct Notification<'a> {
+	#[serde(flatten)]
+	event: &'a WebhookEvent,
+	/// Unix timestamp of the event, in milliseconds
+	timestamp: u64,
+}
+
+#[derive(Clone)]
+pub struct Client {
+	http_client: hyper::Client<HttpsConnector<Connector>, Body>,
+	urls: Vec<String>,
+	timeout: Duration,
+}
+
+impl Client {
+	pub fn new(urls: Vec<String>, timeout: Duration, proxy: Option<Proxy>) -> Self {
+		Client {
+			http_client: hyper::Client::builder().build(https_connector(proxy)),
+			urls,
+			timeout,
+		}
+	}
+
+	async fn post(&self, url: &str, body: String) -> Result<()> {
+		let request = Request::builder()
+			.method(Method::POST)
+			.uri(url)
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(Body::from(body))
+			.wrap_err(format!("Invalid webhook URL {url}"))?;
+		let response = tokio::time::timeout(self.timeout, self.http_client.request(request))
+			.await
+			.map_err(|_| eyre!("Request timed out"))??;
+		if !response.status().is_success() {
+			return Err(eyre!("Unexpected response status {}", response.status()));
+		}
+		Ok(())
+	}
+
+	/// Posts event to all configured webhooks
+	pub async fn notify(&self, event: &WebhookEvent) {
+		let notification = Notification {
+			event,
+			timestamp: unix_timestamp_millis(),
+		};
+		let body = match serde_json::to_string(&notification) {
+			Ok(body) => body,
+			Err(error) => {
+				warn!("Cannot serialize webhook event: {error:#}");
+				return;
+			},
+		};
+		for url in &self.urls {
+			match self.post(url, body.clone()).await {
+				Ok(()) => debug!(url, ?event, "Webhook notified"),
+				Err(error) => warn!(url, "Failed to notify webhook: {error:#}"),
+			}
+		}
+	}
+}
+
+fn confidence_event(block: &BlockVerified, threshold: f64) -> Option<WebhookEvent> {
+	// confidence is not set for the skipped blocks, so only blocks with app data (and valid dimensions) are checked
+	block.extension.as_ref()?;
+	if block
+		.confidence
+		.is_some_and(|confidence| confidence >= threshold)
+	{
+		return None;
+	}
+	Some(WebhookEvent::ConfidenceBelowThreshold {
+		block_number: block.block_num,
+		confidence: block.confidence,
+		threshold,
+	})
+}
+
+fn failover_event(previous: &mut String, current: &str) -> Option<WebhookEvent> {
+	if previous == current {
+		return None;
+	}
+	let from = std::mem::replace(previous, current.to_string());
+	(!from.is_empty()).then(|| WebhookEvent::RpcFailover {
+		from,
+		to: current.to_string(),
+	})
+}
+
+/// Posts operational events to the webhooks, until the shutdown is triggered.
+/// Shutdown event is posted before returning.
+pub async fn run(
+	client: Client,
+	state: Arc<Mutex<State>>,
+	mut lifecycle: broadcast::Receiver<LifecycleState>,
+	mut blocks: Subscriber<BlockVerified>,
+	reloadable_config: watch::Receiver<ReloadableConfig>,
+	min_peers: usize,
+	shutdown: Controller<ShutdownReason>,
+) {
+	info!("Starting webhook notifications...");
+	let mut connected_host = state
+		.lock()
+		.expect("Lock acquired")
+		.connected_node
+		.host
+		.clone();
+
+	loop {
+		let event = tokio::select! {
+			Some(block) = blocks.recv() => {
+				let host = state.lock().expect("Lock acquired").connected_node.host.clone();
+				if let Some(event) = failover_event(&mut connected_host, &host) {
+					client.notify(&event).await;
+				}
+				let threshold = reloadable_config.borrow().confidence;
+				confidence_event(&block, threshold)
+			},
+			Some(lifecycle) = lifecycle.receive() => {
+				let degradations = state.lock().expect("Lock acquired").degradations.clone();
+				let peers_collapsed = lifecycle == LifecycleState::Degraded
+					&& degradations.contains(&Degradation::InsufficientPeers);
+				peers_collapsed.then_some(WebhookEvent::DhtPeersCollapsed { min_peers })
+			},
+			reason = shutdown.triggered_shutdown() => {
+				let event = WebhookEvent::ShutdownTriggered { reason: reason.to_string() };
+				client.notify(&event).await;
+				return;
+			},
+		};
+		if let Some(event) = event {
+			client.notify(&event).await;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use avail_core::DataLookup;
+	use avail_subxt::utils::H256;
+	use kate_recovery::matrix::Dimensions;
+
+	use crate::types::Extension;
+
+	fn block(confidence: Option<f64>) -> BlockVerified {
+		BlockVerified {
+			header_hash: H256::zero(),
+			block_num: 1,
+			extension: Some(Extension {
+				dimensions: Dimensions::new(1, 4).unwrap(),
+				lookup: DataLookup::from_id_and_len_iter([(0u32, 1usize)].into_iter()).unwrap(),
+				commitments: vec![],
+				data_root: H256::zero(),
+			}),
+			confidence,
+		}
+	}
+
+	#[test]
+	fn confidence_events() {
+		assert_eq!(confidence_event(&block(Some(99.9)), 99.9), None);
+		assert_eq!(
+			confidence_event(&block(Some(99.0)), 99.9),
+			Some(WebhookEvent::ConfidenceBelowThreshold {
+				block_number: 1,
+				confidence: Some(99.0),
+				threshold: 99.9
+			})
+		);
+		assert!(confidence_event(&block(None), 99.9).is_some());
+
+		let mut unsampled = block(None);
+		unsampled.extension = None;
+		assert_eq!(confidence_event(&unsampled, 99.9), None);
+	}
+
+	#[test]
+	fn failover_events() {
+		let mut host = String::new();
+		assert_eq!(failover_event(&mut host, "wss://rpc-1"), None);
+		assert_eq!(failover_event(&mut host, "wss://rpc-1"), None);
+		assert_eq!(
+			failover_event(&mut host, "wss://rpc-2"),
+			Some(WebhookEvent::RpcFailover {
+				from: "wss://rpc-1".to_string(),
+				to: "wss://rpc-2".to_string()
+			})
+		);
+	}
+
+	#[test]
+	fn notification_payload() {
+		let event = WebhookEvent::DhtPeersCollapsed { min_peers: 5 };
+		let notification = Notification {
+			event: &event,
+			timestamp: 1,
+		};
+		assert_eq!(
+			serde_json::to_string(&notification).unwrap(),
+			r#"{"event":"dht_peers_collapsed","min_peers":5,"timestamp":1}"#
+		);
+	}
+}
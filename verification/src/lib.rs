@@ -0,0 +1,228 @@
+//! Target independent verification core.
+//!
+//! Commitments parsing, cell selection and KZG proof verification, without dependencies on the tokio runtime, libp2p, RocksDB or jemalloc,
+//! so it can be compiled for the `wasm32-unknown-unknown` target and used for the in-browser sampling.
+//! Cells are fetched using the pluggable [`Transport`], which can be implemented on top of the WebSocket RPC, HTTP gateway or WebRTC peers.
+//!
+//! # Flow
+//!
+//! * Parse dimensions and commitments from the header extension (see [`BlockCommitments::new`])
+//! * Generate random cell positions required for the configured confidence (see [`select_cells`] for the custom RNG)
+//! * Fetch cells using the transport
+//! * Verify cell proofs and calculate the block confidence
+//!
+//! # Notes
+//!
+//! Header extension types are not used, since they come with the native RPC client dependencies.
+//! The `avail-light` crate parses header extension in `avail_light::verification` module, re-exporting this crate.
+
+use async_trait::async_trait;
+use dusk_plonk::commitment_scheme::kzg10::PublicParameters;
+use eyre::{Result, WrapErr};
+use kate_recovery::{
+	commitments,
+	data::Cell,
+	matrix::{Dimensions, Position},
+	proof,
+};
+use primitive_types::H256;
+use rand::{thread_rng, Rng};
+use std::collections::HashSet;
+use tracing::{debug, info};
+
+/// Fetches cells for the given block and positions.
+/// Cells which are not fetched are omitted from the result.
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Transport {
+	async fn fetch_cells(
+		&self,
+		block_number: u32,
+		block_hash: H256,
+		positions: &[Position],
+	) -> Result<Vec<Cell>>;
+}
+
+/// Block header data required for the verification
+#[derive(Clone)]
+pub struct BlockCommitments {
+	pub dimensions: Dimensions,
+	pub commitments: Vec<[u8; 48]>,
+}
+
+impl BlockCommitments {
+	/// Parses dimensions and commitments of the header extension.
+	/// Returns `None` if dimensions are invalid.
+	pub fn new(rows: u16, cols: u16, commitment: &[u8]) -> Result<Option<Self>> {
+		let Some(dimensions) = Dimensions::new(rows, cols) else {
+			debug!("Invalid dimensions {rows}x{cols}");
+			return Ok(None);
+		};
+		let commitments =
+			commitments::from_slice(commitment).wrap_err("Failed to parse commitments")?;
+		Ok(Some(BlockCommitments {
+			dimensions,
+			commitments,
+		}))
+	}
+}
+
+/// Result of the block verification
+#[derive(Clone)]
+pub struct Verification {
+	pub requested: Vec<Position>,
+	pub verified: Vec<Position>,
+	pub unverified: Vec<Position>,
+	pub confidence: f64,
+}
+
+/// Calculates confidence from the number of verified cells
+pub fn calculate_confidence(count: u32) -> f64 {
+	100f64 * (1f64 - 1f64 / 2u32.pow(count) as f64)
+}
+
+/// Generates random cell positions for sampling, using the thread local RNG
+pub fn generate_random_cells(dimensions: Dimensions, cell_count: u32) -> Vec<Position> {
+	select_cells(dimensions, cell_count, &mut thread_rng())
+}
+
+/// Selects random cell positions for sampling, using the given RNG
+pub fn select_cells(dimensions: Dimensions, cell_count: u32, rng: &mut impl Rng) -> Vec<Position> {
+	let max_cells = dimensions.extended_size();
+	let count = if max_cells < cell_count {
+		debug!("Max cells count {max_cells} is lesser than cell_count {cell_count}");
+		max_cells
+	} else {
+		cell_count
+	};
+	let mut indices = HashSet::new();
+	while (indices.len() as u16) < count as u16 {
+		let col = rng.gen_range(0..dimensions.cols().into());
+		let row = rng.gen_range(0..dimensions.extended_rows());
+		indices.insert(Position { row, col });
+	}
+
+	indices.into_iter().collect::<Vec<_>>()
+}
+
+/* @note: fn to take the number of cells needs to get equal to or greater than
+the percentage of confidence mentioned in config file */
+
+pub const CELL_COUNT_99_99: u32 = 14;
+
+/// Calculates number of cells required to achieve given confidence
+pub fn cell_count_for_confidence(confidence: f64) -> u32 {
+	let mut cell_count: u32;
+	if !(50.0..=100f64).contains(&confidence) {
+		//in this default of 8 cells will be taken
+		info!(
+			"confidence is {} invalid so taking default confidence of 99",
+			confidence
+		);
+		cell_count = (-((1f64 - (99.3f64 / 100f64)).log2())).ceil() as u32;
+	} else {
+		cell_count = (-((1f64 - (confidence / 100f64)).log2())).ceil() as u32;
+	}
+	if cell_count <= 1 {
+		info!(
+			"confidence of {} is too low so taking confidence of 50.0",
+			confidence
+		);
+		cell_count = 1;
+	} else if cell_count > CELL_COUNT_99_99 {
+		info!(
+			"confidence of {} is invalid so taking confidence of 99.99",
+			confidence
+		);
+		cell_count = CELL_COUNT_99_99;
+	}
+	cell_count
+}
+
+/// Verifies cell proofs sequentially, returning verified and unverified positions.
+/// Cells with positions outside of the commitments are unverified.
+pub fn verify_cells(
+	public_parameters: &PublicParameters,
+	block: &BlockCommitments,
+	cells: &[Cell],
+) -> Result<(Vec<Position>, Vec<Position>), proof::Error> {
+	let mut verified = Vec::with_capacity(cells.len());
+	let mut unverified = vec![];
+	for cell in cells {
+		let Some(commitment) = block.commitments.get(cell.position.row as usize) else {
+			unverified.push(cell.position);
+			continue;
+		};
+		match proof::verify(public_parameters, block.dimensions, commitment, cell)? {
+			true => verified.push(cell.position),
+			false => unverified.push(cell.position),
+		}
+	}
+	Ok((verified, unverified))
+}
+
+/// Samples and verifies the block with the given confidence, fetching cells using the transport
+pub async fn verify_block(
+	transport: &impl Transport,
+	public_parameters: &PublicParameters,
+	block_number: u32,
+	block_hash: H256,
+	block: &BlockCommitments,
+	confidence: f64,
+) -> Result<Verification> {
+	let cell_count = cell_count_for_confidence(confidence);
+	let requested = generate_random_cells(block.dimensions, cell_count);
+	let cells = transport
+		.fetch_cells(block_number, block_hash, &requested)
+		.await?;
+
+	let (verified, mut unverified) = verify_cells(public_parameters, block, &cells)?;
+	unverified.extend(
+		requested
+			.iter()
+			.filter(|position| !cells.iter().any(|cell| &cell.position == *position)),
+	);
+
+	Ok(Verification {
+		confidence: calculate_confidence(verified.len() as u32),
+		requested,
+		verified,
+		unverified,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct EmptyTransport;
+
+	#[async_trait]
+	impl Transport for EmptyTransport {
+		async fn fetch_cells(&self, _: u32, _: H256, _: &[Position]) -> Result<Vec<Cell>> {
+			Ok(vec![])
+		}
+	}
+
+	#[test]
+	fn block_commitments_invalid_dimensions() {
+		assert!(BlockCommitments::new(0, 4, &[]).unwrap().is_none());
+		let block = BlockCommitments::new(2, 4, &[0; 96]).unwrap().unwrap();
+		assert_eq!(block.dimensions.rows().get(), 2);
+		assert_eq!(block.dimensions.cols().get(), 4);
+		assert_eq!(block.commitments.len(), 2);
+	}
+
+	#[tokio::test]
+	async fn verify_block_unfetched() {
+		let pp = kate_recovery::couscous::public_params();
+		let block = BlockCommitments::new(2, 4, &[0; 96]).unwrap().unwrap();
+		let verification = verify_block(&EmptyTransport, &pp, 1, H256::zero(), &block, 99.9)
+			.await
+			.unwrap();
+		assert_eq!(verification.requested.len(), 10);
+		assert!(verification.verified.is_empty());
+		assert_eq!(verification.unverified.len(), 10);
+		assert_eq!(verification.confidence, 0.0);
+	}
+}